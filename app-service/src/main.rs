@@ -2,7 +2,7 @@ use std::env;
 
 use askama::Template;
 use axum::{
-    http::StatusCode,
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
     response::{Html, IntoResponse},
     routing::get,
     Json, Router,
@@ -46,24 +46,30 @@ async fn root() -> impl IntoResponse {
     Html(template.render().unwrap())
 }
 
-async fn protected(jar: CookieJar) -> impl IntoResponse {
-    let jwt_cookie = match jar.get("jwt") {
-        Some(cookie) => cookie,
-        None => {
-            return StatusCode::UNAUTHORIZED.into_response();
-        }
-    };
-
+// Browsers are authenticated by their JWT cookie; scripts and CI can send an API key or token
+// as `Authorization: Bearer <token>` instead, which auth-service verifies.
+async fn protected(jar: CookieJar, headers: HeaderMap) -> impl IntoResponse {
     let api_client = reqwest::Client::builder().build().unwrap();
 
-    let verify_token_body = serde_json::json!({
-        "token": &jwt_cookie.value(),
-    });
-
     let auth_hostname = env::var("AUTH_SERVICE_HOST_NAME").unwrap_or("0.0.0.0".to_owned());
     let url = format!("http://{}:3000/verify-token", auth_hostname);
 
-    let response = match api_client.post(&url).json(&verify_token_body).send().await {
+    let request = match (headers.get(AUTHORIZATION), jar.get("jwt")) {
+        (Some(authorization), _) => api_client
+            .post(&url)
+            .header(reqwest::header::AUTHORIZATION, authorization.as_bytes()),
+        (None, Some(jwt_cookie)) => {
+            let verify_token_body = serde_json::json!({
+                "token": &jwt_cookie.value(),
+            });
+            api_client.post(&url).json(&verify_token_body)
+        }
+        (None, None) => {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+    };
+
+    let response = match request.send().await {
         Ok(response) => response,
         Err(_) => {
             return StatusCode::INTERNAL_SERVER_ERROR.into_response();
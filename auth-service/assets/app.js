@@ -66,6 +66,9 @@ loginButton.addEventListener("click", (e) => {
             loginForm.email.value = "";
             loginForm.password.value = "";
             loginErrAlter.style.display = "none";
+            if (returnToAuthorization()) {
+                return;
+            }
             alert("You have successfully logged in.");
         } else {
             response.json().then(data => {
@@ -81,6 +84,24 @@ loginButton.addEventListener("click", (e) => {
     });
 });
 
+// Apps using the OAuth flow send users here to log in; once they have, continue the authorization.
+// Only paths on this service are followed so the parameter can't be used as an open redirect.
+function returnToAuthorization() {
+    const returnTo = new URLSearchParams(window.location.search).get("return_to");
+    if (returnTo && returnTo.startsWith("/authorize?")) {
+        window.location.href = returnTo;
+        return true;
+    }
+    return false;
+}
+
+// Federated logins resume the OAuth flow too, once the identity provider sends the user back.
+const federatedLoginLink = document.getElementById("federated-login-link");
+const federatedReturnTo = new URLSearchParams(window.location.search).get("return_to");
+if (federatedReturnTo && federatedReturnTo.startsWith("/authorize?")) {
+    federatedLoginLink.href = "/federated-login?return_to=" + encodeURIComponent(federatedReturnTo);
+}
+
 const signupForm = document.getElementById("signup-form");
 const signupButton = document.getElementById("signup-form-submit");
 const signupErrAlter = document.getElementById("signup-err-alert");
@@ -104,7 +125,12 @@ signupButton.addEventListener("click", (e) => {
             signupForm.password.value = "";
             signupForm.twoFA.checked = false;
             signupErrAlter.style.display = "none";
-            alert("You have successfully created a user.");
+            if (requires2FA) {
+                response.json().then(data => {
+                    alert("Store these recovery codes somewhere safe. Each one can be used once instead of a 2FA code:\n\n" + data.recoveryCodes.join("\n"));
+                });
+            }
+            alert("You have successfully created a user. Check your inbox to verify your email address.");
             loginSection.style.display = "block";
             twoFASection.style.display = "none";
             signupSection.style.display = "none";
@@ -145,6 +171,9 @@ TwoFAButton.addEventListener("click", (e) => {
             TwoFAForm.email_code.value = "";
             TwoFAForm.login_attempt_id.value = "";
             TwoFAErrAlter.style.display = "none";
+            if (returnToAuthorization()) {
+                return;
+            }
             alert("You have successfully logged in.");
             loginSection.style.display = "block";
             twoFASection.style.display = "none";
@@ -161,4 +190,156 @@ TwoFAButton.addEventListener("click", (e) => {
             });
         }
     });
-});
\ No newline at end of file
+});
+
+// Federated logins of accounts with 2FA enabled come back here to enter the code.
+const federatedLoginParams = new URLSearchParams(window.location.search);
+if (federatedLoginParams.get("login_attempt_id")) {
+    TwoFAForm.email.value = federatedLoginParams.get("email");
+    TwoFAForm.login_attempt_id.value = federatedLoginParams.get("login_attempt_id");
+    loginSection.style.display = "none";
+    twoFASection.style.display = "block";
+    signupSection.style.display = "none";
+}
+
+const forgotPasswordSection = document.getElementById("forgot-password-section");
+const resetPasswordSection = document.getElementById("reset-password-section");
+
+const forgotPasswordLink = document.getElementById("forgot-password-link");
+const forgotPasswordLoginLink = document.getElementById("forgot-password-login-link");
+
+forgotPasswordLink.addEventListener("click", (e) => {
+    e.preventDefault();
+
+    loginSection.style.display = "none";
+    forgotPasswordSection.style.display = "block";
+});
+
+forgotPasswordLoginLink.addEventListener("click", (e) => {
+    e.preventDefault();
+
+    loginSection.style.display = "block";
+    forgotPasswordSection.style.display = "none";
+});
+
+const forgotPasswordForm = document.getElementById("forgot-password-form");
+const forgotPasswordButton = document.getElementById("forgot-password-form-submit");
+const forgotPasswordErrAlter = document.getElementById("forgot-password-err-alert");
+
+forgotPasswordButton.addEventListener("click", (e) => {
+    e.preventDefault();
+
+    const email = forgotPasswordForm.email.value;
+
+    fetch('/forgot-password', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ email }),
+    }).then(response => {
+        if (response.ok) {
+            forgotPasswordForm.email.value = "";
+            forgotPasswordErrAlter.style.display = "none";
+            alert("If an account exists for this email, a reset link has been sent.");
+            loginSection.style.display = "block";
+            forgotPasswordSection.style.display = "none";
+        } else {
+            response.json().then(data => {
+                let error_msg = data.error;
+                if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
+                    forgotPasswordErrAlter.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
+                    forgotPasswordErrAlter.style.display = "block";
+                } else {
+                    forgotPasswordErrAlter.style.display = "none";
+                }
+            });
+        }
+    });
+});
+
+const resetPasswordForm = document.getElementById("reset-password-form");
+const resetPasswordButton = document.getElementById("reset-password-form-submit");
+const resetPasswordErrAlter = document.getElementById("reset-password-err-alert");
+
+const resetToken = new URLSearchParams(window.location.search).get("token");
+if (window.location.pathname === "/reset-password" && resetToken) {
+    resetPasswordForm.token.value = resetToken;
+    loginSection.style.display = "none";
+    resetPasswordSection.style.display = "block";
+}
+
+resetPasswordButton.addEventListener("click", (e) => {
+    e.preventDefault();
+
+    const token = resetPasswordForm.token.value;
+    const newPassword = resetPasswordForm.password.value;
+
+    fetch('/reset-password', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ token, newPassword }),
+    }).then(response => {
+        if (response.ok) {
+            resetPasswordForm.token.value = "";
+            resetPasswordForm.password.value = "";
+            resetPasswordErrAlter.style.display = "none";
+            alert("Your password has been reset.");
+            window.history.replaceState({}, "", "/");
+            loginSection.style.display = "block";
+            resetPasswordSection.style.display = "none";
+        } else {
+            response.json().then(data => {
+                let error_msg = data.error;
+                if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
+                    resetPasswordErrAlter.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
+                    resetPasswordErrAlter.style.display = "block";
+                } else {
+                    resetPasswordErrAlter.style.display = "none";
+                }
+            });
+        }
+    });
+});
+const verifyEmailToken = new URLSearchParams(window.location.search).get("token");
+if (window.location.pathname === "/verify-email" && verifyEmailToken) {
+    fetch('/verify-email', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ token: verifyEmailToken }),
+    }).then(response => {
+        window.history.replaceState({}, "", "/");
+        if (response.ok) {
+            alert("Your email address has been verified. You can now log in.");
+        } else {
+            alert("This verification link is invalid or has expired.");
+        }
+    });
+}
+
+const emailChangeToken = new URLSearchParams(window.location.search).get("token");
+const emailChangeMessages = {
+    "/confirm-email-change": "Your email address has been changed. You can now log in with the new one.",
+    "/undo-email-change": "The email change has been undone and you have been logged out everywhere. Consider resetting your password.",
+};
+if (window.location.pathname in emailChangeMessages && emailChangeToken) {
+    fetch(window.location.pathname, {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ token: emailChangeToken }),
+    }).then(response => {
+        const message = emailChangeMessages[window.location.pathname];
+        window.history.replaceState({}, "", "/");
+        if (response.ok) {
+            alert(message);
+        } else {
+            alert("This link is invalid or has expired.");
+        }
+    });
+}
@@ -0,0 +1,9 @@
+-- Add down migration script here
+ALTER TABLE users ADD COLUMN requires_2fa BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE users SET requires_2fa = TRUE WHERE two_fa_method <> 'none';
+
+ALTER TABLE users DROP CONSTRAINT users_totp_secret_check;
+ALTER TABLE users DROP COLUMN pending_totp_secret;
+ALTER TABLE users DROP COLUMN totp_secret;
+ALTER TABLE users DROP COLUMN two_fa_method;
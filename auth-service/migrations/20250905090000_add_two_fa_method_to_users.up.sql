@@ -0,0 +1,12 @@
+-- Add up migration script here
+ALTER TABLE users ADD COLUMN two_fa_method TEXT NOT NULL DEFAULT 'none'
+    CHECK (two_fa_method IN ('none', 'email', 'totp'));
+ALTER TABLE users ADD COLUMN totp_secret TEXT;
+-- Secret generated by an enrollment that hasn't been confirmed with a code yet.
+ALTER TABLE users ADD COLUMN pending_totp_secret TEXT;
+
+UPDATE users SET two_fa_method = 'email' WHERE requires_2fa;
+
+ALTER TABLE users DROP COLUMN requires_2fa;
+ALTER TABLE users ADD CONSTRAINT users_totp_secret_check
+    CHECK ((two_fa_method = 'totp') = (totp_secret IS NOT NULL));
@@ -0,0 +1,8 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS oauth_clients (
+    client_id TEXT NOT NULL PRIMARY KEY,
+    name TEXT NOT NULL,
+    redirect_uris TEXT[] NOT NULL,
+    -- NULL for public clients, which authenticate with PKCE alone.
+    client_secret_hash TEXT
+);
@@ -0,0 +1,9 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS federated_identities(
+   issuer TEXT NOT NULL,
+   subject TEXT NOT NULL,
+   email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE,
+   PRIMARY KEY (issuer, subject)
+);
+
+CREATE INDEX IF NOT EXISTS federated_identities_email_idx ON federated_identities(email);
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE users ADD COLUMN token_version BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- Unix time at which the user deleted their account; NULL unless a deletion is pending.
+ALTER TABLE users ADD COLUMN deleted_at BIGINT;
+
+CREATE INDEX IF NOT EXISTS users_deleted_at_idx ON users(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- The address the user last changed their email from, so the change can be undone.
+ALTER TABLE users ADD COLUMN previous_email TEXT;
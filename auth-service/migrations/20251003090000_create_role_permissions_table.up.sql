@@ -0,0 +1,6 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS role_permissions(
+   role TEXT NOT NULL,
+   permission TEXT NOT NULL,
+   PRIMARY KEY (role, permission)
+);
@@ -0,0 +1,13 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS api_keys(
+   id TEXT NOT NULL PRIMARY KEY,
+   email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE,
+   name TEXT NOT NULL,
+   scopes TEXT[] NOT NULL,
+   key_hash TEXT NOT NULL,
+   created_at BIGINT NOT NULL,
+   expires_at BIGINT NOT NULL,
+   last_used_at BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS api_keys_email_idx ON api_keys(email);
@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- The scopes a client may be granted with the client-credentials grant.
+ALTER TABLE oauth_clients ADD COLUMN scopes TEXT[] NOT NULL DEFAULT '{}';
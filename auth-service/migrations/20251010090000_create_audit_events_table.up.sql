@@ -0,0 +1,19 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS audit_events(
+   id BIGSERIAL PRIMARY KEY,
+   actor TEXT,
+   target TEXT,
+   action TEXT NOT NULL,
+   outcome TEXT NOT NULL,
+   ip TEXT,
+   user_agent TEXT,
+   request_id TEXT,
+   created_at BIGINT NOT NULL,
+   previous_hash TEXT NOT NULL,
+   hash TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS audit_events_actor_idx ON audit_events(actor);
+CREATE INDEX IF NOT EXISTS audit_events_target_idx ON audit_events(target);
+CREATE INDEX IF NOT EXISTS audit_events_action_idx ON audit_events(action);
+CREATE INDEX IF NOT EXISTS audit_events_created_at_idx ON audit_events(created_at);
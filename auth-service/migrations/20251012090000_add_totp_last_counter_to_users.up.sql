@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- The time step of the last TOTP code the user logged in with, so codes can't be replayed.
+ALTER TABLE users ADD COLUMN totp_last_counter BIGINT;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- Unlike the email address, the ID never changes, so it can identify the user to other parties.
+ALTER TABLE users ADD COLUMN id TEXT NOT NULL UNIQUE DEFAULT gen_random_uuid()::TEXT;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS users_previous_email_idx;
+ALTER TABLE users DROP COLUMN IF EXISTS email_changed_at;
@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- When the email was last changed; the previous address stays reserved while the change can be undone.
+ALTER TABLE users ADD COLUMN email_changed_at BIGINT;
+CREATE INDEX IF NOT EXISTS users_previous_email_idx ON users(previous_email);
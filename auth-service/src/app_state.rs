@@ -1,33 +1,92 @@
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use crate::domain::{BannedTokenStore, EmailClient, TwoFACodeStore, UserStore};
+use crate::domain::{
+    ApiKeyStore, AuditHeadStore, AuditLogStore, AuthorizationCodeStore, BannedTokenStore, EmailClient, OAuthClientStore, PasswordResetTokenStore,
+    RateLimitStore, RefreshTokenStore, SessionStore, TwoFACodeStore, UserStore,
+};
+use crate::services::oidc_identity_provider::OidcIdentityProvider;
+use crate::utils::{constants::DEFAULT_ACCOUNT_DELETION_GRACE_PERIOD_SECONDS, rate_limit::RateLimitConfig};
 
 pub type UserStoreType = Arc<RwLock<Box<dyn UserStore + Send + Sync>>>;
 pub type BannedTokenStoreType = Arc<RwLock<Box<dyn BannedTokenStore + Send + Sync>>>;
 pub type TwoFACodeStoreType = Arc<RwLock<Box<dyn TwoFACodeStore + Send + Sync>>>;
+pub type PasswordResetTokenStoreType = Arc<RwLock<Box<dyn PasswordResetTokenStore + Send + Sync>>>;
+pub type RefreshTokenStoreType = Arc<RwLock<Box<dyn RefreshTokenStore + Send + Sync>>>;
+pub type RateLimitStoreType = Arc<RwLock<Box<dyn RateLimitStore + Send + Sync>>>;
+pub type OAuthClientStoreType = Arc<RwLock<Box<dyn OAuthClientStore + Send + Sync>>>;
+pub type AuthorizationCodeStoreType = Arc<RwLock<Box<dyn AuthorizationCodeStore + Send + Sync>>>;
+pub type SessionStoreType = Arc<RwLock<Box<dyn SessionStore + Send + Sync>>>;
+pub type ApiKeyStoreType = Arc<RwLock<Box<dyn ApiKeyStore + Send + Sync>>>;
+// Appends from concurrent requests are serialised by the store itself, not by a lock around it.
+pub type AuditLogStoreType = Arc<dyn AuditLogStore + Send + Sync>;
+pub type AuditHeadStoreType = Arc<dyn AuditHeadStore + Send + Sync>;
 pub type EmailClientType = Arc<dyn EmailClient + Send + Sync>; 
+pub type IdentityProviderType = Arc<OidcIdentityProvider>;
 
 #[derive(Clone)]
 pub struct AppState {
     pub user_store: UserStoreType,
     pub banned_token_store: BannedTokenStoreType,
     pub two_fa_code_store: TwoFACodeStoreType,
+    // Codes confirming that 2FA is being turned on, kept apart from login codes.
+    pub two_fa_enrollment_code_store: TwoFACodeStoreType,
+    pub password_reset_token_store: PasswordResetTokenStoreType,
+    pub refresh_token_store: RefreshTokenStoreType,
+    pub rate_limit_store: RateLimitStoreType,
+    pub rate_limits: RateLimitConfig,
+    pub oauth_client_store: OAuthClientStoreType,
+    pub authorization_code_store: AuthorizationCodeStoreType,
+    pub session_store: SessionStoreType,
+    pub api_key_store: ApiKeyStoreType,
+    pub audit_log_store: AuditLogStoreType,
+    pub audit_head_store: AuditHeadStoreType,
+    // Logging in beyond this many sessions ends the oldest ones; unlimited when unset.
+    pub max_sessions_per_user: Option<usize>,
+    // How long a deleted account can still be restored by logging in.
+    pub account_deletion_grace_period_seconds: i64,
     pub email_client: EmailClientType, 
+    // Upstream provider for federated login; the feature is off when unset.
+    pub identity_provider: Option<IdentityProviderType>,
 }
 
 impl AppState {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         user_store: UserStoreType,
         banned_token_store: BannedTokenStoreType,
         two_fa_code_store: TwoFACodeStoreType,
+        two_fa_enrollment_code_store: TwoFACodeStoreType,
+        password_reset_token_store: PasswordResetTokenStoreType,
+        refresh_token_store: RefreshTokenStoreType,
+        rate_limit_store: RateLimitStoreType,
+        oauth_client_store: OAuthClientStoreType,
+        authorization_code_store: AuthorizationCodeStoreType,
+        session_store: SessionStoreType,
+        api_key_store: ApiKeyStoreType,
+        audit_log_store: AuditLogStoreType,
+        audit_head_store: AuditHeadStoreType,
         email_client: EmailClientType,
     ) -> Self {
         Self {
             user_store,
             banned_token_store,
             two_fa_code_store,
+            two_fa_enrollment_code_store,
+            password_reset_token_store,
+            refresh_token_store,
+            rate_limit_store,
+            rate_limits: RateLimitConfig::default(),
+            oauth_client_store,
+            authorization_code_store,
+            session_store,
+            api_key_store,
+            audit_log_store,
+            audit_head_store,
+            max_sessions_per_user: None,
+            account_deletion_grace_period_seconds: DEFAULT_ACCOUNT_DELETION_GRACE_PERIOD_SECONDS,
             email_client, 
+            identity_provider: None,
         }
     }
 }
\ No newline at end of file
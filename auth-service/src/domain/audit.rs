@@ -0,0 +1,368 @@
+use color_eyre::eyre::{eyre, Result};
+use data_encoding::HEXLOWER;
+use ring::hmac;
+use secrecy::{ExposeSecret, Secret};
+
+// The `previous_hash` of the first event in the log.
+pub const AUDIT_CHAIN_START: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+impl AuditOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditOutcome::Success => "success",
+            AuditOutcome::Failure => "failure",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "success" => Ok(AuditOutcome::Success),
+            "failure" => Ok(AuditOutcome::Failure),
+            _ => Err(eyre!("Unknown audit outcome: {}", s)),
+        }
+    }
+}
+
+// What a request did, as named by its handler. A login that still needs its second factor is
+// `login.2fa_required`; the login completes with `login.2fa`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuditAction {
+    Signup,
+    VerifyEmail,
+    ResendVerificationEmail,
+    Login,
+    LoginTwoFARequired,
+    VerifyTwoFA,
+    ResendTwoFA,
+    FederatedLogin,
+    LinkFederatedIdentity,
+    RefreshToken,
+    IssueToken,
+    Logout,
+    LogoutAll,
+    RevokeSession,
+    ForgotPassword,
+    ResetPassword,
+    ChangePassword,
+    ChangeEmail,
+    ConfirmEmailChange,
+    UndoEmailChange,
+    EnableTwoFA,
+    ConfirmEnableTwoFA,
+    DisableTwoFA,
+    EnrollTotp,
+    ConfirmTotp,
+    RegenerateRecoveryCodes,
+    DeleteAccount,
+    CreateApiKey,
+    RotateApiKey,
+    RevokeApiKey,
+    AdminLockUser,
+    AdminUnlockUser,
+    AdminForcePasswordReset,
+    AdminForceTwoFA,
+    AdminRevokeTokens,
+    AdminAddRole,
+    AdminRemoveRole,
+    AdminRevokeApiKey,
+    AdminRotateApiKey,
+    AdminGrantPermission,
+    AdminRevokePermission,
+    AdminCreateOAuthClient,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Signup => "signup",
+            AuditAction::VerifyEmail => "email.verify",
+            AuditAction::ResendVerificationEmail => "email.resend_verification",
+            AuditAction::Login => "login",
+            AuditAction::LoginTwoFARequired => "login.2fa_required",
+            AuditAction::VerifyTwoFA => "login.2fa",
+            AuditAction::ResendTwoFA => "login.2fa_resend",
+            AuditAction::FederatedLogin => "login.federated",
+            AuditAction::LinkFederatedIdentity => "federated_identity.link",
+            AuditAction::RefreshToken => "token.refresh",
+            AuditAction::IssueToken => "token.issue",
+            AuditAction::Logout => "logout",
+            AuditAction::LogoutAll => "logout.all",
+            AuditAction::RevokeSession => "session.revoke",
+            AuditAction::ForgotPassword => "password.forgot",
+            AuditAction::ResetPassword => "password.reset",
+            AuditAction::ChangePassword => "password.change",
+            AuditAction::ChangeEmail => "email.change",
+            AuditAction::ConfirmEmailChange => "email.change_confirm",
+            AuditAction::UndoEmailChange => "email.change_undo",
+            AuditAction::EnableTwoFA => "2fa.enable",
+            AuditAction::ConfirmEnableTwoFA => "2fa.enable_confirm",
+            AuditAction::DisableTwoFA => "2fa.disable",
+            AuditAction::EnrollTotp => "totp.enroll",
+            AuditAction::ConfirmTotp => "totp.confirm",
+            AuditAction::RegenerateRecoveryCodes => "recovery_codes.regenerate",
+            AuditAction::DeleteAccount => "account.delete",
+            AuditAction::CreateApiKey => "api_key.create",
+            AuditAction::RotateApiKey => "api_key.rotate",
+            AuditAction::RevokeApiKey => "api_key.revoke",
+            AuditAction::AdminLockUser => "admin.user.lock",
+            AuditAction::AdminUnlockUser => "admin.user.unlock",
+            AuditAction::AdminForcePasswordReset => "admin.user.force_password_reset",
+            AuditAction::AdminForceTwoFA => "admin.user.force_2fa",
+            AuditAction::AdminRevokeTokens => "admin.user.revoke_tokens",
+            AuditAction::AdminAddRole => "admin.user.add_role",
+            AuditAction::AdminRemoveRole => "admin.user.remove_role",
+            AuditAction::AdminRevokeApiKey => "admin.api_key.revoke",
+            AuditAction::AdminRotateApiKey => "admin.api_key.rotate",
+            AuditAction::AdminGrantPermission => "admin.role.grant_permission",
+            AuditAction::AdminRevokePermission => "admin.role.revoke_permission",
+            AuditAction::AdminCreateOAuthClient => "admin.oauth_client.create",
+        }
+    }
+}
+
+// Something done through the API. The actor is who did it, when known: the user or client the
+// request authenticated as, or the account a login or signup was attempted for. The target is
+// the account an admin acted on. The action is an `AuditAction`, or the route, e.g.
+// `GET /sessions`, for requests whose handler names none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEvent {
+    pub actor: Option<String>,
+    pub target: Option<String>,
+    pub action: String,
+    pub outcome: AuditOutcome,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: i64,
+}
+
+impl AuditEvent {
+    // HMAC-SHA256 of the event and the hash of the event before it, hex-encoded. Each hash
+    // covering the one before means an event can't be altered, removed or reordered without
+    // breaking every later link of the chain, and the key, which isn't kept in the database,
+    // means the links can't be recomputed by whoever can write to it.
+    pub fn chain_hash(&self, previous_hash: &str, key: &Secret<String>) -> String {
+        // Serialised as a JSON array so no two different events share an input.
+        let input = serde_json::json!([
+            previous_hash,
+            self.actor,
+            self.target,
+            self.action,
+            self.outcome.as_str(),
+            self.ip,
+            self.user_agent,
+            self.request_id,
+            self.created_at,
+        ]);
+        let key = hmac::Key::new(hmac::HMAC_SHA256, key.expose_secret().as_bytes());
+        HEXLOWER.encode(hmac::sign(&key, input.to_string().as_bytes()).as_ref())
+    }
+}
+
+// An event as stored, at its place in the chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    pub id: i64,
+    pub event: AuditEvent,
+    pub previous_hash: String,
+    pub hash: String,
+}
+
+// The newest event of the log, as recorded outside the database. Events removed from the end of
+// the log leave no broken link, so the chain is also expected to reach the head.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditHead {
+    pub id: i64,
+    pub hash: String,
+}
+
+impl From<&AuditRecord> for AuditHead {
+    fn from(record: &AuditRecord) -> Self {
+        Self { id: record.id, hash: record.hash.clone() }
+    }
+}
+
+// Walks the chain one record at a time, in log order, so the log can be checked a page at a
+// time rather than loaded whole.
+pub struct AuditChainVerifier {
+    key: Secret<String>,
+    head: Option<AuditHead>,
+    previous_hash: String,
+    last_id: i64,
+}
+
+impl AuditChainVerifier {
+    pub fn new(key: Secret<String>, head: Option<AuditHead>) -> Self {
+        Self { key, head, previous_hash: AUDIT_CHAIN_START.to_owned(), last_id: 0 }
+    }
+
+    // Whether the record follows from the ones checked before it.
+    pub fn check(&mut self, record: &AuditRecord) -> bool {
+        if record.previous_hash != self.previous_hash || record.hash != record.event.chain_hash(&self.previous_hash, &self.key) {
+            return false;
+        }
+        if self.head.as_ref().is_some_and(|head| head.id == record.id && head.hash != record.hash) {
+            return false;
+        }
+        self.previous_hash.clone_from(&record.hash);
+        self.last_id = record.id;
+        true
+    }
+
+    // Once the whole log has been checked, the id from which events are missing at its end.
+    pub fn missing_from(&self) -> Option<i64> {
+        self.head
+            .as_ref()
+            .filter(|head| self.last_id < head.id)
+            .map(|_| self.last_id + 1)
+    }
+}
+
+// Returns the id of the first record, in log order, that doesn't follow from the ones before it,
+// or where events are missing if the log stops short of the head.
+pub fn find_broken_link(records: &[AuditRecord], key: &Secret<String>, head: Option<AuditHead>) -> Option<i64> {
+    let mut verifier = AuditChainVerifier::new(key.clone(), head);
+    records
+        .iter()
+        .find(|record| !verifier.check(record))
+        .map(|record| record.id)
+        .or_else(|| verifier.missing_from())
+}
+
+// Which events to return. The user matches both actor and target; times are inclusive.
+#[derive(Debug, Clone, Default)]
+pub struct AuditEventFilter {
+    pub user: Option<String>,
+    pub action: Option<String>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+impl AuditEventFilter {
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        let user_matches = self.user.as_ref().is_none_or(|user| {
+            event.actor.as_ref() == Some(user) || event.target.as_ref() == Some(user)
+        });
+        user_matches
+            && self.action.as_ref().is_none_or(|action| &event.action == action)
+            && self.from.is_none_or(|from| event.created_at >= from)
+            && self.to.is_none_or(|to| event.created_at <= to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(action: &str) -> AuditEvent {
+        AuditEvent {
+            actor: Some("user@example.com".to_owned()),
+            target: None,
+            action: action.to_owned(),
+            outcome: AuditOutcome::Success,
+            ip: Some("127.0.0.1".to_owned()),
+            user_agent: None,
+            request_id: Some("request".to_owned()),
+            created_at: 1000,
+        }
+    }
+
+    fn key() -> Secret<String> {
+        Secret::new("secret".to_owned())
+    }
+
+    fn chain(events: Vec<AuditEvent>) -> Vec<AuditRecord> {
+        let mut previous_hash = AUDIT_CHAIN_START.to_owned();
+        events
+            .into_iter()
+            .enumerate()
+            .map(|(i, event)| {
+                let hash = event.chain_hash(&previous_hash, &key());
+                let record = AuditRecord { id: i as i64 + 1, event, previous_hash: previous_hash.clone(), hash };
+                previous_hash = record.hash.clone();
+                record
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_intact_chain_has_no_broken_link() {
+        let records = chain(vec![event("signup"), event("login"), event("logout")]);
+        assert_eq!(find_broken_link(&records, &key(), None), None);
+        assert_eq!(find_broken_link(&records, &key(), Some(AuditHead::from(&records[2]))), None);
+        assert_eq!(find_broken_link(&[], &key(), None), None);
+    }
+
+    #[test]
+    fn test_finds_altered_event() {
+        let mut records = chain(vec![event("signup"), event("login"), event("logout")]);
+        records[1].event.outcome = AuditOutcome::Failure;
+        assert_eq!(find_broken_link(&records, &key(), None), Some(2));
+    }
+
+    #[test]
+    fn test_finds_removed_event() {
+        let mut records = chain(vec![event("signup"), event("login"), event("logout")]);
+        records.remove(1);
+        assert_eq!(find_broken_link(&records, &key(), None), Some(3));
+
+        // Rehashing an altered event doesn't help, as the next one still points at the old hash.
+        let mut records = chain(vec![event("signup"), event("login"), event("logout")]);
+        records[0].event.actor = None;
+        records[0].hash = records[0].event.chain_hash(AUDIT_CHAIN_START, &key());
+        assert_eq!(find_broken_link(&records, &key(), None), Some(2));
+    }
+
+    #[test]
+    fn test_finds_events_removed_from_end() {
+        let mut records = chain(vec![event("signup"), event("login"), event("logout")]);
+        let head = AuditHead::from(&records[2]);
+        records.truncate(1);
+        assert_eq!(find_broken_link(&records, &key(), Some(head.clone())), Some(2));
+        assert_eq!(find_broken_link(&[], &key(), Some(head)), Some(1));
+    }
+
+    #[test]
+    fn test_rejects_chain_hashed_with_another_key() {
+        let mut records = chain(vec![event("signup"), event("login")]);
+        records[1].event.outcome = AuditOutcome::Failure;
+        records[1].hash = records[1].event.chain_hash(&records[0].hash, &Secret::new("guess".to_owned()));
+        assert_eq!(find_broken_link(&records, &key(), None), Some(2));
+    }
+
+    #[test]
+    fn test_filter_matches_user_as_actor_or_target() {
+        let mut admin_action = event("admin.user.lock");
+        admin_action.actor = Some("admin@example.com".to_owned());
+        admin_action.target = Some("user@example.com".to_owned());
+
+        let filter = AuditEventFilter { user: Some("user@example.com".to_owned()), ..Default::default() };
+        assert!(filter.matches(&event("login")));
+        assert!(filter.matches(&admin_action));
+
+        let filter = AuditEventFilter { user: Some("other@example.com".to_owned()), ..Default::default() };
+        assert!(!filter.matches(&admin_action));
+    }
+
+    #[test]
+    fn test_filter_matches_action_and_time_range() {
+        let filter = AuditEventFilter {
+            action: Some("login".to_owned()),
+            from: Some(1000),
+            to: Some(2000),
+            ..Default::default()
+        };
+        assert!(filter.matches(&event("login")));
+        assert!(!filter.matches(&event("logout")));
+
+        let mut late = event("login");
+        late.created_at = 2001;
+        assert!(!filter.matches(&late));
+    }
+}
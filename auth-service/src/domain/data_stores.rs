@@ -2,9 +2,14 @@ use crate::domain::user::User;
 use async_trait::async_trait;
 use std::error::Error;
 use std::fmt;
-use crate::domain::{LoginAttempt, Email, Password};
+use std::str::FromStr;
+use crate::domain::{
+    Authentication, LoginAttempt, Email, Password, PreviousEmail, TotpSecret, TwoFAMethod, UserAccount,
+    api_key::{ApiKey, ApiKeySecret},
+    audit::{AuditEvent, AuditEventFilter, AuditHead, AuditRecord},
+    oauth::{AuthorizationCode, AuthorizationCodeGrant, OAuthClient},
+};
 use color_eyre::eyre::{eyre, Context, Result, Report};
-use rand::Rng;
 use thiserror::Error;
 use secrecy::{Secret, ExposeSecret};
 
@@ -18,6 +23,18 @@ pub enum BannedTokenStoreError {
 pub trait BannedTokenStore: Send + Sync {
     async fn add_token(&mut self, token: Secret<String>) -> Result<(), BannedTokenStoreError>;
     async fn contains_token(&self, token: &Secret<String>) -> Result<bool, BannedTokenStoreError>;
+    async fn revoke_tokens_issued_before(
+        &mut self,
+        email: &Email,
+        timestamp: i64,
+    ) -> Result<(), BannedTokenStoreError>;
+    async fn get_tokens_revoked_before(
+        &self,
+        email: &Email,
+    ) -> Result<Option<i64>, BannedTokenStoreError>;
+    // Bans every token carrying `session_id` as its `jti`.
+    async fn revoke_session(&mut self, session_id: &str) -> Result<(), BannedTokenStoreError>;
+    async fn is_session_revoked(&self, session_id: &str) -> Result<bool, BannedTokenStoreError>;
 }
 
 #[derive(Debug)]
@@ -63,12 +80,38 @@ pub trait TwoFACodeStore: Send + Sync {
         &self,
         email: &Email,
     ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError>;
+
+    // Counts a wrong code against the login attempt. Once `max_attempts` is reached the code is
+    // removed and `TooManyAttempts` is returned for every later attempt, so the user has to log
+    // in again.
+    async fn record_failed_attempt(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        max_attempts: u32,
+    ) -> Result<(), TwoFACodeStoreError>;
+
+    // Records that the pending code is being sent again. Fails with `ResendCooldown` if the code
+    // was last sent, by `add_code` or a resend, less than `cooldown_seconds` ago and with
+    // `TooManyResends` once `max_resends` resends have been made for the login attempt.
+    async fn record_resend(
+        &mut self,
+        email: &Email,
+        cooldown_seconds: i64,
+        max_resends: u32,
+    ) -> Result<(), TwoFACodeStoreError>;
 }
 
 #[derive(Debug, Error)]
 pub enum TwoFACodeStoreError {
     #[error("Login Attempt ID not found")]
     LoginAttemptIdNotFound,
+    #[error("Too many failed attempts")]
+    TooManyAttempts,
+    #[error("Code was resent too recently")]
+    ResendCooldown { retry_after_seconds: u64 },
+    #[error("Too many resends")]
+    TooManyResends,
     #[error("Unexpected error")]
     UnexpectedError(#[source] Report),
 }
@@ -78,6 +121,9 @@ impl PartialEq for TwoFACodeStoreError {
         matches!(
             (self, other),
             (Self::LoginAttemptIdNotFound, Self::LoginAttemptIdNotFound)
+                | (Self::TooManyAttempts, Self::TooManyAttempts)
+                | (Self::ResendCooldown { .. }, Self::ResendCooldown { .. })
+                | (Self::TooManyResends, Self::TooManyResends)
                 | (Self::UnexpectedError(_), Self::UnexpectedError(_))
         )
     }
@@ -122,8 +168,8 @@ impl PartialEq for TwoFACode {
 
 impl TwoFACode {
     pub fn parse(code: String) -> Result<Self> {
-        let code_as_u32 = code.parse::<u32>().wrap_err("Invalid 2FA code")?;
-        if (100_000..=999_999).contains(&code_as_u32) {
+        // Codes may start with a zero, both the ones we generate and TOTP codes.
+        if code.len() == 6 && code.chars().all(|c| c.is_ascii_digit()) {
             Ok(Self(Secret::new(code)))
         } else {
             Err(eyre!("Invalid 2FA code"))
@@ -146,6 +192,291 @@ impl AsRef<Secret<String>> for TwoFACode {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct RecoveryCode(Secret<String>);
+
+// Lowercase letters and digits, minus the ones that are easy to confuse when written down.
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+const RECOVERY_CODE_GROUP_LENGTH: usize = 5;
+
+impl PartialEq for RecoveryCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.expose_secret() == other.0.expose_secret()
+    }
+}
+
+impl RecoveryCode {
+    pub fn parse(code: String) -> Result<Self> {
+        let code = code.trim().to_lowercase();
+        let is_valid = match code.split_once('-') {
+            Some((first, second)) => [first, second].iter().all(|group| {
+                group.len() == RECOVERY_CODE_GROUP_LENGTH
+                    && group.bytes().all(|c| RECOVERY_CODE_ALPHABET.contains(&c))
+            }),
+            None => false,
+        };
+
+        if is_valid {
+            Ok(Self(Secret::new(code)))
+        } else {
+            Err(eyre!("Invalid recovery code"))
+        }
+    }
+}
+
+impl Default for RecoveryCode {
+    fn default() -> Self {
+        use rand::Rng;
+        let mut rng = rand::thread_rng();
+        let mut group = || -> String {
+            (0..RECOVERY_CODE_GROUP_LENGTH)
+                .map(|_| RECOVERY_CODE_ALPHABET[rng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+                .collect()
+        };
+        let code = format!("{}-{}", group(), group());
+        Self(Secret::new(code))
+    }
+}
+
+impl AsRef<Secret<String>> for RecoveryCode {
+    fn as_ref(&self) -> &Secret<String> {
+        &self.0
+    }
+}
+
+#[async_trait]
+pub trait PasswordResetTokenStore: Send + Sync {
+    async fn add_token(
+        &mut self,
+        token: PasswordResetToken,
+        email: Email,
+    ) -> Result<(), PasswordResetTokenStoreError>;
+
+    async fn consume_token(
+        &mut self,
+        token: &PasswordResetToken,
+    ) -> Result<Email, PasswordResetTokenStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum PasswordResetTokenStoreError {
+    #[error("Password reset token not found")]
+    TokenNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for PasswordResetTokenStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::TokenNotFound, Self::TokenNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PasswordResetToken(Secret<String>);
+
+impl PartialEq for PasswordResetToken {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.expose_secret() == other.0.expose_secret()
+    }
+}
+
+impl PasswordResetToken {
+    pub fn parse(token: String) -> Result<Self> {
+        let parsed_token = uuid::Uuid::parse_str(&token).wrap_err("Invalid password reset token")?;
+        Ok(Self(Secret::new(parsed_token.to_string())))
+    }
+}
+
+impl Default for PasswordResetToken {
+    fn default() -> Self {
+        Self(Secret::new(uuid::Uuid::new_v4().to_string()))
+    }
+}
+
+impl AsRef<Secret<String>> for PasswordResetToken {
+    fn as_ref(&self) -> &Secret<String> {
+        &self.0
+    }
+}
+
+#[async_trait]
+pub trait RefreshTokenStore: Send + Sync {
+    // Starts a new token family, i.e. a new login session.
+    async fn add_token(
+        &mut self,
+        token: RefreshToken,
+        email: Email,
+        authentication: Authentication,
+    ) -> Result<(), RefreshTokenStoreError>;
+
+    // Replaces `token` with `new_token` in the same family. Presenting a token that has
+    // already been rotated revokes the whole family and returns `TokenReused`.
+    async fn rotate_token(
+        &mut self,
+        token: &RefreshToken,
+        new_token: RefreshToken,
+    ) -> Result<(Email, Authentication), RefreshTokenStoreError>;
+
+    // Revokes the family `token` belongs to.
+    async fn revoke_token(&mut self, token: &RefreshToken) -> Result<(), RefreshTokenStoreError>;
+
+    // Revokes every family issued to the user.
+    async fn revoke_user_tokens(&mut self, email: &Email) -> Result<(), RefreshTokenStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum RefreshTokenStoreError {
+    #[error("Refresh token not found")]
+    TokenNotFound,
+    #[error("Refresh token reused")]
+    TokenReused,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for RefreshTokenStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::TokenNotFound, Self::TokenNotFound)
+                | (Self::TokenReused, Self::TokenReused)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RefreshToken(Secret<String>);
+
+impl PartialEq for RefreshToken {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.expose_secret() == other.0.expose_secret()
+    }
+}
+
+impl RefreshToken {
+    pub fn parse(token: String) -> Result<Self> {
+        let parsed_token = uuid::Uuid::parse_str(&token).wrap_err("Invalid refresh token")?;
+        Ok(Self(Secret::new(parsed_token.to_string())))
+    }
+}
+
+impl Default for RefreshToken {
+    fn default() -> Self {
+        Self(Secret::new(uuid::Uuid::new_v4().to_string()))
+    }
+}
+
+impl AsRef<Secret<String>> for RefreshToken {
+    fn as_ref(&self) -> &Secret<String> {
+        &self.0
+    }
+}
+
+// A login, as shown to the user so they can recognise and end it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub id: String,
+    // Unix time in milliseconds, so that logins in quick succession are still ordered.
+    pub created_at: i64,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    async fn add_session(&mut self, email: &Email, session: Session) -> Result<(), SessionStoreError>;
+
+    // The user's sessions, oldest first.
+    async fn get_sessions(&self, email: &Email) -> Result<Vec<Session>, SessionStoreError>;
+
+    // Sessions expire along with their refresh tokens, so each refresh keeps the session alive
+    // for as long as the new refresh token is valid.
+    async fn extend_session(&mut self, email: &Email, session_id: &str) -> Result<(), SessionStoreError>;
+
+    async fn remove_session(&mut self, email: &Email, session_id: &str) -> Result<(), SessionStoreError>;
+
+    async fn remove_all_sessions(&mut self, email: &Email) -> Result<(), SessionStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum SessionStoreError {
+    #[error("Session not found")]
+    SessionNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for SessionStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::SessionNotFound, Self::SessionNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    // Counts a request against `key`, unless `limit` has already been reached within the window.
+    async fn check_and_record(
+        &mut self,
+        key: &str,
+        limit: &RateLimit,
+    ) -> Result<RateLimitDecision, RateLimitStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum RateLimitStoreError {
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    pub max_requests: u32,
+    pub window_seconds: i64,
+}
+
+// Parses `<max requests>/<window seconds>`, e.g. `30/60` for 30 requests a minute.
+impl FromStr for RateLimit {
+    type Err = Report;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let (max_requests, window_seconds) = value
+            .split_once('/')
+            .ok_or_else(|| eyre!("expected <max requests>/<window seconds>"))?;
+        let max_requests = max_requests.trim().parse().wrap_err("invalid max requests")?;
+        let window_seconds: i64 = window_seconds.trim().parse().wrap_err("invalid window")?;
+        if window_seconds <= 0 {
+            return Err(eyre!("window must be positive"));
+        }
+
+        Ok(Self { max_requests, window_seconds })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RateLimitDecision {
+    Allowed,
+    Limited { retry_after_seconds: u64 },
+}
+
+impl RateLimitDecision {
+    // Rounds up so that clients retrying after the advertised delay are let through.
+    pub fn limited_for_millis(retry_after_millis: i64) -> Self {
+        Self::Limited {
+            retry_after_seconds: ((retry_after_millis + 999) / 1000).max(1) as u64,
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum UserStoreError {
     #[error("User already exists")]
@@ -175,4 +506,213 @@ pub trait UserStore: Send + Sync {
     async fn add_user(&mut self, user: User) -> Result<(), UserStoreError>;
     async fn get_user(&self, email: &str) -> Result<User, UserStoreError>;
     async fn validate_user(&self, email: &str, password: &str) -> Result<(), UserStoreError>;
-}
\ No newline at end of file
+    async fn update_password(&mut self, email: &str, password: Password) -> Result<(), UserStoreError>;
+    // Moves the user, with everything linked to them, to `new_email`, which counts as verified.
+    // The old address is remembered as the user's previous one, changed at `timestamp`.
+    async fn update_email(&mut self, email: &str, new_email: Email, timestamp: i64) -> Result<(), UserStoreError>;
+    async fn get_previous_email(&self, email: &str) -> Result<Option<PreviousEmail>, UserStoreError>;
+    // Whether some user's email was changed from `email` at or after `timestamp`.
+    async fn is_previous_email_since(&self, email: &str, timestamp: i64) -> Result<bool, UserStoreError>;
+    async fn mark_email_verified(&mut self, email: &str) -> Result<(), UserStoreError>;
+    async fn set_two_fa_method(&mut self, email: &str, method: TwoFAMethod) -> Result<(), UserStoreError>;
+    // Turns 2FA off, discarding the user's TOTP secrets and recovery codes.
+    async fn disable_two_fa(&mut self, email: &str) -> Result<(), UserStoreError>;
+    async fn set_pending_totp_secret(&mut self, email: &str, secret: TotpSecret) -> Result<(), UserStoreError>;
+    async fn get_pending_totp_secret(&self, email: &str) -> Result<Option<TotpSecret>, UserStoreError>;
+    // Records that the user's TOTP code for time step `counter` was used. Returns
+    // `InvalidCredentials` if a code for this or a later step already was, so codes can't be replayed.
+    async fn use_totp_counter(&mut self, email: &str, counter: i64) -> Result<(), UserStoreError>;
+    // Replaces any existing recovery codes for the user.
+    async fn set_recovery_codes(&mut self, email: &str, codes: Vec<RecoveryCode>) -> Result<(), UserStoreError>;
+    // Returns the id of the user's unused recovery code matching `code`, otherwise `InvalidCredentials`.
+    // Checking codes is slow, so this doesn't need the store to be held for writing.
+    async fn find_recovery_code(&self, email: &str, code: &RecoveryCode) -> Result<i64, UserStoreError>;
+    // Consumes a code found with `find_recovery_code`. Returns `InvalidCredentials` if it already was.
+    async fn remove_recovery_code(&mut self, email: &str, id: i64) -> Result<(), UserStoreError>;
+    // Finds the user an external identity provider account (`issuer`, `subject`) is linked to.
+    async fn get_federated_user(&self, issuer: &str, subject: &str) -> Result<User, UserStoreError>;
+    async fn link_federated_identity(&mut self, email: &str, issuer: &str, subject: &str) -> Result<(), UserStoreError>;
+    // The user's ID, which unlike their email address never changes. It is how other parties,
+    // such as OpenID Connect clients, know the user.
+    async fn get_user_id(&self, email: &str) -> Result<String, UserStoreError>;
+    // Tokens carry the version current when they were issued; incrementing it invalidates
+    // every token issued to the user so far.
+    async fn get_token_version(&self, email: &str) -> Result<i64, UserStoreError>;
+    async fn increment_token_version(&mut self, email: &str) -> Result<i64, UserStoreError>;
+    // Soft deletes the user. The account is kept, and can be restored, until it is purged.
+    async fn mark_user_deleted(&mut self, email: &str, timestamp: i64) -> Result<(), UserStoreError>;
+    // Cancels a pending deletion; returns whether there was one.
+    async fn restore_user(&mut self, email: &str) -> Result<bool, UserStoreError>;
+    async fn get_users_deleted_before(&self, timestamp: i64) -> Result<Vec<Email>, UserStoreError>;
+    // Permanently removes the user along with their recovery codes and linked identities, but
+    // only if they were marked deleted before `deleted_before`; otherwise, say because the
+    // deletion was cancelled in the meantime, returns `UserNotFound`.
+    async fn delete_user(&mut self, email: &str, deleted_before: i64) -> Result<(), UserStoreError>;
+    // Pages through users in email order, optionally only those whose email contains `search`,
+    // ignoring case.
+    async fn list_users(&self, search: Option<&str>, offset: i64, limit: i64) -> Result<Vec<UserAccount>, UserStoreError>;
+    async fn count_users(&self, search: Option<&str>) -> Result<i64, UserStoreError>;
+    async fn get_user_account(&self, email: &str) -> Result<UserAccount, UserStoreError>;
+    // Locked users can't log in.
+    async fn set_user_locked(&mut self, email: &str, locked: bool) -> Result<(), UserStoreError>;
+    async fn is_user_locked(&self, email: &str) -> Result<bool, UserStoreError>;
+    async fn get_roles(&self, email: &str) -> Result<Vec<String>, UserStoreError>;
+    async fn add_role(&mut self, email: &str, role: &str) -> Result<(), UserStoreError>;
+    async fn remove_role(&mut self, email: &str, role: &str) -> Result<(), UserStoreError>;
+    async fn get_users_with_role(&self, role: &str) -> Result<Vec<Email>, UserStoreError>;
+    // The permissions granted to any of the user's roles, each listed once.
+    async fn get_permissions(&self, email: &str) -> Result<Vec<String>, UserStoreError>;
+    async fn get_role_permissions(&self, role: &str) -> Result<Vec<String>, UserStoreError>;
+    async fn grant_permission(&mut self, role: &str, permission: &str) -> Result<(), UserStoreError>;
+    async fn revoke_permission(&mut self, role: &str, permission: &str) -> Result<(), UserStoreError>;
+}
+
+#[async_trait]
+pub trait OAuthClientStore: Send + Sync {
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError>;
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError>;
+    async fn validate_client_secret(
+        &self,
+        client_id: &str,
+        client_secret: &Secret<String>,
+    ) -> Result<(), OAuthClientStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum OAuthClientStoreError {
+    #[error("Client already exists")]
+    ClientAlreadyExists,
+    #[error("Client not found")]
+    ClientNotFound,
+    #[error("Invalid client credentials")]
+    InvalidCredentials,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for OAuthClientStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::ClientAlreadyExists, Self::ClientAlreadyExists)
+                | (Self::ClientNotFound, Self::ClientNotFound)
+                | (Self::InvalidCredentials, Self::InvalidCredentials)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[async_trait]
+pub trait AuthorizationCodeStore: Send + Sync {
+    async fn add_code(
+        &mut self,
+        code: AuthorizationCode,
+        grant: AuthorizationCodeGrant,
+    ) -> Result<(), AuthorizationCodeStoreError>;
+
+    // Codes are single use: a code is removed as soon as it is looked up.
+    async fn consume_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationCodeGrant, AuthorizationCodeStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum AuthorizationCodeStoreError {
+    #[error("Authorization code not found")]
+    CodeNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for AuthorizationCodeStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::CodeNotFound, Self::CodeNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[async_trait]
+pub trait ApiKeyStore: Send + Sync {
+    async fn add_key(&mut self, key: ApiKey, secret: &ApiKeySecret) -> Result<(), ApiKeyStoreError>;
+    async fn get_keys(&self, email: &Email) -> Result<Vec<ApiKey>, ApiKeyStoreError>;
+    // Gives the user's key a new secret; the old one stops working.
+    async fn rotate_key(&mut self, email: &Email, id: &str, secret: &ApiKeySecret) -> Result<ApiKey, ApiKeyStoreError>;
+    async fn revoke_key(&mut self, email: &Email, id: &str) -> Result<(), ApiKeyStoreError>;
+    async fn revoke_user_keys(&mut self, email: &Email) -> Result<(), ApiKeyStoreError>;
+    // Returns the unexpired key `secret` belongs to, recording that it was used at `timestamp`.
+    async fn use_key(&mut self, secret: &ApiKeySecret, timestamp: i64) -> Result<ApiKey, ApiKeyStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum ApiKeyStoreError {
+    #[error("API key not found")]
+    KeyNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for ApiKeyStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::KeyNotFound, Self::KeyNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[async_trait]
+pub trait AuditLogStore: Send + Sync {
+    // Adds the event to the end of the log, chained to the last one.
+    async fn append_event(&self, event: AuditEvent) -> Result<AuditRecord, AuditLogStoreError>;
+    // Pages through the events matching `filter`, newest first.
+    async fn get_events(&self, filter: &AuditEventFilter, offset: i64, limit: i64) -> Result<Vec<AuditRecord>, AuditLogStoreError>;
+    async fn count_events(&self, filter: &AuditEventFilter) -> Result<i64, AuditLogStoreError>;
+    // Up to `limit` events after the one with id `after_id`, oldest first, to check the chain.
+    async fn get_chain_page(&self, after_id: i64, limit: i64) -> Result<Vec<AuditRecord>, AuditLogStoreError>;
+}
+
+// Keeps the head of the audit log apart from the log itself.
+#[async_trait]
+pub trait AuditHeadStore: Send + Sync {
+    // Records the event as the head, unless a later one already is.
+    async fn advance_head(&self, head: AuditHead) -> Result<(), AuditLogStoreError>;
+    async fn get_head(&self) -> Result<Option<AuditHead>, AuditLogStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum AuditLogStoreError {
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for AuditLogStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate_limit() {
+        let limit: RateLimit = "30/60".parse().unwrap();
+        assert_eq!(limit, RateLimit { max_requests: 30, window_seconds: 60 });
+    }
+
+    #[test]
+    fn test_parse_invalid_rate_limit() {
+        assert!("30".parse::<RateLimit>().is_err());
+        assert!("thirty/60".parse::<RateLimit>().is_err());
+        assert!("30/0".parse::<RateLimit>().is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -20,6 +20,35 @@ pub enum AuthAPIError {
     MissingToken,
     #[error("Invalid token")]
     InvalidToken,
+    #[error("Email not verified")]
+    EmailNotVerified,
+    #[error("Too many 2FA attempts")]
+    TooMany2FAAttempts,
+    #[error("Too many 2FA resends")]
+    TooMany2FAResends,
+    // Number of seconds the client should wait before retrying.
+    #[error("Too many requests")]
+    TooManyRequests(u64),
+    #[error("Session not found")]
+    SessionNotFound,
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Account locked")]
+    AccountLocked,
+    #[error("Forbidden")]
+    Forbidden,
+    #[error("API key not found")]
+    ApiKeyNotFound,
+    #[error("OAuth client already exists")]
+    OAuthClientAlreadyExists,
+    #[error("Email change can still be undone")]
+    EmailChangeUndoPending,
+    #[error("Federated login is not configured")]
+    FederatedLoginNotConfigured,
+    #[error("Federated login failed")]
+    FederatedLoginFailed(#[source] Report),
+    #[error("Federated identity is not linked")]
+    FederatedIdentityNotLinked,
     #[error("Unexpected error")]
     UnexpectedError(#[source] Report),
 }
@@ -32,6 +61,18 @@ pub struct ErrorResponse {
 impl IntoResponse for AuthAPIError {
     fn into_response(self) -> Response {
         log_error_chain(&self); // New!
+
+        if let AuthAPIError::TooManyRequests(retry_after_seconds) = self {
+            let body = Json(ErrorResponse {
+                error: "Too many requests".to_owned(),
+            });
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after_seconds.to_string())],
+                body,
+            )
+                .into_response();
+        }
         
         let (status, error_message) = match self {
             AuthAPIError::UserAlreadyExists => (StatusCode::CONFLICT, "User already exists"),
@@ -39,6 +80,20 @@ impl IntoResponse for AuthAPIError {
             AuthAPIError::IncorrectCredentials => (StatusCode::UNAUTHORIZED, "Incorrect credentials"),
             AuthAPIError::MissingToken => (StatusCode::BAD_REQUEST, "Missing auth token"),
             AuthAPIError::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid auth token"),
+            AuthAPIError::EmailNotVerified => (StatusCode::FORBIDDEN, "Email not verified"),
+            AuthAPIError::TooMany2FAAttempts => (StatusCode::UNAUTHORIZED, "Too many incorrect 2FA codes, please log in again"),
+            AuthAPIError::TooMany2FAResends => (StatusCode::TOO_MANY_REQUESTS, "Too many 2FA code resends, please log in again"),
+            AuthAPIError::TooManyRequests(_) => (StatusCode::TOO_MANY_REQUESTS, "Too many requests"),
+            AuthAPIError::SessionNotFound => (StatusCode::NOT_FOUND, "Session not found"),
+            AuthAPIError::UserNotFound => (StatusCode::NOT_FOUND, "User not found"),
+            AuthAPIError::AccountLocked => (StatusCode::FORBIDDEN, "Account locked"),
+            AuthAPIError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden"),
+            AuthAPIError::ApiKeyNotFound => (StatusCode::NOT_FOUND, "API key not found"),
+            AuthAPIError::OAuthClientAlreadyExists => (StatusCode::CONFLICT, "OAuth client already exists"),
+            AuthAPIError::EmailChangeUndoPending => (StatusCode::CONFLICT, "The last email change can still be undone, try again later"),
+            AuthAPIError::FederatedLoginNotConfigured => (StatusCode::NOT_FOUND, "Federated login is not configured"),
+            AuthAPIError::FederatedLoginFailed(_) => (StatusCode::UNAUTHORIZED, "Federated login failed"),
+            AuthAPIError::FederatedIdentityNotLinked => (StatusCode::CONFLICT, "An account with this email already exists, log in to link it"),
             AuthAPIError::UnexpectedError(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error"),
         };
 
@@ -50,6 +105,61 @@ impl IntoResponse for AuthAPIError {
     }
 }
 
+// Errors from the OAuth endpoints, in the shape RFC 6749 section 5.2 requires rather than ours.
+#[derive(Debug, Error)]
+pub enum OAuthAPIError {
+    #[error("Invalid request: {0}")]
+    InvalidRequest(&'static str),
+    #[error("Invalid client")]
+    InvalidClient,
+    #[error("Invalid grant")]
+    InvalidGrant,
+    #[error("Unsupported grant type")]
+    UnsupportedGrantType,
+    #[error("Unauthorized client")]
+    UnauthorizedClient,
+    #[error("Invalid scope")]
+    InvalidScope,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+#[derive(Serialize, serde::Deserialize)]
+pub struct OAuthErrorResponse {
+    pub error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_description: Option<String>,
+}
+
+impl IntoResponse for OAuthAPIError {
+    fn into_response(self) -> Response {
+        log_error_chain(&self);
+
+        let (status, error, error_description) = match self {
+            OAuthAPIError::InvalidRequest(description) => {
+                (StatusCode::BAD_REQUEST, "invalid_request", Some(description))
+            }
+            OAuthAPIError::InvalidClient => (StatusCode::UNAUTHORIZED, "invalid_client", None),
+            OAuthAPIError::InvalidGrant => (StatusCode::BAD_REQUEST, "invalid_grant", None),
+            OAuthAPIError::UnsupportedGrantType => {
+                (StatusCode::BAD_REQUEST, "unsupported_grant_type", None)
+            }
+            OAuthAPIError::UnauthorizedClient => (StatusCode::BAD_REQUEST, "unauthorized_client", None),
+            OAuthAPIError::InvalidScope => (StatusCode::BAD_REQUEST, "invalid_scope", None),
+            OAuthAPIError::UnexpectedError(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "server_error", None)
+            }
+        };
+
+        let body = Json(OAuthErrorResponse {
+            error: error.to_owned(),
+            error_description: error_description.map(str::to_owned),
+        });
+
+        (status, [(header::CACHE_CONTROL, "no-store")], body).into_response()
+    }
+}
+
 fn log_error_chain(e: &(dyn Error + 'static)) {
     let separator = "\n-----------------------------------------------------------------------------------\n";
     let mut report = format!("{}{:?}\n", separator, e);
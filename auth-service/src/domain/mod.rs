@@ -7,11 +7,17 @@ pub mod user;
 pub mod error;
 pub mod data_stores;
 pub mod email_client;
+pub mod oauth;
+pub mod api_key;
+pub mod audit;
 
 pub use error::*;
 pub use user::*;
 pub use data_stores::*;
 pub use email_client::*;
+pub use oauth::*;
+pub use api_key::*;
+pub use audit::*;
 
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
@@ -0,0 +1,233 @@
+use color_eyre::eyre::{eyre, Result};
+use data_encoding::BASE64URL_NOPAD;
+use rand::RngCore;
+use ring::digest::{digest, SHA256};
+use secrecy::{ExposeSecret, Secret};
+use super::{Authentication, Email};
+
+// An application that uses the service as its identity provider. Like `User::password`, the
+// secret is given in the clear when registering a client; stores only keep a hash of it.
+// Public clients, such as single-page and mobile apps, have no secret and rely on PKCE alone.
+#[derive(Debug, Clone)]
+pub struct OAuthClient {
+    pub client_id: String,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub client_secret: Option<Secret<String>>,
+    // What a confidential client may be granted when it authenticates as itself, with the
+    // client-credentials grant. Free-form, like the scopes of API keys.
+    pub scopes: Vec<String>,
+}
+
+impl OAuthClient {
+    pub fn new(
+        client_id: String,
+        name: String,
+        redirect_uris: Vec<String>,
+        client_secret: Option<Secret<String>>,
+        scopes: Vec<String>,
+    ) -> Self {
+        Self {
+            client_id,
+            name,
+            redirect_uris,
+            client_secret,
+            scopes,
+        }
+    }
+
+    pub fn is_confidential(&self) -> bool {
+        self.client_secret.is_some()
+    }
+
+    // Redirect URIs are compared exactly, as recommended by the OAuth 2.0 security BCP.
+    pub fn allows_redirect_uri(&self, redirect_uri: &str) -> bool {
+        self.redirect_uris.iter().any(|uri| uri == redirect_uri)
+    }
+
+    // The scopes to grant for a space-separated `scope` request parameter: all of the client's
+    // scopes when none were requested, or `None` if any requested scope isn't one of them.
+    pub fn grant_scopes(&self, scope: Option<&str>) -> Option<Vec<String>> {
+        let Some(scope) = scope else {
+            return Some(self.scopes.clone());
+        };
+
+        let mut scopes: Vec<String> = Vec::new();
+        for requested in scope.split(' ').filter(|scope| !scope.is_empty()) {
+            if !self.scopes.iter().any(|scope| scope == requested) {
+                return None;
+            }
+            if !scopes.iter().any(|scope| scope == requested) {
+                scopes.push(requested.to_owned());
+            }
+        }
+        Some(scopes)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthorizationCode(Secret<String>);
+
+impl PartialEq for AuthorizationCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.expose_secret() == other.0.expose_secret()
+    }
+}
+
+impl AuthorizationCode {
+    pub fn parse(code: String) -> Result<Self> {
+        if !code.is_empty() && BASE64URL_NOPAD.decode(code.as_bytes()).is_ok() {
+            Ok(Self(Secret::new(code)))
+        } else {
+            Err(eyre!("Invalid authorization code"))
+        }
+    }
+}
+
+impl Default for AuthorizationCode {
+    fn default() -> Self {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Self(Secret::new(BASE64URL_NOPAD.encode(&bytes)))
+    }
+}
+
+impl AsRef<Secret<String>> for AuthorizationCode {
+    fn as_ref(&self) -> &Secret<String> {
+        &self.0
+    }
+}
+
+// What an authorization code stands for, checked again when the code is exchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthorizationCodeGrant {
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub email: Email,
+    pub code_challenge: PkceCodeChallenge,
+    pub scope: Option<String>,
+    // OpenID Connect: echoed in the ID token so the client can tie it to its request.
+    pub nonce: Option<String>,
+    pub authentication: Authentication,
+}
+
+impl AuthorizationCodeGrant {
+    // An ID token is only issued when the client asked for the `openid` scope.
+    pub fn is_openid(&self) -> bool {
+        self.scope
+            .as_deref()
+            .is_some_and(|scope| scope.split(' ').any(|scope| scope == "openid"))
+    }
+}
+
+// An S256 PKCE code challenge (RFC 7636). The plain method isn't supported since it offers no
+// protection if the authorization request is observed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PkceCodeChallenge(String);
+
+impl PkceCodeChallenge {
+    pub fn parse(challenge: String) -> Result<Self> {
+        // A base64url encoded SHA-256 digest is always 43 characters long.
+        if challenge.len() == 43 && BASE64URL_NOPAD.decode(challenge.as_bytes()).is_ok() {
+            Ok(Self(challenge))
+        } else {
+            Err(eyre!("Invalid PKCE code challenge"))
+        }
+    }
+
+    // The challenge we send when acting as the client of another provider.
+    pub fn from_verifier(code_verifier: &str) -> Self {
+        Self(BASE64URL_NOPAD.encode(digest(&SHA256, code_verifier.as_bytes()).as_ref()))
+    }
+
+    pub fn verify(&self, code_verifier: &str) -> bool {
+        let is_valid_verifier = (43..=128).contains(&code_verifier.len())
+            && code_verifier
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-._~".contains(c));
+        if !is_valid_verifier {
+            return false;
+        }
+
+        Self::from_verifier(code_verifier) == *self
+    }
+}
+
+impl AsRef<str> for PkceCodeChallenge {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Example from RFC 7636, appendix B.
+    const RFC_7636_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    const RFC_7636_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+    #[test]
+    fn test_pkce_code_challenge_matches_rfc_7636() {
+        let challenge = PkceCodeChallenge::parse(RFC_7636_CHALLENGE.to_owned()).unwrap();
+        assert!(challenge.verify(RFC_7636_VERIFIER));
+    }
+
+    #[test]
+    fn test_pkce_code_challenge_rejects_wrong_verifier() {
+        let challenge = PkceCodeChallenge::parse(RFC_7636_CHALLENGE.to_owned()).unwrap();
+        assert!(!challenge.verify("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXj"));
+        assert!(!challenge.verify("too-short"));
+    }
+
+    #[test]
+    fn test_pkce_code_challenge_from_verifier() {
+        let challenge = PkceCodeChallenge::from_verifier(RFC_7636_VERIFIER);
+        assert_eq!(challenge.as_ref(), RFC_7636_CHALLENGE);
+    }
+
+    #[test]
+    fn test_pkce_code_challenge_parse() {
+        assert!(PkceCodeChallenge::parse("too-short".to_owned()).is_err());
+        assert!(PkceCodeChallenge::parse("!".repeat(43)).is_err());
+    }
+
+    #[test]
+    fn test_redirect_uris_are_matched_exactly() {
+        let client = OAuthClient::new(
+            "client".to_owned(),
+            "Client".to_owned(),
+            vec!["https://app.example.com/callback".to_owned()],
+            None,
+            Vec::new(),
+        );
+
+        assert!(client.allows_redirect_uri("https://app.example.com/callback"));
+        assert!(!client.allows_redirect_uri("https://app.example.com/callback/"));
+        assert!(!client.allows_redirect_uri("https://app.example.com/callback?next=/"));
+    }
+
+    #[test]
+    fn test_grant_scopes() {
+        let client = OAuthClient::new(
+            "service".to_owned(),
+            "Service".to_owned(),
+            Vec::new(),
+            Some(Secret::new("secret".to_owned())),
+            vec!["users:read".to_owned(), "users:write".to_owned()],
+        );
+
+        assert_eq!(client.grant_scopes(None), Some(client.scopes.clone()));
+        assert_eq!(client.grant_scopes(Some("users:read users:read")), Some(vec!["users:read".to_owned()]));
+        assert_eq!(client.grant_scopes(Some("")), Some(Vec::new()));
+        assert_eq!(client.grant_scopes(Some("users:read admin")), None);
+    }
+
+    #[test]
+    fn test_authorization_code_round_trip() {
+        let code = AuthorizationCode::default();
+        let parsed = AuthorizationCode::parse(code.as_ref().expose_secret().clone()).unwrap();
+        assert_eq!(parsed, code);
+        assert!(AuthorizationCode::parse("not a code".to_owned()).is_err());
+    }
+}
@@ -1,18 +1,144 @@
+use chrono::Utc;
+use color_eyre::eyre::{eyre, Result};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
 use crate::domain::{Email, Password};
 
 #[derive(Clone, Debug)]
 pub struct User {
     pub email: Email,
     pub password: Password, 
-    pub requires_2fa: bool,
+    pub two_fa_method: TwoFAMethod,
+    pub email_verified: bool,
 }
 
 impl User {
-    pub fn new(email: Email, password: Password, requires_2fa: bool) -> Self {
+    pub fn new(email: Email, password: Password, two_fa_method: TwoFAMethod) -> Self {
         User {
             email,
             password,
-            requires_2fa,
+            two_fa_method,
+            email_verified: false,
         }
     }
-}
\ No newline at end of file
+}
+
+// A user as the admin API sees them, with the state kept alongside the account.
+#[derive(Clone, Debug)]
+pub struct UserAccount {
+    pub user: User,
+    pub locked: bool,
+    // Unix time the user deleted their account at, if a deletion is pending.
+    pub deleted_at: Option<i64>,
+    pub roles: Vec<String>,
+}
+
+// The address a user's email was last changed from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreviousEmail {
+    pub email: Email,
+    // Unix time the email was changed at.
+    pub changed_at: i64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TwoFAMethod {
+    None,
+    Email,
+    Totp(TotpSecret),
+}
+
+impl TwoFAMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TwoFAMethod::None => "none",
+            TwoFAMethod::Email => "email",
+            TwoFAMethod::Totp(_) => "totp",
+        }
+    }
+}
+
+// When and how a user logged in. It is carried by every token issued for the login, refreshed
+// ones included, and reported in ID tokens as `auth_time` and `amr`. Logins recorded before this
+// existed deserialize to the default, which reads as having authenticated long ago.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Authentication {
+    #[serde(default)]
+    pub auth_time: i64,
+    #[serde(default)]
+    pub methods: Vec<AuthenticationMethod>,
+    // Identifies the session the login started; tokens carry it as their `jti`.
+    #[serde(default)]
+    pub session_id: String,
+    // The user's token version at login, see `UserStore::get_token_version`.
+    #[serde(default)]
+    pub token_version: i64,
+    // The user's roles, and the permissions they grant, as of the last login or refresh.
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+impl Authentication {
+    pub fn now(methods: Vec<AuthenticationMethod>) -> Self {
+        Self {
+            auth_time: Utc::now().timestamp(),
+            methods,
+            session_id: uuid::Uuid::new_v4().to_string(),
+            token_version: 0,
+            roles: Vec::new(),
+            permissions: Vec::new(),
+        }
+    }
+}
+
+// Authentication method reference values from RFC 8176.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthenticationMethod {
+    Pwd,
+    Otp,
+    Mfa,
+}
+
+// Base32 encoded TOTP shared secret, as shown to authenticator apps.
+#[derive(Clone, Debug)]
+pub struct TotpSecret(Secret<String>);
+
+impl PartialEq for TotpSecret {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.expose_secret() == other.0.expose_secret()
+    }
+}
+
+impl TotpSecret {
+    pub fn parse(secret: Secret<String>) -> Result<Self> {
+        match data_encoding::BASE32_NOPAD.decode(secret.expose_secret().as_bytes()) {
+            Ok(bytes) if !bytes.is_empty() => Ok(Self(secret)),
+            _ => Err(eyre!("Invalid TOTP secret")),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        data_encoding::BASE32_NOPAD
+            .decode(self.0.expose_secret().as_bytes())
+            .expect("TOTP secret is valid base32")
+    }
+}
+
+impl Default for TotpSecret {
+    fn default() -> Self {
+        use rand::RngCore;
+        // 160 bits, the key length recommended by RFC 4226 for HMAC-SHA1.
+        let mut bytes = [0u8; 20];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Self(Secret::new(data_encoding::BASE32_NOPAD.encode(&bytes)))
+    }
+}
+
+impl AsRef<Secret<String>> for TotpSecret {
+    fn as_ref(&self) -> &Secret<String> {
+        &self.0
+    }
+}
@@ -1,13 +1,17 @@
 use axum::{
     http::{Method, StatusCode},
-    routing::{get, post},
-    Json, Router,
+    middleware,
+    routing::{delete, get, post},
+    Router,
 };
-use axum::extract::State;
-use axum_extra::extract::CookieJar;
 use tower_http::{cors::CorsLayer, services::ServeDir, trace::TraceLayer};
-use utils::tracing::{make_span_with_request_id, on_request, on_response};
+use utils::{
+    audit::audit,
+    rbac::require_admin,
+    tracing::{make_span_with_request_id, on_request, on_response, set_request_id},
+};
 use secrecy::{Secret, ExposeSecret};
+use std::net::SocketAddr;
 
 use sqlx::{PgPool, postgres::PgPoolOptions};
 use redis::{Client, RedisResult};
@@ -19,7 +23,19 @@ pub mod routes;
 pub mod services;
 
 use app_state::AppState;
-use routes::{signup, login, verify_2fa, logout, verify_token};
+use routes::{
+    signup, login, verify_2fa, resend_2fa, logout, logout_all, verify_token, forgot_password, reset_password, verify_email,
+    resend_verification_email, refresh, enroll_totp, confirm_totp,
+    regenerate_recovery_codes, jwks, authorize, token, introspect, userinfo, openid_configuration,
+    federated_login, federated_login_callback, list_sessions, revoke_session,
+    delete_account, change_password, change_email, confirm_email_change, undo_email_change,
+    enable_2fa, confirm_enable_2fa, disable_2fa,
+    admin_list_users, admin_get_user, admin_lock_user, admin_unlock_user, admin_force_password_reset,
+    admin_force_2fa, admin_revoke_tokens, admin_add_role, admin_remove_role, admin_get_role_permissions,
+    admin_grant_permission, admin_revoke_permission, admin_list_api_keys, admin_rotate_api_key, admin_revoke_api_key,
+    admin_create_oauth_client, admin_list_audit_events, admin_verify_audit_log,
+    create_api_key, list_api_keys, rotate_api_key, revoke_api_key,
+};
 
 pub struct Application {
     pub address: String,
@@ -35,19 +51,81 @@ impl Application {
         ];
 
         let cors = CorsLayer::new()
-            .allow_methods([Method::GET, Method::POST])
+            .allow_methods([Method::GET, Method::POST, Method::DELETE])
             .allow_credentials(true)
             .allow_origin(allowed_origins);
 
-        let router = Router::new()
+        // Every admin route requires the admin role.
+        let admin_router = Router::new()
+            .route("/users", get(admin_list_users))
+            .route("/users/:email", get(admin_get_user))
+            .route("/users/:email/lock", post(admin_lock_user))
+            .route("/users/:email/unlock", post(admin_unlock_user))
+            .route("/users/:email/force-password-reset", post(admin_force_password_reset))
+            .route("/users/:email/force-2fa", post(admin_force_2fa))
+            .route("/users/:email/revoke-tokens", post(admin_revoke_tokens))
+            .route("/users/:email/roles", post(admin_add_role))
+            .route("/users/:email/roles/:role", delete(admin_remove_role))
+            .route("/users/:email/api-keys", get(admin_list_api_keys))
+            .route("/users/:email/api-keys/:id", delete(admin_revoke_api_key))
+            .route("/users/:email/api-keys/:id/rotate", post(admin_rotate_api_key))
+            .route("/roles/:role/permissions", get(admin_get_role_permissions).post(admin_grant_permission))
+            .route("/roles/:role/permissions/:permission", delete(admin_revoke_permission))
+            .route("/oauth-clients", post(admin_create_oauth_client))
+            .route("/audit-events", get(admin_list_audit_events))
+            .route("/audit-events/verify", get(admin_verify_audit_log))
+            .route_layer(middleware::from_fn_with_state(app_state.clone(), require_admin));
+
+        // The pages of the web app, which aren't audited.
+        let page_router = Router::new()
             .nest_service("/assets", ServeDir::new("assets"))
             .route("/", get(serve_login_page))
             .route("/signup", get(serve_login_page))
+            .route("/reset-password", get(serve_login_page))
+            .route("/verify-email", get(serve_login_page))
+            .route("/confirm-email-change", get(serve_login_page))
+            .route("/undo-email-change", get(serve_login_page));
+
+        let router = Router::new()
             .route("/signup", post(signup))
             .route("/login", post(login))
             .route("/verify-2fa", post(verify_2fa))
+            .route("/resend-2fa", post(resend_2fa))
             .route("/logout", post(logout))
+            .route("/logout-all", post(logout_all))
             .route("/verify-token", post(verify_token))
+            .route("/forgot-password", post(forgot_password))
+            .route("/reset-password", post(reset_password))
+            .route("/verify-email", post(verify_email))
+            .route("/resend-verification-email", post(resend_verification_email))
+            .route("/refresh", post(refresh))
+            .route("/enroll-totp", post(enroll_totp))
+            .route("/confirm-totp", post(confirm_totp))
+            .route("/regenerate-recovery-codes", post(regenerate_recovery_codes))
+            .route("/.well-known/jwks.json", get(jwks))
+            .route("/authorize", get(authorize))
+            .route("/token", post(token))
+            .route("/introspect", post(introspect))
+            .route("/userinfo", get(userinfo).post(userinfo))
+            .route("/.well-known/openid-configuration", get(openid_configuration))
+            .route("/federated-login", get(federated_login))
+            .route("/federated-login/callback", get(federated_login_callback))
+            .route("/sessions", get(list_sessions))
+            .route("/sessions/:id", delete(revoke_session))
+            .route("/account", delete(delete_account))
+            .route("/change-password", post(change_password))
+            .route("/change-email", post(change_email))
+            .route("/confirm-email-change", post(confirm_email_change))
+            .route("/undo-email-change", post(undo_email_change))
+            .route("/enable-2fa", post(enable_2fa))
+            .route("/confirm-enable-2fa", post(confirm_enable_2fa))
+            .route("/disable-2fa", post(disable_2fa))
+            .route("/api-keys", get(list_api_keys).post(create_api_key))
+            .route("/api-keys/:id", delete(revoke_api_key))
+            .route("/api-keys/:id/rotate", post(rotate_api_key))
+            .nest("/admin", admin_router)
+            .route_layer(middleware::from_fn_with_state(app_state.clone(), audit))
+            .merge(page_router)
             .with_state(app_state)
             .layer(cors)
             .layer(
@@ -55,13 +133,15 @@ impl Application {
                     .make_span_with(make_span_with_request_id)
                     .on_request(on_request)
                     .on_response(on_response),
-            );
+            )
+            .layer(middleware::from_fn(set_request_id));
 
         let listener = tokio::net::TcpListener::bind(address).await?;
         let actual_address = listener.local_addr()?.to_string();
         
         let server = tokio::spawn(async move {
-            axum::serve(listener, router)
+            // Connection info gives handlers the client address, which rate limiting is keyed on.
+            axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>())
                 .await
                 .expect("Failed to start server");
         });
@@ -94,5 +174,3 @@ pub fn get_redis_client(redis_hostname: String) -> RedisResult<Client> {
     let redis_url = format!("redis://{}/", redis_hostname);
     redis::Client::open(redis_url)
 }
-
-use axum::response::IntoResponse;
\ No newline at end of file
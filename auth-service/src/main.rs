@@ -7,11 +7,30 @@ use auth_service::{
         RedisTwoFACodeStore,
         PostgresUserStore,
         RedisBannedTokenStore,
+        RedisPasswordResetTokenStore,
+        RedisRefreshTokenStore,
+        RedisRateLimitStore,
+        PostgresOAuthClientStore,
+        RedisAuthorizationCodeStore,
+        RedisSessionStore,
+        PostgresApiKeyStore,
+        PostgresAuditLogStore,
+        RedisAuditHeadStore,
+    },
+    services::{OidcIdentityProvider, PostmarkEmailClient}, // CHANGÉ ICI
+    domain::{data_stores::{UserStore, BannedTokenStore, TwoFACodeStore, PasswordResetTokenStore, RefreshTokenStore, RateLimitStore, OAuthClientStore, AuthorizationCodeStore, SessionStore, ApiKeyStore, AuditLogStore, AuditHeadStore}, Email},
+    utils::constants::{
+        prod, ACCOUNT_DELETION_GRACE_PERIOD_SECONDS, ACCOUNT_PURGE_INTERVAL, AUDIT_LOG_SECRET, DATABASE_URL,
+        FEDERATED_LOGIN_CLIENT_ID, FEDERATED_LOGIN_CLIENT_SECRET, FEDERATED_LOGIN_ISSUER,
+        FORGOT_PASSWORD_RATE_LIMIT_PER_EMAIL, FORGOT_PASSWORD_RATE_LIMIT_PER_IP, JWT_KEYRING_REFRESH_INTERVAL,
+        LOGIN_RATE_LIMIT_PER_EMAIL, LOGIN_RATE_LIMIT_PER_IP, MAX_SESSIONS_PER_USER, POSTMARK_AUTH_TOKEN,
+    },
+    utils::{
+        account_purge::spawn_account_purge,
+        init_tracing,
+        keyring::spawn_keyring_refresh,
+        rate_limit::{RateLimitConfig, RouteRateLimits},
     },
-    services::PostmarkEmailClient, // CHANGÉ ICI
-    domain::{data_stores::{UserStore, BannedTokenStore, TwoFACodeStore}, Email},
-    utils::constants::{prod, DATABASE_URL, POSTMARK_AUTH_TOKEN},
-    utils::init_tracing,
 };
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -24,10 +43,20 @@ use secrecy::Secret;
 async fn main() {
     color_eyre::install().expect("Failed to install color_eyre");
     init_tracing();
+    spawn_keyring_refresh(JWT_KEYRING_REFRESH_INTERVAL);
 
     let pg_pool = configure_postgresql().await;
     let redis_conn = configure_redis();
 
+    let oauth_client_store = PostgresOAuthClientStore::new(pg_pool.clone());
+    let boxed_oauth_client_store = Arc::new(RwLock::new(Box::new(oauth_client_store) as Box<dyn OAuthClientStore + Send + Sync>));
+
+    let api_key_store = PostgresApiKeyStore::new(pg_pool.clone());
+    let boxed_api_key_store = Arc::new(RwLock::new(Box::new(api_key_store) as Box<dyn ApiKeyStore + Send + Sync>));
+
+    let audit_log_store = PostgresAuditLogStore::new(pg_pool.clone(), AUDIT_LOG_SECRET.clone());
+    let boxed_audit_log_store = Arc::new(audit_log_store) as Arc<dyn AuditLogStore + Send + Sync>;
+
     let user_store = PostgresUserStore::new(pg_pool);
     let boxed_user_store = Arc::new(RwLock::new(Box::new(user_store) as Box<dyn UserStore + Send + Sync>));
 
@@ -38,14 +67,67 @@ async fn main() {
     let two_fa_code_store = RedisTwoFACodeStore::new(Arc::new(RwLock::new(redis_conn_2fa)));
     let boxed_two_fa_code_store = Arc::new(RwLock::new(Box::new(two_fa_code_store) as Box<dyn TwoFACodeStore + Send + Sync>));
 
+    let redis_conn_2fa_enrollment = configure_redis();
+    let two_fa_enrollment_code_store = RedisTwoFACodeStore::with_namespace(Arc::new(RwLock::new(redis_conn_2fa_enrollment)), "two_fa_enrollment");
+    let boxed_two_fa_enrollment_code_store = Arc::new(RwLock::new(Box::new(two_fa_enrollment_code_store) as Box<dyn TwoFACodeStore + Send + Sync>));
+
+    let redis_conn_password_reset = configure_redis();
+    let password_reset_token_store = RedisPasswordResetTokenStore::new(Arc::new(RwLock::new(redis_conn_password_reset)));
+    let boxed_password_reset_token_store = Arc::new(RwLock::new(Box::new(password_reset_token_store) as Box<dyn PasswordResetTokenStore + Send + Sync>));
+
+    let redis_conn_refresh_token = configure_redis();
+    let refresh_token_store = RedisRefreshTokenStore::new(Arc::new(RwLock::new(redis_conn_refresh_token)));
+    let boxed_refresh_token_store = Arc::new(RwLock::new(Box::new(refresh_token_store) as Box<dyn RefreshTokenStore + Send + Sync>));
+
+    let redis_conn_rate_limit = configure_redis();
+    let rate_limit_store = RedisRateLimitStore::new(Arc::new(RwLock::new(redis_conn_rate_limit)));
+    let boxed_rate_limit_store = Arc::new(RwLock::new(Box::new(rate_limit_store) as Box<dyn RateLimitStore + Send + Sync>));
+
+    let redis_conn_authorization_code = configure_redis();
+    let authorization_code_store = RedisAuthorizationCodeStore::new(Arc::new(RwLock::new(redis_conn_authorization_code)));
+    let boxed_authorization_code_store = Arc::new(RwLock::new(Box::new(authorization_code_store) as Box<dyn AuthorizationCodeStore + Send + Sync>));
+
+    let redis_conn_session = configure_redis();
+    let session_store = RedisSessionStore::new(Arc::new(RwLock::new(redis_conn_session)));
+    let boxed_session_store = Arc::new(RwLock::new(Box::new(session_store) as Box<dyn SessionStore + Send + Sync>));
+
+    let redis_conn_audit_head = configure_redis();
+    let audit_head_store = RedisAuditHeadStore::new(Arc::new(RwLock::new(redis_conn_audit_head)));
+    let boxed_audit_head_store = Arc::new(audit_head_store) as Arc<dyn AuditHeadStore + Send + Sync>;
+
     let email_client = Arc::new(configure_postmark_email_client()); // CHANGÉ ICI
 
-    let app_state = AppState::new(
+    let mut app_state = AppState::new(
         boxed_user_store, 
         boxed_banned_token_store,
         boxed_two_fa_code_store,
+        boxed_two_fa_enrollment_code_store,
+        boxed_password_reset_token_store,
+        boxed_refresh_token_store,
+        boxed_rate_limit_store,
+        boxed_oauth_client_store,
+        boxed_authorization_code_store,
+        boxed_session_store,
+        boxed_api_key_store,
+        boxed_audit_log_store,
+        boxed_audit_head_store,
         email_client,
     );
+    app_state.max_sessions_per_user = *MAX_SESSIONS_PER_USER;
+    app_state.account_deletion_grace_period_seconds = *ACCOUNT_DELETION_GRACE_PERIOD_SECONDS;
+    app_state.rate_limits = RateLimitConfig {
+        login: RouteRateLimits {
+            per_ip: *LOGIN_RATE_LIMIT_PER_IP,
+            per_email: *LOGIN_RATE_LIMIT_PER_EMAIL,
+        },
+        forgot_password: RouteRateLimits {
+            per_ip: *FORGOT_PASSWORD_RATE_LIMIT_PER_IP,
+            per_email: *FORGOT_PASSWORD_RATE_LIMIT_PER_EMAIL,
+        },
+    };
+    app_state.identity_provider = configure_identity_provider().map(Arc::new);
+
+    spawn_account_purge(app_state.clone(), ACCOUNT_PURGE_INTERVAL);
 
     let app = Application::build(app_state, prod::APP_ADDRESS)
         .await
@@ -69,6 +151,26 @@ fn configure_postmark_email_client() -> PostmarkEmailClient {
     )
 }
 
+// Federated login is enabled by setting both the provider's issuer URL and our client id there.
+fn configure_identity_provider() -> Option<OidcIdentityProvider> {
+    let (issuer, client_id) = match (FEDERATED_LOGIN_ISSUER.as_ref(), FEDERATED_LOGIN_CLIENT_ID.as_ref()) {
+        (Some(issuer), Some(client_id)) => (issuer.clone(), client_id.clone()),
+        _ => return None,
+    };
+
+    let http_client = Client::builder()
+        .timeout(prod::identity_provider::TIMEOUT)
+        .build()
+        .expect("Failed to build HTTP client");
+
+    Some(OidcIdentityProvider::new(
+        issuer,
+        client_id,
+        FEDERATED_LOGIN_CLIENT_SECRET.clone(),
+        http_client,
+    ))
+}
+
 async fn configure_postgresql() -> PgPool {
     let pg_pool = get_postgres_pool(&DATABASE_URL)
         .await
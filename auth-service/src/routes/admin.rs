@@ -0,0 +1,669 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use data_encoding::BASE64URL_NOPAD;
+use rand::RngCore;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use crate::{
+    app_state::AppState,
+    domain::{
+        data_stores::{OAuthClientStoreError, UserStoreError},
+        AuditAction, AuditChainVerifier, AuditEventFilter, AuditRecord, AuthAPIError, Email, OAuthClient, Password,
+        TwoFAMethod, UserAccount,
+    },
+    routes::{
+        get_api_keys, revoke_user_api_key, rotate_user_api_key, send_2fa_change_notification,
+        send_password_reset_email, ApiKeySecretResponse, ApiKeysResponse,
+    },
+    utils::{
+        audit::AuditContext,
+        constants::{AUDIT_LOG_SECRET, AUDIT_VERIFY_PAGE_SIZE, DEFAULT_ADMIN_PAGE_SIZE, MAX_ADMIN_PAGE_SIZE},
+        session::revoke_all_tokens,
+    },
+};
+
+#[derive(Deserialize)]
+pub struct ListUsersQuery {
+    pub search: Option<String>,
+    // Pages are numbered from 1.
+    pub page: Option<i64>,
+    #[serde(rename = "perPage")]
+    pub per_page: Option<i64>,
+}
+
+// `user` matches both who acted and whose account was acted on. `from` and `to` are inclusive
+// Unix timestamps.
+#[derive(Deserialize)]
+pub struct ListAuditEventsQuery {
+    pub user: Option<String>,
+    pub action: Option<String>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub page: Option<i64>,
+    #[serde(rename = "perPage")]
+    pub per_page: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct AddRoleRequest {
+    pub role: String,
+}
+
+#[derive(Deserialize)]
+pub struct GrantPermissionRequest {
+    pub permission: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RolePermissionsResponse {
+    pub permissions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateOAuthClientRequest {
+    pub client_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub redirect_uris: Vec<String>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub confidential: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthClientResponse {
+    pub client_id: String,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub scopes: Vec<String>,
+    // Only ever returned when the client is created; stores keep a hash of it.
+    pub client_secret: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminUsersResponse {
+    pub users: Vec<AdminUserResponse>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminUserResponse {
+    pub email: String,
+    pub email_verified: bool,
+    #[serde(rename = "twoFAMethod")]
+    pub two_fa_method: String,
+    pub locked: bool,
+    pub deleted_at: Option<i64>,
+    pub roles: Vec<String>,
+}
+
+impl From<UserAccount> for AdminUserResponse {
+    fn from(account: UserAccount) -> Self {
+        Self {
+            email: account.user.email.as_ref().expose_secret().clone(),
+            email_verified: account.user.email_verified,
+            two_fa_method: account.user.two_fa_method.as_str().to_owned(),
+            locked: account.locked,
+            deleted_at: account.deleted_at,
+            roles: account.roles,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEventsResponse {
+    pub events: Vec<AuditEventResponse>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEventResponse {
+    pub id: i64,
+    pub actor: Option<String>,
+    pub target: Option<String>,
+    pub action: String,
+    pub outcome: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: i64,
+    pub previous_hash: String,
+    pub hash: String,
+}
+
+impl From<AuditRecord> for AuditEventResponse {
+    fn from(record: AuditRecord) -> Self {
+        Self {
+            id: record.id,
+            actor: record.event.actor,
+            target: record.event.target,
+            action: record.event.action,
+            outcome: record.event.outcome.as_str().to_owned(),
+            ip: record.event.ip,
+            user_agent: record.event.user_agent,
+            request_id: record.event.request_id,
+            created_at: record.event.created_at,
+            previous_hash: record.previous_hash,
+            hash: record.hash,
+        }
+    }
+}
+
+// `firstInvalidId` is the first event that doesn't chain to the ones before it; it and
+// everything after it can't be trusted.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogVerificationResponse {
+    pub valid: bool,
+    pub first_invalid_id: Option<i64>,
+}
+
+#[tracing::instrument(name = "Admin list users", skip_all)]
+pub async fn admin_list_users(
+    State(state): State<AppState>,
+    Query(query): Query<ListUsersQuery>,
+) -> Result<(StatusCode, Json<AdminUsersResponse>), AuthAPIError> {
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(DEFAULT_ADMIN_PAGE_SIZE);
+    let offset = page_offset(page, per_page)?;
+    let search = query.search.as_deref().filter(|search| !search.is_empty());
+
+    let user_store = state.user_store.read().await;
+    let users = user_store
+        .list_users(search, offset, per_page)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    let total = user_store
+        .count_users(search)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let response = AdminUsersResponse {
+        users: users.into_iter().map(AdminUserResponse::from).collect(),
+        page,
+        per_page,
+        total,
+    };
+    Ok((StatusCode::OK, Json(response)))
+}
+
+#[tracing::instrument(name = "Admin get user", skip_all)]
+pub async fn admin_get_user(
+    State(state): State<AppState>,
+    Path(email): Path<String>,
+) -> Result<(StatusCode, Json<AdminUserResponse>), AuthAPIError> {
+    let email = parse_email(email)?;
+
+    let account = state
+        .user_store
+        .read()
+        .await
+        .get_user_account(email.as_ref().expose_secret())
+        .await
+        .map_err(map_user_store_error)?;
+
+    Ok((StatusCode::OK, Json(account.into())))
+}
+
+// Locks the user out and logs them out everywhere.
+#[tracing::instrument(name = "Admin lock user", skip_all)]
+pub async fn admin_lock_user(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Path(email): Path<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::AdminLockUser);
+
+    let email = parse_email(email)?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .set_user_locked(email.as_ref().expose_secret(), true)
+        .await
+        .map_err(map_user_store_error)?;
+    revoke_all_tokens(&state, &email).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "Admin unlock user", skip_all)]
+pub async fn admin_unlock_user(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Path(email): Path<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::AdminUnlockUser);
+
+    let email = parse_email(email)?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .set_user_locked(email.as_ref().expose_secret(), false)
+        .await
+        .map_err(map_user_store_error)?;
+
+    Ok(StatusCode::OK)
+}
+
+// Replaces the user's password with a random one nobody knows, logs them out everywhere and
+// emails them a link to choose a new one.
+#[tracing::instrument(name = "Admin force password reset", skip_all)]
+pub async fn admin_force_password_reset(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Path(email): Path<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::AdminForcePasswordReset);
+
+    let email = parse_email(email)?;
+
+    let password = Password::parse(Secret::new(uuid::Uuid::new_v4().to_string()))
+        .map_err(AuthAPIError::UnexpectedError)?;
+    state
+        .user_store
+        .write()
+        .await
+        .update_password(email.as_ref().expose_secret(), password)
+        .await
+        .map_err(map_user_store_error)?;
+    revoke_all_tokens(&state, &email).await?;
+    send_password_reset_email(&state, &email).await?;
+
+    Ok(StatusCode::OK)
+}
+
+// Turns on email 2FA for a user who has no 2FA; users who already have it keep their method.
+#[tracing::instrument(name = "Admin force 2FA", skip_all)]
+pub async fn admin_force_2fa(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Path(email): Path<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::AdminForceTwoFA);
+
+    let email = parse_email(email)?;
+
+    {
+        let mut user_store = state.user_store.write().await;
+        let user = user_store
+            .get_user(email.as_ref().expose_secret())
+            .await
+            .map_err(map_user_store_error)?;
+        if user.two_fa_method != TwoFAMethod::None {
+            return Ok(StatusCode::OK);
+        }
+
+        user_store
+            .set_two_fa_method(email.as_ref().expose_secret(), TwoFAMethod::Email)
+            .await
+            .map_err(map_user_store_error)?;
+    }
+
+    send_2fa_change_notification(&state.email_client, &email, "Two-factor authentication was turned on").await;
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "Admin revoke tokens", skip_all)]
+pub async fn admin_revoke_tokens(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Path(email): Path<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::AdminRevokeTokens);
+
+    let email = parse_email(email)?;
+
+    // Fails for unknown users before anything is revoked.
+    state
+        .user_store
+        .read()
+        .await
+        .get_token_version(email.as_ref().expose_secret())
+        .await
+        .map_err(map_user_store_error)?;
+    revoke_all_tokens(&state, &email).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "Admin list API keys", skip_all)]
+pub async fn admin_list_api_keys(
+    State(state): State<AppState>,
+    Path(email): Path<String>,
+) -> Result<(StatusCode, Json<ApiKeysResponse>), AuthAPIError> {
+    let email = parse_email(email)?;
+
+    // Unknown users are reported as such rather than as having no keys.
+    state
+        .user_store
+        .read()
+        .await
+        .get_token_version(email.as_ref().expose_secret())
+        .await
+        .map_err(map_user_store_error)?;
+    let api_keys = get_api_keys(&state, &email).await?;
+
+    Ok((StatusCode::OK, Json(api_keys)))
+}
+
+#[tracing::instrument(name = "Admin rotate API key", skip_all)]
+pub async fn admin_rotate_api_key(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Path((email, id)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<ApiKeySecretResponse>), AuthAPIError> {
+    audit.set_action(AuditAction::AdminRotateApiKey);
+
+    let email = parse_email(email)?;
+
+    let response = rotate_user_api_key(&state, &email, &id).await?;
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+#[tracing::instrument(name = "Admin revoke API key", skip_all)]
+pub async fn admin_revoke_api_key(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Path((email, id)): Path<(String, String)>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::AdminRevokeApiKey);
+
+    let email = parse_email(email)?;
+
+    revoke_user_api_key(&state, &email, &id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+// The role reaches the user's tokens when they next log in or refresh.
+#[tracing::instrument(name = "Admin add role", skip_all)]
+pub async fn admin_add_role(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Path(email): Path<String>,
+    Json(request): Json<AddRoleRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::AdminAddRole);
+
+    let email = parse_email(email)?;
+    let role = parse_name(request.role)?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .add_role(email.as_ref().expose_secret(), &role)
+        .await
+        .map_err(map_user_store_error)?;
+
+    Ok(StatusCode::OK)
+}
+
+// Tokens already issued carry the role, so the user is logged out everywhere for the removal to
+// take effect at once.
+#[tracing::instrument(name = "Admin remove role", skip_all)]
+pub async fn admin_remove_role(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Path((email, role)): Path<(String, String)>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::AdminRemoveRole);
+
+    let email = parse_email(email)?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .remove_role(email.as_ref().expose_secret(), &role)
+        .await
+        .map_err(map_user_store_error)?;
+    revoke_all_tokens(&state, &email).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "Admin get role permissions", skip_all)]
+pub async fn admin_get_role_permissions(
+    State(state): State<AppState>,
+    Path(role): Path<String>,
+) -> Result<(StatusCode, Json<RolePermissionsResponse>), AuthAPIError> {
+    let permissions = state
+        .user_store
+        .read()
+        .await
+        .get_role_permissions(&role)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok((StatusCode::OK, Json(RolePermissionsResponse { permissions })))
+}
+
+// Granted permissions reach the tokens of the role's users when they next log in or refresh.
+#[tracing::instrument(name = "Admin grant permission", skip_all)]
+pub async fn admin_grant_permission(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Path(role): Path<String>,
+    Json(request): Json<GrantPermissionRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::AdminGrantPermission);
+
+    let role = parse_name(role)?;
+    let permission = parse_name(request.permission)?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .grant_permission(&role, &permission)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(StatusCode::OK)
+}
+
+// Like removing a role, the role's users are logged out everywhere for the revocation to take
+// effect at once.
+#[tracing::instrument(name = "Admin revoke permission", skip_all)]
+pub async fn admin_revoke_permission(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Path((role, permission)): Path<(String, String)>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::AdminRevokePermission);
+
+    let emails = {
+        let mut user_store = state.user_store.write().await;
+        user_store
+            .revoke_permission(&role, &permission)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        user_store
+            .get_users_with_role(&role)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?
+    };
+    for email in &emails {
+        revoke_all_tokens(&state, email).await?;
+    }
+
+    Ok(StatusCode::OK)
+}
+
+// Confidential clients get a random secret. Services authenticate with it for the
+// client-credentials grant, so they need no redirect URIs.
+#[tracing::instrument(name = "Admin create OAuth client", skip_all)]
+pub async fn admin_create_oauth_client(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Json(request): Json<CreateOAuthClientRequest>,
+) -> Result<(StatusCode, Json<OAuthClientResponse>), AuthAPIError> {
+    audit.set_action(AuditAction::AdminCreateOAuthClient);
+
+    let client_id = parse_name(request.client_id)?;
+    let name = request.name.trim().to_owned();
+    if name.is_empty() || request.redirect_uris.iter().any(|uri| Url::parse(uri).is_err()) {
+        return Err(AuthAPIError::InvalidCredentials);
+    }
+    let scopes = request
+        .scopes
+        .into_iter()
+        .map(parse_name)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let client_secret = request.confidential.then(|| {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        BASE64URL_NOPAD.encode(&bytes)
+    });
+
+    let client = OAuthClient::new(
+        client_id,
+        name,
+        request.redirect_uris,
+        client_secret.clone().map(Secret::new),
+        scopes,
+    );
+    let response = OAuthClientResponse {
+        client_id: client.client_id.clone(),
+        name: client.name.clone(),
+        redirect_uris: client.redirect_uris.clone(),
+        scopes: client.scopes.clone(),
+        client_secret,
+    };
+
+    state
+        .oauth_client_store
+        .write()
+        .await
+        .add_client(client)
+        .await
+        .map_err(|e| match e {
+            OAuthClientStoreError::ClientAlreadyExists => AuthAPIError::OAuthClientAlreadyExists,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+#[tracing::instrument(name = "Admin list audit events", skip_all)]
+pub async fn admin_list_audit_events(
+    State(state): State<AppState>,
+    Query(query): Query<ListAuditEventsQuery>,
+) -> Result<(StatusCode, Json<AuditEventsResponse>), AuthAPIError> {
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(DEFAULT_ADMIN_PAGE_SIZE);
+    let offset = page_offset(page, per_page)?;
+    let filter = AuditEventFilter {
+        user: query.user.filter(|user| !user.is_empty()),
+        action: query.action.filter(|action| !action.is_empty()),
+        from: query.from,
+        to: query.to,
+    };
+
+    let events = state
+        .audit_log_store
+        .get_events(&filter, offset, per_page)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    let total = state
+        .audit_log_store
+        .count_events(&filter)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let response = AuditEventsResponse {
+        events: events.into_iter().map(AuditEventResponse::from).collect(),
+        page,
+        per_page,
+        total,
+    };
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// Recomputes the hash chain of the whole audit log to detect events that were altered, removed
+// or reordered, and checks that it still reaches the head recorded before reading it. The log
+// is read a page at a time; events appended meanwhile are checked too.
+#[tracing::instrument(name = "Admin verify audit log", skip_all)]
+pub async fn admin_verify_audit_log(
+    State(state): State<AppState>,
+) -> Result<(StatusCode, Json<AuditLogVerificationResponse>), AuthAPIError> {
+    let head = state
+        .audit_head_store
+        .get_head()
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let mut verifier = AuditChainVerifier::new(AUDIT_LOG_SECRET.clone(), head);
+    let mut after_id = 0;
+    let mut first_invalid_id = None;
+    while first_invalid_id.is_none() {
+        let page = state
+            .audit_log_store
+            .get_chain_page(after_id, AUDIT_VERIFY_PAGE_SIZE)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        after_id = last.id;
+        first_invalid_id = page.iter().find(|record| !verifier.check(record)).map(|record| record.id);
+    }
+    let first_invalid_id = first_invalid_id.or_else(|| verifier.missing_from());
+
+    let response = AuditLogVerificationResponse {
+        valid: first_invalid_id.is_none(),
+        first_invalid_id,
+    };
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// Offset of the first item on `page`, rejecting pages and page sizes out of range.
+fn page_offset(page: i64, per_page: i64) -> Result<i64, AuthAPIError> {
+    if page < 1 || !(1..=MAX_ADMIN_PAGE_SIZE).contains(&per_page) {
+        return Err(AuthAPIError::InvalidCredentials);
+    }
+    (page - 1)
+        .checked_mul(per_page)
+        .ok_or(AuthAPIError::InvalidCredentials)
+}
+
+fn parse_email(email: String) -> Result<Email, AuthAPIError> {
+    Email::parse(Secret::new(email)).map_err(|_| AuthAPIError::InvalidCredentials)
+}
+
+// Role, permission, scope and client names are non-empty and have no whitespace.
+fn parse_name(name: String) -> Result<String, AuthAPIError> {
+    if name.is_empty() || name.chars().any(char::is_whitespace) {
+        return Err(AuthAPIError::InvalidCredentials);
+    }
+    Ok(name)
+}
+
+fn map_user_store_error(e: UserStoreError) -> AuthAPIError {
+    match e {
+        UserStoreError::UserNotFound => AuthAPIError::UserNotFound,
+        e => AuthAPIError::UnexpectedError(e.into()),
+    }
+}
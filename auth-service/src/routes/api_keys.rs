@@ -0,0 +1,211 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use axum_extra::extract::CookieJar;
+use chrono::Utc;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use crate::{
+    app_state::AppState,
+    domain::{
+        data_stores::ApiKeyStoreError,
+        AuditAction, AuthAPIError, ApiKey, ApiKeySecret, Email,
+    },
+    utils::{
+        audit::AuditContext,
+        auth::validate_token,
+        constants::{DEFAULT_API_KEY_TTL_DAYS, JWT_COOKIE_NAME, MAX_API_KEY_NAME_LENGTH, MAX_API_KEY_TTL_DAYS},
+    },
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeysResponse {
+    pub api_keys: Vec<ApiKeyResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyResponse {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub last_used_at: Option<i64>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            id: key.id,
+            name: key.name,
+            scopes: key.scopes,
+            created_at: key.created_at,
+            expires_at: key.expires_at,
+            last_used_at: key.last_used_at,
+        }
+    }
+}
+
+// The only response that ever contains the key itself.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeySecretResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+    pub key: String,
+}
+
+#[tracing::instrument(name = "Create API key", skip_all)]
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<ApiKeySecretResponse>), AuthAPIError> {
+    audit.set_action(AuditAction::CreateApiKey);
+
+    let email = authenticate(&state, &jar).await?;
+
+    let name = request.name.trim().to_owned();
+    if name.is_empty() || name.chars().count() > MAX_API_KEY_NAME_LENGTH {
+        return Err(AuthAPIError::InvalidCredentials);
+    }
+    if request.scopes.iter().any(|scope| scope.is_empty() || scope.chars().any(char::is_whitespace)) {
+        return Err(AuthAPIError::InvalidCredentials);
+    }
+    let expires_in_days = request.expires_in_days.unwrap_or(DEFAULT_API_KEY_TTL_DAYS);
+    if !(1..=MAX_API_KEY_TTL_DAYS).contains(&expires_in_days) {
+        return Err(AuthAPIError::InvalidCredentials);
+    }
+
+    let now = Utc::now().timestamp();
+    let key = ApiKey::new(email, name, request.scopes, now, now + expires_in_days * 24 * 60 * 60);
+    let secret = ApiKeySecret::generate(&key.id);
+    state
+        .api_key_store
+        .write()
+        .await
+        .add_key(key.clone(), &secret)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok((StatusCode::CREATED, Json(secret_response(key, &secret))))
+}
+
+#[tracing::instrument(name = "List API keys", skip_all)]
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    jar: CookieJar,
+) -> Result<(StatusCode, Json<ApiKeysResponse>), AuthAPIError> {
+    let email = authenticate(&state, &jar).await?;
+
+    let api_keys = get_api_keys(&state, &email).await?;
+
+    Ok((StatusCode::OK, Json(api_keys)))
+}
+
+#[tracing::instrument(name = "Rotate API key", skip_all)]
+pub async fn rotate_api_key(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+    Path(id): Path<String>,
+) -> Result<(StatusCode, Json<ApiKeySecretResponse>), AuthAPIError> {
+    audit.set_action(AuditAction::RotateApiKey);
+
+    let email = authenticate(&state, &jar).await?;
+
+    let response = rotate_user_api_key(&state, &email, &id).await?;
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+#[tracing::instrument(name = "Revoke API key", skip_all)]
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::RevokeApiKey);
+
+    let email = authenticate(&state, &jar).await?;
+
+    revoke_user_api_key(&state, &email, &id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Shared with the admin routes, which act on any user's keys.
+pub async fn get_api_keys(state: &AppState, email: &Email) -> Result<ApiKeysResponse, AuthAPIError> {
+    let keys = state
+        .api_key_store
+        .read()
+        .await
+        .get_keys(email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(ApiKeysResponse {
+        api_keys: keys.into_iter().map(ApiKeyResponse::from).collect(),
+    })
+}
+
+// The key keeps its id, name, scopes and expiry; only the secret changes.
+pub async fn rotate_user_api_key(state: &AppState, email: &Email, id: &str) -> Result<ApiKeySecretResponse, AuthAPIError> {
+    let secret = ApiKeySecret::generate(id);
+    let key = state
+        .api_key_store
+        .write()
+        .await
+        .rotate_key(email, id, &secret)
+        .await
+        .map_err(map_api_key_store_error)?;
+
+    Ok(secret_response(key, &secret))
+}
+
+pub async fn revoke_user_api_key(state: &AppState, email: &Email, id: &str) -> Result<(), AuthAPIError> {
+    state
+        .api_key_store
+        .write()
+        .await
+        .revoke_key(email, id)
+        .await
+        .map_err(map_api_key_store_error)
+}
+
+fn secret_response(key: ApiKey, secret: &ApiKeySecret) -> ApiKeySecretResponse {
+    ApiKeySecretResponse {
+        api_key: key.into(),
+        key: secret.as_ref().expose_secret().clone(),
+    }
+}
+
+fn map_api_key_store_error(e: ApiKeyStoreError) -> AuthAPIError {
+    match e {
+        ApiKeyStoreError::KeyNotFound => AuthAPIError::ApiKeyNotFound,
+        e => AuthAPIError::UnexpectedError(e.into()),
+    }
+}
+
+// Keys are managed from a logged-in session only; an API key can't be used to mint more keys.
+async fn authenticate(state: &AppState, jar: &CookieJar) -> Result<Email, AuthAPIError> {
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let claims = validate_token(cookie.value(), state.banned_token_store.clone(), state.user_store.clone()).await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    Email::parse(Secret::new(claims.sub)).map_err(AuthAPIError::UnexpectedError)
+}
@@ -0,0 +1,133 @@
+use axum::{
+    extract::{Query, RawQuery, State},
+    response::Redirect,
+};
+use axum_extra::extract::CookieJar;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use url::{form_urlencoded, Url};
+use crate::{
+    app_state::AppState,
+    domain::{
+        data_stores::OAuthClientStoreError,
+        error::OAuthAPIError,
+        oauth::{AuthorizationCode, AuthorizationCodeGrant, PkceCodeChallenge},
+        Email,
+    },
+    utils::{auth::validate_token, constants::JWT_COOKIE_NAME},
+};
+
+#[derive(Deserialize)]
+pub struct AuthorizeRequest {
+    pub response_type: Option<String>,
+    pub client_id: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+}
+
+// Authorization endpoint of the authorization code grant (RFC 6749 section 4.1) with PKCE.
+// Users who aren't logged in are sent to the login page, which brings them back here once
+// they have gone through `login` and, if enabled, `verify_2fa`.
+#[tracing::instrument(name = "Authorize", skip_all)]
+pub async fn authorize(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    RawQuery(raw_query): RawQuery,
+    Query(request): Query<AuthorizeRequest>,
+) -> Result<Redirect, OAuthAPIError> {
+    // Until the redirect URI is known to belong to the client, errors are shown to the user
+    // rather than sent to a possibly malicious site.
+    let client_id = request
+        .client_id
+        .ok_or(OAuthAPIError::InvalidRequest("client_id is required"))?;
+    let client = match state.oauth_client_store.read().await.get_client(&client_id).await {
+        Ok(client) => client,
+        Err(OAuthClientStoreError::ClientNotFound) => {
+            return Err(OAuthAPIError::InvalidRequest("unknown client_id"))
+        }
+        Err(e) => return Err(OAuthAPIError::UnexpectedError(e.into())),
+    };
+
+    let redirect_uri = request
+        .redirect_uri
+        .ok_or(OAuthAPIError::InvalidRequest("redirect_uri is required"))?;
+    if !client.allows_redirect_uri(&redirect_uri) {
+        return Err(OAuthAPIError::InvalidRequest("redirect_uri is not registered for this client"));
+    }
+    let redirect_to = |params: &[(&str, &str)]| {
+        build_redirect(&redirect_uri, params, request.state.as_deref())
+    };
+
+    if request.response_type.as_deref() != Some("code") {
+        return redirect_to(&[("error", "unsupported_response_type")]);
+    }
+
+    if request.code_challenge_method.as_deref() != Some("S256") {
+        return redirect_to(&[
+            ("error", "invalid_request"),
+            ("error_description", "PKCE with the S256 method is required"),
+        ]);
+    }
+    let Some(code_challenge) = request
+        .code_challenge
+        .and_then(|code_challenge| PkceCodeChallenge::parse(code_challenge).ok())
+    else {
+        return redirect_to(&[
+            ("error", "invalid_request"),
+            ("error_description", "invalid code_challenge"),
+        ]);
+    };
+
+    let claims = match jar.get(JWT_COOKIE_NAME) {
+        Some(cookie) => validate_token(cookie.value(), state.banned_token_store.clone(), state.user_store.clone()).await.ok(),
+        None => None,
+    };
+    let Some(claims) = claims else {
+        let return_to = format!("/authorize?{}", raw_query.unwrap_or_default());
+        let return_to: String = form_urlencoded::byte_serialize(return_to.as_bytes()).collect();
+        return Ok(Redirect::to(&format!("/?return_to={}", return_to)));
+    };
+    let email = Email::parse(Secret::new(claims.sub.clone()))
+        .map_err(OAuthAPIError::UnexpectedError)?;
+
+    let code = AuthorizationCode::default();
+    let grant = AuthorizationCodeGrant {
+        client_id: client.client_id,
+        redirect_uri: redirect_uri.clone(),
+        email,
+        code_challenge,
+        scope: request.scope,
+        nonce: request.nonce,
+        authentication: claims.authentication(),
+    };
+    state
+        .authorization_code_store
+        .write()
+        .await
+        .add_code(code.clone(), grant)
+        .await
+        .map_err(|e| OAuthAPIError::UnexpectedError(e.into()))?;
+
+    redirect_to(&[("code", code.as_ref().expose_secret())])
+}
+
+fn build_redirect(
+    redirect_uri: &str,
+    params: &[(&str, &str)],
+    state: Option<&str>,
+) -> Result<Redirect, OAuthAPIError> {
+    let mut url = Url::parse(redirect_uri)
+        .map_err(|_| OAuthAPIError::InvalidRequest("invalid redirect_uri"))?;
+    {
+        let mut query = url.query_pairs_mut();
+        query.extend_pairs(params);
+        if let Some(state) = state {
+            query.append_pair("state", state);
+        }
+    }
+    Ok(Redirect::to(url.as_str()))
+}
@@ -0,0 +1,250 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use axum_extra::extract::CookieJar;
+use chrono::Utc;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use crate::{
+    app_state::AppState,
+    domain::{data_stores::{UserStore, UserStoreError}, AuditAction, AuthAPIError, Email},
+    utils::{
+        audit::AuditContext,
+        auth::{
+            generate_email_change_token, validate_email_change_token, validate_token, EmailChange,
+            EmailChangeLink,
+        },
+        constants::{AUTH_SERVICE_URL, JWT_COOKIE_NAME},
+    },
+};
+
+#[derive(Deserialize)]
+pub struct ChangeEmailRequest {
+    #[serde(rename = "newEmail")]
+    pub new_email: Secret<String>,
+    pub password: Secret<String>,
+}
+
+#[derive(Deserialize)]
+pub struct EmailChangeTokenRequest {
+    pub token: String,
+}
+
+// Starts changing the logged-in user's email address. Nothing changes until the link sent to the
+// new address is opened; the old address is told about the request and can undo it.
+#[tracing::instrument(name = "Change email", skip_all)]
+pub async fn change_email(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+    Json(request): Json<ChangeEmailRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::ChangeEmail);
+
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let claims = validate_token(cookie.value(), state.banned_token_store.clone(), state.user_store.clone()).await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    let email = Email::parse(Secret::new(claims.sub))
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    let new_email = Email::parse(request.new_email)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    if new_email == email {
+        return Err(AuthAPIError::InvalidCredentials);
+    }
+
+    let token_version = {
+        let user_store = state.user_store.read().await;
+        if user_store.validate_user(email.as_ref().expose_secret(), request.password.expose_secret()).await.is_err() {
+            return Err(AuthAPIError::IncorrectCredentials);
+        }
+
+        // Changing again would leave the last change's undo link nothing to move back.
+        match user_store.get_previous_email(email.as_ref().expose_secret()).await {
+            Ok(Some(previous)) if previous.changed_at >= undo_window_start() => {
+                return Err(AuthAPIError::EmailChangeUndoPending)
+            }
+            Ok(_) => {}
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        }
+
+        match user_store.get_user(new_email.as_ref().expose_secret()).await {
+            Ok(_) => return Err(AuthAPIError::UserAlreadyExists),
+            Err(UserStoreError::UserNotFound) => {}
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        }
+        if is_email_reserved(&**user_store, new_email.as_ref().expose_secret()).await? {
+            return Err(AuthAPIError::UserAlreadyExists);
+        }
+
+        user_store
+            .get_token_version(email.as_ref().expose_secret())
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?
+    };
+
+    let change = EmailChange { email, new_email, token_version };
+    let confirm_token = generate_email_change_token(&change, EmailChangeLink::Confirm)
+        .map_err(AuthAPIError::UnexpectedError)?;
+    let undo_token = generate_email_change_token(&change, EmailChangeLink::Undo)
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    let content = format!(
+        "Confirm your new email address by opening the link below. It expires in {} hours.\n\n{}/confirm-email-change?token={}",
+        EmailChangeLink::Confirm.ttl_seconds() / 3600,
+        AUTH_SERVICE_URL.as_str(),
+        confirm_token
+    );
+    state
+        .email_client
+        .send_email(&change.new_email, "Confirm your new email address", &content)
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    let content = format!(
+        "A change of your account's email address to {} was requested. If this wasn't you, open the link below within {} days to cancel the change, or to undo it if it was already confirmed.\n\n{}/undo-email-change?token={}",
+        change.new_email.as_ref().expose_secret(),
+        EmailChangeLink::Undo.ttl_seconds() / 86400,
+        AUTH_SERVICE_URL.as_str(),
+        undo_token
+    );
+    state
+        .email_client
+        .send_email(&change.email, "Your email address is being changed", &content)
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    Ok(StatusCode::OK)
+}
+
+// Moves the account to the new address. The user is logged out everywhere, since their tokens
+// name the old address, and logs in again with the new one.
+#[tracing::instrument(name = "Confirm email change", skip_all)]
+pub async fn confirm_email_change(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Json(request): Json<EmailChangeTokenRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::ConfirmEmailChange);
+
+    let change = validate_email_change_token(&request.token, EmailChangeLink::Confirm)
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    audit.set_actor(change.email.as_ref().expose_secret());
+
+    {
+        let mut user_store = state.user_store.write().await;
+        match user_store.get_token_version(change.email.as_ref().expose_secret()).await {
+            Ok(version) if version == change.token_version => {}
+            Ok(_) | Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        }
+        if is_email_reserved(&**user_store, change.new_email.as_ref().expose_secret()).await? {
+            return Err(AuthAPIError::UserAlreadyExists);
+        }
+
+        move_account(&mut **user_store, &change.email, &change.new_email).await?;
+    }
+
+    end_all_sessions(&state, &change.email).await?;
+    Ok(StatusCode::OK)
+}
+
+// Cancels a pending change, or moves the account back to the old address if the change was
+// already confirmed. Either way the account is logged out everywhere, as whoever asked for the
+// change may have had access to it.
+#[tracing::instrument(name = "Undo email change", skip_all)]
+pub async fn undo_email_change(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Json(request): Json<EmailChangeTokenRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::UndoEmailChange);
+
+    let change = validate_email_change_token(&request.token, EmailChangeLink::Undo)
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    audit.set_actor(change.email.as_ref().expose_secret());
+
+    let mut user_store = state.user_store.write().await;
+    // Only an account that was moved here from the old address is moved back, never one that
+    // was signed up with the new address independently.
+    let confirmed = match user_store.get_previous_email(change.new_email.as_ref().expose_secret()).await {
+        Ok(previous) => previous.is_some_and(|previous| previous.email == change.email),
+        Err(UserStoreError::UserNotFound) => false,
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    if confirmed {
+        move_account(&mut **user_store, &change.new_email, &change.email).await?;
+    } else {
+        match user_store.get_token_version(change.email.as_ref().expose_secret()).await {
+            Ok(version) if version == change.token_version => {}
+            Ok(_) | Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        }
+        // Outdates the confirmation link.
+        user_store
+            .increment_token_version(change.email.as_ref().expose_secret())
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    }
+
+    drop(user_store);
+
+    if confirmed {
+        end_all_sessions(&state, &change.new_email).await?;
+    }
+    end_all_sessions(&state, &change.email).await?;
+    Ok(StatusCode::OK)
+}
+
+// Start of the period in which email changes can still be undone.
+fn undo_window_start() -> i64 {
+    Utc::now().timestamp() - EmailChangeLink::Undo.ttl_seconds()
+}
+
+// An address an account moved away from stays reserved for as long as the change can be undone,
+// so that undoing it can always move the account back.
+pub(crate) async fn is_email_reserved(user_store: &dyn UserStore, email: &str) -> Result<bool, AuthAPIError> {
+    user_store
+        .is_previous_email_since(email, undo_window_start())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
+}
+
+async fn move_account(
+    user_store: &mut dyn UserStore,
+    from: &Email,
+    to: &Email,
+) -> Result<(), AuthAPIError> {
+    user_store
+        .update_email(from.as_ref().expose_secret(), to.clone(), Utc::now().timestamp())
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserAlreadyExists => AuthAPIError::UserAlreadyExists,
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+    user_store
+        .increment_token_version(to.as_ref().expose_secret())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    Ok(())
+}
+
+// Sessions and refresh tokens are kept by email address, so those of an address the account has
+// moved away from are dropped; its access tokens already fail on the token version.
+async fn end_all_sessions(state: &AppState, email: &Email) -> Result<(), AuthAPIError> {
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_user_tokens(email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .session_store
+        .write()
+        .await
+        .remove_all_sessions(email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
+}
@@ -0,0 +1,75 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use axum_extra::extract::CookieJar;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use crate::{
+    app_state::AppState,
+    domain::{AuditAction, AuthAPIError, Email, Password},
+    utils::{audit::AuditContext, auth::validate_token, constants::JWT_COOKIE_NAME, session::end_session},
+};
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    #[serde(rename = "currentPassword")]
+    pub current_password: Secret<String>,
+    #[serde(rename = "newPassword")]
+    pub new_password: Secret<String>,
+}
+
+// Changes the password of the logged-in user. Every other session is ended, so a device that
+// was logged in with the old password has to log in again; the current one stays logged in.
+#[tracing::instrument(name = "Change password", skip_all)]
+pub async fn change_password(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+    Json(request): Json<ChangePasswordRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::ChangePassword);
+
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let claims = validate_token(cookie.value(), state.banned_token_store.clone(), state.user_store.clone()).await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    let email = Email::parse(Secret::new(claims.sub))
+        .map_err(AuthAPIError::UnexpectedError)?;
+    let email_str = email.as_ref().expose_secret();
+
+    let password = Password::parse(request.new_password)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    // Hashing is slow, so the current password is checked without holding the store for writing.
+    if state.user_store.read().await.validate_user(email_str, request.current_password.expose_secret()).await.is_err() {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    state
+        .user_store
+        .write()
+        .await
+        .update_password(email_str, password)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let sessions = state
+        .session_store
+        .read()
+        .await
+        .get_sessions(&email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    for session in sessions.iter().filter(|session| session.id != claims.jti) {
+        end_session(&state, &email, &session.id).await?;
+    }
+
+    // The password has already been changed, so a failed notification isn't reported to the user.
+    let content = "The password of your account was just changed. If this wasn't you, reset your password right away.";
+    if let Err(e) = state
+        .email_client
+        .send_email(&email, "Your password was changed", content)
+        .await
+    {
+        tracing::error!("Failed to send password change notification: {:?}", e);
+    }
+
+    Ok(StatusCode::OK)
+}
@@ -0,0 +1,65 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use axum_extra::extract::CookieJar;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use crate::{
+    app_state::AppState,
+    domain::{data_stores::{TwoFACode, UserStoreError}, AuditAction, AuthAPIError, Email, TwoFAMethod},
+    routes::{generate_recovery_codes, send_2fa_change_notification, RecoveryCodesResponse},
+    utils::{audit::AuditContext, auth::validate_token, constants::JWT_COOKIE_NAME, totp::verify_totp_code},
+};
+
+#[derive(Deserialize)]
+pub struct ConfirmTotpRequest {
+    pub code: String,
+}
+
+#[tracing::instrument(name = "Confirm TOTP", skip_all)]
+pub async fn confirm_totp(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+    Json(request): Json<ConfirmTotpRequest>,
+) -> Result<(StatusCode, Json<RecoveryCodesResponse>), AuthAPIError> {
+    audit.set_action(AuditAction::ConfirmTotp);
+
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let claims = validate_token(cookie.value(), state.banned_token_store.clone(), state.user_store.clone()).await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let code = TwoFACode::parse(request.code)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let mut user_store = state.user_store.write().await;
+
+    let secret = user_store
+        .get_pending_totp_secret(&claims.sub)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?
+        .ok_or(AuthAPIError::InvalidCredentials)?;
+
+    let counter = verify_totp_code(&secret, code.as_ref().expose_secret())
+        .ok_or(AuthAPIError::IncorrectCredentials)?;
+    // The code used here can't be used again to log in.
+    match user_store.use_totp_counter(&claims.sub, counter).await {
+        Ok(()) => {}
+        Err(UserStoreError::InvalidCredentials) => return Err(AuthAPIError::IncorrectCredentials),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    user_store
+        .set_two_fa_method(&claims.sub, TwoFAMethod::Totp(secret))
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    drop(user_store);
+
+    let codes = generate_recovery_codes(&state.user_store, &claims.sub)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let email = Email::parse(Secret::new(claims.sub))
+        .map_err(AuthAPIError::UnexpectedError)?;
+    send_2fa_change_notification(&state.email_client, &email, "Authenticator app 2FA was turned on").await;
+
+    Ok((StatusCode::OK, Json(codes.into())))
+}
@@ -0,0 +1,83 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use axum_extra::extract::CookieJar;
+use chrono::Utc;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use crate::{
+    app_state::AppState,
+    domain::{AuditAction, AuthAPIError, Email},
+    utils::{
+        audit::AuditContext,
+        auth::validate_token,
+        constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+    },
+};
+
+#[derive(Deserialize)]
+pub struct DeleteAccountRequest {
+    pub password: Secret<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteAccountResponse {
+    // Unix time after which the account is purged; logging in before then restores it.
+    #[serde(rename = "purgeAfter")]
+    pub purge_after: i64,
+}
+
+// Deletes the user's account once the grace period is over, logging them out everywhere now.
+#[tracing::instrument(name = "Delete account", skip_all)]
+pub async fn delete_account(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+    Json(request): Json<DeleteAccountRequest>,
+) -> Result<(CookieJar, (StatusCode, Json<DeleteAccountResponse>)), AuthAPIError> {
+    audit.set_action(AuditAction::DeleteAccount);
+
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let claims = validate_token(cookie.value(), state.banned_token_store.clone(), state.user_store.clone()).await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    let email = Email::parse(Secret::new(claims.sub))
+        .map_err(AuthAPIError::UnexpectedError)?;
+    let email_str = email.as_ref().expose_secret();
+
+    if state.user_store.read().await.validate_user(email_str, request.password.expose_secret()).await.is_err() {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    let now = Utc::now().timestamp();
+    {
+        let mut user_store = state.user_store.write().await;
+        user_store
+            .mark_user_deleted(email_str, now)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        user_store
+            .increment_token_version(email_str)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    }
+
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_user_tokens(&email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .session_store
+        .write()
+        .await
+        .remove_all_sessions(&email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let response = DeleteAccountResponse {
+        purge_after: now + state.account_deletion_grace_period_seconds,
+    };
+    let updated_jar = jar.remove(JWT_COOKIE_NAME).remove(REFRESH_TOKEN_COOKIE_NAME);
+    Ok((updated_jar, (StatusCode::OK, Json(response))))
+}
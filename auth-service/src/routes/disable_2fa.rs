@@ -0,0 +1,59 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use axum_extra::extract::CookieJar;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use crate::{
+    app_state::AppState,
+    domain::{AuditAction, AuthAPIError, Email, TwoFAMethod},
+    routes::send_2fa_change_notification,
+    utils::{audit::AuditContext, auth::validate_token, constants::JWT_COOKIE_NAME},
+};
+
+#[derive(Deserialize)]
+pub struct Disable2FARequest {
+    pub password: Secret<String>,
+}
+
+// Turns 2FA off for the logged-in user, whichever method they use, and discards their
+// recovery codes.
+#[tracing::instrument(name = "Disable 2FA", skip_all)]
+pub async fn disable_2fa(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+    Json(request): Json<Disable2FARequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::DisableTwoFA);
+
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let claims = validate_token(cookie.value(), state.banned_token_store.clone(), state.user_store.clone()).await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    let email = Email::parse(Secret::new(claims.sub))
+        .map_err(AuthAPIError::UnexpectedError)?;
+    let email_str = email.as_ref().expose_secret();
+
+    {
+        let user_store = state.user_store.read().await;
+        if user_store.validate_user(email_str, request.password.expose_secret()).await.is_err() {
+            return Err(AuthAPIError::IncorrectCredentials);
+        }
+
+        let user = user_store.get_user(email_str).await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        if user.two_fa_method == TwoFAMethod::None {
+            return Err(AuthAPIError::InvalidCredentials);
+        }
+    }
+
+    state
+        .user_store
+        .write()
+        .await
+        .disable_two_fa(email_str)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    send_2fa_change_notification(&state.email_client, &email, "Two-factor authentication was turned off").await;
+
+    Ok(StatusCode::OK)
+}
@@ -0,0 +1,123 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use axum_extra::extract::CookieJar;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use crate::{
+    app_state::{AppState, EmailClientType},
+    domain::{
+        data_stores::{LoginAttemptId, TwoFACode, TwoFACodeStoreError},
+        AuditAction, AuthAPIError, Email, TwoFAMethod,
+    },
+    routes::{generate_recovery_codes, RecoveryCodesResponse},
+    utils::{audit::AuditContext, auth::validate_token, constants::{JWT_COOKIE_NAME, MAX_2FA_ATTEMPTS}},
+};
+
+#[derive(Deserialize)]
+pub struct ConfirmEnable2FARequest {
+    #[serde(rename = "2FACode")]
+    pub two_fa_code: String,
+}
+
+// Emails a code to the logged-in user; entering it at /confirm-enable-2fa turns on email 2FA.
+#[tracing::instrument(name = "Enable 2FA", skip_all)]
+pub async fn enable_2fa(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::EnableTwoFA);
+
+    let email = authenticate(&state, &jar).await?;
+
+    let user = state.user_store.read().await.get_user(email.as_ref().expose_secret()).await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    if user.two_fa_method != TwoFAMethod::None {
+        return Err(AuthAPIError::InvalidCredentials);
+    }
+
+    // The code is kept like a login's, apart from login codes so neither can replace or stand in
+    // for the other. Its login attempt ID is never handed out.
+    let two_fa_code = TwoFACode::default();
+    state
+        .two_fa_enrollment_code_store
+        .write()
+        .await
+        .add_code(email.clone(), LoginAttemptId::default(), two_fa_code.clone())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .email_client
+        .send_email(&email, "2FA Code", two_fa_code.as_ref().expose_secret())
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "Confirm enable 2FA", skip_all)]
+pub async fn confirm_enable_2fa(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+    Json(request): Json<ConfirmEnable2FARequest>,
+) -> Result<(StatusCode, Json<RecoveryCodesResponse>), AuthAPIError> {
+    audit.set_action(AuditAction::ConfirmEnableTwoFA);
+
+    let email = authenticate(&state, &jar).await?;
+
+    let two_fa_code = TwoFACode::parse(request.two_fa_code)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let (login_attempt_id, stored_two_fa_code) = state.two_fa_enrollment_code_store.read().await
+        .get_code(&email).await
+        .map_err(|_| AuthAPIError::IncorrectCredentials)?;
+
+    if two_fa_code != stored_two_fa_code {
+        let mut two_fa_code_store = state.two_fa_enrollment_code_store.write().await;
+        return match two_fa_code_store.record_failed_attempt(&email, &login_attempt_id, MAX_2FA_ATTEMPTS).await {
+            Ok(()) | Err(TwoFACodeStoreError::LoginAttemptIdNotFound) => Err(AuthAPIError::IncorrectCredentials),
+            Err(TwoFACodeStoreError::TooManyAttempts) => Err(AuthAPIError::TooMany2FAAttempts),
+            Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+        };
+    }
+
+    state.two_fa_enrollment_code_store.write().await
+        .remove_code(&email).await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .set_two_fa_method(email.as_ref().expose_secret(), TwoFAMethod::Email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let codes = generate_recovery_codes(&state.user_store, email.as_ref().expose_secret())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    send_2fa_change_notification(&state.email_client, &email, "Two-factor authentication was turned on").await;
+
+    Ok((StatusCode::OK, Json(codes.into())))
+}
+
+// Tells the user their 2FA setting changed. The change has already been made, so a failure to
+// send is only logged.
+pub async fn send_2fa_change_notification(email_client: &EmailClientType, email: &Email, subject: &str) {
+    let content = format!(
+        "{} for your account. If this wasn't you, reset your password right away.",
+        subject
+    );
+    if let Err(e) = email_client.send_email(email, subject, &content).await {
+        tracing::error!("Failed to send 2FA change notification: {:?}", e);
+    }
+}
+
+async fn authenticate(state: &AppState, jar: &CookieJar) -> Result<Email, AuthAPIError> {
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let claims = validate_token(cookie.value(), state.banned_token_store.clone(), state.user_store.clone()).await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    Email::parse(Secret::new(claims.sub)).map_err(AuthAPIError::UnexpectedError)
+}
@@ -0,0 +1,49 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use axum_extra::extract::CookieJar;
+use secrecy::{ExposeSecret, Secret};
+use serde::Serialize;
+use crate::{
+    app_state::AppState,
+    domain::{AuditAction, AuthAPIError, Email, TotpSecret},
+    utils::{audit::AuditContext, auth::validate_token, constants::JWT_COOKIE_NAME, totp::get_totp_provisioning_uri},
+};
+
+#[derive(Debug, Serialize)]
+pub struct EnrollTotpResponse {
+    pub secret: String,
+    // Can be rendered as a QR code for authenticator apps to scan.
+    #[serde(rename = "otpauthUri")]
+    pub otpauth_uri: String,
+}
+
+#[tracing::instrument(name = "Enroll TOTP", skip_all)]
+pub async fn enroll_totp(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+) -> Result<(StatusCode, Json<EnrollTotpResponse>), AuthAPIError> {
+    audit.set_action(AuditAction::EnrollTotp);
+
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let claims = validate_token(cookie.value(), state.banned_token_store.clone(), state.user_store.clone()).await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    let email = Email::parse(Secret::new(claims.sub))
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    // The secret only replaces the current 2FA method once a code generated from it is confirmed.
+    let secret = TotpSecret::default();
+    state
+        .user_store
+        .write()
+        .await
+        .set_pending_totp_secret(email.as_ref().expose_secret(), secret.clone())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let response = EnrollTotpResponse {
+        secret: secret.as_ref().expose_secret().clone(),
+        otpauth_uri: get_totp_provisioning_uri(&secret, &email),
+    };
+
+    Ok((StatusCode::OK, Json(response)))
+}
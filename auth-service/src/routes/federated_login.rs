@@ -0,0 +1,326 @@
+use std::net::SocketAddr;
+use axum::{
+    extract::{ConnectInfo, Query, State},
+    http::HeaderMap,
+    response::Redirect,
+    Extension,
+};
+use axum_extra::extract::{
+    cookie::{Cookie, SameSite},
+    CookieJar,
+};
+use color_eyre::eyre::{eyre, Context, Result};
+use data_encoding::BASE64URL_NOPAD;
+use rand::RngCore;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use url::form_urlencoded;
+use crate::{
+    app_state::AppState,
+    domain::{
+        data_stores::UserStoreError, oauth::PkceCodeChallenge, AuditAction, AuthAPIError,
+        AuthenticationMethod, Email, Password, TwoFAMethod, User,
+    },
+    routes::{is_email_reserved, login::start_2fa},
+    services::oidc_identity_provider::{AuthorizationRequest, FederatedIdTokenClaims},
+    utils::{
+        audit::AuditContext,
+        auth::{generate_auth_cookie, generate_refresh_cookie, validate_token},
+        constants::{AUTH_SERVICE_URL, FEDERATED_LOGIN_COOKIE_NAME, JWT_COOKIE_NAME},
+        session::start_session,
+    },
+};
+
+const FEDERATED_LOGIN_PATH: &str = "/federated-login";
+const FEDERATED_LOGIN_CALLBACK_PATH: &str = "/federated-login/callback";
+
+#[derive(Deserialize)]
+pub struct FederatedLoginRequest {
+    pub return_to: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct FederatedLoginCallbackRequest {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+// What the callback needs to check the provider's response against. It is kept in a cookie
+// scoped to the federated login paths for the duration of one login.
+#[derive(Serialize, Deserialize)]
+struct PendingFederatedLogin {
+    state: String,
+    nonce: String,
+    code_verifier: String,
+    return_to: Option<String>,
+}
+
+// Starts a login with the configured external identity provider by redirecting to it.
+#[tracing::instrument(name = "Federated login", skip_all)]
+pub async fn federated_login(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    Query(request): Query<FederatedLoginRequest>,
+) -> Result<(CookieJar, Redirect), AuthAPIError> {
+    let provider = state
+        .identity_provider
+        .clone()
+        .ok_or(AuthAPIError::FederatedLoginNotConfigured)?;
+    let metadata = provider.discover().await.map_err(AuthAPIError::UnexpectedError)?;
+
+    let pending_login = PendingFederatedLogin {
+        state: random_token(),
+        nonce: random_token(),
+        code_verifier: random_token(),
+        // Only the OAuth flow is resumed, like the login page does, so this can't be used as
+        // an open redirect.
+        return_to: request
+            .return_to
+            .filter(|return_to| return_to.starts_with("/authorize?")),
+    };
+    let code_challenge = PkceCodeChallenge::from_verifier(&pending_login.code_verifier);
+    let redirect_uri = callback_uri();
+
+    let url = provider
+        .authorization_url(
+            &metadata,
+            &AuthorizationRequest {
+                redirect_uri: &redirect_uri,
+                state: &pending_login.state,
+                nonce: &pending_login.nonce,
+                code_challenge: code_challenge.as_ref(),
+            },
+        )
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    let cookie = create_pending_login_cookie(&pending_login).map_err(AuthAPIError::UnexpectedError)?;
+    Ok((jar.add(cookie), Redirect::to(url.as_str())))
+}
+
+// Where the identity provider sends the user back. A user who is already logged in gets the
+// provider's account linked to theirs. Otherwise the returned ID token identifies the linked
+// account, or a new one is created, and the user is logged in as usual: accounts with 2FA
+// enabled are sent to the login page to enter their code.
+#[tracing::instrument(name = "Federated login callback", skip_all)]
+pub async fn federated_login_callback(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+    Query(request): Query<FederatedLoginCallbackRequest>,
+) -> Result<(CookieJar, Redirect), AuthAPIError> {
+    audit.set_action(AuditAction::FederatedLogin);
+
+    let provider = state
+        .identity_provider
+        .clone()
+        .ok_or(AuthAPIError::FederatedLoginNotConfigured)?;
+
+    let pending_login = jar
+        .get(FEDERATED_LOGIN_COOKIE_NAME)
+        .and_then(|cookie| parse_pending_login_cookie(cookie.value()).ok())
+        .ok_or(AuthAPIError::FederatedLoginFailed(eyre!("no federated login in progress")))?;
+    let jar = jar.remove(
+        Cookie::build(FEDERATED_LOGIN_COOKIE_NAME)
+            .path(FEDERATED_LOGIN_PATH)
+            .build(),
+    );
+
+    if request.state.as_deref() != Some(pending_login.state.as_str()) {
+        return Err(AuthAPIError::FederatedLoginFailed(eyre!("state does not match")));
+    }
+    if let Some(error) = request.error {
+        return Err(AuthAPIError::FederatedLoginFailed(eyre!(
+            "identity provider returned error {}",
+            error
+        )));
+    }
+    let code = request
+        .code
+        .ok_or(AuthAPIError::FederatedLoginFailed(eyre!("no code in callback")))?;
+
+    let metadata = provider.discover().await.map_err(AuthAPIError::UnexpectedError)?;
+    let claims = provider
+        .exchange_code(
+            &metadata,
+            &code,
+            &callback_uri(),
+            &pending_login.code_verifier,
+            &pending_login.nonce,
+        )
+        .await
+        .map_err(AuthAPIError::FederatedLoginFailed)?;
+
+    let logged_in = match jar.get(JWT_COOKIE_NAME) {
+        Some(cookie) => validate_token(
+            cookie.value(),
+            state.banned_token_store.clone(),
+            state.user_store.clone(),
+        )
+        .await
+        .ok(),
+        None => None,
+    };
+    if let Some(logged_in) = logged_in {
+        audit.set_actor(&logged_in.sub);
+        audit.set_action(AuditAction::LinkFederatedIdentity);
+        link_identity(&state, &logged_in.sub, provider.issuer(), &claims.sub).await?;
+        return Ok((jar, Redirect::to(pending_login.return_to.as_deref().unwrap_or("/"))));
+    }
+
+    let user = find_or_create_user(&state, provider.issuer(), &claims).await?;
+    audit.set_actor(user.email.as_ref().expose_secret());
+
+    if user.two_fa_method != TwoFAMethod::None {
+        let login_attempt_id = start_2fa(&state, &user.email, &user.two_fa_method).await?;
+        audit.set_action(AuditAction::LoginTwoFARequired);
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        query
+            .append_pair("email", user.email.as_ref().expose_secret())
+            .append_pair("login_attempt_id", login_attempt_id.as_ref().expose_secret())
+            .append_pair("two_fa_method", user.two_fa_method.as_str());
+        if let Some(return_to) = &pending_login.return_to {
+            query.append_pair("return_to", return_to);
+        }
+        return Ok((jar, Redirect::to(&format!("/?{}", query.finish()))));
+    }
+
+    let methods = claims
+        .amr
+        .iter()
+        .filter_map(|method| match method.as_str() {
+            "pwd" => Some(AuthenticationMethod::Pwd),
+            "otp" => Some(AuthenticationMethod::Otp),
+            "mfa" => Some(AuthenticationMethod::Mfa),
+            _ => None,
+        })
+        .collect();
+    let mut authentication = start_session(&state, &user.email, methods, addr.ip(), &headers).await?;
+    if let Some(auth_time) = claims.auth_time {
+        authentication.auth_time = auth_time;
+    }
+    let auth_cookie = generate_auth_cookie(&user.email, &authentication)
+        .map_err(AuthAPIError::UnexpectedError)?;
+    let refresh_cookie = generate_refresh_cookie(&user.email, &authentication, state.refresh_token_store.clone())
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    let return_to = pending_login.return_to.unwrap_or_else(|| "/".to_owned());
+    Ok((jar.add(auth_cookie).add(refresh_cookie), Redirect::to(&return_to)))
+}
+
+// Links the provider's account to the logged-in user's. Having logged in here, second factor
+// included, they have proven both accounts are theirs.
+#[tracing::instrument(name = "Linking federated identity", skip_all)]
+async fn link_identity(
+    state: &AppState,
+    email: &str,
+    issuer: &str,
+    subject: &str,
+) -> Result<(), AuthAPIError> {
+    let mut user_store = state.user_store.write().await;
+
+    match user_store.get_federated_user(issuer, subject).await {
+        Ok(user) if user.email.as_ref().expose_secret() == email => return Ok(()),
+        Ok(_) => {
+            return Err(AuthAPIError::FederatedLoginFailed(eyre!(
+                "identity is linked to another account"
+            )))
+        }
+        Err(UserStoreError::UserNotFound) => {}
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    user_store
+        .link_federated_identity(email, issuer, subject)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
+}
+
+#[tracing::instrument(name = "Finding or creating federated user", skip_all)]
+async fn find_or_create_user(
+    state: &AppState,
+    issuer: &str,
+    claims: &FederatedIdTokenClaims,
+) -> Result<User, AuthAPIError> {
+    let mut user_store = state.user_store.write().await;
+
+    match user_store.get_federated_user(issuer, &claims.sub).await {
+        Ok(user) => return Ok(user),
+        Err(UserStoreError::UserNotFound) => {}
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    let email = match &claims.email {
+        Some(email) if claims.email_verified => email,
+        _ => return Err(AuthAPIError::EmailNotVerified),
+    };
+    let email = Email::parse(Secret::new(email.to_owned()))
+        .map_err(AuthAPIError::FederatedLoginFailed)?;
+    let email_str = email.as_ref().expose_secret();
+
+    // An existing account is never linked here: the provider vouching for the address says
+    // nothing about who holds the account's password and second factor. Its owner has to log in
+    // first and link the provider from there.
+    match user_store.get_user(email_str).await {
+        Ok(_) => return Err(AuthAPIError::FederatedIdentityNotLinked),
+        Err(UserStoreError::UserNotFound) => {}
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+    if is_email_reserved(&**user_store, email_str).await? {
+        return Err(AuthAPIError::UserAlreadyExists);
+    }
+
+    // Federated users have no usable password until they set one through forgot-password.
+    let password = random_password().map_err(AuthAPIError::UnexpectedError)?;
+    let mut user = User::new(email.clone(), password, TwoFAMethod::None);
+    user.email_verified = true;
+    user_store
+        .add_user(user.clone())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    user_store
+        .link_federated_identity(email_str, issuer, &claims.sub)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(user)
+}
+
+fn callback_uri() -> String {
+    format!(
+        "{}{}",
+        AUTH_SERVICE_URL.trim_end_matches('/'),
+        FEDERATED_LOGIN_CALLBACK_PATH
+    )
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE64URL_NOPAD.encode(&bytes)
+}
+
+fn random_password() -> Result<Password> {
+    Password::parse(Secret::new(random_token()))
+}
+
+fn create_pending_login_cookie(pending_login: &PendingFederatedLogin) -> Result<Cookie<'static>> {
+    let value = serde_json::to_vec(pending_login).wrap_err("failed to serialize federated login")?;
+    Ok(Cookie::build((FEDERATED_LOGIN_COOKIE_NAME, BASE64URL_NOPAD.encode(&value)))
+        .path(FEDERATED_LOGIN_PATH)
+        .http_only(true)
+        // Lax, so that the cookie is sent on the provider's top-level redirect back to us.
+        .same_site(SameSite::Lax)
+        .build())
+}
+
+fn parse_pending_login_cookie(value: &str) -> Result<PendingFederatedLogin> {
+    let value = BASE64URL_NOPAD
+        .decode(value.as_bytes())
+        .wrap_err("invalid federated login cookie")?;
+    serde_json::from_slice(&value).wrap_err("invalid federated login cookie")
+}
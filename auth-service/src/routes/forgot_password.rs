@@ -0,0 +1,81 @@
+use std::net::SocketAddr;
+use axum::{extract::{ConnectInfo, State}, http::StatusCode, Extension, Json};
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use crate::{
+    app_state::AppState,
+    domain::{
+        data_stores::{PasswordResetToken, UserStoreError},
+        AuditAction, AuthAPIError, Email,
+    },
+    utils::{
+        audit::AuditContext,
+        constants::{AUTH_SERVICE_URL, PASSWORD_RESET_TOKEN_TTL_SECONDS},
+        rate_limit::enforce_rate_limits,
+    },
+};
+
+#[derive(Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: Secret<String>,
+}
+
+#[tracing::instrument(name = "Forgot password", skip_all)]
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Extension(audit): Extension<AuditContext>,
+    Json(request): Json<ForgotPasswordRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::ForgotPassword);
+
+    let email = Email::parse(request.email)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    audit.set_actor(email.as_ref().expose_secret());
+
+    enforce_rate_limits(
+        &state.rate_limit_store,
+        "forgot_password",
+        &state.rate_limits.forgot_password,
+        addr.ip(),
+        email.as_ref().expose_secret(),
+    )
+    .await?;
+
+    // Answer the same way whether or not the account exists so the route can't be used to enumerate users.
+    match state.user_store.read().await.get_user(email.as_ref().expose_secret()).await {
+        Ok(_) => {}
+        Err(UserStoreError::UserNotFound) => return Ok(StatusCode::OK),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    send_password_reset_email(&state, &email).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "Sending password reset email", skip_all)]
+pub async fn send_password_reset_email(state: &AppState, email: &Email) -> Result<(), AuthAPIError> {
+    let token = PasswordResetToken::default();
+
+    state
+        .password_reset_token_store
+        .write()
+        .await
+        .add_token(token.clone(), email.clone())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let content = format!(
+        "Use the link below to choose a new password. It expires in {} minutes.\n\n{}/reset-password?token={}",
+        PASSWORD_RESET_TOKEN_TTL_SECONDS / 60,
+        AUTH_SERVICE_URL.as_str(),
+        token.as_ref().expose_secret()
+    );
+
+    state
+        .email_client
+        .send_email(email, "Reset your password", &content)
+        .await
+        .map_err(AuthAPIError::UnexpectedError)
+}
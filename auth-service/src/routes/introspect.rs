@@ -0,0 +1,147 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    Extension, Form, Json,
+};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use crate::{
+    app_state::AppState,
+    domain::{error::OAuthAPIError, ApiKeySecret, AuthAPIError},
+    routes::{verify_api_key, verify_jwt, VerifiedToken},
+    utils::{
+        audit::AuditContext,
+        oauth::{authenticate_client, parse_bearer_token, validate_service_token},
+    },
+};
+
+#[derive(Deserialize)]
+pub struct IntrospectionRequest {
+    pub token: Option<String>,
+    // Accepted as RFC 7662 requires, but not needed: every kind of token is recognisable.
+    pub token_type_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<Secret<String>>,
+}
+
+// RFC 7662 section 2.2. Inactive tokens only get `active: false`, whatever the reason. `roles`
+// and `permissions` are only given for users' auth tokens; `scope` is space-separated.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IntrospectionResponse {
+    pub active: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roles: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Vec<String>>,
+}
+
+impl From<VerifiedToken> for IntrospectionResponse {
+    fn from(token: VerifiedToken) -> Self {
+        match token {
+            VerifiedToken::User(claims) => Self {
+                active: true,
+                sub: Some(claims.sub),
+                exp: Some(claims.exp as i64),
+                iat: Some(claims.iat as i64),
+                roles: Some(claims.roles),
+                permissions: Some(claims.permissions),
+                ..Self::default()
+            },
+            VerifiedToken::Service(claims) => Self {
+                active: true,
+                sub: Some(claims.sub),
+                exp: Some(claims.exp as i64),
+                iat: Some(claims.iat as i64),
+                scope: Some(claims.scope),
+                client_id: Some(claims.client_id),
+                ..Self::default()
+            },
+            VerifiedToken::UserAccess(claims) => Self {
+                active: true,
+                sub: Some(claims.sub),
+                exp: Some(claims.exp as i64),
+                iat: Some(claims.iat as i64),
+                scope: Some(claims.scope),
+                client_id: Some(claims.client_id),
+                aud: Some(claims.aud),
+                ..Self::default()
+            },
+            VerifiedToken::ApiKey(key) => Self {
+                active: true,
+                sub: Some(key.email.as_ref().expose_secret().clone()),
+                exp: Some(key.expires_at),
+                iat: Some(key.created_at),
+                scope: Some(key.scopes.join(" ")),
+                ..Self::default()
+            },
+        }
+    }
+}
+
+// Token introspection (RFC 7662), which tells a relying service whether a token is active and
+// what it says, so the service doesn't have to decode tokens itself. Auth tokens, service tokens
+// and API keys are all accepted, with the same checks as `verify_token`. Only services may call
+// it, so tokens can't be probed anonymously.
+#[tracing::instrument(name = "Introspect", skip_all)]
+pub async fn introspect(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Extension(audit): Extension<AuditContext>,
+    Form(request): Form<IntrospectionRequest>,
+) -> Result<impl IntoResponse, OAuthAPIError> {
+    let caller = authenticate_caller(&state, &headers, request.client_id, request.client_secret).await?;
+    audit.set_actor(&caller);
+
+    let token = request
+        .token
+        .ok_or(OAuthAPIError::InvalidRequest("token is required"))?;
+    let result = match ApiKeySecret::parse(Secret::new(token.clone())) {
+        Ok(secret) => verify_api_key(&state, &secret).await,
+        Err(_) => verify_jwt(&state, &token).await,
+    };
+
+    let response = match result {
+        Ok(token) => IntrospectionResponse::from(token),
+        Err(AuthAPIError::UnexpectedError(e)) => return Err(OAuthAPIError::UnexpectedError(e)),
+        Err(_) => IntrospectionResponse::default(),
+    };
+
+    Ok(([(header::CACHE_CONTROL, "no-store")], Json(response)))
+}
+
+// The caller is a confidential client, authenticating either with its credentials, as at the
+// token endpoint, or with a service token it got from the client-credentials grant. Returns its
+// client ID.
+async fn authenticate_caller(
+    state: &AppState,
+    headers: &HeaderMap,
+    client_id: Option<String>,
+    client_secret: Option<Secret<String>>,
+) -> Result<String, OAuthAPIError> {
+    if let Some(token) = parse_bearer_token(headers) {
+        let claims = validate_service_token(token, &state.oauth_client_store)
+            .await
+            .map_err(|_| OAuthAPIError::InvalidClient)?;
+        return Ok(claims.client_id);
+    }
+
+    let client = authenticate_client(&state.oauth_client_store, headers, client_id, client_secret).await?;
+    if !client.is_confidential() {
+        return Err(OAuthAPIError::InvalidClient);
+    }
+
+    Ok(client.client_id)
+}
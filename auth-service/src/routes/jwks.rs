@@ -0,0 +1,10 @@
+use axum::Json;
+use jsonwebtoken::jwk::JwkSet;
+use crate::utils::keyring::current_keyring;
+
+// Public keys that auth tokens are signed with, for services that verify tokens themselves.
+// Keys being rotated out stay listed until they are retired.
+#[tracing::instrument(name = "JWKS", skip_all)]
+pub async fn jwks() -> Json<JwkSet> {
+    Json(current_keyring().jwks())
+}
@@ -1,15 +1,20 @@
-use axum::{extract::State, Json};
-use axum::http::StatusCode;
+use std::net::{IpAddr, SocketAddr};
+use axum::{extract::{ConnectInfo, State}, Extension, Json};
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
 use axum_extra::extract::CookieJar;
 use serde::{Deserialize, Serialize};
 use secrecy::{Secret, ExposeSecret};
 use crate::{
     app_state::AppState,
-    domain::{error::AuthAPIError, Email, data_stores::{LoginAttemptId, TwoFACode, TwoFACodeStore}},
-    utils::auth::generate_auth_cookie,
+    domain::{error::AuthAPIError, AuditAction, AuthenticationMethod, Email, TwoFAMethod, data_stores::{LoginAttemptId, TwoFACode}},
+    utils::{
+        audit::AuditContext,
+        auth::{generate_auth_cookie, generate_refresh_cookie},
+        rate_limit::enforce_rate_limits,
+        session::start_session,
+    },
 };
-use color_eyre::eyre::eyre;
 
 #[derive(Deserialize)]
 pub struct LoginRequest {
@@ -29,14 +34,21 @@ pub struct TwoFactorAuthResponse {
     pub message: String,
     #[serde(rename = "loginAttemptId")]
     pub login_attempt_id: String,
+    #[serde(rename = "twoFAMethod")]
+    pub two_fa_method: String,
 }
 
 #[tracing::instrument(name = "Login", skip_all)]
 pub async fn login(
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Extension(audit): Extension<AuditContext>,
     jar: CookieJar,
     Json(request): Json<LoginRequest>,
 ) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    audit.set_action(AuditAction::Login);
+
     if request.email.trim().is_empty() || request.password.expose_secret().trim().is_empty() {
         return (jar, Err(AuthAPIError::InvalidCredentials));
     }
@@ -46,9 +58,22 @@ pub async fn login(
     }
 
     let email = request.email.as_str();
+    audit.set_actor(email);
+
+    if let Err(e) = enforce_rate_limits(
+        &state.rate_limit_store,
+        "login",
+        &state.rate_limits.login,
+        addr.ip(),
+        email,
+    )
+    .await
+    {
+        return (jar, Err(e));
+    }
 
     let user_store = state.user_store.read().await;
-    if let Err(_) = user_store.validate_user(email, request.password.expose_secret()).await {
+    if user_store.validate_user(email, request.password.expose_secret()).await.is_err() {
         return (jar, Err(AuthAPIError::IncorrectCredentials));
     }
 
@@ -57,63 +82,110 @@ pub async fn login(
         Err(_) => return (jar, Err(AuthAPIError::IncorrectCredentials)),
     };
 
+    // Also checked when the session starts; checking here too saves sending a 2FA code.
+    match user_store.is_user_locked(email).await {
+        Ok(false) => {}
+        Ok(true) => return (jar, Err(AuthAPIError::AccountLocked)),
+        Err(e) => return (jar, Err(AuthAPIError::UnexpectedError(e.into()))),
+    }
+
     drop(user_store);
 
-    match user.requires_2fa {
-        true => handle_2fa(&user.email, &state, jar).await,  
-        false => handle_no_2fa(&user.email, jar).await,    
+    if !user.email_verified {
+        return (jar, Err(AuthAPIError::EmailNotVerified));
+    }
+
+    match user.two_fa_method {
+        TwoFAMethod::None => handle_no_2fa(&user.email, &state, jar, addr.ip(), &headers).await,
+        ref method => handle_2fa(&user.email, method, &state, &audit, jar).await,
     }
 }
 
 #[tracing::instrument(name = "Handle 2FA", skip_all)]
 async fn handle_2fa(
     email: &Email,
+    method: &TwoFAMethod,
     state: &AppState,
+    audit: &AuditContext,
     jar: CookieJar,
 ) -> (
     CookieJar,
     Result<(StatusCode, Json<LoginResponse>), AuthAPIError>,
 ) {
-    let login_attempt_id = LoginAttemptId::default();
-    let two_fa_code = TwoFACode::default();
-
-    {
-        let mut two_fa_store = state.two_fa_code_store.write().await;
-        if let Err(e) = two_fa_store.add_code(email.clone(), login_attempt_id.clone(), two_fa_code.clone()).await {
-            return (jar, Err(AuthAPIError::UnexpectedError(e.into())));
-        }
-    }
-
-    if let Err(e) = state
-        .email_client
-        .send_email(email, "2FA Code", two_fa_code.as_ref().expose_secret())
-        .await
-    {
-        return (jar, Err(AuthAPIError::UnexpectedError(e.into())));
-    }
+    let login_attempt_id = match start_2fa(state, email, method).await {
+        Ok(login_attempt_id) => login_attempt_id,
+        Err(e) => return (jar, Err(e)),
+    };
+    // The password was right, but the login isn't done until `verify_2fa` checks the code.
+    audit.set_action(AuditAction::LoginTwoFARequired);
 
     let response = Json(LoginResponse::TwoFactorAuth(TwoFactorAuthResponse {
         message: "2FA required".to_owned(),
         login_attempt_id: login_attempt_id.as_ref().expose_secret().clone(),
+        two_fa_method: method.as_str().to_owned(),
     }));
 
     (jar, Ok((StatusCode::PARTIAL_CONTENT, response)))
 }
 
+// Starts the second step of a login: stores a fresh code and, for email 2FA, sends it. The
+// returned login attempt ID has to be presented along with the code to `verify_2fa`.
+#[tracing::instrument(name = "Start 2FA", skip_all)]
+pub(crate) async fn start_2fa(
+    state: &AppState,
+    email: &Email,
+    method: &TwoFAMethod,
+) -> Result<LoginAttemptId, AuthAPIError> {
+    let login_attempt_id = LoginAttemptId::default();
+    // With TOTP the stored code is never sent; `verify_2fa` checks against the user's secret instead.
+    let two_fa_code = TwoFACode::default();
+
+    state
+        .two_fa_code_store
+        .write()
+        .await
+        .add_code(email.clone(), login_attempt_id.clone(), two_fa_code.clone())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    if *method == TwoFAMethod::Email {
+        state
+            .email_client
+            .send_email(email, "2FA Code", two_fa_code.as_ref().expose_secret())
+            .await
+            .map_err(AuthAPIError::UnexpectedError)?;
+    }
+
+    Ok(login_attempt_id)
+}
+
 #[tracing::instrument(name = "Handle no 2FA", skip_all)]
 async fn handle_no_2fa(
     email: &Email,
+    state: &AppState,
     jar: CookieJar,
+    ip: IpAddr,
+    headers: &HeaderMap,
 ) -> (
     CookieJar,
     Result<(StatusCode, Json<LoginResponse>), AuthAPIError>,
 ) {
-    let auth_cookie = match generate_auth_cookie(email) {
+    let authentication = match start_session(state, email, vec![AuthenticationMethod::Pwd], ip, headers).await {
+        Ok(authentication) => authentication,
+        Err(e) => return (jar, Err(e)),
+    };
+
+    let auth_cookie = match generate_auth_cookie(email, &authentication) {
         Ok(cookie) => cookie,
-        Err(e) => return (jar, Err(AuthAPIError::UnexpectedError(e.into()))),
+        Err(e) => return (jar, Err(AuthAPIError::UnexpectedError(e))),
+    };
+
+    let refresh_cookie = match generate_refresh_cookie(email, &authentication, state.refresh_token_store.clone()).await {
+        Ok(cookie) => cookie,
+        Err(e) => return (jar, Err(AuthAPIError::UnexpectedError(e))),
     };
 
-    let updated_jar = jar.add(auth_cookie);
+    let updated_jar = jar.add(auth_cookie).add(refresh_cookie);
     (
         updated_jar,
         Ok((StatusCode::OK, Json(LoginResponse::RegularAuth))),
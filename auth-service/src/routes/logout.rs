@@ -1,27 +1,46 @@
-use axum::{extract::State, http::StatusCode};
+use axum::{extract::State, http::StatusCode, Extension};
 use axum_extra::extract::CookieJar;
 use secrecy::Secret;
 use crate::app_state::AppState;
-use crate::domain::error::AuthAPIError;
-use crate::utils::constants::JWT_COOKIE_NAME;
-use crate::utils::auth::validate_token;
+use crate::domain::{data_stores::RefreshToken, error::AuthAPIError, AuditAction, Email};
+use crate::utils::constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME};
+use crate::utils::{audit::AuditContext, auth::validate_token, session::end_session};
 
 #[tracing::instrument(name = "Logout", skip_all)]
 pub async fn logout(
     State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
     jar: CookieJar,
 ) -> Result<(CookieJar, StatusCode), AuthAPIError> {
+    audit.set_action(AuditAction::Logout);
+
     let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
     let token = cookie.value().to_string();
 
-    validate_token(&token, state.banned_token_store.clone()).await
-        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    let claims = validate_token(&token, state.banned_token_store.clone(), state.user_store.clone()).await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
 
     let mut banned_store = state.banned_token_store.write().await;
     banned_store.add_token(Secret::new(token)).await
         .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
     drop(banned_store);
 
-    let updated_jar = jar.remove(JWT_COOKIE_NAME);
+    let email = Email::parse(Secret::new(claims.sub))
+        .map_err(AuthAPIError::UnexpectedError)?;
+    match end_session(&state, &email, &claims.jti).await {
+        Ok(()) | Err(AuthAPIError::SessionNotFound) => {}
+        Err(e) => return Err(e),
+    }
+
+    // A malformed refresh token can't match anything in the store, so there is nothing to revoke.
+    if let Some(refresh_token) = jar
+        .get(REFRESH_TOKEN_COOKIE_NAME)
+        .and_then(|cookie| RefreshToken::parse(cookie.value().to_owned()).ok())
+    {
+        state.refresh_token_store.write().await.revoke_token(&refresh_token).await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    }
+
+    let updated_jar = jar.remove(JWT_COOKIE_NAME).remove(REFRESH_TOKEN_COOKIE_NAME);
     Ok((updated_jar, StatusCode::OK))
-}
\ No newline at end of file
+}
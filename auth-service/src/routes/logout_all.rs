@@ -0,0 +1,34 @@
+use axum::{extract::State, http::StatusCode, Extension};
+use axum_extra::extract::CookieJar;
+use secrecy::Secret;
+use crate::{
+    app_state::AppState,
+    domain::{AuditAction, AuthAPIError, Email},
+    utils::{
+        audit::AuditContext,
+        auth::validate_token,
+        constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+        session::revoke_all_tokens,
+    },
+};
+
+// Logs the user out of every session, on every device, including the current one.
+#[tracing::instrument(name = "Logout all", skip_all)]
+pub async fn logout_all(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+) -> Result<(CookieJar, StatusCode), AuthAPIError> {
+    audit.set_action(AuditAction::LogoutAll);
+
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let claims = validate_token(cookie.value(), state.banned_token_store.clone(), state.user_store.clone()).await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    let email = Email::parse(Secret::new(claims.sub))
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    revoke_all_tokens(&state, &email).await?;
+
+    let updated_jar = jar.remove(JWT_COOKIE_NAME).remove(REFRESH_TOKEN_COOKIE_NAME);
+    Ok((updated_jar, StatusCode::OK))
+}
@@ -1,11 +1,61 @@
 pub mod login; 
 mod logout;
+mod logout_all;
 mod signup;
 mod verify_2fa;
+mod resend_2fa;
 mod verify_token;
+mod forgot_password;
+mod reset_password;
+mod verify_email;
+mod resend_verification_email;
+mod refresh;
+mod enroll_totp;
+mod confirm_totp;
+mod regenerate_recovery_codes;
+mod jwks;
+mod authorize;
+mod token;
+mod userinfo;
+mod openid_configuration;
+mod federated_login;
+mod sessions;
+mod delete_account;
+mod change_password;
+mod change_email;
+mod enable_2fa;
+mod disable_2fa;
+mod admin;
+mod api_keys;
+mod introspect;
 
 pub use login::*;
 pub use logout::*;
+pub use logout_all::*;
 pub use signup::*;
 pub use verify_2fa::*;
-pub use verify_token::*;
\ No newline at end of file
+pub use resend_2fa::*;
+pub use verify_token::*;
+pub use forgot_password::*;
+pub use reset_password::*;
+pub use verify_email::*;
+pub use resend_verification_email::*;
+pub use refresh::*;
+pub use enroll_totp::*;
+pub use confirm_totp::*;
+pub use regenerate_recovery_codes::*;
+pub use jwks::*;
+pub use authorize::*;
+pub use token::*;
+pub use userinfo::*;
+pub use openid_configuration::*;
+pub use federated_login::*;
+pub use sessions::*;
+pub use delete_account::*;
+pub use change_password::*;
+pub use change_email::*;
+pub use enable_2fa::*;
+pub use disable_2fa::*;
+pub use admin::*;
+pub use api_keys::*;
+pub use introspect::*;
\ No newline at end of file
@@ -0,0 +1,49 @@
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use crate::utils::oidc::issuer;
+
+// OpenID Provider metadata (OpenID Connect Discovery 1.0 section 3), which lets standard OIDC
+// client libraries configure themselves from the issuer URL alone.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenIdConfiguration {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    // From OAuth 2.0 Authorization Server Metadata (RFC 8414), for relying services.
+    pub introspection_endpoint: String,
+    pub userinfo_endpoint: String,
+    pub jwks_uri: String,
+    pub response_types_supported: Vec<String>,
+    pub grant_types_supported: Vec<String>,
+    pub subject_types_supported: Vec<String>,
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    pub scopes_supported: Vec<String>,
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+    pub code_challenge_methods_supported: Vec<String>,
+    pub claims_supported: Vec<String>,
+}
+
+#[tracing::instrument(name = "OpenID configuration", skip_all)]
+pub async fn openid_configuration() -> Json<OpenIdConfiguration> {
+    let issuer = issuer();
+    let strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect();
+
+    Json(OpenIdConfiguration {
+        authorization_endpoint: format!("{}/authorize", issuer),
+        token_endpoint: format!("{}/token", issuer),
+        introspection_endpoint: format!("{}/introspect", issuer),
+        userinfo_endpoint: format!("{}/userinfo", issuer),
+        jwks_uri: format!("{}/.well-known/jwks.json", issuer),
+        issuer,
+        response_types_supported: strings(&["code"]),
+        grant_types_supported: strings(&["authorization_code", "client_credentials"]),
+        subject_types_supported: strings(&["public"]),
+        id_token_signing_alg_values_supported: strings(&["EdDSA"]),
+        scopes_supported: strings(&["openid", "email"]),
+        token_endpoint_auth_methods_supported: strings(&["client_secret_basic", "client_secret_post", "none"]),
+        code_challenge_methods_supported: strings(&["S256"]),
+        claims_supported: strings(&[
+            "iss", "sub", "aud", "exp", "iat", "auth_time", "amr", "nonce", "email", "email_verified",
+        ]),
+    })
+}
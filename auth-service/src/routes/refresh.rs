@@ -0,0 +1,71 @@
+use axum::{extract::State, http::StatusCode, Extension};
+use axum_extra::extract::CookieJar;
+use secrecy::ExposeSecret;
+use crate::{
+    app_state::AppState,
+    domain::{
+        data_stores::{RefreshToken, RefreshTokenStoreError, SessionStoreError},
+        AuditAction, AuthAPIError,
+    },
+    utils::{
+        audit::AuditContext,
+        auth::{create_refresh_cookie, generate_auth_cookie},
+        constants::REFRESH_TOKEN_COOKIE_NAME,
+        session::load_access,
+    },
+};
+
+#[tracing::instrument(name = "Refresh", skip_all)]
+pub async fn refresh(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+) -> Result<(CookieJar, StatusCode), AuthAPIError> {
+    audit.set_action(AuditAction::RefreshToken);
+
+    let cookie = jar.get(REFRESH_TOKEN_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let token = RefreshToken::parse(cookie.value().to_owned())
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let new_token = RefreshToken::default();
+    let (email, mut authentication) = state
+        .refresh_token_store
+        .write()
+        .await
+        .rotate_token(&token, new_token.clone())
+        .await
+        .map_err(|e| match e {
+            RefreshTokenStoreError::TokenNotFound => AuthAPIError::InvalidToken,
+            RefreshTokenStoreError::TokenReused => {
+                tracing::warn!("Refresh token reused, token family revoked");
+                AuthAPIError::InvalidToken
+            }
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+    audit.set_actor(email.as_ref().expose_secret());
+
+    // The session may have been ended since the refresh token was issued.
+    match state
+        .session_store
+        .write()
+        .await
+        .extend_session(&email, &authentication.session_id)
+        .await
+    {
+        Ok(()) => {}
+        Err(SessionStoreError::SessionNotFound) => {
+            state.refresh_token_store.write().await.revoke_token(&new_token).await
+                .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+            return Err(AuthAPIError::InvalidToken);
+        }
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    load_access(&state, &email, &mut authentication).await?;
+
+    let auth_cookie = generate_auth_cookie(&email, &authentication)
+        .map_err(AuthAPIError::UnexpectedError)?;
+    let updated_jar = jar.add(auth_cookie).add(create_refresh_cookie(&new_token));
+
+    Ok((updated_jar, StatusCode::OK))
+}
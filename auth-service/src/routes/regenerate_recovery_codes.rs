@@ -0,0 +1,72 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use axum_extra::extract::CookieJar;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use crate::{
+    app_state::{AppState, UserStoreType},
+    domain::{
+        data_stores::{RecoveryCode, UserStoreError},
+        AuditAction, AuthAPIError,
+    },
+    utils::{
+        audit::AuditContext,
+        auth::validate_token,
+        constants::{JWT_COOKIE_NAME, RECOVERY_CODE_COUNT},
+    },
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoveryCodesResponse {
+    #[serde(rename = "recoveryCodes")]
+    pub recovery_codes: Vec<String>,
+}
+
+impl From<Vec<RecoveryCode>> for RecoveryCodesResponse {
+    fn from(codes: Vec<RecoveryCode>) -> Self {
+        Self {
+            recovery_codes: codes
+                .iter()
+                .map(|code| code.as_ref().expose_secret().clone())
+                .collect(),
+        }
+    }
+}
+
+#[tracing::instrument(name = "Regenerate recovery codes", skip_all)]
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+) -> Result<(StatusCode, Json<RecoveryCodesResponse>), AuthAPIError> {
+    audit.set_action(AuditAction::RegenerateRecoveryCodes);
+
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let claims = validate_token(cookie.value(), state.banned_token_store.clone(), state.user_store.clone()).await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let codes = generate_recovery_codes(&state.user_store, &claims.sub)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok((StatusCode::OK, Json(codes.into())))
+}
+
+// Replaces the user's recovery codes with a fresh set and returns them in plain text.
+// This is the only time the codes are available, only their hashes are stored.
+#[tracing::instrument(name = "Generating recovery codes", skip_all)]
+pub async fn generate_recovery_codes(
+    user_store: &UserStoreType,
+    email: &str,
+) -> Result<Vec<RecoveryCode>, UserStoreError> {
+    let codes: Vec<RecoveryCode> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| RecoveryCode::default())
+        .collect();
+
+    user_store
+        .write()
+        .await
+        .set_recovery_codes(email, codes.clone())
+        .await?;
+
+    Ok(codes)
+}
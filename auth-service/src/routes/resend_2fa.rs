@@ -0,0 +1,72 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use crate::{
+    app_state::AppState,
+    domain::{data_stores::{LoginAttemptId, TwoFACodeStoreError}, AuditAction, AuthAPIError, Email, TwoFAMethod},
+    utils::{
+        audit::AuditContext,
+        constants::{MAX_2FA_RESENDS, TWO_FA_RESEND_COOLDOWN_SECONDS},
+    },
+};
+
+#[derive(Deserialize)]
+pub struct Resend2FARequest {
+    pub email: String,
+    #[serde(rename = "loginAttemptId")]
+    pub login_attempt_id: String,
+}
+
+#[tracing::instrument(name = "Resend 2FA code", skip_all)]
+pub async fn resend_2fa(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Json(request): Json<Resend2FARequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::ResendTwoFA);
+
+    let email = Email::parse(Secret::new(request.email))
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    audit.set_actor(email.as_ref().expose_secret());
+
+    let login_attempt_id = LoginAttemptId::parse(request.login_attempt_id)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let (stored_login_attempt_id, stored_two_fa_code) = state.two_fa_code_store.read().await
+        .get_code(&email).await
+        .map_err(|_| AuthAPIError::IncorrectCredentials)?;
+
+    if login_attempt_id != stored_login_attempt_id {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    // Authenticator app codes are never emailed, so there is nothing to resend.
+    let user = state.user_store.read().await.get_user(email.as_ref().expose_secret()).await
+        .map_err(|_| AuthAPIError::IncorrectCredentials)?;
+    if user.two_fa_method != TwoFAMethod::Email {
+        return Err(AuthAPIError::InvalidCredentials);
+    }
+
+    // The same code is sent again rather than a new one, so resending doesn't reset the count of
+    // failed attempts against it.
+    let resend_result = state.two_fa_code_store.write().await
+        .record_resend(&email, TWO_FA_RESEND_COOLDOWN_SECONDS, MAX_2FA_RESENDS)
+        .await;
+    match resend_result {
+        Ok(()) => {}
+        Err(TwoFACodeStoreError::LoginAttemptIdNotFound) => return Err(AuthAPIError::IncorrectCredentials),
+        Err(TwoFACodeStoreError::ResendCooldown { retry_after_seconds }) => {
+            return Err(AuthAPIError::TooManyRequests(retry_after_seconds))
+        }
+        Err(TwoFACodeStoreError::TooManyResends) => return Err(AuthAPIError::TooMany2FAResends),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    state
+        .email_client
+        .send_email(&email, "2FA Code", stored_two_fa_code.as_ref().expose_secret())
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    Ok(StatusCode::OK)
+}
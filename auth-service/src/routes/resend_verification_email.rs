@@ -0,0 +1,44 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use crate::{
+    app_state::AppState,
+    domain::{data_stores::UserStoreError, AuditAction, AuthAPIError, Email},
+    routes::send_verification_email,
+    utils::audit::AuditContext,
+};
+
+#[derive(Deserialize)]
+pub struct ResendVerificationEmailRequest {
+    pub email: Secret<String>,
+}
+
+#[tracing::instrument(name = "Resend verification email", skip_all)]
+pub async fn resend_verification_email(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Json(request): Json<ResendVerificationEmailRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::ResendVerificationEmail);
+
+    let email = Email::parse(request.email)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    audit.set_actor(email.as_ref().expose_secret());
+
+    // Answer the same way whether or not the account exists so the route can't be used to enumerate users.
+    let user = match state.user_store.read().await.get_user(email.as_ref().expose_secret()).await {
+        Ok(user) => user,
+        Err(UserStoreError::UserNotFound) => return Ok(StatusCode::OK),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    if user.email_verified {
+        return Ok(StatusCode::OK);
+    }
+
+    send_verification_email(&state.email_client, &user.email)
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    Ok(StatusCode::OK)
+}
@@ -0,0 +1,81 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use chrono::Utc;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use crate::{
+    app_state::AppState,
+    domain::{
+        data_stores::{PasswordResetToken, PasswordResetTokenStoreError},
+        AuditAction, AuthAPIError, Password,
+    },
+    utils::audit::AuditContext,
+};
+
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    #[serde(rename = "newPassword")]
+    pub new_password: Secret<String>,
+}
+
+#[tracing::instrument(name = "Reset password", skip_all)]
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Json(request): Json<ResetPasswordRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::ResetPassword);
+
+    // Validate the new password first so a rejected password doesn't burn the token.
+    let password = Password::parse(request.new_password)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let token = PasswordResetToken::parse(request.token)
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let email = state
+        .password_reset_token_store
+        .write()
+        .await
+        .consume_token(&token)
+        .await
+        .map_err(|e| match e {
+            PasswordResetTokenStoreError::TokenNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+    audit.set_actor(email.as_ref().expose_secret());
+
+    state
+        .user_store
+        .write()
+        .await
+        .update_password(email.as_ref().expose_secret(), password)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .banned_token_store
+        .write()
+        .await
+        .revoke_tokens_issued_before(&email, Utc::now().timestamp())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_user_tokens(&email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .session_store
+        .write()
+        .await
+        .remove_all_sessions(&email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(StatusCode::OK)
+}
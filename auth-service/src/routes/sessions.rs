@@ -0,0 +1,89 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use axum_extra::extract::CookieJar;
+use secrecy::Secret;
+use serde::{Deserialize, Serialize};
+use crate::{
+    app_state::AppState,
+    domain::{AuditAction, AuthAPIError, Email},
+    utils::{
+        audit::AuditContext,
+        auth::{validate_token, Claims},
+        constants::JWT_COOKIE_NAME,
+        session::end_session,
+    },
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionsResponse {
+    pub sessions: Vec<SessionResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResponse {
+    pub id: String,
+    pub created_at: i64,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    // Whether this is the session the request was made from.
+    pub current: bool,
+}
+
+#[tracing::instrument(name = "List sessions", skip_all)]
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    jar: CookieJar,
+) -> Result<(StatusCode, Json<SessionsResponse>), AuthAPIError> {
+    let (claims, email) = authenticate(&state, &jar).await?;
+
+    let sessions = state
+        .session_store
+        .read()
+        .await
+        .get_sessions(&email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let sessions = sessions
+        .into_iter()
+        .map(|session| SessionResponse {
+            current: session.id == claims.jti,
+            id: session.id,
+            created_at: session.created_at,
+            ip: session.ip,
+            user_agent: session.user_agent,
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(SessionsResponse { sessions })))
+}
+
+// Logs out one of the user's sessions, possibly the current one.
+#[tracing::instrument(name = "Revoke session", skip_all)]
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    jar: CookieJar,
+    Path(session_id): Path<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::RevokeSession);
+
+    let (_, email) = authenticate(&state, &jar).await?;
+
+    end_session(&state, &email, &session_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn authenticate(state: &AppState, jar: &CookieJar) -> Result<(Claims, Email), AuthAPIError> {
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let claims = validate_token(cookie.value(), state.banned_token_store.clone(), state.user_store.clone()).await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    let email = Email::parse(Secret::new(claims.sub.clone()))
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    Ok((claims, email))
+}
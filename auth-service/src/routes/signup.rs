@@ -1,12 +1,14 @@
-use axum::{extract::State, Json};
+use axum::{extract::State, Extension, Json};
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::response::{IntoResponse, Response};
 use serde::Deserialize;
-use secrecy::{Secret, ExposeSecret};
+use secrecy::{ExposeSecret, Secret};
 use color_eyre::eyre::eyre;
 use crate::app_state::AppState;
 use crate::domain::error::AuthAPIError;
-use crate::domain::{user::User, Email, Password};
+use crate::domain::{user::{TwoFAMethod, User}, AuditAction, Email, Password};
+use crate::routes::{generate_recovery_codes, is_email_reserved, send_verification_email, RecoveryCodesResponse};
+use crate::utils::audit::AuditContext;
 
 #[derive(Deserialize)]
 pub struct SignupRequest {
@@ -19,23 +21,46 @@ pub struct SignupRequest {
 #[tracing::instrument(name = "Signup", skip_all, err(Debug))]
 pub async fn signup(
     State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
     Json(request): Json<SignupRequest>,
-) -> Result<impl IntoResponse, AuthAPIError> {
+) -> Result<Response, AuthAPIError> {
+    audit.set_action(AuditAction::Signup);
+
     let email = Email::parse(request.email)
         .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    audit.set_actor(email.as_ref().expose_secret());
     
     let password = Password::parse(request.password)
         .map_err(|_| AuthAPIError::InvalidCredentials)?;
 
     let mut user_store = state.user_store.write().await;
-    let user = User::new(email, password, request.requires_2fa);
-    
+    // Authenticator apps can only be set up once the account exists, see `enroll_totp`.
+    let two_fa_method = if request.requires_2fa { TwoFAMethod::Email } else { TwoFAMethod::None };
+    let user = User::new(email.clone(), password, two_fa_method.clone());
+
+    if is_email_reserved(&**user_store, email.as_ref().expose_secret()).await? {
+        return Err(AuthAPIError::UserAlreadyExists);
+    }
     if let Err(e) = user_store.add_user(user).await {
         return Err(match e {
             crate::domain::data_stores::UserStoreError::UserAlreadyExists => AuthAPIError::UserAlreadyExists,
-            _ => AuthAPIError::UnexpectedError(eyre!("User store error: {}", e).into()),
+            _ => AuthAPIError::UnexpectedError(eyre!("User store error: {}", e)),
         });
     }
+    drop(user_store);
+
+    // The account exists at this point; if the email can't be sent the user can ask for it again.
+    if let Err(e) = send_verification_email(&state.email_client, &email).await {
+        tracing::error!("Failed to send verification email: {:?}", e);
+    }
     
-    Ok(StatusCode::CREATED)
+    if two_fa_method == TwoFAMethod::None {
+        return Ok(StatusCode::CREATED.into_response());
+    }
+
+    let recovery_codes = generate_recovery_codes(&state.user_store, email.as_ref().expose_secret())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok((StatusCode::CREATED, Json(RecoveryCodesResponse::from(recovery_codes))).into_response())
 }
\ No newline at end of file
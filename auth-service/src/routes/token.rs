@@ -0,0 +1,166 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    Extension, Form, Json,
+};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use crate::{
+    app_state::AppState,
+    domain::{
+        data_stores::AuthorizationCodeStoreError,
+        error::OAuthAPIError,
+        oauth::AuthorizationCode,
+        AuditAction,
+    },
+    utils::{
+        audit::AuditContext,
+        auth::TOKEN_TTL_SECONDS,
+        oauth::{authenticate_client, generate_service_token, generate_user_access_token, SERVICE_TOKEN_TTL_SECONDS},
+        oidc::generate_id_token,
+    },
+};
+
+#[derive(Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: Option<String>,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<Secret<String>>,
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+}
+
+// Token endpoint (RFC 6749 section 3.2). All access tokens are verifiable with the published
+// JWKS.
+#[tracing::instrument(name = "Token", skip_all)]
+pub async fn token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Extension(audit): Extension<AuditContext>,
+    Form(request): Form<TokenRequest>,
+) -> Result<impl IntoResponse, OAuthAPIError> {
+    audit.set_action(AuditAction::IssueToken);
+
+    let response = match request.grant_type.as_deref() {
+        Some("authorization_code") => exchange_authorization_code(&state, &headers, &audit, request).await?,
+        Some("client_credentials") => grant_client_credentials(&state, &headers, &audit, request).await?,
+        Some(_) => return Err(OAuthAPIError::UnsupportedGrantType),
+        None => return Err(OAuthAPIError::InvalidRequest("grant_type is required")),
+    };
+
+    Ok((
+        [(header::CACHE_CONTROL, "no-store"), (header::PRAGMA, "no-cache")],
+        Json(response),
+    ))
+}
+
+// Exchanges an authorization code issued by `authorize` for an access token bound to the client,
+// and for an ID token when the `openid` scope was requested.
+async fn exchange_authorization_code(
+    state: &AppState,
+    headers: &HeaderMap,
+    audit: &AuditContext,
+    request: TokenRequest,
+) -> Result<TokenResponse, OAuthAPIError> {
+    let client = authenticate_client(
+        &state.oauth_client_store,
+        headers,
+        request.client_id,
+        request.client_secret,
+    )
+    .await?;
+    audit.set_actor(&client.client_id);
+
+    let code = request
+        .code
+        .ok_or(OAuthAPIError::InvalidRequest("code is required"))?;
+    let code_verifier = request
+        .code_verifier
+        .ok_or(OAuthAPIError::InvalidRequest("code_verifier is required"))?;
+    let code = AuthorizationCode::parse(code).map_err(|_| OAuthAPIError::InvalidGrant)?;
+
+    let grant = match state.authorization_code_store.write().await.consume_code(&code).await {
+        Ok(grant) => grant,
+        Err(AuthorizationCodeStoreError::CodeNotFound) => return Err(OAuthAPIError::InvalidGrant),
+        Err(e) => return Err(OAuthAPIError::UnexpectedError(e.into())),
+    };
+
+    if grant.client_id != client.client_id
+        || request.redirect_uri.as_deref() != Some(grant.redirect_uri.as_str())
+        || !grant.code_challenge.verify(&code_verifier)
+    {
+        return Err(OAuthAPIError::InvalidGrant);
+    }
+
+    let access_token = generate_user_access_token(&client.client_id, &grant.email, &grant.authentication, grant.scope.as_deref())
+        .map_err(OAuthAPIError::UnexpectedError)?;
+
+    let id_token = if grant.is_openid() {
+        let user_store = state.user_store.read().await;
+        let email = grant.email.as_ref().expose_secret();
+        let user = user_store.get_user(email).await.map_err(|_| OAuthAPIError::InvalidGrant)?;
+        let user_id = user_store.get_user_id(email).await.map_err(|_| OAuthAPIError::InvalidGrant)?;
+        let id_token = generate_id_token(&client.client_id, &user_id, &user, &grant.authentication, grant.nonce)
+            .map_err(OAuthAPIError::UnexpectedError)?;
+        Some(id_token)
+    } else {
+        None
+    };
+
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer".to_owned(),
+        expires_in: TOKEN_TTL_SECONDS,
+        scope: grant.scope,
+        id_token,
+    })
+}
+
+// Client-credentials grant (RFC 6749 section 4.4), with which a service gets a token for itself
+// rather than for a user. Only confidential clients can use it, and no refresh token is issued;
+// the client simply asks again.
+async fn grant_client_credentials(
+    state: &AppState,
+    headers: &HeaderMap,
+    audit: &AuditContext,
+    request: TokenRequest,
+) -> Result<TokenResponse, OAuthAPIError> {
+    let client = authenticate_client(
+        &state.oauth_client_store,
+        headers,
+        request.client_id,
+        request.client_secret,
+    )
+    .await?;
+    audit.set_actor(&client.client_id);
+    if !client.is_confidential() {
+        return Err(OAuthAPIError::UnauthorizedClient);
+    }
+
+    let scopes = client
+        .grant_scopes(request.scope.as_deref())
+        .ok_or(OAuthAPIError::InvalidScope)?;
+    let access_token = generate_service_token(&client, &scopes).map_err(OAuthAPIError::UnexpectedError)?;
+
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer".to_owned(),
+        expires_in: SERVICE_TOKEN_TTL_SECONDS,
+        scope: Some(scopes.join(" ")),
+        id_token: None,
+    })
+}
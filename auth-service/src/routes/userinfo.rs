@@ -0,0 +1,61 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    Json,
+};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use crate::{
+    app_state::AppState,
+    domain::{data_stores::UserStoreError, AuthAPIError},
+    utils::{
+        auth::validate_token,
+        oauth::{is_service_token, validate_user_access_token},
+    },
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserInfoResponse {
+    pub sub: String,
+    pub email: String,
+    pub email_verified: bool,
+}
+
+// OpenID Connect UserInfo endpoint. Takes an access token from `token`, issued to any client, or
+// the user's own auth token as a bearer token (RFC 6750) and returns the claims of the user.
+#[tracing::instrument(name = "UserInfo", skip_all)]
+pub async fn userinfo(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<UserInfoResponse>, AuthAPIError> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(AuthAPIError::MissingToken)?;
+
+    let sub = if is_service_token(token) {
+        validate_user_access_token(token, &state.banned_token_store, &state.user_store)
+            .await
+            .map(|claims| claims.sub)
+    } else {
+        validate_token(token, state.banned_token_store.clone(), state.user_store.clone())
+            .await
+            .map(|claims| claims.sub)
+    }
+    .map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let user_store = state.user_store.read().await;
+    let (user, user_id) = match tokio::try_join!(user_store.get_user(&sub), user_store.get_user_id(&sub)) {
+        Ok(found) => found,
+        Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    // The same subject as in the user's ID tokens.
+    Ok(Json(UserInfoResponse {
+        sub: user_id,
+        email: user.email.as_ref().expose_secret().to_owned(),
+        email_verified: user.email_verified,
+    }))
+}
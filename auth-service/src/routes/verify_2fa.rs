@@ -1,13 +1,19 @@
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use std::net::SocketAddr;
+use axum::{extract::{ConnectInfo, State}, http::{HeaderMap, StatusCode}, response::IntoResponse, Extension, Json};
 use axum_extra::extract::CookieJar;
 use serde::Deserialize;
 use secrecy::{Secret, ExposeSecret};
 use crate::{
     app_state::AppState,
-    domain::{AuthAPIError, Email, data_stores::{LoginAttemptId, TwoFACode}},
-    utils::auth::generate_auth_cookie,
+    domain::{AuditAction, AuthAPIError, AuthenticationMethod, Email, TwoFAMethod, data_stores::{LoginAttemptId, RecoveryCode, TwoFACode, TwoFACodeStoreError, UserStoreError}},
+    utils::{
+        audit::AuditContext,
+        auth::{generate_auth_cookie, generate_refresh_cookie},
+        constants::MAX_2FA_ATTEMPTS,
+        session::start_session,
+        totp::verify_totp_code,
+    },
 };
-use color_eyre::eyre::eyre;
 
 #[derive(Deserialize)]
 pub struct Verify2FARequest {
@@ -18,20 +24,41 @@ pub struct Verify2FARequest {
     pub two_fa_code: String,
 }
 
+// A recovery code can be entered in place of the usual 2FA code.
+enum SecondFactor {
+    Code(TwoFACode),
+    RecoveryCode(RecoveryCode),
+}
+
+impl SecondFactor {
+    fn parse(code: String) -> Option<Self> {
+        if let Ok(code) = TwoFACode::parse(code.clone()) {
+            return Some(Self::Code(code));
+        }
+        RecoveryCode::parse(code).ok().map(Self::RecoveryCode)
+    }
+}
+
 #[tracing::instrument(name = "Verify 2FA", skip_all)]
 pub async fn verify_2fa(
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Extension(audit): Extension<AuditContext>,
     jar: CookieJar,
     Json(request): Json<Verify2FARequest>,
 ) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+    audit.set_action(AuditAction::VerifyTwoFA);
+
     let email = Email::parse(Secret::new(request.email))
         .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    audit.set_actor(email.as_ref().expose_secret());
 
     let login_attempt_id = LoginAttemptId::parse(request.login_attempt_id)
         .map_err(|_| AuthAPIError::InvalidCredentials)?;
 
-    let two_fa_code = TwoFACode::parse(request.two_fa_code)
-        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let second_factor = SecondFactor::parse(request.two_fa_code)
+        .ok_or(AuthAPIError::InvalidCredentials)?;
 
     let code_tuple = {
         let two_fa_code_store = state.two_fa_code_store.read().await;
@@ -45,8 +72,40 @@ pub async fn verify_2fa(
         return Err(AuthAPIError::IncorrectCredentials);
     }
 
-    if two_fa_code.as_ref().expose_secret() != stored_two_fa_code.as_ref().expose_secret() {
-        return Err(AuthAPIError::IncorrectCredentials);
+    let code_is_valid = match second_factor {
+        SecondFactor::Code(two_fa_code) => {
+            let user = state.user_store.read().await.get_user(email.as_ref().expose_secret()).await
+                .map_err(|_| AuthAPIError::IncorrectCredentials)?;
+
+            match user.two_fa_method {
+                TwoFAMethod::Totp(ref secret) => match verify_totp_code(secret, two_fa_code.as_ref().expose_secret()) {
+                    Some(counter) => use_totp_counter(&state, &email, counter).await?,
+                    None => false,
+                },
+                _ => two_fa_code == stored_two_fa_code,
+            }
+        }
+        SecondFactor::RecoveryCode(recovery_code) => {
+            // Hashing is slow, so the code is checked without holding the store for writing.
+            let found = state.user_store.read().await.find_recovery_code(email.as_ref().expose_secret(), &recovery_code).await;
+            let removed = match found {
+                Ok(id) => state.user_store.write().await.remove_recovery_code(email.as_ref().expose_secret(), id).await,
+                Err(e) => Err(e),
+            };
+            match removed {
+                Ok(()) => true,
+                Err(UserStoreError::InvalidCredentials) => false,
+                Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+            }
+        }
+    };
+    if !code_is_valid {
+        let mut two_fa_code_store = state.two_fa_code_store.write().await;
+        return match two_fa_code_store.record_failed_attempt(&email, &login_attempt_id, MAX_2FA_ATTEMPTS).await {
+            Ok(()) | Err(TwoFACodeStoreError::LoginAttemptIdNotFound) => Err(AuthAPIError::IncorrectCredentials),
+            Err(TwoFACodeStoreError::TooManyAttempts) => Err(AuthAPIError::TooMany2FAAttempts),
+            Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+        };
     }
 
     {
@@ -55,9 +114,27 @@ pub async fn verify_2fa(
             .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
     }
 
-    let auth_cookie = generate_auth_cookie(&email)
-        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
-    let updated_jar = jar.add(auth_cookie);
+    let methods = vec![
+        AuthenticationMethod::Pwd,
+        AuthenticationMethod::Otp,
+        AuthenticationMethod::Mfa,
+    ];
+    let authentication = start_session(&state, &email, methods, addr.ip(), &headers).await?;
+    let auth_cookie = generate_auth_cookie(&email, &authentication)
+        .map_err(AuthAPIError::UnexpectedError)?;
+    let refresh_cookie = generate_refresh_cookie(&email, &authentication, state.refresh_token_store.clone())
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+    let updated_jar = jar.add(auth_cookie).add(refresh_cookie);
 
     Ok((updated_jar, StatusCode::OK.into_response()))
-}
\ No newline at end of file
+}
+
+// Each TOTP code works once; a code already used, or older than one that was, is rejected.
+async fn use_totp_counter(state: &AppState, email: &Email, counter: i64) -> Result<bool, AuthAPIError> {
+    match state.user_store.write().await.use_totp_counter(email.as_ref().expose_secret(), counter).await {
+        Ok(()) => Ok(true),
+        Err(UserStoreError::InvalidCredentials) => Ok(false),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
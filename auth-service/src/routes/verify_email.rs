@@ -0,0 +1,58 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use color_eyre::eyre::Result;
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use crate::{
+    app_state::{AppState, EmailClientType},
+    domain::{data_stores::UserStoreError, AuditAction, AuthAPIError, Email},
+    utils::{
+        audit::AuditContext,
+        auth::{generate_email_verification_token, validate_email_verification_token},
+        constants::AUTH_SERVICE_URL,
+    },
+};
+
+#[derive(Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+#[tracing::instrument(name = "Verify email", skip_all)]
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Extension(audit): Extension<AuditContext>,
+    Json(request): Json<VerifyEmailRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    audit.set_action(AuditAction::VerifyEmail);
+
+    let email = validate_email_verification_token(&request.token)
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    audit.set_actor(email.as_ref().expose_secret());
+
+    state
+        .user_store
+        .write()
+        .await
+        .mark_email_verified(email.as_ref().expose_secret())
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "Sending verification email", skip_all)]
+pub async fn send_verification_email(email_client: &EmailClientType, email: &Email) -> Result<()> {
+    let token = generate_email_verification_token(email)?;
+    let content = format!(
+        "Confirm your email address by opening the link below.\n\n{}/verify-email?token={}",
+        AUTH_SERVICE_URL.as_str(),
+        token
+    );
+
+    email_client
+        .send_email(email, "Verify your email address", &content)
+        .await
+}
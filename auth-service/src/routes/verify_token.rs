@@ -1,22 +1,149 @@
-use axum::{extract::State, Json};
-use axum::http::StatusCode;
-use serde::Deserialize;
+use axum::{
+    extract::{rejection::JsonRejection, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
 use crate::app_state::AppState;
-use crate::domain::error::AuthAPIError;
-use crate::utils::auth::validate_token;
+use crate::domain::{error::AuthAPIError, data_stores::ApiKeyStoreError, ApiKey, ApiKeySecret};
+use crate::utils::{
+    auth::{validate_token, Claims},
+    oauth::{
+        is_service_token, parse_bearer_token, validate_service_token, validate_user_access_token, ServiceClaims,
+        UserAccessClaims,
+    },
+};
 
 #[derive(Deserialize)]
 pub struct VerifyTokenRequest {
     pub token: String,
 }
 
+// What the token says about its user, for the service relying on it to make permission decisions.
+// API keys and service tokens carry no roles or permissions, only scopes. The subject of a
+// service token is the client it was issued to. `aud` is only set for the access tokens clients
+// get on a user's behalf, and names that client; a service should refuse ones not meant for it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyTokenResponse {
+    pub sub: String,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+}
+
+// A token that passed every check, of any of the kinds relying services may be given.
+pub enum VerifiedToken {
+    User(Claims),
+    Service(ServiceClaims),
+    UserAccess(UserAccessClaims),
+    ApiKey(ApiKey),
+}
+
+impl From<VerifiedToken> for VerifyTokenResponse {
+    fn from(token: VerifiedToken) -> Self {
+        match token {
+            VerifiedToken::User(claims) => Self {
+                sub: claims.sub,
+                roles: claims.roles,
+                permissions: claims.permissions,
+                scopes: Vec::new(),
+                aud: None,
+            },
+            VerifiedToken::Service(claims) => Self {
+                scopes: claims.scopes(),
+                sub: claims.sub,
+                roles: Vec::new(),
+                permissions: Vec::new(),
+                aud: None,
+            },
+            VerifiedToken::UserAccess(claims) => Self {
+                scopes: claims.scopes(),
+                sub: claims.sub,
+                roles: Vec::new(),
+                permissions: Vec::new(),
+                aud: Some(claims.aud),
+            },
+            VerifiedToken::ApiKey(key) => Self {
+                sub: key.email.as_ref().expose_secret().clone(),
+                roles: Vec::new(),
+                permissions: Vec::new(),
+                scopes: key.scopes,
+                aud: None,
+            },
+        }
+    }
+}
+
+// The token is either sent in the body, or as `Authorization: Bearer <token>`, which also
+// accepts API keys. The header takes precedence. Service tokens are accepted either way.
 #[tracing::instrument(name = "Verify token", skip_all)]
 pub async fn verify_token(
     State(state): State<AppState>,
-    Json(request): Json<VerifyTokenRequest>,
-) -> Result<StatusCode, AuthAPIError> {
-    validate_token(&request.token, state.banned_token_store.clone()).await
+    headers: HeaderMap,
+    request: Result<Json<VerifyTokenRequest>, JsonRejection>,
+) -> Response {
+    let result = match parse_bearer_token(&headers) {
+        Some(token) => match ApiKeySecret::parse(Secret::new(token.to_owned())) {
+            Ok(secret) => verify_api_key(&state, &secret).await,
+            Err(_) => verify_jwt(&state, token).await,
+        },
+        None => match request {
+            Ok(Json(request)) => verify_jwt(&state, &request.token).await,
+            Err(rejection) => return rejection.into_response(),
+        },
+    };
+
+    result
+        .map(|token| (StatusCode::OK, Json(VerifyTokenResponse::from(token))))
+        .into_response()
+}
+
+// Auth tokens go through `validate_token`, so banned, revoked and expired tokens are refused.
+pub async fn verify_jwt(state: &AppState, token: &str) -> Result<VerifiedToken, AuthAPIError> {
+    if is_service_token(token) {
+        if let Ok(claims) = validate_service_token(token, &state.oauth_client_store).await {
+            return Ok(VerifiedToken::Service(claims));
+        }
+        let claims = validate_user_access_token(token, &state.banned_token_store, &state.user_store).await
+            .map_err(|_| AuthAPIError::InvalidToken)?;
+        return Ok(VerifiedToken::UserAccess(claims));
+    }
+
+    let claims = validate_token(token, state.banned_token_store.clone(), state.user_store.clone()).await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    Ok(VerifiedToken::User(claims))
+}
+
+// Keys of locked users and of accounts pending deletion are refused, as their owners couldn't
+// log in either.
+pub async fn verify_api_key(state: &AppState, secret: &ApiKeySecret) -> Result<VerifiedToken, AuthAPIError> {
+    let key = state
+        .api_key_store
+        .write()
+        .await
+        .use_key(secret, Utc::now().timestamp())
+        .await
+        .map_err(|e| match e {
+            ApiKeyStoreError::KeyNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    let account = state
+        .user_store
+        .read()
+        .await
+        .get_user_account(key.email.as_ref().expose_secret())
+        .await
         .map_err(|_| AuthAPIError::InvalidToken)?;
-    
-    Ok(StatusCode::OK)
-}
\ No newline at end of file
+    if account.locked || account.deleted_at.is_some() {
+        return Err(AuthAPIError::InvalidToken);
+    }
+
+    Ok(VerifiedToken::ApiKey(key))
+}
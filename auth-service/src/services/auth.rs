@@ -1,11 +1,10 @@
-use jsonwebtoken::{decode, DecodingKey, Validation, errors::Error};
+use jsonwebtoken::{decode, decode_header, Validation, errors::{Error, ErrorKind}};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use crate::domain::data_stores::BannedTokenStore;
-use crate::utils::constants::JWT_SECRET; 
-use color_eyre::eyre::{eyre, Context, ContextCompat, Result};
-use secrecy::{ExposeSecret, Secret};
+use crate::utils::{keyring::current_keyring, signing_key::SIGNING_ALGORITHM};
+use secrecy::Secret;
 
 
 // AJOUT: Structure Claims manquante
@@ -25,10 +24,13 @@ pub async fn validate_token(
         return Err(Error::from(jsonwebtoken::errors::ErrorKind::InvalidToken));
     }
 
-    // Validate the token using the secret key
+    // Validate the token using the public key it was signed with
+    let kid = decode_header(token)?.kid.ok_or(Error::from(ErrorKind::InvalidToken))?;
+    let keyring = current_keyring();
+    let decoding_key = keyring.decoding_key(&kid).ok_or(Error::from(ErrorKind::InvalidToken))?;
     decode::<Claims>(
         token,
-        &DecodingKey::from_secret(JWT_SECRET.expose_secret().as_bytes()),
-        &Validation::default(),
+        decoding_key,
+        &Validation::new(SIGNING_ALGORITHM),
     ).map(|_| ())
 }
\ No newline at end of file
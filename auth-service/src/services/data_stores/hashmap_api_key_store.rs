@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use crate::domain::{
+    api_key::{ApiKey, ApiKeySecret},
+    data_stores::{ApiKeyStore, ApiKeyStoreError},
+    Email,
+};
+
+#[derive(Default)]
+pub struct HashmapApiKeyStore {
+    // Key id to the key and the hash of its secret.
+    keys: HashMap<String, (ApiKey, String)>,
+}
+
+impl HashmapApiKeyStore {
+    fn user_key(&mut self, email: &Email, id: &str) -> Result<&mut (ApiKey, String), ApiKeyStoreError> {
+        self.keys
+            .get_mut(id)
+            .filter(|(key, _)| &key.email == email)
+            .ok_or(ApiKeyStoreError::KeyNotFound)
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiKeyStore for HashmapApiKeyStore {
+    async fn add_key(&mut self, key: ApiKey, secret: &ApiKeySecret) -> Result<(), ApiKeyStoreError> {
+        self.keys.insert(key.id.clone(), (key, secret.hash()));
+        Ok(())
+    }
+
+    async fn get_keys(&self, email: &Email) -> Result<Vec<ApiKey>, ApiKeyStoreError> {
+        let mut keys: Vec<ApiKey> = self
+            .keys
+            .values()
+            .filter(|(key, _)| &key.email == email)
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort_by_key(|key| key.created_at);
+        Ok(keys)
+    }
+
+    async fn rotate_key(&mut self, email: &Email, id: &str, secret: &ApiKeySecret) -> Result<ApiKey, ApiKeyStoreError> {
+        let (key, hash) = self.user_key(email, id)?;
+        *hash = secret.hash();
+        Ok(key.clone())
+    }
+
+    async fn revoke_key(&mut self, email: &Email, id: &str) -> Result<(), ApiKeyStoreError> {
+        self.user_key(email, id)?;
+        self.keys.remove(id);
+        Ok(())
+    }
+
+    async fn revoke_user_keys(&mut self, email: &Email) -> Result<(), ApiKeyStoreError> {
+        self.keys.retain(|_, (key, _)| &key.email != email);
+        Ok(())
+    }
+
+    async fn use_key(&mut self, secret: &ApiKeySecret, timestamp: i64) -> Result<ApiKey, ApiKeyStoreError> {
+        match self.keys.get_mut(secret.id()) {
+            Some((key, hash)) if *hash == secret.hash() && key.expires_at > timestamp => {
+                key.last_used_at = Some(timestamp);
+                Ok(key.clone())
+            }
+            _ => Err(ApiKeyStoreError::KeyNotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::Secret;
+
+    fn email(email: &str) -> Email {
+        Email::parse(Secret::new(email.to_owned())).unwrap()
+    }
+
+    async fn add_key(store: &mut HashmapApiKeyStore, owner: &str) -> (ApiKey, ApiKeySecret) {
+        let key = ApiKey::new(email(owner), "CI".to_owned(), vec!["deploy".to_owned()], 1000, 2000);
+        let secret = ApiKeySecret::generate(&key.id);
+        store.add_key(key.clone(), &secret).await.unwrap();
+        (key, secret)
+    }
+
+    #[tokio::test]
+    async fn test_use_key() {
+        let mut store = HashmapApiKeyStore::default();
+        let (key, secret) = add_key(&mut store, "test@example.com").await;
+
+        let used = store.use_key(&secret, 1500).await.unwrap();
+        assert_eq!(used.id, key.id);
+        assert_eq!(used.last_used_at, Some(1500));
+        assert_eq!(store.get_keys(&key.email).await.unwrap()[0].last_used_at, Some(1500));
+
+        // Expired
+        assert_eq!(store.use_key(&secret, 2000).await.unwrap_err(), ApiKeyStoreError::KeyNotFound);
+        // Right id, wrong secret
+        let forged = ApiKeySecret::generate(&key.id);
+        assert_eq!(store.use_key(&forged, 1500).await.unwrap_err(), ApiKeyStoreError::KeyNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_rotate_key() {
+        let mut store = HashmapApiKeyStore::default();
+        let (key, old_secret) = add_key(&mut store, "test@example.com").await;
+
+        let new_secret = ApiKeySecret::generate(&key.id);
+        store.rotate_key(&key.email, &key.id, &new_secret).await.unwrap();
+        assert!(store.use_key(&old_secret, 1500).await.is_err());
+        assert!(store.use_key(&new_secret, 1500).await.is_ok());
+
+        let result = store.rotate_key(&email("other@example.com"), &key.id, &old_secret).await;
+        assert_eq!(result.unwrap_err(), ApiKeyStoreError::KeyNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_key() {
+        let mut store = HashmapApiKeyStore::default();
+        let (key, secret) = add_key(&mut store, "test@example.com").await;
+        add_key(&mut store, "other@example.com").await;
+
+        assert_eq!(
+            store.revoke_key(&email("other@example.com"), &key.id).await.unwrap_err(),
+            ApiKeyStoreError::KeyNotFound
+        );
+        store.revoke_key(&key.email, &key.id).await.unwrap();
+        assert!(store.get_keys(&key.email).await.unwrap().is_empty());
+        assert!(store.use_key(&secret, 1500).await.is_err());
+        assert_eq!(store.get_keys(&email("other@example.com")).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_user_keys() {
+        let mut store = HashmapApiKeyStore::default();
+        let (key, secret) = add_key(&mut store, "test@example.com").await;
+        add_key(&mut store, "test@example.com").await;
+        add_key(&mut store, "other@example.com").await;
+
+        store.revoke_user_keys(&key.email).await.unwrap();
+        assert!(store.get_keys(&key.email).await.unwrap().is_empty());
+        assert!(store.use_key(&secret, 1500).await.is_err());
+        assert_eq!(store.get_keys(&email("other@example.com")).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,48 @@
+use tokio::sync::RwLock;
+
+use crate::domain::{
+    audit::AuditHead,
+    data_stores::{AuditHeadStore, AuditLogStoreError},
+};
+
+#[derive(Default)]
+pub struct HashmapAuditHeadStore {
+    head: RwLock<Option<AuditHead>>,
+}
+
+#[async_trait::async_trait]
+impl AuditHeadStore for HashmapAuditHeadStore {
+    async fn advance_head(&self, head: AuditHead) -> Result<(), AuditLogStoreError> {
+        let mut current = self.head.write().await;
+        if current.as_ref().is_none_or(|current| current.id < head.id) {
+            *current = Some(head);
+        }
+        Ok(())
+    }
+
+    async fn get_head(&self) -> Result<Option<AuditHead>, AuditLogStoreError> {
+        Ok(self.head.read().await.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn head(id: i64) -> AuditHead {
+        AuditHead { id, hash: format!("hash{}", id) }
+    }
+
+    #[tokio::test]
+    async fn test_advance_head_keeps_latest() {
+        let store = HashmapAuditHeadStore::default();
+        assert_eq!(store.get_head().await.unwrap(), None);
+
+        store.advance_head(head(2)).await.unwrap();
+        store.advance_head(head(1)).await.unwrap();
+        assert_eq!(store.get_head().await.unwrap(), Some(head(2)));
+
+        store.advance_head(head(3)).await.unwrap();
+        assert_eq!(store.get_head().await.unwrap(), Some(head(3)));
+    }
+}
@@ -0,0 +1,124 @@
+use secrecy::Secret;
+use tokio::sync::RwLock;
+
+use crate::domain::{
+    audit::{AuditEvent, AuditEventFilter, AuditRecord, AUDIT_CHAIN_START},
+    data_stores::{AuditLogStore, AuditLogStoreError},
+};
+
+pub struct HashmapAuditLogStore {
+    key: Secret<String>,
+    // Oldest first; an event's id is its position plus one.
+    records: RwLock<Vec<AuditRecord>>,
+}
+
+impl HashmapAuditLogStore {
+    pub fn new(key: Secret<String>) -> Self {
+        Self { key, records: RwLock::default() }
+    }
+}
+
+fn matching<'a>(records: &'a [AuditRecord], filter: &'a AuditEventFilter) -> impl Iterator<Item = &'a AuditRecord> + 'a {
+    records.iter().rev().filter(move |record| filter.matches(&record.event))
+}
+
+#[async_trait::async_trait]
+impl AuditLogStore for HashmapAuditLogStore {
+    async fn append_event(&self, event: AuditEvent) -> Result<AuditRecord, AuditLogStoreError> {
+        let mut records = self.records.write().await;
+        let previous_hash = records
+            .last()
+            .map(|record| record.hash.clone())
+            .unwrap_or_else(|| AUDIT_CHAIN_START.to_owned());
+        let record = AuditRecord {
+            id: records.len() as i64 + 1,
+            hash: event.chain_hash(&previous_hash, &self.key),
+            event,
+            previous_hash,
+        };
+        records.push(record.clone());
+        Ok(record)
+    }
+
+    async fn get_events(&self, filter: &AuditEventFilter, offset: i64, limit: i64) -> Result<Vec<AuditRecord>, AuditLogStoreError> {
+        let records = self.records.read().await;
+        Ok(matching(&records, filter)
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn count_events(&self, filter: &AuditEventFilter) -> Result<i64, AuditLogStoreError> {
+        let records = self.records.read().await;
+        Ok(matching(&records, filter).count() as i64)
+    }
+
+    async fn get_chain_page(&self, after_id: i64, limit: i64) -> Result<Vec<AuditRecord>, AuditLogStoreError> {
+        let records = self.records.read().await;
+        Ok(records
+            .iter()
+            .filter(|record| record.id > after_id)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::audit::{find_broken_link, AuditHead, AuditOutcome};
+
+    fn key() -> Secret<String> {
+        Secret::new("secret".to_owned())
+    }
+
+    fn event(actor: &str, action: &str, created_at: i64) -> AuditEvent {
+        AuditEvent {
+            actor: Some(actor.to_owned()),
+            target: None,
+            action: action.to_owned(),
+            outcome: AuditOutcome::Success,
+            ip: None,
+            user_agent: None,
+            request_id: None,
+            created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_append_event_chains_to_previous() {
+        let store = HashmapAuditLogStore::new(key());
+        let first = store.append_event(event("a@example.com", "signup", 1000)).await.unwrap();
+        let second = store.append_event(event("a@example.com", "login", 1001)).await.unwrap();
+
+        assert_eq!(first.previous_hash, AUDIT_CHAIN_START);
+        assert_eq!(second.previous_hash, first.hash);
+        let chain = store.get_chain_page(0, 10).await.unwrap();
+        assert_eq!(chain, vec![first, second.clone()]);
+        assert_eq!(find_broken_link(&chain, &key(), Some(AuditHead::from(&second))), None);
+        assert_eq!(store.get_chain_page(1, 10).await.unwrap(), vec![second]);
+    }
+
+    #[tokio::test]
+    async fn test_get_events_newest_first_with_filter() {
+        let store = HashmapAuditLogStore::new(key());
+        store.append_event(event("a@example.com", "login", 1000)).await.unwrap();
+        store.append_event(event("b@example.com", "login", 1001)).await.unwrap();
+        store.append_event(event("a@example.com", "logout", 1002)).await.unwrap();
+        store.append_event(event("a@example.com", "login", 1003)).await.unwrap();
+
+        let filter = AuditEventFilter {
+            user: Some("a@example.com".to_owned()),
+            action: Some("login".to_owned()),
+            ..Default::default()
+        };
+        let events = store.get_events(&filter, 0, 10).await.unwrap();
+        assert_eq!(events.iter().map(|record| record.id).collect::<Vec<_>>(), vec![4, 1]);
+        assert_eq!(store.count_events(&filter).await.unwrap(), 2);
+
+        let page = store.get_events(&AuditEventFilter::default(), 1, 2).await.unwrap();
+        assert_eq!(page.iter().map(|record| record.id).collect::<Vec<_>>(), vec![3, 2]);
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use secrecy::ExposeSecret;
+use crate::{
+    domain::{
+        data_stores::{AuthorizationCodeStore, AuthorizationCodeStoreError},
+        oauth::{AuthorizationCode, AuthorizationCodeGrant},
+    },
+    utils::constants::AUTHORIZATION_CODE_TTL_SECONDS,
+};
+
+#[derive(Default)]
+pub struct HashmapAuthorizationCodeStore {
+    codes: HashMap<String, (AuthorizationCodeGrant, DateTime<Utc>)>,
+}
+
+impl HashmapAuthorizationCodeStore {
+    pub fn new() -> Self {
+        Self {
+            codes: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl AuthorizationCodeStore for HashmapAuthorizationCodeStore {
+    async fn add_code(
+        &mut self,
+        code: AuthorizationCode,
+        grant: AuthorizationCodeGrant,
+    ) -> Result<(), AuthorizationCodeStoreError> {
+        let expires_at = Utc::now() + Duration::seconds(AUTHORIZATION_CODE_TTL_SECONDS);
+        self.codes
+            .insert(code.as_ref().expose_secret().clone(), (grant, expires_at));
+        Ok(())
+    }
+
+    async fn consume_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationCodeGrant, AuthorizationCodeStoreError> {
+        match self.codes.remove(code.as_ref().expose_secret()) {
+            Some((grant, expires_at)) if expires_at > Utc::now() => Ok(grant),
+            _ => Err(AuthorizationCodeStoreError::CodeNotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::Secret;
+    use crate::domain::{oauth::PkceCodeChallenge, Authentication, Email};
+
+    fn grant() -> AuthorizationCodeGrant {
+        AuthorizationCodeGrant {
+            client_id: "client".to_owned(),
+            redirect_uri: "https://app.example.com/callback".to_owned(),
+            email: Email::parse(Secret::new("test@example.com".to_owned())).unwrap(),
+            code_challenge: PkceCodeChallenge::parse(
+                "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM".to_owned(),
+            )
+            .unwrap(),
+            scope: None,
+            nonce: None,
+            authentication: Authentication::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consume_code_only_once() {
+        let mut store = HashmapAuthorizationCodeStore::new();
+        let code = AuthorizationCode::default();
+
+        store.add_code(code.clone(), grant()).await.unwrap();
+
+        assert_eq!(store.consume_code(&code).await.unwrap(), grant());
+        let result = store.consume_code(&code).await;
+        assert_eq!(result.unwrap_err(), AuthorizationCodeStoreError::CodeNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_consume_expired_code() {
+        let mut store = HashmapAuthorizationCodeStore::new();
+        let code = AuthorizationCode::default();
+
+        store.add_code(code.clone(), grant()).await.unwrap();
+        store
+            .codes
+            .get_mut(code.as_ref().expose_secret())
+            .unwrap()
+            .1 = Utc::now() - Duration::seconds(1);
+
+        let result = store.consume_code(&code).await;
+        assert_eq!(result.unwrap_err(), AuthorizationCodeStoreError::CodeNotFound);
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use secrecy::{ExposeSecret, Secret};
+use crate::domain::{
+    data_stores::{OAuthClientStore, OAuthClientStoreError},
+    oauth::OAuthClient,
+};
+
+#[derive(Default)]
+pub struct HashmapOAuthClientStore {
+    clients: HashMap<String, OAuthClient>,
+}
+
+impl HashmapOAuthClientStore {
+    pub fn new() -> Self {
+        Self {
+            clients: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl OAuthClientStore for HashmapOAuthClientStore {
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError> {
+        if self.clients.contains_key(&client.client_id) {
+            return Err(OAuthClientStoreError::ClientAlreadyExists);
+        }
+        self.clients.insert(client.client_id.clone(), client);
+        Ok(())
+    }
+
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError> {
+        self.clients
+            .get(client_id)
+            .cloned()
+            .ok_or(OAuthClientStoreError::ClientNotFound)
+    }
+
+    async fn validate_client_secret(
+        &self,
+        client_id: &str,
+        client_secret: &Secret<String>,
+    ) -> Result<(), OAuthClientStoreError> {
+        let client = self
+            .clients
+            .get(client_id)
+            .ok_or(OAuthClientStoreError::ClientNotFound)?;
+
+        match &client.client_secret {
+            Some(secret) if secret.expose_secret() == client_secret.expose_secret() => Ok(()),
+            _ => Err(OAuthClientStoreError::InvalidCredentials),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(client_secret: Option<&str>) -> OAuthClient {
+        OAuthClient::new(
+            "client".to_owned(),
+            "Client".to_owned(),
+            vec!["https://app.example.com/callback".to_owned()],
+            client_secret.map(|secret| Secret::new(secret.to_owned())),
+            Vec::new(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_add_and_get_client() {
+        let mut store = HashmapOAuthClientStore::new();
+        store.add_client(client(None)).await.unwrap();
+
+        let result = store.get_client("client").await.unwrap();
+        assert_eq!(result.redirect_uris, vec!["https://app.example.com/callback".to_owned()]);
+        assert!(!result.is_confidential());
+
+        let result = store.add_client(client(None)).await;
+        assert_eq!(result.unwrap_err(), OAuthClientStoreError::ClientAlreadyExists);
+    }
+
+    #[tokio::test]
+    async fn test_get_nonexistent_client() {
+        let store = HashmapOAuthClientStore::new();
+        let result = store.get_client("client").await;
+        assert_eq!(result.unwrap_err(), OAuthClientStoreError::ClientNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_validate_client_secret() {
+        let mut store = HashmapOAuthClientStore::new();
+        store.add_client(client(Some("secret"))).await.unwrap();
+
+        assert!(store
+            .validate_client_secret("client", &Secret::new("secret".to_owned()))
+            .await
+            .is_ok());
+        let result = store
+            .validate_client_secret("client", &Secret::new("wrong".to_owned()))
+            .await;
+        assert_eq!(result.unwrap_err(), OAuthClientStoreError::InvalidCredentials);
+    }
+
+    #[tokio::test]
+    async fn test_public_client_has_no_secret() {
+        let mut store = HashmapOAuthClientStore::new();
+        store.add_client(client(None)).await.unwrap();
+
+        let result = store
+            .validate_client_secret("client", &Secret::new("secret".to_owned()))
+            .await;
+        assert_eq!(result.unwrap_err(), OAuthClientStoreError::InvalidCredentials);
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use secrecy::ExposeSecret;
+use crate::{
+    domain::{
+        data_stores::{PasswordResetToken, PasswordResetTokenStore, PasswordResetTokenStoreError},
+        Email,
+    },
+    utils::constants::PASSWORD_RESET_TOKEN_TTL_SECONDS,
+};
+
+#[derive(Default)]
+pub struct HashmapPasswordResetTokenStore {
+    tokens: HashMap<String, (Email, DateTime<Utc>)>,
+}
+
+impl HashmapPasswordResetTokenStore {
+    pub fn new() -> Self {
+        Self {
+            tokens: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl PasswordResetTokenStore for HashmapPasswordResetTokenStore {
+    async fn add_token(
+        &mut self,
+        token: PasswordResetToken,
+        email: Email,
+    ) -> Result<(), PasswordResetTokenStoreError> {
+        let expires_at = Utc::now() + Duration::seconds(PASSWORD_RESET_TOKEN_TTL_SECONDS);
+        self.tokens
+            .insert(token.as_ref().expose_secret().clone(), (email, expires_at));
+        Ok(())
+    }
+
+    async fn consume_token(
+        &mut self,
+        token: &PasswordResetToken,
+    ) -> Result<Email, PasswordResetTokenStoreError> {
+        match self.tokens.remove(token.as_ref().expose_secret()) {
+            Some((email, expires_at)) if expires_at > Utc::now() => Ok(email),
+            _ => Err(PasswordResetTokenStoreError::TokenNotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_add_and_consume_token() {
+        let mut store = HashmapPasswordResetTokenStore::new();
+        let email = Email("test@example.com".to_string().into());
+        let token = PasswordResetToken::default();
+
+        assert!(store.add_token(token.clone(), email.clone()).await.is_ok());
+
+        let result = store.consume_token(&token).await;
+        assert_eq!(result.unwrap(), email);
+    }
+
+    #[tokio::test]
+    async fn test_token_can_only_be_consumed_once() {
+        let mut store = HashmapPasswordResetTokenStore::new();
+        let email = Email("test@example.com".to_string().into());
+        let token = PasswordResetToken::default();
+
+        store.add_token(token.clone(), email).await.unwrap();
+        store.consume_token(&token).await.unwrap();
+
+        let result = store.consume_token(&token).await;
+        assert_eq!(result.unwrap_err(), PasswordResetTokenStoreError::TokenNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_consume_nonexistent_token() {
+        let mut store = HashmapPasswordResetTokenStore::new();
+        let token = PasswordResetToken::default();
+
+        let result = store.consume_token(&token).await;
+        assert_eq!(result.unwrap_err(), PasswordResetTokenStoreError::TokenNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_consume_expired_token() {
+        let mut store = HashmapPasswordResetTokenStore::new();
+        let email = Email("test@example.com".to_string().into());
+        let token = PasswordResetToken::default();
+
+        store.tokens.insert(
+            token.as_ref().expose_secret().clone(),
+            (email, Utc::now() - Duration::seconds(1)),
+        );
+
+        let result = store.consume_token(&token).await;
+        assert_eq!(result.unwrap_err(), PasswordResetTokenStoreError::TokenNotFound);
+    }
+}
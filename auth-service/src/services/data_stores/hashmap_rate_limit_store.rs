@@ -0,0 +1,105 @@
+use std::collections::{HashMap, VecDeque};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use crate::domain::data_stores::{RateLimit, RateLimitDecision, RateLimitStore, RateLimitStoreError};
+
+#[derive(Default)]
+pub struct HashmapRateLimitStore {
+    // Timestamps of the requests still inside the window, oldest first.
+    requests: HashMap<String, VecDeque<DateTime<Utc>>>,
+}
+
+impl HashmapRateLimitStore {
+    pub fn new() -> Self {
+        Self {
+            requests: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for HashmapRateLimitStore {
+    async fn check_and_record(
+        &mut self,
+        key: &str,
+        limit: &RateLimit,
+    ) -> Result<RateLimitDecision, RateLimitStoreError> {
+        let now = Utc::now();
+        let window = Duration::seconds(limit.window_seconds);
+        let requests = self.requests.entry(key.to_owned()).or_default();
+
+        while requests.front().is_some_and(|oldest| *oldest + window <= now) {
+            requests.pop_front();
+        }
+
+        if requests.len() >= limit.max_requests as usize {
+            let oldest = requests.front().copied().unwrap_or(now);
+            let retry_after = (oldest + window - now).num_milliseconds();
+            return Ok(RateLimitDecision::limited_for_millis(retry_after));
+        }
+
+        requests.push_back(now);
+        Ok(RateLimitDecision::Allowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: RateLimit = RateLimit {
+        max_requests: 2,
+        window_seconds: 60,
+    };
+
+    #[tokio::test]
+    async fn test_allows_requests_up_to_limit() {
+        let mut store = HashmapRateLimitStore::new();
+
+        for _ in 0..LIMIT.max_requests {
+            let decision = store.check_and_record("key", &LIMIT).await.unwrap();
+            assert_eq!(decision, RateLimitDecision::Allowed);
+        }
+
+        let decision = store.check_and_record("key", &LIMIT).await.unwrap();
+        assert!(matches!(
+            decision,
+            RateLimitDecision::Limited { retry_after_seconds } if (1..=60).contains(&retry_after_seconds)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_limited_independently() {
+        let mut store = HashmapRateLimitStore::new();
+
+        for _ in 0..LIMIT.max_requests {
+            store.check_and_record("key", &LIMIT).await.unwrap();
+        }
+
+        let decision = store.check_and_record("other-key", &LIMIT).await.unwrap();
+        assert_eq!(decision, RateLimitDecision::Allowed);
+    }
+
+    #[tokio::test]
+    async fn test_requests_outside_window_are_forgotten() {
+        let mut store = HashmapRateLimitStore::new();
+        let expired = Utc::now() - Duration::seconds(LIMIT.window_seconds);
+        store
+            .requests
+            .insert("key".to_owned(), VecDeque::from([expired, expired]));
+
+        let decision = store.check_and_record("key", &LIMIT).await.unwrap();
+        assert_eq!(decision, RateLimitDecision::Allowed);
+    }
+
+    #[tokio::test]
+    async fn test_limited_requests_are_not_counted() {
+        let mut store = HashmapRateLimitStore::new();
+
+        for _ in 0..LIMIT.max_requests + 3 {
+            store.check_and_record("key", &LIMIT).await.unwrap();
+        }
+
+        assert_eq!(store.requests["key"].len(), LIMIT.max_requests as usize);
+    }
+}
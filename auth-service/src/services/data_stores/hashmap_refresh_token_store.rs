@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use secrecy::ExposeSecret;
+use crate::{
+    domain::{
+        data_stores::{RefreshToken, RefreshTokenStore, RefreshTokenStoreError},
+        Authentication, Email,
+    },
+    utils::constants::REFRESH_TOKEN_TTL_SECONDS,
+};
+
+struct RefreshTokenRecord {
+    email: Email,
+    authentication: Authentication,
+    family_id: String,
+    rotated: bool,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+pub struct HashmapRefreshTokenStore {
+    tokens: HashMap<String, RefreshTokenRecord>,
+    // Active families and the user they were issued to.
+    families: HashMap<String, Email>,
+}
+
+impl HashmapRefreshTokenStore {
+    pub fn new() -> Self {
+        Self {
+            tokens: HashMap::new(),
+            families: HashMap::new(),
+        }
+    }
+
+    fn insert_token(
+        &mut self,
+        token: RefreshToken,
+        email: Email,
+        authentication: Authentication,
+        family_id: String,
+    ) {
+        let record = RefreshTokenRecord {
+            email,
+            authentication,
+            family_id,
+            rotated: false,
+            expires_at: Utc::now() + Duration::seconds(REFRESH_TOKEN_TTL_SECONDS),
+        };
+        self.tokens.insert(token.as_ref().expose_secret().clone(), record);
+    }
+}
+
+#[async_trait]
+impl RefreshTokenStore for HashmapRefreshTokenStore {
+    async fn add_token(
+        &mut self,
+        token: RefreshToken,
+        email: Email,
+        authentication: Authentication,
+    ) -> Result<(), RefreshTokenStoreError> {
+        let family_id = uuid::Uuid::new_v4().to_string();
+        self.families.insert(family_id.clone(), email.clone());
+        self.insert_token(token, email, authentication, family_id);
+        Ok(())
+    }
+
+    async fn rotate_token(
+        &mut self,
+        token: &RefreshToken,
+        new_token: RefreshToken,
+    ) -> Result<(Email, Authentication), RefreshTokenStoreError> {
+        let record = match self.tokens.get_mut(token.as_ref().expose_secret()) {
+            Some(record) if record.expires_at > Utc::now() => record,
+            _ => return Err(RefreshTokenStoreError::TokenNotFound),
+        };
+
+        if !self.families.contains_key(&record.family_id) {
+            return Err(RefreshTokenStoreError::TokenNotFound);
+        }
+
+        if record.rotated {
+            let family_id = record.family_id.clone();
+            self.families.remove(&family_id);
+            return Err(RefreshTokenStoreError::TokenReused);
+        }
+
+        record.rotated = true;
+        let email = record.email.clone();
+        let authentication = record.authentication.clone();
+        let family_id = record.family_id.clone();
+        self.insert_token(new_token, email.clone(), authentication.clone(), family_id);
+
+        Ok((email, authentication))
+    }
+
+    async fn revoke_token(&mut self, token: &RefreshToken) -> Result<(), RefreshTokenStoreError> {
+        if let Some(record) = self.tokens.get(token.as_ref().expose_secret()) {
+            self.families.remove(&record.family_id);
+        }
+        Ok(())
+    }
+
+    async fn revoke_user_tokens(&mut self, email: &Email) -> Result<(), RefreshTokenStoreError> {
+        self.families.retain(|_, family_email| family_email != email);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::AuthenticationMethod;
+
+    #[tokio::test]
+    async fn test_rotate_token() {
+        let mut store = HashmapRefreshTokenStore::new();
+        let email = Email("test@example.com".to_string().into());
+        let token = RefreshToken::default();
+        let new_token = RefreshToken::default();
+
+        let authentication = Authentication::now(vec![AuthenticationMethod::Pwd]);
+
+        store.add_token(token.clone(), email.clone(), authentication.clone()).await.unwrap();
+
+        let result = store.rotate_token(&token, new_token.clone()).await;
+        assert_eq!(result.unwrap(), (email.clone(), authentication.clone()));
+
+        // The original login is kept through every rotation.
+        let result = store.rotate_token(&new_token, RefreshToken::default()).await;
+        assert_eq!(result.unwrap(), (email, authentication));
+    }
+
+    #[tokio::test]
+    async fn test_reusing_rotated_token_revokes_family() {
+        let mut store = HashmapRefreshTokenStore::new();
+        let email = Email("test@example.com".to_string().into());
+        let token = RefreshToken::default();
+        let new_token = RefreshToken::default();
+
+        store.add_token(token.clone(), email, Authentication::default()).await.unwrap();
+        store.rotate_token(&token, new_token.clone()).await.unwrap();
+
+        let result = store.rotate_token(&token, RefreshToken::default()).await;
+        assert_eq!(result.unwrap_err(), RefreshTokenStoreError::TokenReused);
+
+        let result = store.rotate_token(&new_token, RefreshToken::default()).await;
+        assert_eq!(result.unwrap_err(), RefreshTokenStoreError::TokenNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_rotate_nonexistent_token() {
+        let mut store = HashmapRefreshTokenStore::new();
+
+        let result = store
+            .rotate_token(&RefreshToken::default(), RefreshToken::default())
+            .await;
+        assert_eq!(result.unwrap_err(), RefreshTokenStoreError::TokenNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_rotate_expired_token() {
+        let mut store = HashmapRefreshTokenStore::new();
+        let email = Email("test@example.com".to_string().into());
+        let token = RefreshToken::default();
+
+        store.add_token(token.clone(), email, Authentication::default()).await.unwrap();
+        store
+            .tokens
+            .get_mut(token.as_ref().expose_secret())
+            .unwrap()
+            .expires_at = Utc::now() - Duration::seconds(1);
+
+        let result = store.rotate_token(&token, RefreshToken::default()).await;
+        assert_eq!(result.unwrap_err(), RefreshTokenStoreError::TokenNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_token() {
+        let mut store = HashmapRefreshTokenStore::new();
+        let email = Email("test@example.com".to_string().into());
+        let token = RefreshToken::default();
+
+        store.add_token(token.clone(), email, Authentication::default()).await.unwrap();
+        store.revoke_token(&token).await.unwrap();
+
+        let result = store.rotate_token(&token, RefreshToken::default()).await;
+        assert_eq!(result.unwrap_err(), RefreshTokenStoreError::TokenNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_user_tokens() {
+        let mut store = HashmapRefreshTokenStore::new();
+        let email = Email("test@example.com".to_string().into());
+        let other_email = Email("other@example.com".to_string().into());
+        let first_token = RefreshToken::default();
+        let second_token = RefreshToken::default();
+        let other_token = RefreshToken::default();
+
+        store.add_token(first_token.clone(), email.clone(), Authentication::default()).await.unwrap();
+        store.add_token(second_token.clone(), email.clone(), Authentication::default()).await.unwrap();
+        store.add_token(other_token.clone(), other_email, Authentication::default()).await.unwrap();
+
+        store.revoke_user_tokens(&email).await.unwrap();
+
+        let result = store.rotate_token(&first_token, RefreshToken::default()).await;
+        assert_eq!(result.unwrap_err(), RefreshTokenStoreError::TokenNotFound);
+        let result = store.rotate_token(&second_token, RefreshToken::default()).await;
+        assert_eq!(result.unwrap_err(), RefreshTokenStoreError::TokenNotFound);
+        assert!(store.rotate_token(&other_token, RefreshToken::default()).await.is_ok());
+    }
+}
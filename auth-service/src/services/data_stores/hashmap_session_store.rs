@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use crate::{
+    domain::{
+        data_stores::{Session, SessionStore, SessionStoreError},
+        Email,
+    },
+    utils::constants::REFRESH_TOKEN_TTL_SECONDS,
+};
+
+#[derive(Default)]
+pub struct HashmapSessionStore {
+    sessions: HashMap<Email, Vec<(Session, DateTime<Utc>)>>,
+}
+
+impl HashmapSessionStore {
+    pub fn new() -> Self {
+        Self {
+            sessions: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl SessionStore for HashmapSessionStore {
+    async fn add_session(&mut self, email: &Email, session: Session) -> Result<(), SessionStoreError> {
+        let expires_at = Utc::now() + Duration::seconds(REFRESH_TOKEN_TTL_SECONDS);
+        self.sessions
+            .entry(email.clone())
+            .or_default()
+            .push((session, expires_at));
+        Ok(())
+    }
+
+    async fn get_sessions(&self, email: &Email) -> Result<Vec<Session>, SessionStoreError> {
+        let now = Utc::now();
+        let mut sessions: Vec<Session> = self
+            .sessions
+            .get(email)
+            .into_iter()
+            .flatten()
+            .filter(|(_, expires_at)| *expires_at > now)
+            .map(|(session, _)| session.clone())
+            .collect();
+        sessions.sort_by_key(|session| session.created_at);
+        Ok(sessions)
+    }
+
+    async fn extend_session(&mut self, email: &Email, session_id: &str) -> Result<(), SessionStoreError> {
+        let now = Utc::now();
+        let (_, expires_at) = self
+            .sessions
+            .get_mut(email)
+            .and_then(|sessions| {
+                sessions
+                    .iter_mut()
+                    .find(|(session, expires_at)| session.id == session_id && *expires_at > now)
+            })
+            .ok_or(SessionStoreError::SessionNotFound)?;
+        *expires_at = now + Duration::seconds(REFRESH_TOKEN_TTL_SECONDS);
+        Ok(())
+    }
+
+    async fn remove_session(&mut self, email: &Email, session_id: &str) -> Result<(), SessionStoreError> {
+        let now = Utc::now();
+        let sessions = self
+            .sessions
+            .get_mut(email)
+            .ok_or(SessionStoreError::SessionNotFound)?;
+        let index = sessions
+            .iter()
+            .position(|(session, expires_at)| session.id == session_id && *expires_at > now)
+            .ok_or(SessionStoreError::SessionNotFound)?;
+        sessions.remove(index);
+        Ok(())
+    }
+
+    async fn remove_all_sessions(&mut self, email: &Email) -> Result<(), SessionStoreError> {
+        self.sessions.remove(email);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::Secret;
+
+    fn session(id: &str, created_at: i64) -> Session {
+        Session {
+            id: id.to_owned(),
+            created_at,
+            ip: Some("127.0.0.1".to_owned()),
+            user_agent: Some("test".to_owned()),
+        }
+    }
+
+    fn email() -> Email {
+        Email::parse(Secret::new("test@example.com".to_owned())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_sessions_oldest_first() {
+        let mut store = HashmapSessionStore::new();
+        let email = email();
+
+        store.add_session(&email, session("new", 2)).await.unwrap();
+        store.add_session(&email, session("old", 1)).await.unwrap();
+
+        let sessions = store.get_sessions(&email).await.unwrap();
+        assert_eq!(sessions, vec![session("old", 1), session("new", 2)]);
+    }
+
+    #[tokio::test]
+    async fn test_get_sessions_of_unknown_user() {
+        let store = HashmapSessionStore::new();
+        assert!(store.get_sessions(&email()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_extend_session() {
+        let mut store = HashmapSessionStore::new();
+        let email = email();
+        store.add_session(&email, session("session", 1)).await.unwrap();
+
+        assert!(store.extend_session(&email, "session").await.is_ok());
+        assert_eq!(
+            store.extend_session(&email, "unknown").await,
+            Err(SessionStoreError::SessionNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remove_session() {
+        let mut store = HashmapSessionStore::new();
+        let email = email();
+        store.add_session(&email, session("first", 1)).await.unwrap();
+        store.add_session(&email, session("second", 2)).await.unwrap();
+
+        assert!(store.remove_session(&email, "first").await.is_ok());
+        assert_eq!(store.get_sessions(&email).await.unwrap(), vec![session("second", 2)]);
+        assert_eq!(
+            store.remove_session(&email, "first").await,
+            Err(SessionStoreError::SessionNotFound)
+        );
+        assert_eq!(
+            store.extend_session(&email, "first").await,
+            Err(SessionStoreError::SessionNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remove_all_sessions() {
+        let mut store = HashmapSessionStore::new();
+        let email = email();
+        store.add_session(&email, session("first", 1)).await.unwrap();
+        store.add_session(&email, session("second", 2)).await.unwrap();
+
+        assert!(store.remove_all_sessions(&email).await.is_ok());
+        assert!(store.get_sessions(&email).await.unwrap().is_empty());
+    }
+}
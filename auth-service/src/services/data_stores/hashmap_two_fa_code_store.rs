@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use secrecy::ExposeSecret;
 use crate::domain::{
     data_stores::{LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError},
     Email,
@@ -9,12 +11,18 @@ use crate::domain::{
 #[derive(Default)]
 pub struct HashmapTwoFACodeStore {
     codes: HashMap<Email, (LoginAttemptId, TwoFACode)>,
+    // Keyed by login attempt ID.
+    failed_attempts: HashMap<String, u32>,
+    // Number of resends and when the code was last sent.
+    resends: HashMap<Email, (u32, DateTime<Utc>)>,
 }
 
 impl HashmapTwoFACodeStore {
     pub fn new() -> Self {
         Self {
             codes: HashMap::new(),
+            failed_attempts: HashMap::new(),
+            resends: HashMap::new(),
         }
     }
 }
@@ -27,11 +35,13 @@ impl TwoFACodeStore for HashmapTwoFACodeStore {
         login_attempt_id: LoginAttemptId,
         code: TwoFACode,
     ) -> Result<(), TwoFACodeStoreError> {
+        self.resends.insert(email.clone(), (0, Utc::now()));
         self.codes.insert(email, (login_attempt_id, code));
         Ok(())
     }
 
     async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
+        self.resends.remove(email);
         match self.codes.remove(email) {
             Some(_) => Ok(()),
             None => Err(TwoFACodeStoreError::LoginAttemptIdNotFound),
@@ -49,6 +59,59 @@ impl TwoFACodeStore for HashmapTwoFACodeStore {
             None => Err(TwoFACodeStoreError::LoginAttemptIdNotFound),
         }
     }
+
+    async fn record_failed_attempt(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        max_attempts: u32,
+    ) -> Result<(), TwoFACodeStoreError> {
+        let failed_attempts = self
+            .failed_attempts
+            .entry(login_attempt_id.as_ref().expose_secret().clone())
+            .or_insert(0);
+        *failed_attempts += 1;
+        if *failed_attempts >= max_attempts {
+            if self.codes.get(email).is_some_and(|(id, _)| id == login_attempt_id) {
+                self.remove_code(email).await?;
+            }
+            return Err(TwoFACodeStoreError::TooManyAttempts);
+        }
+
+        match self.codes.get(email) {
+            Some((id, _)) if id == login_attempt_id => Ok(()),
+            _ => Err(TwoFACodeStoreError::LoginAttemptIdNotFound),
+        }
+    }
+
+    async fn record_resend(
+        &mut self,
+        email: &Email,
+        cooldown_seconds: i64,
+        max_resends: u32,
+    ) -> Result<(), TwoFACodeStoreError> {
+        if !self.codes.contains_key(email) {
+            return Err(TwoFACodeStoreError::LoginAttemptIdNotFound);
+        }
+
+        let now = Utc::now();
+        if let Some((count, last_sent_at)) = self.resends.get(email) {
+            if *count >= max_resends {
+                return Err(TwoFACodeStoreError::TooManyResends);
+            }
+            let available_at = *last_sent_at + Duration::seconds(cooldown_seconds);
+            if available_at > now {
+                return Err(TwoFACodeStoreError::ResendCooldown {
+                    retry_after_seconds: (available_at - now).num_seconds().max(1) as u64,
+                });
+            }
+        }
+
+        let entry = self.resends.entry(email.clone()).or_insert((0, now));
+        entry.0 += 1;
+        entry.1 = now;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -130,4 +193,91 @@ mod tests {
         assert_eq!(retrieved_id, login_attempt_id2);
         assert_eq!(retrieved_code, code2);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_record_failed_attempt_invalidates_code_after_max_attempts() {
+        let mut store = HashmapTwoFACodeStore::new();
+        let email = Email("test@example.com".to_string().into());
+
+        let login_attempt_id = LoginAttemptId::default();
+
+        store.add_code(email.clone(), login_attempt_id.clone(), TwoFACode::default()).await.unwrap();
+
+        assert!(store.record_failed_attempt(&email, &login_attempt_id, 3).await.is_ok());
+        assert!(store.record_failed_attempt(&email, &login_attempt_id, 3).await.is_ok());
+        let result = store.record_failed_attempt(&email, &login_attempt_id, 3).await;
+        assert_eq!(result.unwrap_err(), TwoFACodeStoreError::TooManyAttempts);
+
+        let result = store.get_code(&email).await;
+        assert_eq!(result.unwrap_err(), TwoFACodeStoreError::LoginAttemptIdNotFound);
+
+        // Later attempts for the same login keep failing.
+        let result = store.record_failed_attempt(&email, &login_attempt_id, 3).await;
+        assert_eq!(result.unwrap_err(), TwoFACodeStoreError::TooManyAttempts);
+    }
+
+    #[tokio::test]
+    async fn test_new_code_resets_failed_attempts() {
+        let mut store = HashmapTwoFACodeStore::new();
+        let email = Email("test@example.com".to_string().into());
+
+        let first_attempt_id = LoginAttemptId::default();
+        store.add_code(email.clone(), first_attempt_id.clone(), TwoFACode::default()).await.unwrap();
+        store.record_failed_attempt(&email, &first_attempt_id, 2).await.unwrap();
+
+        let second_attempt_id = LoginAttemptId::default();
+        store.add_code(email.clone(), second_attempt_id.clone(), TwoFACode::default()).await.unwrap();
+        assert!(store.record_failed_attempt(&email, &second_attempt_id, 2).await.is_ok());
+
+        // Failures against the replaced attempt don't remove the new code.
+        let result = store.record_failed_attempt(&email, &first_attempt_id, 2).await;
+        assert_eq!(result.unwrap_err(), TwoFACodeStoreError::TooManyAttempts);
+        assert!(store.get_code(&email).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_record_failed_attempt_without_code() {
+        let mut store = HashmapTwoFACodeStore::new();
+        let email = Email("test@example.com".to_string().into());
+
+        let result = store.record_failed_attempt(&email, &LoginAttemptId::default(), 3).await;
+        assert_eq!(result.unwrap_err(), TwoFACodeStoreError::LoginAttemptIdNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_record_resend_enforces_cooldown() {
+        let mut store = HashmapTwoFACodeStore::new();
+        let email = Email("test@example.com".to_string().into());
+
+        store.add_code(email.clone(), LoginAttemptId::default(), TwoFACode::default()).await.unwrap();
+
+        // The cooldown runs from when the code was first sent.
+        let result = store.record_resend(&email, 60, 3).await;
+        assert!(matches!(
+            result,
+            Err(TwoFACodeStoreError::ResendCooldown { retry_after_seconds }) if retry_after_seconds <= 60
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_record_resend_enforces_max_resends() {
+        let mut store = HashmapTwoFACodeStore::new();
+        let email = Email("test@example.com".to_string().into());
+
+        store.add_code(email.clone(), LoginAttemptId::default(), TwoFACode::default()).await.unwrap();
+
+        assert!(store.record_resend(&email, 0, 2).await.is_ok());
+        assert!(store.record_resend(&email, 0, 2).await.is_ok());
+        let result = store.record_resend(&email, 0, 2).await;
+        assert_eq!(result.unwrap_err(), TwoFACodeStoreError::TooManyResends);
+    }
+
+    #[tokio::test]
+    async fn test_record_resend_without_code() {
+        let mut store = HashmapTwoFACodeStore::new();
+        let email = Email("test@example.com".to_string().into());
+
+        let result = store.record_resend(&email, 60, 3).await;
+        assert_eq!(result.unwrap_err(), TwoFACodeStoreError::LoginAttemptIdNotFound);
+    }
+}
@@ -1,16 +1,53 @@
-use std::collections::HashMap;
-use secrecy::{Secret, ExposeSecret};
-use crate::domain::user::User;
-use crate::domain::data_stores::{UserStoreError, UserStore};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use secrecy::ExposeSecret;
+use crate::domain::user::{PreviousEmail, TotpSecret, TwoFAMethod, User, UserAccount};
+use crate::domain::data_stores::{RecoveryCode, UserStoreError, UserStore};
 use crate::domain::{Email, Password};
 
+#[derive(Default)]
 pub struct HashmapUserStore {
     users: HashMap<String, User>,
+    pending_totp_secrets: HashMap<String, TotpSecret>,
+    // The time step of the last TOTP code each user logged in with.
+    totp_last_counters: HashMap<String, i64>,
+    // Each code with its id.
+    recovery_codes: HashMap<String, Vec<(i64, RecoveryCode)>>,
+    next_recovery_code_id: i64,
+    // (issuer, subject) to email.
+    federated_identities: HashMap<(String, String), String>,
+    user_ids: HashMap<String, String>,
+    token_versions: HashMap<String, i64>,
+    // When each soft deleted user was deleted.
+    deleted_at: HashMap<String, i64>,
+    previous_emails: HashMap<String, PreviousEmail>,
+    locked: HashSet<String>,
+    roles: HashMap<String, BTreeSet<String>>,
+    role_permissions: HashMap<String, BTreeSet<String>>,
 }
 
-impl Default for HashmapUserStore {
-    fn default() -> Self {
-        Self { users: HashMap::new() }
+impl HashmapUserStore {
+    fn account(&self, user: &User) -> UserAccount {
+        let email = user.email.as_ref().expose_secret();
+        UserAccount {
+            user: user.clone(),
+            locked: self.locked.contains(email),
+            deleted_at: self.deleted_at.get(email).copied(),
+            roles: self.roles.get(email).into_iter().flatten().cloned().collect(),
+        }
+    }
+
+    fn matching_users(&self, search: Option<&str>) -> Vec<&User> {
+        let search = search.map(str::to_lowercase);
+        let mut users: Vec<&User> = self
+            .users
+            .values()
+            .filter(|user| match &search {
+                Some(search) => user.email.as_ref().expose_secret().to_lowercase().contains(search),
+                None => true,
+            })
+            .collect();
+        users.sort_by(|a, b| a.email.as_ref().expose_secret().cmp(b.email.as_ref().expose_secret()));
+        users
     }
 }
 
@@ -20,7 +57,9 @@ impl UserStore for HashmapUserStore {
         if self.users.contains_key(user.email.as_ref().expose_secret()) {
             Err(UserStoreError::UserAlreadyExists)
         } else {
-            self.users.insert(user.email.as_ref().expose_secret().clone(), user);
+            let email = user.email.as_ref().expose_secret().clone();
+            self.user_ids.insert(email.clone(), uuid::Uuid::new_v4().to_string());
+            self.users.insert(email, user);
             Ok(())
         }
     }
@@ -36,6 +75,351 @@ impl UserStore for HashmapUserStore {
             None => Err(UserStoreError::UserNotFound),
         }
     }
+
+    async fn update_password(&mut self, email: &str, password: Password) -> Result<(), UserStoreError> {
+        match self.users.get_mut(email) {
+            Some(user) => {
+                user.password = password;
+                Ok(())
+            }
+            None => Err(UserStoreError::UserNotFound),
+        }
+    }
+
+    async fn update_email(&mut self, email: &str, new_email: Email, timestamp: i64) -> Result<(), UserStoreError> {
+        let new_key = new_email.as_ref().expose_secret().to_owned();
+        if self.users.contains_key(&new_key) {
+            return Err(UserStoreError::UserAlreadyExists);
+        }
+        let mut user = self.users.remove(email).ok_or(UserStoreError::UserNotFound)?;
+        let previous_email = std::mem::replace(&mut user.email, new_email);
+        user.email_verified = true;
+        self.users.insert(new_key.clone(), user);
+
+        if let Some(secret) = self.pending_totp_secrets.remove(email) {
+            self.pending_totp_secrets.insert(new_key.clone(), secret);
+        }
+        if let Some(codes) = self.recovery_codes.remove(email) {
+            self.recovery_codes.insert(new_key.clone(), codes);
+        }
+        if let Some(counter) = self.totp_last_counters.remove(email) {
+            self.totp_last_counters.insert(new_key.clone(), counter);
+        }
+        for linked_email in self.federated_identities.values_mut().filter(|linked_email| *linked_email == email) {
+            *linked_email = new_key.clone();
+        }
+        if let Some(id) = self.user_ids.remove(email) {
+            self.user_ids.insert(new_key.clone(), id);
+        }
+        if let Some(version) = self.token_versions.remove(email) {
+            self.token_versions.insert(new_key.clone(), version);
+        }
+        if let Some(deleted_at) = self.deleted_at.remove(email) {
+            self.deleted_at.insert(new_key.clone(), deleted_at);
+        }
+        if self.locked.remove(email) {
+            self.locked.insert(new_key.clone());
+        }
+        if let Some(roles) = self.roles.remove(email) {
+            self.roles.insert(new_key.clone(), roles);
+        }
+        self.previous_emails.remove(email);
+        self.previous_emails.insert(new_key, PreviousEmail { email: previous_email, changed_at: timestamp });
+        Ok(())
+    }
+
+    async fn get_previous_email(&self, email: &str) -> Result<Option<PreviousEmail>, UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        Ok(self.previous_emails.get(email).cloned())
+    }
+
+    async fn is_previous_email_since(&self, email: &str, timestamp: i64) -> Result<bool, UserStoreError> {
+        Ok(self
+            .previous_emails
+            .values()
+            .any(|previous| previous.email.as_ref().expose_secret() == email && previous.changed_at >= timestamp))
+    }
+
+    async fn mark_email_verified(&mut self, email: &str) -> Result<(), UserStoreError> {
+        match self.users.get_mut(email) {
+            Some(user) => {
+                user.email_verified = true;
+                Ok(())
+            }
+            None => Err(UserStoreError::UserNotFound),
+        }
+    }
+
+    async fn set_two_fa_method(&mut self, email: &str, method: TwoFAMethod) -> Result<(), UserStoreError> {
+        match self.users.get_mut(email) {
+            Some(user) => {
+                user.two_fa_method = method;
+                self.pending_totp_secrets.remove(email);
+                Ok(())
+            }
+            None => Err(UserStoreError::UserNotFound),
+        }
+    }
+
+    async fn disable_two_fa(&mut self, email: &str) -> Result<(), UserStoreError> {
+        match self.users.get_mut(email) {
+            Some(user) => {
+                user.two_fa_method = TwoFAMethod::None;
+                self.pending_totp_secrets.remove(email);
+                self.recovery_codes.remove(email);
+                Ok(())
+            }
+            None => Err(UserStoreError::UserNotFound),
+        }
+    }
+
+    async fn set_pending_totp_secret(&mut self, email: &str, secret: TotpSecret) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        self.pending_totp_secrets.insert(email.to_owned(), secret);
+        Ok(())
+    }
+
+    async fn get_pending_totp_secret(&self, email: &str) -> Result<Option<TotpSecret>, UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        Ok(self.pending_totp_secrets.get(email).cloned())
+    }
+
+    async fn use_totp_counter(&mut self, email: &str, counter: i64) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        match self.totp_last_counters.get(email) {
+            Some(last_counter) if *last_counter >= counter => Err(UserStoreError::InvalidCredentials),
+            _ => {
+                self.totp_last_counters.insert(email.to_owned(), counter);
+                Ok(())
+            }
+        }
+    }
+
+    async fn set_recovery_codes(&mut self, email: &str, codes: Vec<RecoveryCode>) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        let codes = codes
+            .into_iter()
+            .map(|code| {
+                self.next_recovery_code_id += 1;
+                (self.next_recovery_code_id, code)
+            })
+            .collect();
+        self.recovery_codes.insert(email.to_owned(), codes);
+        Ok(())
+    }
+
+    async fn find_recovery_code(&self, email: &str, code: &RecoveryCode) -> Result<i64, UserStoreError> {
+        self.recovery_codes
+            .get(email)
+            .and_then(|codes| codes.iter().find(|(_, stored)| stored == code))
+            .map(|(id, _)| *id)
+            .ok_or(UserStoreError::InvalidCredentials)
+    }
+
+    async fn remove_recovery_code(&mut self, email: &str, id: i64) -> Result<(), UserStoreError> {
+        let codes = self.recovery_codes.get_mut(email).ok_or(UserStoreError::InvalidCredentials)?;
+        match codes.iter().position(|(stored_id, _)| *stored_id == id) {
+            Some(index) => {
+                codes.remove(index);
+                Ok(())
+            }
+            None => Err(UserStoreError::InvalidCredentials),
+        }
+    }
+
+    async fn get_federated_user(&self, issuer: &str, subject: &str) -> Result<User, UserStoreError> {
+        self.federated_identities
+            .get(&(issuer.to_owned(), subject.to_owned()))
+            .and_then(|email| self.users.get(email))
+            .cloned()
+            .ok_or(UserStoreError::UserNotFound)
+    }
+
+    async fn link_federated_identity(&mut self, email: &str, issuer: &str, subject: &str) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        self.federated_identities
+            .insert((issuer.to_owned(), subject.to_owned()), email.to_owned());
+        Ok(())
+    }
+
+    async fn get_user_id(&self, email: &str) -> Result<String, UserStoreError> {
+        self.user_ids.get(email).cloned().ok_or(UserStoreError::UserNotFound)
+    }
+
+    async fn get_token_version(&self, email: &str) -> Result<i64, UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        Ok(self.token_versions.get(email).copied().unwrap_or_default())
+    }
+
+    async fn increment_token_version(&mut self, email: &str) -> Result<i64, UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        let version = self.token_versions.entry(email.to_owned()).or_default();
+        *version += 1;
+        Ok(*version)
+    }
+
+    async fn mark_user_deleted(&mut self, email: &str, timestamp: i64) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        self.deleted_at.insert(email.to_owned(), timestamp);
+        Ok(())
+    }
+
+    async fn restore_user(&mut self, email: &str) -> Result<bool, UserStoreError> {
+        Ok(self.deleted_at.remove(email).is_some())
+    }
+
+    async fn get_users_deleted_before(&self, timestamp: i64) -> Result<Vec<Email>, UserStoreError> {
+        Ok(self
+            .deleted_at
+            .iter()
+            .filter(|(_, deleted_at)| **deleted_at < timestamp)
+            .filter_map(|(email, _)| self.users.get(email))
+            .map(|user| user.email.clone())
+            .collect())
+    }
+
+    async fn delete_user(&mut self, email: &str, deleted_before: i64) -> Result<(), UserStoreError> {
+        match self.deleted_at.get(email) {
+            Some(deleted_at) if *deleted_at < deleted_before => {}
+            _ => return Err(UserStoreError::UserNotFound),
+        }
+        self.users.remove(email);
+        self.pending_totp_secrets.remove(email);
+        self.totp_last_counters.remove(email);
+        self.recovery_codes.remove(email);
+        self.federated_identities.retain(|_, linked_email| linked_email != email);
+        self.user_ids.remove(email);
+        self.token_versions.remove(email);
+        self.deleted_at.remove(email);
+        self.previous_emails.remove(email);
+        self.locked.remove(email);
+        self.roles.remove(email);
+        Ok(())
+    }
+
+    async fn list_users(&self, search: Option<&str>, offset: i64, limit: i64) -> Result<Vec<UserAccount>, UserStoreError> {
+        Ok(self
+            .matching_users(search)
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .map(|user| self.account(user))
+            .collect())
+    }
+
+    async fn count_users(&self, search: Option<&str>) -> Result<i64, UserStoreError> {
+        Ok(self.matching_users(search).len() as i64)
+    }
+
+    async fn get_user_account(&self, email: &str) -> Result<UserAccount, UserStoreError> {
+        self.users
+            .get(email)
+            .map(|user| self.account(user))
+            .ok_or(UserStoreError::UserNotFound)
+    }
+
+    async fn set_user_locked(&mut self, email: &str, locked: bool) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        if locked {
+            self.locked.insert(email.to_owned());
+        } else {
+            self.locked.remove(email);
+        }
+        Ok(())
+    }
+
+    async fn is_user_locked(&self, email: &str) -> Result<bool, UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        Ok(self.locked.contains(email))
+    }
+
+    async fn get_roles(&self, email: &str) -> Result<Vec<String>, UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        Ok(self.roles.get(email).into_iter().flatten().cloned().collect())
+    }
+
+    async fn add_role(&mut self, email: &str, role: &str) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        self.roles.entry(email.to_owned()).or_default().insert(role.to_owned());
+        Ok(())
+    }
+
+    async fn remove_role(&mut self, email: &str, role: &str) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        if let Some(roles) = self.roles.get_mut(email) {
+            roles.remove(role);
+        }
+        Ok(())
+    }
+
+    async fn get_users_with_role(&self, role: &str) -> Result<Vec<Email>, UserStoreError> {
+        Ok(self
+            .roles
+            .iter()
+            .filter(|(_, roles)| roles.contains(role))
+            .filter_map(|(email, _)| self.users.get(email))
+            .map(|user| user.email.clone())
+            .collect())
+    }
+
+    async fn get_permissions(&self, email: &str) -> Result<Vec<String>, UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        let permissions: BTreeSet<&String> = self
+            .roles
+            .get(email)
+            .into_iter()
+            .flatten()
+            .filter_map(|role| self.role_permissions.get(role))
+            .flatten()
+            .collect();
+        Ok(permissions.into_iter().cloned().collect())
+    }
+
+    async fn get_role_permissions(&self, role: &str) -> Result<Vec<String>, UserStoreError> {
+        Ok(self.role_permissions.get(role).into_iter().flatten().cloned().collect())
+    }
+
+    async fn grant_permission(&mut self, role: &str, permission: &str) -> Result<(), UserStoreError> {
+        self.role_permissions.entry(role.to_owned()).or_default().insert(permission.to_owned());
+        Ok(())
+    }
+
+    async fn revoke_permission(&mut self, role: &str, permission: &str) -> Result<(), UserStoreError> {
+        if let Some(permissions) = self.role_permissions.get_mut(role) {
+            permissions.remove(permission);
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -47,7 +431,8 @@ mod tests {
         User {
             email: Email::parse(Secret::new(email.to_string())).unwrap(),
             password: Password::parse(Secret::new(password.to_string())).unwrap(),
-            requires_2fa: false,
+            two_fa_method: TwoFAMethod::None,
+            email_verified: false,
         }
     }
 
@@ -106,4 +491,367 @@ mod tests {
         let result = store.validate_user("nonexistent@example.com", "password123").await;
         assert!(matches!(result, Err(UserStoreError::UserNotFound)));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_update_password() {
+        let mut store = HashmapUserStore::default();
+        let user = create_test_user("test@example.com", "password123");
+        store.add_user(user).await.unwrap();
+        let new_password = Password::parse(Secret::new("newpassword123".to_string())).unwrap();
+        assert!(store.update_password("test@example.com", new_password).await.is_ok());
+        assert!(store.validate_user("test@example.com", "newpassword123").await.is_ok());
+        let result = store.validate_user("test@example.com", "password123").await;
+        assert!(matches!(result, Err(UserStoreError::InvalidCredentials)));
+    }
+
+    #[tokio::test]
+    async fn test_update_password_nonexistent_user() {
+        let mut store = HashmapUserStore::default();
+        let new_password = Password::parse(Secret::new("newpassword123".to_string())).unwrap();
+        let result = store.update_password("nonexistent@example.com", new_password).await;
+        assert!(matches!(result, Err(UserStoreError::UserNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_mark_email_verified() {
+        let mut store = HashmapUserStore::default();
+        let user = create_test_user("test@example.com", "password123");
+        store.add_user(user).await.unwrap();
+        assert!(!store.get_user("test@example.com").await.unwrap().email_verified);
+        assert!(store.mark_email_verified("test@example.com").await.is_ok());
+        assert!(store.get_user("test@example.com").await.unwrap().email_verified);
+    }
+
+    #[tokio::test]
+    async fn test_mark_email_verified_nonexistent_user() {
+        let mut store = HashmapUserStore::default();
+        let result = store.mark_email_verified("nonexistent@example.com").await;
+        assert!(matches!(result, Err(UserStoreError::UserNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_enroll_totp() {
+        let mut store = HashmapUserStore::default();
+        let user = create_test_user("test@example.com", "password123");
+        store.add_user(user).await.unwrap();
+        let secret = TotpSecret::default();
+
+        store.set_pending_totp_secret("test@example.com", secret.clone()).await.unwrap();
+        let pending = store.get_pending_totp_secret("test@example.com").await.unwrap();
+        assert_eq!(pending, Some(secret.clone()));
+
+        store.set_two_fa_method("test@example.com", TwoFAMethod::Totp(secret.clone())).await.unwrap();
+        let user = store.get_user("test@example.com").await.unwrap();
+        assert_eq!(user.two_fa_method, TwoFAMethod::Totp(secret));
+        assert_eq!(store.get_pending_totp_secret("test@example.com").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_use_totp_counter() {
+        let mut store = HashmapUserStore::default();
+        store.add_user(create_test_user("test@example.com", "password123")).await.unwrap();
+
+        store.use_totp_counter("test@example.com", 100).await.unwrap();
+        assert_eq!(store.use_totp_counter("test@example.com", 100).await, Err(UserStoreError::InvalidCredentials));
+        assert_eq!(store.use_totp_counter("test@example.com", 99).await, Err(UserStoreError::InvalidCredentials));
+        store.use_totp_counter("test@example.com", 101).await.unwrap();
+        assert_eq!(store.use_totp_counter("other@example.com", 1).await, Err(UserStoreError::UserNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_set_two_fa_method_nonexistent_user() {
+        let mut store = HashmapUserStore::default();
+        let result = store.set_two_fa_method("nonexistent@example.com", TwoFAMethod::Email).await;
+        assert!(matches!(result, Err(UserStoreError::UserNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_use_recovery_code() {
+        let mut store = HashmapUserStore::default();
+        let user = create_test_user("test@example.com", "password123");
+        store.add_user(user).await.unwrap();
+        let code = RecoveryCode::default();
+
+        store.set_recovery_codes("test@example.com", vec![code.clone()]).await.unwrap();
+        let id = store.find_recovery_code("test@example.com", &code).await.unwrap();
+        assert!(store.remove_recovery_code("test@example.com", id).await.is_ok());
+
+        // A code found by two requests at once is only consumed by one of them.
+        let result = store.remove_recovery_code("test@example.com", id).await;
+        assert!(matches!(result, Err(UserStoreError::InvalidCredentials)));
+        let result = store.find_recovery_code("test@example.com", &code).await;
+        assert!(matches!(result, Err(UserStoreError::InvalidCredentials)));
+    }
+
+    #[tokio::test]
+    async fn test_set_recovery_codes_replaces_existing_codes() {
+        let mut store = HashmapUserStore::default();
+        let user = create_test_user("test@example.com", "password123");
+        store.add_user(user).await.unwrap();
+        let old_code = RecoveryCode::default();
+        let new_code = RecoveryCode::default();
+
+        store.set_recovery_codes("test@example.com", vec![old_code.clone()]).await.unwrap();
+        store.set_recovery_codes("test@example.com", vec![new_code.clone()]).await.unwrap();
+
+        let result = store.find_recovery_code("test@example.com", &old_code).await;
+        assert!(matches!(result, Err(UserStoreError::InvalidCredentials)));
+        assert!(store.find_recovery_code("test@example.com", &new_code).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_federated_identity_link() {
+        let mut store = HashmapUserStore::default();
+        let user = create_test_user("test@example.com", "password123");
+        store.add_user(user.clone()).await.unwrap();
+
+        let result = store.get_federated_user("https://idp.example.com", "subject").await;
+        assert_eq!(result.unwrap_err(), UserStoreError::UserNotFound);
+
+        store
+            .link_federated_identity("test@example.com", "https://idp.example.com", "subject")
+            .await
+            .unwrap();
+        let linked = store.get_federated_user("https://idp.example.com", "subject").await.unwrap();
+        assert_eq!(linked.email, user.email);
+
+        let result = store.get_federated_user("https://other.example.com", "subject").await;
+        assert_eq!(result.unwrap_err(), UserStoreError::UserNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_link_federated_identity_to_unknown_user() {
+        let mut store = HashmapUserStore::default();
+
+        let result = store
+            .link_federated_identity("test@example.com", "https://idp.example.com", "subject")
+            .await;
+        assert_eq!(result.unwrap_err(), UserStoreError::UserNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_increment_token_version() {
+        let mut store = HashmapUserStore::default();
+        store.add_user(create_test_user("test@example.com", "password123")).await.unwrap();
+
+        assert_eq!(store.get_token_version("test@example.com").await.unwrap(), 0);
+        assert_eq!(store.increment_token_version("test@example.com").await.unwrap(), 1);
+        assert_eq!(store.get_token_version("test@example.com").await.unwrap(), 1);
+        assert_eq!(
+            store.increment_token_version("unknown@example.com").await.unwrap_err(),
+            UserStoreError::UserNotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn test_soft_delete_and_restore_user() {
+        let mut store = HashmapUserStore::default();
+        store.add_user(create_test_user("test@example.com", "password123")).await.unwrap();
+
+        store.mark_user_deleted("test@example.com", 100).await.unwrap();
+        assert!(store.get_users_deleted_before(100).await.unwrap().is_empty());
+        let deleted = store.get_users_deleted_before(101).await.unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].as_ref().expose_secret(), "test@example.com");
+
+        assert!(store.restore_user("test@example.com").await.unwrap());
+        assert!(!store.restore_user("test@example.com").await.unwrap());
+        assert!(store.get_users_deleted_before(101).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_disable_two_fa() {
+        let mut store = HashmapUserStore::default();
+        let mut user = create_test_user("test@example.com", "password123");
+        user.two_fa_method = TwoFAMethod::Email;
+        store.add_user(user).await.unwrap();
+        let code = RecoveryCode::default();
+        store.set_recovery_codes("test@example.com", vec![code.clone()]).await.unwrap();
+
+        store.disable_two_fa("test@example.com").await.unwrap();
+
+        assert_eq!(store.get_user("test@example.com").await.unwrap().two_fa_method, TwoFAMethod::None);
+        assert_eq!(
+            store.find_recovery_code("test@example.com", &code).await.unwrap_err(),
+            UserStoreError::InvalidCredentials
+        );
+        assert_eq!(
+            store.disable_two_fa("nonexistent@example.com").await.unwrap_err(),
+            UserStoreError::UserNotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_email() {
+        let mut store = HashmapUserStore::default();
+        store.add_user(create_test_user("old@example.com", "password123")).await.unwrap();
+        store
+            .link_federated_identity("old@example.com", "https://idp.example.com", "subject")
+            .await
+            .unwrap();
+        store.increment_token_version("old@example.com").await.unwrap();
+        let user_id = store.get_user_id("old@example.com").await.unwrap();
+
+        let new_email = Email::parse(Secret::new("new@example.com".to_string())).unwrap();
+        store.update_email("old@example.com", new_email.clone(), 100).await.unwrap();
+
+        assert_eq!(store.get_user("old@example.com").await.unwrap_err(), UserStoreError::UserNotFound);
+        let user = store.get_user("new@example.com").await.unwrap();
+        assert_eq!(user.email, new_email);
+        assert!(user.email_verified);
+        assert!(store.validate_user("new@example.com", "password123").await.is_ok());
+        assert_eq!(store.get_token_version("new@example.com").await.unwrap(), 1);
+        assert_eq!(store.get_user_id("new@example.com").await.unwrap(), user_id);
+        assert_eq!(
+            store.get_previous_email("new@example.com").await.unwrap(),
+            Some(PreviousEmail {
+                email: Email::parse(Secret::new("old@example.com".to_string())).unwrap(),
+                changed_at: 100,
+            })
+        );
+        assert!(store.is_previous_email_since("old@example.com", 100).await.unwrap());
+        assert!(!store.is_previous_email_since("old@example.com", 101).await.unwrap());
+        assert!(!store.is_previous_email_since("new@example.com", 0).await.unwrap());
+        assert_eq!(
+            store.get_federated_user("https://idp.example.com", "subject").await.unwrap().email,
+            new_email
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_email_to_existing_user() {
+        let mut store = HashmapUserStore::default();
+        store.add_user(create_test_user("old@example.com", "password123")).await.unwrap();
+        store.add_user(create_test_user("new@example.com", "password123")).await.unwrap();
+
+        let new_email = Email::parse(Secret::new("new@example.com".to_string())).unwrap();
+        assert_eq!(
+            store.update_email("old@example.com", new_email.clone(), 100).await.unwrap_err(),
+            UserStoreError::UserAlreadyExists
+        );
+        assert!(store.get_user("old@example.com").await.is_ok());
+
+        let other_email = Email::parse(Secret::new("other@example.com".to_string())).unwrap();
+        assert_eq!(
+            store.update_email("nonexistent@example.com", other_email, 100).await.unwrap_err(),
+            UserStoreError::UserNotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_and_count_users() {
+        let mut store = HashmapUserStore::default();
+        for email in ["carol@example.com", "alice@example.com", "bob@test.com"] {
+            store.add_user(create_test_user(email, "password123")).await.unwrap();
+        }
+
+        let emails = |accounts: Vec<UserAccount>| -> Vec<String> {
+            accounts
+                .into_iter()
+                .map(|account| account.user.email.as_ref().expose_secret().clone())
+                .collect()
+        };
+
+        let page = store.list_users(None, 0, 2).await.unwrap();
+        assert_eq!(emails(page), vec!["alice@example.com", "bob@test.com"]);
+        let page = store.list_users(None, 2, 2).await.unwrap();
+        assert_eq!(emails(page), vec!["carol@example.com"]);
+        assert_eq!(store.count_users(None).await.unwrap(), 3);
+
+        let page = store.list_users(Some("EXAMPLE"), 0, 10).await.unwrap();
+        assert_eq!(emails(page), vec!["alice@example.com", "carol@example.com"]);
+        assert_eq!(store.count_users(Some("example")).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_lock_user() {
+        let mut store = HashmapUserStore::default();
+        store.add_user(create_test_user("test@example.com", "password123")).await.unwrap();
+        assert!(!store.is_user_locked("test@example.com").await.unwrap());
+
+        store.set_user_locked("test@example.com", true).await.unwrap();
+        assert!(store.is_user_locked("test@example.com").await.unwrap());
+        assert!(store.get_user_account("test@example.com").await.unwrap().locked);
+
+        store.set_user_locked("test@example.com", false).await.unwrap();
+        assert!(!store.is_user_locked("test@example.com").await.unwrap());
+        assert_eq!(
+            store.set_user_locked("nonexistent@example.com", true).await.unwrap_err(),
+            UserStoreError::UserNotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn test_roles() {
+        let mut store = HashmapUserStore::default();
+        store.add_user(create_test_user("test@example.com", "password123")).await.unwrap();
+        assert!(store.get_roles("test@example.com").await.unwrap().is_empty());
+
+        store.add_role("test@example.com", "support").await.unwrap();
+        store.add_role("test@example.com", "admin").await.unwrap();
+        store.add_role("test@example.com", "admin").await.unwrap();
+        assert_eq!(store.get_roles("test@example.com").await.unwrap(), vec!["admin", "support"]);
+        assert_eq!(
+            store.get_users_with_role("support").await.unwrap(),
+            vec![Email::parse(Secret::new("test@example.com".to_string())).unwrap()]
+        );
+
+        store.remove_role("test@example.com", "support").await.unwrap();
+        assert!(store.get_users_with_role("support").await.unwrap().is_empty());
+        assert_eq!(store.get_user_account("test@example.com").await.unwrap().roles, vec!["admin"]);
+        assert_eq!(
+            store.add_role("nonexistent@example.com", "admin").await.unwrap_err(),
+            UserStoreError::UserNotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn test_permissions() {
+        let mut store = HashmapUserStore::default();
+        store.add_user(create_test_user("test@example.com", "password123")).await.unwrap();
+        store.add_role("test@example.com", "admin").await.unwrap();
+        store.add_role("test@example.com", "support").await.unwrap();
+
+        store.grant_permission("admin", "users:write").await.unwrap();
+        store.grant_permission("admin", "users:read").await.unwrap();
+        store.grant_permission("support", "users:read").await.unwrap();
+        store.grant_permission("billing", "invoices:read").await.unwrap();
+        assert_eq!(store.get_role_permissions("admin").await.unwrap(), vec!["users:read", "users:write"]);
+        assert_eq!(
+            store.get_permissions("test@example.com").await.unwrap(),
+            vec!["users:read", "users:write"]
+        );
+
+        store.revoke_permission("admin", "users:write").await.unwrap();
+        assert_eq!(store.get_permissions("test@example.com").await.unwrap(), vec!["users:read"]);
+        assert_eq!(
+            store.get_permissions("nonexistent@example.com").await.unwrap_err(),
+            UserStoreError::UserNotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_user() {
+        let mut store = HashmapUserStore::default();
+        store.add_user(create_test_user("test@example.com", "password123")).await.unwrap();
+        store
+            .link_federated_identity("test@example.com", "https://idp.example.com", "subject")
+            .await
+            .unwrap();
+
+        // Only accounts marked deleted before the cutoff are removed.
+        assert_eq!(store.delete_user("test@example.com", 100).await.unwrap_err(), UserStoreError::UserNotFound);
+        store.mark_user_deleted("test@example.com", 100).await.unwrap();
+        assert_eq!(store.delete_user("test@example.com", 100).await.unwrap_err(), UserStoreError::UserNotFound);
+        assert!(store.get_user("test@example.com").await.is_ok());
+
+        store.delete_user("test@example.com", 101).await.unwrap();
+
+        assert_eq!(store.get_user("test@example.com").await.unwrap_err(), UserStoreError::UserNotFound);
+        assert_eq!(
+            store.get_federated_user("https://idp.example.com", "subject").await.unwrap_err(),
+            UserStoreError::UserNotFound
+        );
+        assert_eq!(store.delete_user("test@example.com", 101).await.unwrap_err(), UserStoreError::UserNotFound);
+    }
+}
@@ -1,18 +1,25 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use async_trait::async_trait;
 use secrecy::{Secret, ExposeSecret};
 use color_eyre::eyre::eyre;
-use crate::domain::data_stores::{BannedTokenStore, BannedTokenStoreError};
+use crate::domain::{
+    data_stores::{BannedTokenStore, BannedTokenStoreError},
+    Email,
+};
 
 #[derive(Clone)]
 pub struct HashsetBannedTokenStore {
     tokens: HashSet<String>,
+    revoked_before: HashMap<Email, i64>,
+    revoked_sessions: HashSet<String>,
 }
 
 impl HashsetBannedTokenStore {
     pub fn new() -> Self {
         HashsetBannedTokenStore {
             tokens: HashSet::new(),
+            revoked_before: HashMap::new(),
+            revoked_sessions: HashSet::new(),
         }
     }
 }
@@ -27,7 +34,7 @@ impl Default for HashsetBannedTokenStore {
 impl BannedTokenStore for HashsetBannedTokenStore {
     async fn add_token(&mut self, token: Secret<String>) -> Result<(), BannedTokenStoreError> {
         if !self.tokens.insert(token.expose_secret().clone()) {
-            return Err(BannedTokenStoreError::UnexpectedError(eyre!("Token already exists").into()));
+            return Err(BannedTokenStoreError::UnexpectedError(eyre!("Token already exists")));
         }
         Ok(())
     }
@@ -35,6 +42,31 @@ impl BannedTokenStore for HashsetBannedTokenStore {
     async fn contains_token(&self, token: &Secret<String>) -> Result<bool, BannedTokenStoreError> {
         Ok(self.tokens.contains(token.expose_secret()))
     }
+
+    async fn revoke_tokens_issued_before(
+        &mut self,
+        email: &Email,
+        timestamp: i64,
+    ) -> Result<(), BannedTokenStoreError> {
+        self.revoked_before.insert(email.clone(), timestamp);
+        Ok(())
+    }
+
+    async fn get_tokens_revoked_before(
+        &self,
+        email: &Email,
+    ) -> Result<Option<i64>, BannedTokenStoreError> {
+        Ok(self.revoked_before.get(email).copied())
+    }
+
+    async fn revoke_session(&mut self, session_id: &str) -> Result<(), BannedTokenStoreError> {
+        self.revoked_sessions.insert(session_id.to_owned());
+        Ok(())
+    }
+
+    async fn is_session_revoked(&self, session_id: &str) -> Result<bool, BannedTokenStoreError> {
+        Ok(self.revoked_sessions.contains(session_id))
+    }
 }
 
 #[cfg(test)]
@@ -66,4 +98,24 @@ mod tests {
 
         assert!(!store.contains_token(&token).await.unwrap());
     }
+
+    #[tokio::test]
+    async fn test_revoke_tokens_issued_before() {
+        let mut store = HashsetBannedTokenStore::new();
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+
+        assert_eq!(store.get_tokens_revoked_before(&email).await.unwrap(), None);
+        assert!(store.revoke_tokens_issued_before(&email, 1_700_000_000).await.is_ok());
+        assert_eq!(store.get_tokens_revoked_before(&email).await.unwrap(), Some(1_700_000_000));
+    }
+
+    #[tokio::test]
+    async fn test_revoke_session() {
+        let mut store = HashsetBannedTokenStore::new();
+
+        assert!(!store.is_session_revoked("session").await.unwrap());
+        assert!(store.revoke_session("session").await.is_ok());
+        assert!(store.is_session_revoked("session").await.unwrap());
+        assert!(!store.is_session_revoked("other_session").await.unwrap());
+    }
 }
\ No newline at end of file
@@ -1,13 +1,49 @@
 pub mod hashmap_user_store;
 pub mod hashset_banned_token_store;
 pub mod hashmap_two_fa_code_store;
+pub mod hashmap_password_reset_token_store;
+pub mod hashmap_refresh_token_store;
+pub mod hashmap_rate_limit_store;
+pub mod hashmap_oauth_client_store;
+pub mod hashmap_authorization_code_store;
+pub mod hashmap_session_store;
+pub mod hashmap_api_key_store;
+pub mod hashmap_audit_log_store;
+pub mod hashmap_audit_head_store;
 pub mod postgres_user_store;
+pub mod postgres_oauth_client_store;
+pub mod postgres_api_key_store;
+pub mod postgres_audit_log_store;
 pub mod redis_banned_token_store;
 pub mod redis_two_fa_code_store; // Nouveau
+pub mod redis_password_reset_token_store;
+pub mod redis_refresh_token_store;
+pub mod redis_rate_limit_store;
+pub mod redis_authorization_code_store;
+pub mod redis_session_store;
+pub mod redis_audit_head_store;
 
 pub use hashmap_user_store::*;
 pub use hashset_banned_token_store::*;
 pub use hashmap_two_fa_code_store::*;
+pub use hashmap_password_reset_token_store::*;
+pub use hashmap_refresh_token_store::*;
+pub use hashmap_rate_limit_store::*;
+pub use hashmap_oauth_client_store::*;
+pub use hashmap_authorization_code_store::*;
+pub use hashmap_session_store::*;
+pub use hashmap_api_key_store::*;
+pub use hashmap_audit_log_store::*;
+pub use hashmap_audit_head_store::*;
 pub use postgres_user_store::*;
+pub use postgres_oauth_client_store::*;
+pub use postgres_api_key_store::*;
+pub use postgres_audit_log_store::*;
 pub use redis_banned_token_store::*;
-pub use redis_two_fa_code_store::*; // Nouveau
\ No newline at end of file
+pub use redis_two_fa_code_store::*; // Nouveau
+pub use redis_password_reset_token_store::*;
+pub use redis_refresh_token_store::*;
+pub use redis_rate_limit_store::*;
+pub use redis_authorization_code_store::*;
+pub use redis_session_store::*;
+pub use redis_audit_head_store::*;
\ No newline at end of file
@@ -0,0 +1,187 @@
+use sqlx::PgPool;
+use secrecy::{ExposeSecret, Secret};
+use crate::domain::{
+    api_key::{ApiKey, ApiKeySecret},
+    data_stores::{ApiKeyStore, ApiKeyStoreError},
+    Email,
+};
+
+pub struct PostgresApiKeyStore {
+    pool: PgPool,
+}
+
+impl PostgresApiKeyStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiKeyStore for PostgresApiKeyStore {
+    #[tracing::instrument(name = "Adding API key to PostgreSQL", skip_all)]
+    async fn add_key(&mut self, key: ApiKey, secret: &ApiKeySecret) -> Result<(), ApiKeyStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO api_keys (id, email, name, scopes, key_hash, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            key.id,
+            key.email.as_ref().expose_secret(),
+            key.name,
+            &key.scopes,
+            secret.hash(),
+            key.created_at,
+            key.expires_at
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiKeyStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving API keys from PostgreSQL", skip_all)]
+    async fn get_keys(&self, email: &Email) -> Result<Vec<ApiKey>, ApiKeyStoreError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, email, name, scopes, created_at, expires_at, last_used_at
+            FROM api_keys
+            WHERE email = $1
+            ORDER BY created_at, id
+            "#,
+            email.as_ref().expose_secret()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiKeyStoreError::UnexpectedError(e.into()))?;
+
+        rows.into_iter()
+            .map(|row| {
+                parse_api_key(
+                    row.id,
+                    row.email,
+                    row.name,
+                    row.scopes,
+                    row.created_at,
+                    row.expires_at,
+                    row.last_used_at,
+                )
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(name = "Rotating API key in PostgreSQL", skip_all)]
+    async fn rotate_key(&mut self, email: &Email, id: &str, secret: &ApiKeySecret) -> Result<ApiKey, ApiKeyStoreError> {
+        let row = sqlx::query!(
+            r#"
+            UPDATE api_keys
+            SET key_hash = $3
+            WHERE email = $1 AND id = $2
+            RETURNING id, email, name, scopes, created_at, expires_at, last_used_at
+            "#,
+            email.as_ref().expose_secret(),
+            id,
+            secret.hash()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| ApiKeyStoreError::UnexpectedError(e.into()))?
+        .ok_or(ApiKeyStoreError::KeyNotFound)?;
+
+        parse_api_key(
+            row.id,
+            row.email,
+            row.name,
+            row.scopes,
+            row.created_at,
+            row.expires_at,
+            row.last_used_at,
+        )
+    }
+
+    #[tracing::instrument(name = "Revoking API key in PostgreSQL", skip_all)]
+    async fn revoke_key(&mut self, email: &Email, id: &str) -> Result<(), ApiKeyStoreError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM api_keys
+            WHERE email = $1 AND id = $2
+            "#,
+            email.as_ref().expose_secret(),
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiKeyStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(ApiKeyStoreError::KeyNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Revoking user API keys in PostgreSQL", skip_all)]
+    async fn revoke_user_keys(&mut self, email: &Email) -> Result<(), ApiKeyStoreError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM api_keys
+            WHERE email = $1
+            "#,
+            email.as_ref().expose_secret()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiKeyStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Using API key in PostgreSQL", skip_all)]
+    async fn use_key(&mut self, secret: &ApiKeySecret, timestamp: i64) -> Result<ApiKey, ApiKeyStoreError> {
+        let row = sqlx::query!(
+            r#"
+            UPDATE api_keys
+            SET last_used_at = $3
+            WHERE id = $1 AND key_hash = $2 AND expires_at > $3
+            RETURNING id, email, name, scopes, created_at, expires_at, last_used_at
+            "#,
+            secret.id(),
+            secret.hash(),
+            timestamp
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| ApiKeyStoreError::UnexpectedError(e.into()))?
+        .ok_or(ApiKeyStoreError::KeyNotFound)?;
+
+        parse_api_key(
+            row.id,
+            row.email,
+            row.name,
+            row.scopes,
+            row.created_at,
+            row.expires_at,
+            row.last_used_at,
+        )
+    }
+}
+
+fn parse_api_key(
+    id: String,
+    email: String,
+    name: String,
+    scopes: Vec<String>,
+    created_at: i64,
+    expires_at: i64,
+    last_used_at: Option<i64>,
+) -> Result<ApiKey, ApiKeyStoreError> {
+    Ok(ApiKey {
+        id,
+        email: Email::parse(Secret::new(email)).map_err(ApiKeyStoreError::UnexpectedError)?,
+        name,
+        scopes,
+        created_at,
+        expires_at,
+        last_used_at,
+    })
+}
@@ -0,0 +1,216 @@
+use secrecy::Secret;
+use sqlx::PgPool;
+use crate::domain::{
+    audit::{AuditEvent, AuditEventFilter, AuditOutcome, AuditRecord, AUDIT_CHAIN_START},
+    data_stores::{AuditLogStore, AuditLogStoreError},
+};
+
+// Key of the advisory lock taken for appends, unique to this table.
+const AUDIT_APPEND_LOCK_KEY: i64 = 0x6175_6469_745f_6c6f;
+
+pub struct PostgresAuditLogStore {
+    pool: PgPool,
+    // Keys the hash chain; it isn't stored with the log.
+    key: Secret<String>,
+}
+
+impl PostgresAuditLogStore {
+    pub fn new(pool: PgPool, key: Secret<String>) -> Self {
+        Self { pool, key }
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditLogStore for PostgresAuditLogStore {
+    #[tracing::instrument(name = "Appending audit event to PostgreSQL", skip_all)]
+    async fn append_event(&self, event: AuditEvent) -> Result<AuditRecord, AuditLogStoreError> {
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| AuditLogStoreError::UnexpectedError(e.into()))?;
+
+        // Appends are serialised, across instances too, so each event chains to the one before it.
+        // The lock is released when the transaction ends and nothing else waits on it.
+        sqlx::query!("SELECT pg_advisory_xact_lock($1)", AUDIT_APPEND_LOCK_KEY)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| AuditLogStoreError::UnexpectedError(e.into()))?;
+
+        let previous_hash = sqlx::query!(
+            r#"
+            SELECT hash
+            FROM audit_events
+            ORDER BY id DESC
+            LIMIT 1
+            "#
+        )
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(|e| AuditLogStoreError::UnexpectedError(e.into()))?
+        .map(|row| row.hash)
+        .unwrap_or_else(|| AUDIT_CHAIN_START.to_owned());
+        let hash = event.chain_hash(&previous_hash, &self.key);
+
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO audit_events (actor, target, action, outcome, ip, user_agent, request_id, created_at, previous_hash, hash)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id
+            "#,
+            event.actor,
+            event.target,
+            event.action,
+            event.outcome.as_str(),
+            event.ip,
+            event.user_agent,
+            event.request_id,
+            event.created_at,
+            previous_hash,
+            hash
+        )
+        .fetch_one(&mut *transaction)
+        .await
+        .map_err(|e| AuditLogStoreError::UnexpectedError(e.into()))?;
+
+        transaction.commit().await
+            .map_err(|e| AuditLogStoreError::UnexpectedError(e.into()))?;
+
+        Ok(AuditRecord {
+            id: row.id,
+            event,
+            previous_hash,
+            hash,
+        })
+    }
+
+    #[tracing::instrument(name = "Retrieving audit events from PostgreSQL", skip_all)]
+    async fn get_events(&self, filter: &AuditEventFilter, offset: i64, limit: i64) -> Result<Vec<AuditRecord>, AuditLogStoreError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, actor, target, action, outcome, ip, user_agent, request_id, created_at, previous_hash, hash
+            FROM audit_events
+            WHERE ($1::TEXT IS NULL OR actor = $1 OR target = $1)
+              AND ($2::TEXT IS NULL OR action = $2)
+              AND ($3::BIGINT IS NULL OR created_at >= $3)
+              AND ($4::BIGINT IS NULL OR created_at <= $4)
+            ORDER BY id DESC
+            LIMIT $5 OFFSET $6
+            "#,
+            filter.user,
+            filter.action,
+            filter.from,
+            filter.to,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AuditLogStoreError::UnexpectedError(e.into()))?;
+
+        rows.into_iter()
+            .map(|row| {
+                parse_audit_record(
+                    row.id,
+                    row.actor,
+                    row.target,
+                    row.action,
+                    &row.outcome,
+                    row.ip,
+                    row.user_agent,
+                    row.request_id,
+                    row.created_at,
+                    row.previous_hash,
+                    row.hash,
+                )
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(name = "Counting audit events in PostgreSQL", skip_all)]
+    async fn count_events(&self, filter: &AuditEventFilter) -> Result<i64, AuditLogStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM audit_events
+            WHERE ($1::TEXT IS NULL OR actor = $1 OR target = $1)
+              AND ($2::TEXT IS NULL OR action = $2)
+              AND ($3::BIGINT IS NULL OR created_at >= $3)
+              AND ($4::BIGINT IS NULL OR created_at <= $4)
+            "#,
+            filter.user,
+            filter.action,
+            filter.from,
+            filter.to
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AuditLogStoreError::UnexpectedError(e.into()))?;
+
+        Ok(row.count)
+    }
+
+    #[tracing::instrument(name = "Retrieving audit chain page from PostgreSQL", skip_all)]
+    async fn get_chain_page(&self, after_id: i64, limit: i64) -> Result<Vec<AuditRecord>, AuditLogStoreError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, actor, target, action, outcome, ip, user_agent, request_id, created_at, previous_hash, hash
+            FROM audit_events
+            WHERE id > $1
+            ORDER BY id
+            LIMIT $2
+            "#,
+            after_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AuditLogStoreError::UnexpectedError(e.into()))?;
+
+        rows.into_iter()
+            .map(|row| {
+                parse_audit_record(
+                    row.id,
+                    row.actor,
+                    row.target,
+                    row.action,
+                    &row.outcome,
+                    row.ip,
+                    row.user_agent,
+                    row.request_id,
+                    row.created_at,
+                    row.previous_hash,
+                    row.hash,
+                )
+            })
+            .collect()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn parse_audit_record(
+    id: i64,
+    actor: Option<String>,
+    target: Option<String>,
+    action: String,
+    outcome: &str,
+    ip: Option<String>,
+    user_agent: Option<String>,
+    request_id: Option<String>,
+    created_at: i64,
+    previous_hash: String,
+    hash: String,
+) -> Result<AuditRecord, AuditLogStoreError> {
+    Ok(AuditRecord {
+        id,
+        event: AuditEvent {
+            actor,
+            target,
+            action,
+            outcome: AuditOutcome::parse(outcome).map_err(AuditLogStoreError::UnexpectedError)?,
+            ip,
+            user_agent,
+            request_id,
+            created_at,
+        },
+        previous_hash,
+        hash,
+    })
+}
@@ -0,0 +1,96 @@
+use sqlx::PgPool;
+use secrecy::{ExposeSecret, Secret};
+use crate::domain::{
+    data_stores::{OAuthClientStore, OAuthClientStoreError},
+    oauth::OAuthClient,
+};
+use super::postgres_user_store::{compute_password_hash, verify_password_hash};
+
+pub struct PostgresOAuthClientStore {
+    pool: PgPool,
+}
+
+impl PostgresOAuthClientStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl OAuthClientStore for PostgresOAuthClientStore {
+    #[tracing::instrument(name = "Adding OAuth client to PostgreSQL", skip_all)]
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError> {
+        let client_secret_hash = match client.client_secret {
+            Some(secret) => Some(
+                compute_password_hash(secret)
+                    .await
+                    .map_err(OAuthClientStoreError::UnexpectedError)?,
+            ),
+            None => None,
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO oauth_clients (client_id, name, redirect_uris, client_secret_hash, scopes)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            client.client_id,
+            client.name,
+            &client.redirect_uris,
+            client_secret_hash.as_ref().map(|hash| hash.expose_secret().as_str()),
+            &client.scopes
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+                OAuthClientStoreError::ClientAlreadyExists
+            }
+            e => OAuthClientStoreError::UnexpectedError(e.into()),
+        })?;
+
+        Ok(())
+    }
+
+    // The returned client carries the hash of its secret, not the secret itself.
+    #[tracing::instrument(name = "Retrieving OAuth client from PostgreSQL", skip_all)]
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError> {
+        sqlx::query!(
+            r#"
+            SELECT client_id, name, redirect_uris, client_secret_hash, scopes
+            FROM oauth_clients
+            WHERE client_id = $1
+            "#,
+            client_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| OAuthClientStoreError::UnexpectedError(e.into()))?
+        .map(|row| {
+            OAuthClient::new(
+                row.client_id,
+                row.name,
+                row.redirect_uris,
+                row.client_secret_hash.map(Secret::new),
+                row.scopes,
+            )
+        })
+        .ok_or(OAuthClientStoreError::ClientNotFound)
+    }
+
+    #[tracing::instrument(name = "Validating OAuth client secret in PostgreSQL", skip_all)]
+    async fn validate_client_secret(
+        &self,
+        client_id: &str,
+        client_secret: &Secret<String>,
+    ) -> Result<(), OAuthClientStoreError> {
+        let client = self.get_client(client_id).await?;
+        let client_secret_hash = client
+            .client_secret
+            .ok_or(OAuthClientStoreError::InvalidCredentials)?;
+
+        verify_password_hash(client_secret_hash, client_secret.clone())
+            .await
+            .map_err(|_| OAuthClientStoreError::InvalidCredentials)
+    }
+}
@@ -1,4 +1,3 @@
-use std::error::Error;
 use argon2::{
     password_hash::SaltString, Algorithm, Argon2, Params, PasswordHash, PasswordHasher,
     PasswordVerifier, Version,
@@ -7,8 +6,8 @@ use sqlx::PgPool;
 use color_eyre::eyre::{eyre, Context, Result};
 use secrecy::{ExposeSecret, Secret};
 use crate::domain::{
-    data_stores::{UserStore, UserStoreError},
-    user::User,
+    data_stores::{RecoveryCode, UserStore, UserStoreError},
+    user::{PreviousEmail, TotpSecret, TwoFAMethod, User, UserAccount},
     Email, Password,
 };
 
@@ -32,16 +31,23 @@ impl UserStore for PostgresUserStore {
 
         sqlx::query!(
             r#"
-            INSERT INTO users (email, password_hash, requires_2fa)
-            VALUES ($1, $2, $3)
+            INSERT INTO users (email, password_hash, two_fa_method, totp_secret, email_verified)
+            VALUES ($1, $2, $3, $4, $5)
             "#,
             user.email.as_ref().expose_secret(),
             password_hash.expose_secret(),
-            user.requires_2fa
+            user.two_fa_method.as_str(),
+            get_totp_secret(&user.two_fa_method),
+            user.email_verified
         )
         .execute(&self.pool)
         .await
-        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+                UserStoreError::UserAlreadyExists
+            }
+            e => UserStoreError::UnexpectedError(e.into()),
+        })?;
 
         Ok(())
     }
@@ -50,7 +56,7 @@ impl UserStore for PostgresUserStore {
     async fn get_user(&self, email: &str) -> Result<User, UserStoreError> {
         sqlx::query!(
             r#"
-            SELECT email, password_hash, requires_2fa
+            SELECT email, password_hash, two_fa_method, totp_secret, email_verified
             FROM users
             WHERE email = $1
             "#,
@@ -59,15 +65,7 @@ impl UserStore for PostgresUserStore {
         .fetch_optional(&self.pool)
         .await
         .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
-        .map(|row| {
-            Ok(User {
-                email: Email::parse(Secret::new(row.email))
-                    .map_err(UserStoreError::UnexpectedError)?,
-                password: Password::parse(Secret::new(row.password_hash))
-                    .map_err(UserStoreError::UnexpectedError)?,
-                requires_2fa: row.requires_2fa,
-            })
-        })
+        .map(|row| parse_user(row.email, row.password_hash, &row.two_fa_method, row.totp_secret, row.email_verified))
         .ok_or(UserStoreError::UserNotFound)?
     }
 
@@ -96,10 +94,859 @@ impl UserStore for PostgresUserStore {
             Err(e) => Err(UserStoreError::UnexpectedError(e.into())),
         }
     }
+
+    #[tracing::instrument(name = "Updating user password in PostgreSQL", skip_all)]
+    async fn update_password(&mut self, email: &str, password: Password) -> Result<(), UserStoreError> {
+        let password_hash = compute_password_hash(password.as_ref().clone())
+            .await
+            .map_err(UserStoreError::UnexpectedError)?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET password_hash = $2
+            WHERE email = $1
+            "#,
+            email,
+            password_hash.expose_secret()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Updating user email in PostgreSQL", skip_all)]
+    async fn update_email(&mut self, email: &str, new_email: Email, timestamp: i64) -> Result<(), UserStoreError> {
+        // Recovery codes and federated identities follow through their foreign keys.
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET email = $2, email_verified = TRUE, previous_email = email, email_changed_at = $3
+            WHERE email = $1
+            "#,
+            email,
+            new_email.as_ref().expose_secret(),
+            timestamp
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+                UserStoreError::UserAlreadyExists
+            }
+            e => UserStoreError::UnexpectedError(e.into()),
+        })?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving user previous email from PostgreSQL", skip_all)]
+    async fn get_previous_email(&self, email: &str) -> Result<Option<PreviousEmail>, UserStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT previous_email, email_changed_at
+            FROM users
+            WHERE email = $1
+            "#,
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .ok_or(UserStoreError::UserNotFound)?;
+
+        row.previous_email
+            .map(|previous_email| {
+                Ok(PreviousEmail {
+                    email: Email::parse(Secret::new(previous_email))?,
+                    // Changes made before the time was recorded are long past.
+                    changed_at: row.email_changed_at.unwrap_or(0),
+                })
+            })
+            .transpose()
+            .map_err(UserStoreError::UnexpectedError)
+    }
+
+    #[tracing::instrument(name = "Checking previous emails in PostgreSQL", skip_all)]
+    async fn is_previous_email_since(&self, email: &str, timestamp: i64) -> Result<bool, UserStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1
+                FROM users
+                WHERE previous_email = $1 AND email_changed_at >= $2
+            ) AS "exists!"
+            "#,
+            email,
+            timestamp
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        Ok(row.exists)
+    }
+
+    #[tracing::instrument(name = "Marking user email as verified in PostgreSQL", skip_all)]
+    async fn mark_email_verified(&mut self, email: &str) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET email_verified = TRUE
+            WHERE email = $1
+            "#,
+            email
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Setting user 2FA method in PostgreSQL", skip_all)]
+    async fn set_two_fa_method(&mut self, email: &str, method: TwoFAMethod) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET two_fa_method = $2, totp_secret = $3, pending_totp_secret = NULL
+            WHERE email = $1
+            "#,
+            email,
+            method.as_str(),
+            get_totp_secret(&method)
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Disabling user 2FA in PostgreSQL", skip_all)]
+    async fn disable_two_fa(&mut self, email: &str) -> Result<(), UserStoreError> {
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET two_fa_method = $2, totp_secret = NULL, pending_totp_secret = NULL
+            WHERE email = $1
+            "#,
+            email,
+            TwoFAMethod::None.as_str()
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        sqlx::query!(
+            r#"
+            DELETE FROM recovery_codes
+            WHERE email = $1
+            "#,
+            email
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        transaction.commit().await
+            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Setting pending TOTP secret in PostgreSQL", skip_all)]
+    async fn set_pending_totp_secret(&mut self, email: &str, secret: TotpSecret) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET pending_totp_secret = $2
+            WHERE email = $1
+            "#,
+            email,
+            secret.as_ref().expose_secret()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving pending TOTP secret from PostgreSQL", skip_all)]
+    async fn get_pending_totp_secret(&self, email: &str) -> Result<Option<TotpSecret>, UserStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT pending_totp_secret
+            FROM users
+            WHERE email = $1
+            "#,
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .ok_or(UserStoreError::UserNotFound)?;
+
+        row.pending_totp_secret
+            .map(|secret| TotpSecret::parse(Secret::new(secret)))
+            .transpose()
+            .map_err(UserStoreError::UnexpectedError)
+    }
+
+    #[tracing::instrument(name = "Recording used TOTP counter in PostgreSQL", skip_all)]
+    async fn use_totp_counter(&mut self, email: &str, counter: i64) -> Result<(), UserStoreError> {
+        // A single conditional update, so two requests can't both use the same code.
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET totp_last_counter = $2
+            WHERE email = $1 AND (totp_last_counter IS NULL OR totp_last_counter < $2)
+            "#,
+            email,
+            counter
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            self.get_token_version(email).await?;
+            return Err(UserStoreError::InvalidCredentials);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Setting recovery codes in PostgreSQL", skip_all)]
+    async fn set_recovery_codes(&mut self, email: &str, codes: Vec<RecoveryCode>) -> Result<(), UserStoreError> {
+        let mut code_hashes = Vec::with_capacity(codes.len());
+        for code in codes {
+            let code_hash = compute_password_hash(code.as_ref().clone())
+                .await
+                .map_err(UserStoreError::UnexpectedError)?;
+            code_hashes.push(code_hash.expose_secret().clone());
+        }
+
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        let user_exists = sqlx::query!(
+            r#"
+            SELECT email
+            FROM users
+            WHERE email = $1
+            FOR UPDATE
+            "#,
+            email
+        )
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .is_some();
+
+        if !user_exists {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        sqlx::query!(
+            r#"
+            DELETE FROM recovery_codes
+            WHERE email = $1
+            "#,
+            email
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO recovery_codes (email, code_hash)
+            SELECT $1, code_hash FROM UNNEST($2::TEXT[]) AS code_hash
+            "#,
+            email,
+            &code_hashes
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        transaction.commit().await
+            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Finding recovery code in PostgreSQL", skip_all)]
+    async fn find_recovery_code(&self, email: &str, code: &RecoveryCode) -> Result<i64, UserStoreError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, code_hash
+            FROM recovery_codes
+            WHERE email = $1
+            "#,
+            email
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        for row in rows {
+            let matches = verify_password_hash(Secret::new(row.code_hash), code.as_ref().clone())
+                .await
+                .is_ok();
+            if matches {
+                return Ok(row.id.into());
+            }
+        }
+
+        Err(UserStoreError::InvalidCredentials)
+    }
+
+    #[tracing::instrument(name = "Removing recovery code from PostgreSQL", skip_all)]
+    async fn remove_recovery_code(&mut self, email: &str, id: i64) -> Result<(), UserStoreError> {
+        // Deleting is what redeems the code, so a concurrent attempt with the same code finds nothing to delete.
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM recovery_codes
+            WHERE id = $1::BIGINT AND email = $2
+            "#,
+            id,
+            email
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        match result.rows_affected() {
+            0 => Err(UserStoreError::InvalidCredentials),
+            _ => Ok(()),
+        }
+    }
+
+    #[tracing::instrument(name = "Retrieving federated user from PostgreSQL", skip_all)]
+    async fn get_federated_user(&self, issuer: &str, subject: &str) -> Result<User, UserStoreError> {
+        let email = sqlx::query!(
+            r#"
+            SELECT email
+            FROM federated_identities
+            WHERE issuer = $1 AND subject = $2
+            "#,
+            issuer,
+            subject
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .ok_or(UserStoreError::UserNotFound)?
+        .email;
+
+        self.get_user(&email).await
+    }
+
+    #[tracing::instrument(name = "Linking federated identity in PostgreSQL", skip_all)]
+    async fn link_federated_identity(&mut self, email: &str, issuer: &str, subject: &str) -> Result<(), UserStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO federated_identities (issuer, subject, email)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (issuer, subject) DO UPDATE SET email = EXCLUDED.email
+            "#,
+            issuer,
+            subject,
+            email
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db_err) if db_err.is_foreign_key_violation() => {
+                UserStoreError::UserNotFound
+            }
+            e => UserStoreError::UnexpectedError(e.into()),
+        })?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Getting user ID from PostgreSQL", skip_all)]
+    async fn get_user_id(&self, email: &str) -> Result<String, UserStoreError> {
+        sqlx::query!(
+            r#"
+            SELECT id
+            FROM users
+            WHERE email = $1
+            "#,
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .map(|row| row.id)
+        .ok_or(UserStoreError::UserNotFound)
+    }
+
+    #[tracing::instrument(name = "Getting user token version from PostgreSQL", skip_all)]
+    async fn get_token_version(&self, email: &str) -> Result<i64, UserStoreError> {
+        sqlx::query!(
+            r#"
+            SELECT token_version
+            FROM users
+            WHERE email = $1
+            "#,
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .map(|row| row.token_version)
+        .ok_or(UserStoreError::UserNotFound)
+    }
+
+    #[tracing::instrument(name = "Incrementing user token version in PostgreSQL", skip_all)]
+    async fn increment_token_version(&mut self, email: &str) -> Result<i64, UserStoreError> {
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET token_version = token_version + 1
+            WHERE email = $1
+            RETURNING token_version
+            "#,
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .map(|row| row.token_version)
+        .ok_or(UserStoreError::UserNotFound)
+    }
+
+    #[tracing::instrument(name = "Marking user as deleted in PostgreSQL", skip_all)]
+    async fn mark_user_deleted(&mut self, email: &str, timestamp: i64) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET deleted_at = $2
+            WHERE email = $1
+            "#,
+            email,
+            timestamp
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Restoring deleted user in PostgreSQL", skip_all)]
+    async fn restore_user(&mut self, email: &str) -> Result<bool, UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET deleted_at = NULL
+            WHERE email = $1 AND deleted_at IS NOT NULL
+            "#,
+            email
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    #[tracing::instrument(name = "Retrieving deleted users from PostgreSQL", skip_all)]
+    async fn get_users_deleted_before(&self, timestamp: i64) -> Result<Vec<Email>, UserStoreError> {
+        sqlx::query!(
+            r#"
+            SELECT email
+            FROM users
+            WHERE deleted_at < $1
+            "#,
+            timestamp
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .into_iter()
+        .map(|row| Email::parse(Secret::new(row.email)).map_err(UserStoreError::UnexpectedError))
+        .collect()
+    }
+
+    #[tracing::instrument(name = "Deleting user from PostgreSQL", skip_all)]
+    async fn delete_user(&mut self, email: &str, deleted_before: i64) -> Result<(), UserStoreError> {
+        // Recovery codes and federated identities are removed by their foreign keys.
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM users
+            WHERE email = $1 AND deleted_at IS NOT NULL AND deleted_at < $2
+            "#,
+            email,
+            deleted_before
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Listing users from PostgreSQL", skip_all)]
+    async fn list_users(&self, search: Option<&str>, offset: i64, limit: i64) -> Result<Vec<UserAccount>, UserStoreError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT u.email, u.password_hash, u.two_fa_method, u.totp_secret, u.email_verified,
+                   u.locked, u.deleted_at,
+                   ARRAY(SELECT r.role FROM user_roles r WHERE r.email = u.email ORDER BY r.role) AS "roles!"
+            FROM users u
+            WHERE $1::TEXT IS NULL OR u.email ILIKE $1
+            ORDER BY u.email
+            LIMIT $2 OFFSET $3
+            "#,
+            search.map(like_pattern),
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(UserAccount {
+                    user: parse_user(row.email, row.password_hash, &row.two_fa_method, row.totp_secret, row.email_verified)?,
+                    locked: row.locked,
+                    deleted_at: row.deleted_at,
+                    roles: row.roles,
+                })
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(name = "Counting users in PostgreSQL", skip_all)]
+    async fn count_users(&self, search: Option<&str>) -> Result<i64, UserStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM users
+            WHERE $1::TEXT IS NULL OR email ILIKE $1
+            "#,
+            search.map(like_pattern)
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        Ok(row.count)
+    }
+
+    #[tracing::instrument(name = "Retrieving user account from PostgreSQL", skip_all)]
+    async fn get_user_account(&self, email: &str) -> Result<UserAccount, UserStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT u.email, u.password_hash, u.two_fa_method, u.totp_secret, u.email_verified,
+                   u.locked, u.deleted_at,
+                   ARRAY(SELECT r.role FROM user_roles r WHERE r.email = u.email ORDER BY r.role) AS "roles!"
+            FROM users u
+            WHERE u.email = $1
+            "#,
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .ok_or(UserStoreError::UserNotFound)?;
+
+        Ok(UserAccount {
+            user: parse_user(row.email, row.password_hash, &row.two_fa_method, row.totp_secret, row.email_verified)?,
+            locked: row.locked,
+            deleted_at: row.deleted_at,
+            roles: row.roles,
+        })
+    }
+
+    #[tracing::instrument(name = "Setting user locked in PostgreSQL", skip_all)]
+    async fn set_user_locked(&mut self, email: &str, locked: bool) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET locked = $2
+            WHERE email = $1
+            "#,
+            email,
+            locked
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving user locked from PostgreSQL", skip_all)]
+    async fn is_user_locked(&self, email: &str) -> Result<bool, UserStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT locked
+            FROM users
+            WHERE email = $1
+            "#,
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .ok_or(UserStoreError::UserNotFound)?;
+
+        Ok(row.locked)
+    }
+
+    #[tracing::instrument(name = "Retrieving user roles from PostgreSQL", skip_all)]
+    async fn get_roles(&self, email: &str) -> Result<Vec<String>, UserStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT ARRAY(SELECT r.role FROM user_roles r WHERE r.email = u.email ORDER BY r.role) AS "roles!"
+            FROM users u
+            WHERE u.email = $1
+            "#,
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .ok_or(UserStoreError::UserNotFound)?;
+
+        Ok(row.roles)
+    }
+
+    #[tracing::instrument(name = "Adding user role in PostgreSQL", skip_all)]
+    async fn add_role(&mut self, email: &str, role: &str) -> Result<(), UserStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO user_roles (email, role)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+            email,
+            role
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db_err) if db_err.is_foreign_key_violation() => {
+                UserStoreError::UserNotFound
+            }
+            e => UserStoreError::UnexpectedError(e.into()),
+        })?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Removing user role in PostgreSQL", skip_all)]
+    async fn remove_role(&mut self, email: &str, role: &str) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_roles
+            WHERE email = $1 AND role = $2
+            "#,
+            email,
+            role
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        // Removing a role the user doesn't have is fine, as long as the user exists.
+        if result.rows_affected() == 0 {
+            self.get_token_version(email).await?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving users with role from PostgreSQL", skip_all)]
+    async fn get_users_with_role(&self, role: &str) -> Result<Vec<Email>, UserStoreError> {
+        sqlx::query!(
+            r#"
+            SELECT email
+            FROM user_roles
+            WHERE role = $1
+            "#,
+            role
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .into_iter()
+        .map(|row| Email::parse(Secret::new(row.email)).map_err(UserStoreError::UnexpectedError))
+        .collect()
+    }
+
+    #[tracing::instrument(name = "Retrieving user permissions from PostgreSQL", skip_all)]
+    async fn get_permissions(&self, email: &str) -> Result<Vec<String>, UserStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT ARRAY(
+                SELECT DISTINCT p.permission
+                FROM user_roles r
+                JOIN role_permissions p ON p.role = r.role
+                WHERE r.email = u.email
+                ORDER BY p.permission
+            ) AS "permissions!"
+            FROM users u
+            WHERE u.email = $1
+            "#,
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .ok_or(UserStoreError::UserNotFound)?;
+
+        Ok(row.permissions)
+    }
+
+    #[tracing::instrument(name = "Retrieving role permissions from PostgreSQL", skip_all)]
+    async fn get_role_permissions(&self, role: &str) -> Result<Vec<String>, UserStoreError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT permission
+            FROM role_permissions
+            WHERE role = $1
+            ORDER BY permission
+            "#,
+            role
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        Ok(rows.into_iter().map(|row| row.permission).collect())
+    }
+
+    #[tracing::instrument(name = "Granting role permission in PostgreSQL", skip_all)]
+    async fn grant_permission(&mut self, role: &str, permission: &str) -> Result<(), UserStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO role_permissions (role, permission)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+            role,
+            permission
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Revoking role permission in PostgreSQL", skip_all)]
+    async fn revoke_permission(&mut self, role: &str, permission: &str) -> Result<(), UserStoreError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM role_permissions
+            WHERE role = $1 AND permission = $2
+            "#,
+            role,
+            permission
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+}
+
+fn parse_user(
+    email: String,
+    password_hash: String,
+    two_fa_method: &str,
+    totp_secret: Option<String>,
+    email_verified: bool,
+) -> Result<User, UserStoreError> {
+    Ok(User {
+        email: Email::parse(Secret::new(email))
+            .map_err(UserStoreError::UnexpectedError)?,
+        password: Password::parse(Secret::new(password_hash))
+            .map_err(UserStoreError::UnexpectedError)?,
+        two_fa_method: parse_two_fa_method(two_fa_method, totp_secret)
+            .map_err(UserStoreError::UnexpectedError)?,
+        email_verified,
+    })
+}
+
+// Matches emails containing `search`, with LIKE wildcards in it taken literally.
+fn like_pattern(search: &str) -> String {
+    let escaped = search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn get_totp_secret(method: &TwoFAMethod) -> Option<&str> {
+    match method {
+        TwoFAMethod::Totp(secret) => Some(secret.as_ref().expose_secret().as_str()),
+        _ => None,
+    }
+}
+
+fn parse_two_fa_method(method: &str, totp_secret: Option<String>) -> Result<TwoFAMethod> {
+    match (method, totp_secret) {
+        ("none", _) => Ok(TwoFAMethod::None),
+        ("email", _) => Ok(TwoFAMethod::Email),
+        ("totp", Some(secret)) => Ok(TwoFAMethod::Totp(TotpSecret::parse(Secret::new(secret))?)),
+        (method, _) => Err(eyre!("invalid 2FA method: {}", method)),
+    }
 }
 
 #[tracing::instrument(name = "Verify password hash", skip_all)]
-async fn verify_password_hash(
+pub(crate) async fn verify_password_hash(
     expected_password_hash: Secret<String>,
     password_candidate: Secret<String>,
 ) -> Result<()> {
@@ -123,7 +970,7 @@ async fn verify_password_hash(
 }
 
 #[tracing::instrument(name = "Computing password hash", skip_all)]
-async fn compute_password_hash(password: Secret<String>) -> Result<Secret<String>> {
+pub(crate) async fn compute_password_hash(password: Secret<String>) -> Result<Secret<String>> {
     let current_span: tracing::Span = tracing::Span::current();
 
     let result = tokio::task::spawn_blocking(move || {
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+use redis::{Commands, Connection};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use color_eyre::eyre::Context;
+use crate::domain::{
+    audit::AuditHead,
+    data_stores::{AuditHeadStore, AuditLogStoreError},
+};
+
+pub struct RedisAuditHeadStore {
+    conn: Arc<RwLock<Connection>>,
+    key: String,
+}
+
+impl RedisAuditHeadStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self::with_namespace(conn, DEFAULT_NAMESPACE)
+    }
+
+    // Keeps the head apart from that of logs in other namespaces, e.g. other databases sharing
+    // the Redis instance.
+    pub fn with_namespace(conn: Arc<RwLock<Connection>>, namespace: &str) -> Self {
+        Self { conn, key: format!("{}{}", namespace, HEAD_SUFFIX) }
+    }
+}
+
+// Layout: `audit_head` is a sorted set of serialized `AuditHeadRecord`s scored by event id, so
+// instances advancing the head concurrently can't move it back. Older entries are trimmed when
+// a later one is added.
+#[async_trait::async_trait]
+impl AuditHeadStore for RedisAuditHeadStore {
+    #[tracing::instrument(name = "Advancing audit head in Redis", skip_all)]
+    async fn advance_head(&self, head: AuditHead) -> Result<(), AuditLogStoreError> {
+        let serialized_record = serde_json::to_string(&AuditHeadRecord { id: head.id, hash: head.hash })
+            .wrap_err("failed to serialize audit head")
+            .map_err(AuditLogStoreError::UnexpectedError)?;
+
+        let mut conn = self.conn.write().await;
+        redis::pipe()
+            .atomic()
+            .zadd(&self.key, serialized_record, head.id)
+            .ignore()
+            .zrembyscore(&self.key, "-inf", head.id - 1)
+            .ignore()
+            .query::<()>(&mut *conn)
+            .wrap_err("failed to set audit head in Redis")
+            .map_err(AuditLogStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving audit head from Redis", skip_all)]
+    async fn get_head(&self) -> Result<Option<AuditHead>, AuditLogStoreError> {
+        let serialized_records: Vec<String> = self
+            .conn
+            .write()
+            .await
+            .zrange(&self.key, -1, -1)
+            .wrap_err("failed to get audit head from Redis")
+            .map_err(AuditLogStoreError::UnexpectedError)?;
+
+        serialized_records
+            .first()
+            .map(|serialized_record| {
+                let record: AuditHeadRecord = serde_json::from_str(serialized_record)
+                    .wrap_err("failed to deserialize audit head")
+                    .map_err(AuditLogStoreError::UnexpectedError)?;
+                Ok(AuditHead { id: record.id, hash: record.hash })
+            })
+            .transpose()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct AuditHeadRecord {
+    id: i64,
+    hash: String,
+}
+
+const DEFAULT_NAMESPACE: &str = "audit";
+const HEAD_SUFFIX: &str = "_head";
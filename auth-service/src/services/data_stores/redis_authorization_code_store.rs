@@ -0,0 +1,110 @@
+use std::sync::Arc;
+use redis::{Commands, Connection};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use secrecy::{ExposeSecret, Secret};
+use color_eyre::eyre::Context;
+use crate::{
+    domain::{
+        data_stores::{AuthorizationCodeStore, AuthorizationCodeStoreError},
+        oauth::{AuthorizationCode, AuthorizationCodeGrant, PkceCodeChallenge},
+        Authentication, Email,
+    },
+    utils::constants::AUTHORIZATION_CODE_TTL_SECONDS,
+};
+
+pub struct RedisAuthorizationCodeStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisAuthorizationCodeStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl AuthorizationCodeStore for RedisAuthorizationCodeStore {
+    #[tracing::instrument(name = "Adding authorization code to Redis", skip_all)]
+    async fn add_code(
+        &mut self,
+        code: AuthorizationCode,
+        grant: AuthorizationCodeGrant,
+    ) -> Result<(), AuthorizationCodeStoreError> {
+        let record = AuthorizationCodeRecord {
+            client_id: grant.client_id,
+            redirect_uri: grant.redirect_uri,
+            email: grant.email.as_ref().expose_secret().clone(),
+            code_challenge: grant.code_challenge.as_ref().to_owned(),
+            scope: grant.scope,
+            nonce: grant.nonce,
+            authentication: grant.authentication,
+        };
+        let serialized_record = serde_json::to_string(&record)
+            .wrap_err("failed to serialize authorization code record")
+            .map_err(AuthorizationCodeStoreError::UnexpectedError)?;
+        let ttl: u64 = AUTHORIZATION_CODE_TTL_SECONDS
+            .try_into()
+            .wrap_err("failed to cast AUTHORIZATION_CODE_TTL_SECONDS to u64")
+            .map_err(AuthorizationCodeStoreError::UnexpectedError)?;
+
+        let mut conn = self.conn.write().await;
+        conn.set_ex::<String, String, ()>(get_key(&code), serialized_record, ttl)
+            .wrap_err("failed to set authorization code in Redis")
+            .map_err(AuthorizationCodeStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Consuming authorization code from Redis", skip_all)]
+    async fn consume_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationCodeGrant, AuthorizationCodeStoreError> {
+        let key = get_key(code);
+        let mut conn = self.conn.write().await;
+        // Read and delete in one transaction so a code can never be redeemed twice.
+        let (serialized_record,): (Option<String>,) = redis::pipe()
+            .atomic()
+            .get(&key)
+            .del(&key)
+            .ignore()
+            .query(&mut *conn)
+            .wrap_err("failed to consume authorization code in Redis")
+            .map_err(AuthorizationCodeStoreError::UnexpectedError)?;
+
+        let serialized_record = serialized_record.ok_or(AuthorizationCodeStoreError::CodeNotFound)?;
+        let record: AuthorizationCodeRecord = serde_json::from_str(&serialized_record)
+            .wrap_err("failed to deserialize authorization code record")
+            .map_err(AuthorizationCodeStoreError::UnexpectedError)?;
+
+        Ok(AuthorizationCodeGrant {
+            client_id: record.client_id,
+            redirect_uri: record.redirect_uri,
+            email: Email::parse(Secret::new(record.email))
+                .map_err(AuthorizationCodeStoreError::UnexpectedError)?,
+            code_challenge: PkceCodeChallenge::parse(record.code_challenge)
+                .map_err(AuthorizationCodeStoreError::UnexpectedError)?,
+            scope: record.scope,
+            nonce: record.nonce,
+            authentication: record.authentication,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct AuthorizationCodeRecord {
+    client_id: String,
+    redirect_uri: String,
+    email: String,
+    code_challenge: String,
+    scope: Option<String>,
+    nonce: Option<String>,
+    authentication: Authentication,
+}
+
+const AUTHORIZATION_CODE_PREFIX: &str = "authorization_code:";
+
+fn get_key(code: &AuthorizationCode) -> String {
+    format!("{}{}", AUTHORIZATION_CODE_PREFIX, code.as_ref().expose_secret())
+}
@@ -4,7 +4,10 @@ use tokio::sync::RwLock;
 use secrecy::{Secret, ExposeSecret};
 use color_eyre::eyre::Context;
 use crate::{
-    domain::data_stores::{BannedTokenStore, BannedTokenStoreError},
+    domain::{
+        data_stores::{BannedTokenStore, BannedTokenStoreError},
+        Email,
+    },
     utils::auth::TOKEN_TTL_SECONDS,
 };
 
@@ -53,10 +56,94 @@ impl BannedTokenStore for RedisBannedTokenStore {
         
         Ok(is_banned)
     }
+
+    #[tracing::instrument(name = "Revoking user tokens in Redis", skip_all)]
+    async fn revoke_tokens_issued_before(
+        &mut self,
+        email: &Email,
+        timestamp: i64,
+    ) -> Result<(), BannedTokenStoreError> {
+        let key = get_revoked_before_key(email);
+        // Every token issued before the cutoff has expired once TOKEN_TTL_SECONDS have elapsed.
+        let ttl: u64 = TOKEN_TTL_SECONDS
+            .try_into()
+            .wrap_err("failed to cast TOKEN_TTL_SECONDS to u64")
+            .map_err(BannedTokenStoreError::UnexpectedError)?;
+
+        let _: () = self
+            .conn
+            .write()
+            .await
+            .set_ex(&key, timestamp, ttl)
+            .wrap_err("failed to set token revocation timestamp in Redis")
+            .map_err(BannedTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Getting user token revocation from Redis", skip_all)]
+    async fn get_tokens_revoked_before(
+        &self,
+        email: &Email,
+    ) -> Result<Option<i64>, BannedTokenStoreError> {
+        let key = get_revoked_before_key(email);
+        let timestamp: Option<i64> = self
+            .conn
+            .write()
+            .await
+            .get(&key)
+            .wrap_err("failed to get token revocation timestamp from Redis")
+            .map_err(BannedTokenStoreError::UnexpectedError)?;
+
+        Ok(timestamp)
+    }
+
+    #[tracing::instrument(name = "Revoking session in Redis", skip_all)]
+    async fn revoke_session(&mut self, session_id: &str) -> Result<(), BannedTokenStoreError> {
+        // The session's refresh tokens stop working once it is removed from the session store,
+        // so only access tokens already issued for it need to be outlived.
+        let ttl: u64 = TOKEN_TTL_SECONDS
+            .try_into()
+            .wrap_err("failed to cast TOKEN_TTL_SECONDS to u64")
+            .map_err(BannedTokenStoreError::UnexpectedError)?;
+
+        let _: () = self
+            .conn
+            .write()
+            .await
+            .set_ex(get_revoked_session_key(session_id), true, ttl)
+            .wrap_err("failed to set revoked session in Redis")
+            .map_err(BannedTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Checking revoked session in Redis", skip_all)]
+    async fn is_session_revoked(&self, session_id: &str) -> Result<bool, BannedTokenStoreError> {
+        let is_revoked: bool = self
+            .conn
+            .write()
+            .await
+            .exists(get_revoked_session_key(session_id))
+            .wrap_err("failed to check if session is revoked in Redis")
+            .map_err(BannedTokenStoreError::UnexpectedError)?;
+
+        Ok(is_revoked)
+    }
 }
 
 const BANNED_TOKEN_KEY_PREFIX: &str = "banned_token:";
+const TOKENS_REVOKED_BEFORE_KEY_PREFIX: &str = "tokens_revoked_before:";
+const REVOKED_SESSION_KEY_PREFIX: &str = "revoked_session:";
 
 fn get_key(token: &str) -> String {
     format!("{}{}", BANNED_TOKEN_KEY_PREFIX, token)
+}
+
+fn get_revoked_before_key(email: &Email) -> String {
+    format!("{}{}", TOKENS_REVOKED_BEFORE_KEY_PREFIX, email.as_ref().expose_secret())
+}
+
+fn get_revoked_session_key(session_id: &str) -> String {
+    format!("{}{}", REVOKED_SESSION_KEY_PREFIX, session_id)
 }
\ No newline at end of file
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+use redis::{Commands, Connection};
+use tokio::sync::RwLock;
+use secrecy::{ExposeSecret, Secret};
+use color_eyre::eyre::Context;
+use crate::{
+    domain::{
+        data_stores::{PasswordResetToken, PasswordResetTokenStore, PasswordResetTokenStoreError},
+        Email,
+    },
+    utils::constants::PASSWORD_RESET_TOKEN_TTL_SECONDS,
+};
+
+pub struct RedisPasswordResetTokenStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisPasswordResetTokenStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl PasswordResetTokenStore for RedisPasswordResetTokenStore {
+    #[tracing::instrument(name = "Adding password reset token to Redis", skip_all)]
+    async fn add_token(
+        &mut self,
+        token: PasswordResetToken,
+        email: Email,
+    ) -> Result<(), PasswordResetTokenStoreError> {
+        let key = get_key(&token);
+        let ttl: u64 = PASSWORD_RESET_TOKEN_TTL_SECONDS
+            .try_into()
+            .wrap_err("failed to cast PASSWORD_RESET_TOKEN_TTL_SECONDS to u64")
+            .map_err(PasswordResetTokenStoreError::UnexpectedError)?;
+
+        let mut conn = self.conn.write().await;
+        conn.set_ex::<String, String, ()>(key, email.as_ref().expose_secret().clone(), ttl)
+            .wrap_err("failed to set password reset token in Redis")
+            .map_err(PasswordResetTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Consuming password reset token from Redis", skip_all)]
+    async fn consume_token(
+        &mut self,
+        token: &PasswordResetToken,
+    ) -> Result<Email, PasswordResetTokenStoreError> {
+        let key = get_key(token);
+        let mut conn = self.conn.write().await;
+        // Read and delete in one transaction so a token can never be redeemed twice.
+        let (email,): (Option<String>,) = redis::pipe()
+            .atomic()
+            .get(&key)
+            .del(&key)
+            .ignore()
+            .query(&mut *conn)
+            .wrap_err("failed to consume password reset token in Redis")
+            .map_err(PasswordResetTokenStoreError::UnexpectedError)?;
+
+        let email = email.ok_or(PasswordResetTokenStoreError::TokenNotFound)?;
+        Email::parse(Secret::new(email)).map_err(PasswordResetTokenStoreError::UnexpectedError)
+    }
+}
+
+const PASSWORD_RESET_TOKEN_PREFIX: &str = "password_reset_token:";
+
+fn get_key(token: &PasswordResetToken) -> String {
+    format!("{}{}", PASSWORD_RESET_TOKEN_PREFIX, token.as_ref().expose_secret())
+}
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+use chrono::Utc;
+use redis::Connection;
+use tokio::sync::RwLock;
+use color_eyre::eyre::Context;
+use crate::domain::data_stores::{RateLimit, RateLimitDecision, RateLimitStore, RateLimitStoreError};
+
+pub struct RedisRateLimitStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisRateLimitStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+// Sliding window log: each key is a sorted set of request ids scored by their timestamp in milliseconds.
+#[async_trait::async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+    #[tracing::instrument(name = "Checking rate limit in Redis", skip_all)]
+    async fn check_and_record(
+        &mut self,
+        key: &str,
+        limit: &RateLimit,
+    ) -> Result<RateLimitDecision, RateLimitStoreError> {
+        let key = get_key(key);
+        let now = Utc::now().timestamp_millis();
+        let window = limit.window_seconds * 1000;
+
+        let mut conn = self.conn.write().await;
+        let (count, oldest): (u32, Vec<(String, f64)>) = redis::pipe()
+            .atomic()
+            .zrembyscore(&key, "-inf", now - window)
+            .ignore()
+            .zcard(&key)
+            .zrange_withscores(&key, 0, 0)
+            .query(&mut *conn)
+            .wrap_err("failed to read rate limit window from Redis")
+            .map_err(RateLimitStoreError::UnexpectedError)?;
+
+        if count >= limit.max_requests {
+            let oldest = oldest.first().map(|(_, timestamp)| *timestamp as i64).unwrap_or(now);
+            return Ok(RateLimitDecision::limited_for_millis(oldest + window - now));
+        }
+
+        redis::pipe()
+            .atomic()
+            .zadd(&key, uuid::Uuid::new_v4().to_string(), now)
+            .ignore()
+            .expire(&key, limit.window_seconds)
+            .ignore()
+            .query::<()>(&mut *conn)
+            .wrap_err("failed to record request in Redis")
+            .map_err(RateLimitStoreError::UnexpectedError)?;
+
+        Ok(RateLimitDecision::Allowed)
+    }
+}
+
+const RATE_LIMIT_KEY_PREFIX: &str = "rate_limit:";
+
+fn get_key(key: &str) -> String {
+    format!("{}{}", RATE_LIMIT_KEY_PREFIX, key)
+}
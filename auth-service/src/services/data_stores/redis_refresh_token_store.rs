@@ -0,0 +1,214 @@
+use std::sync::Arc;
+use redis::{Commands, Connection};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use secrecy::{ExposeSecret, Secret};
+use color_eyre::eyre::Context;
+use crate::{
+    domain::{
+        data_stores::{RefreshToken, RefreshTokenStore, RefreshTokenStoreError},
+        Authentication, Email,
+    },
+    utils::constants::REFRESH_TOKEN_TTL_SECONDS,
+};
+
+pub struct RedisRefreshTokenStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisRefreshTokenStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+// Layout:
+// - `refresh_token:<token>` holds a serialized `RefreshTokenRecord`. Rotated tokens are kept
+//   until they expire so that a replay can be detected.
+// - `refresh_token_family:<family_id>` holds the owner's email and only exists while the
+//   family is active.
+// - `refresh_token_families:<email>` is the set of family ids issued to a user.
+#[async_trait::async_trait]
+impl RefreshTokenStore for RedisRefreshTokenStore {
+    #[tracing::instrument(name = "Adding refresh token to Redis", skip_all)]
+    async fn add_token(
+        &mut self,
+        token: RefreshToken,
+        email: Email,
+        authentication: Authentication,
+    ) -> Result<(), RefreshTokenStoreError> {
+        let family_id = uuid::Uuid::new_v4().to_string();
+        let record = RefreshTokenRecord {
+            email: email.as_ref().expose_secret().clone(),
+            authentication,
+            family_id: family_id.clone(),
+            rotated: false,
+        };
+        let serialized_record = serialize_record(&record)?;
+        let ttl = get_ttl()?;
+
+        let mut conn = self.conn.write().await;
+        redis::pipe()
+            .atomic()
+            .set_ex(get_token_key(&token), serialized_record, ttl)
+            .ignore()
+            .set_ex(get_family_key(&family_id), &record.email, ttl)
+            .ignore()
+            .sadd(get_user_families_key(&email), &family_id)
+            .ignore()
+            .expire(get_user_families_key(&email), ttl as i64)
+            .ignore()
+            .query::<()>(&mut *conn)
+            .wrap_err("failed to add refresh token to Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Rotating refresh token in Redis", skip_all)]
+    async fn rotate_token(
+        &mut self,
+        token: &RefreshToken,
+        new_token: RefreshToken,
+    ) -> Result<(Email, Authentication), RefreshTokenStoreError> {
+        let token_key = get_token_key(token);
+        let mut conn = self.conn.write().await;
+
+        let serialized_record: Option<String> = conn
+            .get(&token_key)
+            .wrap_err("failed to get refresh token from Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        let mut record: RefreshTokenRecord = match serialized_record {
+            Some(serialized_record) => serde_json::from_str(&serialized_record)
+                .wrap_err("failed to deserialize refresh token record")
+                .map_err(RefreshTokenStoreError::UnexpectedError)?,
+            None => return Err(RefreshTokenStoreError::TokenNotFound),
+        };
+
+        let family_key = get_family_key(&record.family_id);
+        let family_active: bool = conn
+            .exists(&family_key)
+            .wrap_err("failed to check refresh token family in Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        if !family_active {
+            return Err(RefreshTokenStoreError::TokenNotFound);
+        }
+
+        if record.rotated {
+            conn.del::<String, ()>(family_key)
+                .wrap_err("failed to revoke refresh token family in Redis")
+                .map_err(RefreshTokenStoreError::UnexpectedError)?;
+            return Err(RefreshTokenStoreError::TokenReused);
+        }
+
+        record.rotated = true;
+        let rotated_record = serialize_record(&record)?;
+        record.rotated = false;
+        let new_record = serialize_record(&record)?;
+        let ttl = get_ttl()?;
+        let email = Email::parse(Secret::new(record.email))
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        // The user's set of families must outlive every family in it, or revoking all of the
+        // user's tokens would miss the ones still being rotated.
+        redis::pipe()
+            .atomic()
+            .set_ex(&token_key, rotated_record, ttl)
+            .ignore()
+            .set_ex(get_token_key(&new_token), new_record, ttl)
+            .ignore()
+            .expire(&family_key, ttl as i64)
+            .ignore()
+            .expire(get_user_families_key(&email), ttl as i64)
+            .ignore()
+            .query::<()>(&mut *conn)
+            .wrap_err("failed to rotate refresh token in Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok((email, record.authentication))
+    }
+
+    #[tracing::instrument(name = "Revoking refresh token in Redis", skip_all)]
+    async fn revoke_token(&mut self, token: &RefreshToken) -> Result<(), RefreshTokenStoreError> {
+        let mut conn = self.conn.write().await;
+
+        let serialized_record: Option<String> = conn
+            .get(get_token_key(token))
+            .wrap_err("failed to get refresh token from Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        let Some(serialized_record) = serialized_record else {
+            return Ok(());
+        };
+        let record: RefreshTokenRecord = serde_json::from_str(&serialized_record)
+            .wrap_err("failed to deserialize refresh token record")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        conn.del::<String, ()>(get_family_key(&record.family_id))
+            .wrap_err("failed to revoke refresh token family in Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Revoking user refresh tokens in Redis", skip_all)]
+    async fn revoke_user_tokens(&mut self, email: &Email) -> Result<(), RefreshTokenStoreError> {
+        let user_families_key = get_user_families_key(email);
+        let mut conn = self.conn.write().await;
+
+        let family_ids: Vec<String> = conn
+            .smembers(&user_families_key)
+            .wrap_err("failed to get refresh token families from Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for family_id in &family_ids {
+            pipe.del(get_family_key(family_id)).ignore();
+        }
+        pipe.del(&user_families_key)
+            .ignore()
+            .query::<()>(&mut *conn)
+            .wrap_err("failed to revoke refresh token families in Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RefreshTokenRecord {
+    email: String,
+    #[serde(default)]
+    authentication: Authentication,
+    family_id: String,
+    rotated: bool,
+}
+
+const REFRESH_TOKEN_PREFIX: &str = "refresh_token:";
+const REFRESH_TOKEN_FAMILY_PREFIX: &str = "refresh_token_family:";
+const REFRESH_TOKEN_USER_FAMILIES_PREFIX: &str = "refresh_token_families:";
+
+fn get_token_key(token: &RefreshToken) -> String {
+    format!("{}{}", REFRESH_TOKEN_PREFIX, token.as_ref().expose_secret())
+}
+
+fn get_family_key(family_id: &str) -> String {
+    format!("{}{}", REFRESH_TOKEN_FAMILY_PREFIX, family_id)
+}
+
+fn get_user_families_key(email: &Email) -> String {
+    format!("{}{}", REFRESH_TOKEN_USER_FAMILIES_PREFIX, email.as_ref().expose_secret())
+}
+
+fn get_ttl() -> Result<u64, RefreshTokenStoreError> {
+    REFRESH_TOKEN_TTL_SECONDS
+        .try_into()
+        .wrap_err("failed to cast REFRESH_TOKEN_TTL_SECONDS to u64")
+        .map_err(RefreshTokenStoreError::UnexpectedError)
+}
+
+fn serialize_record(record: &RefreshTokenRecord) -> Result<String, RefreshTokenStoreError> {
+    serde_json::to_string(record)
+        .wrap_err("failed to serialize refresh token record")
+        .map_err(RefreshTokenStoreError::UnexpectedError)
+}
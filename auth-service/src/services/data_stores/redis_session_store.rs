@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::Utc;
+use redis::{Commands, Connection};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use secrecy::ExposeSecret;
+use color_eyre::eyre::Context;
+use crate::{
+    domain::{
+        data_stores::{Session, SessionStore, SessionStoreError},
+        Email,
+    },
+    utils::constants::REFRESH_TOKEN_TTL_SECONDS,
+};
+
+pub struct RedisSessionStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisSessionStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+
+    async fn get_records(&self, email: &Email) -> Result<HashMap<String, SessionRecord>, SessionStoreError> {
+        let serialized_records: HashMap<String, String> = self
+            .conn
+            .write()
+            .await
+            .hgetall(get_key(email))
+            .wrap_err("failed to get sessions from Redis")
+            .map_err(SessionStoreError::UnexpectedError)?;
+
+        let now = Utc::now().timestamp();
+        let mut records = HashMap::new();
+        for (session_id, serialized_record) in serialized_records {
+            let record: SessionRecord = serde_json::from_str(&serialized_record)
+                .wrap_err("failed to deserialize session record")
+                .map_err(SessionStoreError::UnexpectedError)?;
+            if record.expires_at > now {
+                records.insert(session_id, record);
+            }
+        }
+        Ok(records)
+    }
+
+    async fn set_record(&mut self, email: &Email, session_id: &str, record: &SessionRecord) -> Result<(), SessionStoreError> {
+        let serialized_record = serde_json::to_string(record)
+            .wrap_err("failed to serialize session record")
+            .map_err(SessionStoreError::UnexpectedError)?;
+
+        let key = get_key(email);
+        let mut conn = self.conn.write().await;
+        redis::pipe()
+            .atomic()
+            .hset(&key, session_id, serialized_record)
+            .ignore()
+            // The hash lives as long as the session that was last added or extended.
+            .expire(&key, REFRESH_TOKEN_TTL_SECONDS)
+            .ignore()
+            .query::<()>(&mut *conn)
+            .wrap_err("failed to set session in Redis")
+            .map_err(SessionStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+}
+
+// Layout: `sessions:<email>` is a hash of session ids to serialized `SessionRecord`s. Expired
+// sessions are skipped when reading and cleared with the hash.
+#[async_trait::async_trait]
+impl SessionStore for RedisSessionStore {
+    #[tracing::instrument(name = "Adding session to Redis", skip_all)]
+    async fn add_session(&mut self, email: &Email, session: Session) -> Result<(), SessionStoreError> {
+        let record = SessionRecord {
+            created_at: session.created_at,
+            ip: session.ip,
+            user_agent: session.user_agent,
+            expires_at: Utc::now().timestamp() + REFRESH_TOKEN_TTL_SECONDS,
+        };
+        self.set_record(email, &session.id, &record).await
+    }
+
+    #[tracing::instrument(name = "Getting sessions from Redis", skip_all)]
+    async fn get_sessions(&self, email: &Email) -> Result<Vec<Session>, SessionStoreError> {
+        let mut sessions: Vec<Session> = self
+            .get_records(email)
+            .await?
+            .into_iter()
+            .map(|(id, record)| Session {
+                id,
+                created_at: record.created_at,
+                ip: record.ip,
+                user_agent: record.user_agent,
+            })
+            .collect();
+        sessions.sort_by_key(|session| session.created_at);
+        Ok(sessions)
+    }
+
+    #[tracing::instrument(name = "Extending session in Redis", skip_all)]
+    async fn extend_session(&mut self, email: &Email, session_id: &str) -> Result<(), SessionStoreError> {
+        let mut record = self
+            .get_records(email)
+            .await?
+            .remove(session_id)
+            .ok_or(SessionStoreError::SessionNotFound)?;
+        record.expires_at = Utc::now().timestamp() + REFRESH_TOKEN_TTL_SECONDS;
+        self.set_record(email, session_id, &record).await
+    }
+
+    #[tracing::instrument(name = "Removing session from Redis", skip_all)]
+    async fn remove_session(&mut self, email: &Email, session_id: &str) -> Result<(), SessionStoreError> {
+        if !self.get_records(email).await?.contains_key(session_id) {
+            return Err(SessionStoreError::SessionNotFound);
+        }
+
+        let _: () = self
+            .conn
+            .write()
+            .await
+            .hdel(get_key(email), session_id)
+            .wrap_err("failed to remove session from Redis")
+            .map_err(SessionStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Removing user sessions from Redis", skip_all)]
+    async fn remove_all_sessions(&mut self, email: &Email) -> Result<(), SessionStoreError> {
+        let _: () = self
+            .conn
+            .write()
+            .await
+            .del(get_key(email))
+            .wrap_err("failed to remove sessions from Redis")
+            .map_err(SessionStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SessionRecord {
+    created_at: i64,
+    ip: Option<String>,
+    user_agent: Option<String>,
+    expires_at: i64,
+}
+
+const SESSIONS_KEY_PREFIX: &str = "sessions:";
+
+fn get_key(email: &Email) -> String {
+    format!("{}{}", SESSIONS_KEY_PREFIX, email.as_ref().expose_secret())
+}
@@ -1,9 +1,10 @@
 use std::sync::Arc;
+use chrono::Utc;
 use redis::{Commands, Connection};
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 use secrecy::ExposeSecret;
-use color_eyre::eyre::{Context, eyre};
+use color_eyre::eyre::Context;
 use crate::domain::{
     data_stores::{LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError},
     Email,
@@ -11,11 +12,30 @@ use crate::domain::{
 
 pub struct RedisTwoFACodeStore {
     conn: Arc<RwLock<Connection>>,
+    namespace: &'static str,
 }
 
 impl RedisTwoFACodeStore {
     pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
-        Self { conn }
+        Self::with_namespace(conn, DEFAULT_NAMESPACE)
+    }
+
+    // Keeps the codes apart from those of stores in other namespaces, which may be kept for the
+    // same email.
+    pub fn with_namespace(conn: Arc<RwLock<Connection>>, namespace: &'static str) -> Self {
+        Self { conn, namespace }
+    }
+
+    fn get_key(&self, email: &Email) -> String {
+        format!("{}{}{}", self.namespace, CODE_SUFFIX, email.as_ref().expose_secret())
+    }
+
+    fn get_failed_attempts_key(&self, login_attempt_id: &LoginAttemptId) -> String {
+        format!("{}{}{}", self.namespace, FAILED_ATTEMPTS_SUFFIX, login_attempt_id.as_ref().expose_secret())
+    }
+
+    fn get_resends_key(&self, email: &Email) -> String {
+        format!("{}{}{}", self.namespace, RESENDS_SUFFIX, email.as_ref().expose_secret())
     }
 }
 
@@ -28,7 +48,7 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
         login_attempt_id: LoginAttemptId,
         code: TwoFACode,
     ) -> Result<(), TwoFACodeStoreError> {
-        let key = get_key(&email);
+        let key = self.get_key(&email);
         let two_fa_tuple = TwoFATuple(
             login_attempt_id.as_ref().expose_secret().clone(),
             code.as_ref().expose_secret().clone(),
@@ -37,9 +57,20 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
         let serialized_tuple = serde_json::to_string(&two_fa_tuple)
             .wrap_err("failed to serialize 2FA tuple")
             .map_err(TwoFACodeStoreError::UnexpectedError)?;
+        let serialized_record = serde_json::to_string(&ResendRecord { count: 0, last_sent_at: Utc::now().timestamp() })
+            .wrap_err("failed to serialize 2FA resends record")
+            .map_err(TwoFACodeStoreError::UnexpectedError)?;
         
         let mut conn = self.conn.write().await;
-        conn.set_ex::<String, String, ()>(key, serialized_tuple, TEN_MINUTES_IN_SECONDS)
+        // A new code starts with a clean slate of resends, its cooldown running from now. Failed
+        // attempts are counted per login attempt, so its new ID starts from zero.
+        redis::pipe()
+            .atomic()
+            .set_ex(key, serialized_tuple, TEN_MINUTES_IN_SECONDS)
+            .ignore()
+            .set_ex(self.get_resends_key(&email), serialized_record, TEN_MINUTES_IN_SECONDS)
+            .ignore()
+            .query::<()>(&mut *conn)
             .wrap_err("failed to set 2FA code in Redis")
             .map_err(TwoFACodeStoreError::UnexpectedError)?;
         
@@ -48,9 +79,9 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
 
     #[tracing::instrument(name = "Removing 2FA code from Redis", skip_all)]
     async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
-        let key = get_key(email);
+        let keys = vec![self.get_key(email), self.get_resends_key(email)];
         let mut conn = self.conn.write().await;
-        conn.del::<String, ()>(key)
+        conn.del::<Vec<String>, ()>(keys)
             .wrap_err("failed to delete 2FA code from Redis")
             .map_err(TwoFACodeStoreError::UnexpectedError)?;
         
@@ -62,7 +93,7 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
         &self,
         email: &Email,
     ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
-        let key = get_key(email);
+        let key = self.get_key(email);
         let mut conn = self.conn.write().await;
         let serialized_tuple: String = conn.get(&key)
             .map_err(|_| TwoFACodeStoreError::LoginAttemptIdNotFound)?;
@@ -72,20 +103,116 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
             .map_err(TwoFACodeStoreError::UnexpectedError)?;
         
         let login_attempt_id = LoginAttemptId::parse(two_fa_tuple.0)
-            .map_err(|e| TwoFACodeStoreError::UnexpectedError(e.into()))?;
+            .map_err(TwoFACodeStoreError::UnexpectedError)?;
         let two_fa_code = TwoFACode::parse(two_fa_tuple.1)
-            .map_err(|e| TwoFACodeStoreError::UnexpectedError(e.into()))?;
+            .map_err(TwoFACodeStoreError::UnexpectedError)?;
         
         Ok((login_attempt_id, two_fa_code))
     }
+
+    #[tracing::instrument(name = "Recording failed 2FA attempt in Redis", skip_all)]
+    async fn record_failed_attempt(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        max_attempts: u32,
+    ) -> Result<(), TwoFACodeStoreError> {
+        let failed_attempts_key = self.get_failed_attempts_key(login_attempt_id);
+
+        // The count INCR returns decides, so concurrent attempts can't all slip in under the limit.
+        let (failed_attempts,): (u32,) = redis::pipe()
+            .atomic()
+            .incr(&failed_attempts_key, 1)
+            .expire(&failed_attempts_key, TEN_MINUTES_IN_SECONDS as i64)
+            .ignore()
+            .query(&mut *self.conn.write().await)
+            .wrap_err("failed to record failed 2FA attempt in Redis")
+            .map_err(TwoFACodeStoreError::UnexpectedError)?;
+
+        // Only a code still belonging to this login attempt is removed, not one a newer login
+        // has replaced it with.
+        let is_current_attempt = match self.get_code(email).await {
+            Ok((stored_login_attempt_id, _)) => &stored_login_attempt_id == login_attempt_id,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound) => false,
+            Err(e) => return Err(e),
+        };
+        if failed_attempts >= max_attempts {
+            if is_current_attempt {
+                self.remove_code(email).await?;
+            }
+            return Err(TwoFACodeStoreError::TooManyAttempts);
+        }
+        if !is_current_attempt {
+            return Err(TwoFACodeStoreError::LoginAttemptIdNotFound);
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Recording 2FA code resend in Redis", skip_all)]
+    async fn record_resend(
+        &mut self,
+        email: &Email,
+        cooldown_seconds: i64,
+        max_resends: u32,
+    ) -> Result<(), TwoFACodeStoreError> {
+        let resends_key = self.get_resends_key(email);
+        let mut conn = self.conn.write().await;
+
+        let code_exists: bool = conn
+            .exists(self.get_key(email))
+            .wrap_err("failed to check 2FA code in Redis")
+            .map_err(TwoFACodeStoreError::UnexpectedError)?;
+        if !code_exists {
+            return Err(TwoFACodeStoreError::LoginAttemptIdNotFound);
+        }
+
+        let serialized_record: Option<String> = conn
+            .get(&resends_key)
+            .wrap_err("failed to get 2FA resends from Redis")
+            .map_err(TwoFACodeStoreError::UnexpectedError)?;
+        let mut record = match serialized_record {
+            Some(serialized_record) => serde_json::from_str(&serialized_record)
+                .wrap_err("failed to deserialize 2FA resends record")
+                .map_err(TwoFACodeStoreError::UnexpectedError)?,
+            None => ResendRecord { count: 0, last_sent_at: 0 },
+        };
+
+        let now = Utc::now().timestamp();
+        if record.count >= max_resends {
+            return Err(TwoFACodeStoreError::TooManyResends);
+        }
+        let available_at = record.last_sent_at + cooldown_seconds;
+        if available_at > now {
+            return Err(TwoFACodeStoreError::ResendCooldown {
+                retry_after_seconds: (available_at - now) as u64,
+            });
+        }
+
+        record.count += 1;
+        record.last_sent_at = now;
+        let serialized_record = serde_json::to_string(&record)
+            .wrap_err("failed to serialize 2FA resends record")
+            .map_err(TwoFACodeStoreError::UnexpectedError)?;
+
+        conn.set_ex::<String, String, ()>(resends_key, serialized_record, TEN_MINUTES_IN_SECONDS)
+            .wrap_err("failed to set 2FA resends in Redis")
+            .map_err(TwoFACodeStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 struct TwoFATuple(pub String, pub String);
 
-const TEN_MINUTES_IN_SECONDS: u64 = 600;
-const TWO_FA_CODE_PREFIX: &str = "two_fa_code:";
+#[derive(Serialize, Deserialize)]
+struct ResendRecord {
+    count: u32,
+    last_sent_at: i64,
+}
 
-fn get_key(email: &Email) -> String {
-    format!("{}{}", TWO_FA_CODE_PREFIX, email.as_ref().expose_secret())
-}
\ No newline at end of file
+const TEN_MINUTES_IN_SECONDS: u64 = 600;
+const DEFAULT_NAMESPACE: &str = "two_fa";
+const CODE_SUFFIX: &str = "_code:";
+const FAILED_ATTEMPTS_SUFFIX: &str = "_failed_attempts:";
+const RESENDS_SUFFIX: &str = "_resends:";
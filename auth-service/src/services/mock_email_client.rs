@@ -1,5 +1,5 @@
 use crate::domain::{Email, EmailClient};
-use color_eyre::eyre::{eyre, Result};
+use color_eyre::eyre::Result;
 use secrecy::ExposeSecret;
 
 pub struct MockEmailClient;
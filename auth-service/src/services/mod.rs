@@ -2,8 +2,10 @@ pub mod data_stores;
 pub mod auth;
 pub mod mock_email_client;
 pub mod postmark_email_client;
+pub mod oidc_identity_provider;
 
 pub use data_stores::*;
 pub use auth::*;
 pub use mock_email_client::*;
-pub use postmark_email_client::*;
\ No newline at end of file
+pub use postmark_email_client::*;
+pub use oidc_identity_provider::*;
\ No newline at end of file
@@ -0,0 +1,185 @@
+use color_eyre::eyre::{eyre, Context, Result};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use reqwest::{Client, Url};
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+
+// An external OpenID Connect provider that users can log in with instead of a password, such
+// as a corporate identity provider. auth-service acts as a relying party of it, using the
+// authorization code flow with PKCE.
+pub struct OidcIdentityProvider {
+    issuer: String,
+    client_id: String,
+    client_secret: Option<Secret<String>>,
+    http_client: Client,
+}
+
+// The subset of the provider's discovery document needed for the authorization code flow.
+#[derive(Debug, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+// The claims auth-service relies on from the provider's ID tokens.
+#[derive(Debug, Deserialize)]
+pub struct FederatedIdTokenClaims {
+    pub sub: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub nonce: Option<String>,
+    pub auth_time: Option<i64>,
+    #[serde(default)]
+    pub amr: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+// Parameters of one authorization request, checked again when the provider redirects back.
+pub struct AuthorizationRequest<'a> {
+    pub redirect_uri: &'a str,
+    pub state: &'a str,
+    pub nonce: &'a str,
+    pub code_challenge: &'a str,
+}
+
+impl OidcIdentityProvider {
+    pub fn new(
+        issuer: String,
+        client_id: String,
+        client_secret: Option<Secret<String>>,
+        http_client: Client,
+    ) -> Self {
+        Self {
+            issuer: issuer.trim_end_matches('/').to_owned(),
+            client_id,
+            client_secret,
+            http_client,
+        }
+    }
+
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    #[tracing::instrument(name = "Fetching identity provider metadata", skip_all)]
+    pub async fn discover(&self) -> Result<ProviderMetadata> {
+        let url = format!("{}/.well-known/openid-configuration", self.issuer);
+        let metadata: ProviderMetadata = self
+            .http_client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .wrap_err("failed to parse identity provider metadata")?;
+
+        // OpenID Connect Discovery 1.0 section 4.3: the issuer must match the one we asked.
+        if metadata.issuer.trim_end_matches('/') != self.issuer {
+            return Err(eyre!("identity provider metadata is for issuer {}", metadata.issuer));
+        }
+
+        Ok(metadata)
+    }
+
+    pub fn authorization_url(
+        &self,
+        metadata: &ProviderMetadata,
+        request: &AuthorizationRequest<'_>,
+    ) -> Result<Url> {
+        let mut url = Url::parse(&metadata.authorization_endpoint)
+            .wrap_err("invalid authorization endpoint")?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", request.redirect_uri)
+            .append_pair("scope", "openid email")
+            .append_pair("state", request.state)
+            .append_pair("nonce", request.nonce)
+            .append_pair("code_challenge", request.code_challenge)
+            .append_pair("code_challenge_method", "S256");
+        Ok(url)
+    }
+
+    // Redeems an authorization code and returns the validated claims of the ID token it yields.
+    #[tracing::instrument(name = "Exchanging code with identity provider", skip_all)]
+    pub async fn exchange_code(
+        &self,
+        metadata: &ProviderMetadata,
+        code: &str,
+        redirect_uri: &str,
+        code_verifier: &str,
+        nonce: &str,
+    ) -> Result<FederatedIdTokenClaims> {
+        let form = [
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("code_verifier", code_verifier),
+            ("client_id", self.client_id.as_str()),
+        ];
+        let mut request = self.http_client.post(&metadata.token_endpoint).form(&form);
+        if let Some(client_secret) = &self.client_secret {
+            request = request.basic_auth(&self.client_id, Some(client_secret.expose_secret()));
+        }
+
+        let response: TokenResponse = request
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .wrap_err("failed to parse identity provider token response")?;
+
+        self.validate_id_token(metadata, &response.id_token, nonce).await
+    }
+
+    #[tracing::instrument(name = "Validating identity provider ID token", skip_all)]
+    async fn validate_id_token(
+        &self,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<FederatedIdTokenClaims> {
+        let header = decode_header(id_token).wrap_err("failed to decode ID token header")?;
+        // Only asymmetric signatures are accepted, so a token can't be forged with the client secret.
+        if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+            return Err(eyre!("ID token is signed with unsupported algorithm {:?}", header.alg));
+        }
+
+        let jwks: JwkSet = self
+            .http_client
+            .get(&metadata.jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .wrap_err("failed to parse identity provider JWKS")?;
+        let kid = header.kid.as_deref().ok_or(eyre!("ID token has no key ID"))?;
+        let jwk = jwks
+            .find(kid)
+            .ok_or(eyre!("ID token was not signed with a published key"))?;
+        let decoding_key = DecodingKey::from_jwk(jwk).wrap_err("invalid identity provider JWK")?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.issuer, &metadata.issuer]);
+        validation.set_audience(&[&self.client_id]);
+        let claims = decode::<FederatedIdTokenClaims>(id_token, &decoding_key, &validation)
+            .wrap_err("failed to validate ID token")?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(eyre!("ID token nonce does not match"));
+        }
+
+        Ok(claims)
+    }
+}
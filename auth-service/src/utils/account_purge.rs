@@ -0,0 +1,213 @@
+use std::time::Duration;
+use chrono::Utc;
+use color_eyre::eyre::Result;
+use secrecy::ExposeSecret;
+use crate::{
+    app_state::AppState,
+    domain::{
+        data_stores::{TwoFACodeStoreError, UserStoreError},
+        Email,
+    },
+};
+
+// Periodically purges accounts whose deletion grace period is over.
+pub fn spawn_account_purge(state: AppState, interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = purge_deleted_accounts(&state).await {
+                tracing::error!("Failed to purge deleted accounts: {:?}", e);
+            }
+        }
+    })
+}
+
+// Permanently removes the accounts deleted more than the grace period ago, together with
+// their pending 2FA codes, sessions, refresh tokens and API keys. Returns how many were purged; an
+// account that fails to purge is logged and retried on the next run.
+#[tracing::instrument(name = "Purging deleted accounts", skip_all)]
+pub async fn purge_deleted_accounts(state: &AppState) -> Result<usize> {
+    let cutoff = Utc::now().timestamp() - state.account_deletion_grace_period_seconds;
+    let emails = state
+        .user_store
+        .read()
+        .await
+        .get_users_deleted_before(cutoff)
+        .await?;
+
+    let mut purged = 0;
+    for email in &emails {
+        match purge_account(state, email, cutoff).await {
+            Ok(true) => purged += 1,
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to purge deleted account: {:?}", e),
+        }
+    }
+
+    Ok(purged)
+}
+
+// Returns false if the account was restored since it was listed for purging.
+async fn purge_account(state: &AppState, email: &Email, cutoff: i64) -> Result<bool> {
+    match state
+        .user_store
+        .write()
+        .await
+        .delete_user(email.as_ref().expose_secret(), cutoff)
+        .await
+    {
+        Ok(()) => {}
+        Err(UserStoreError::UserNotFound) => return Ok(false),
+        Err(e) => return Err(e.into()),
+    }
+
+    for two_fa_code_store in [&state.two_fa_code_store, &state.two_fa_enrollment_code_store] {
+        match two_fa_code_store.write().await.remove_code(email).await {
+            Ok(()) | Err(TwoFACodeStoreError::LoginAttemptIdNotFound) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    state.session_store.write().await.remove_all_sessions(email).await?;
+    state.refresh_token_store.write().await.revoke_user_tokens(email).await?;
+    state.api_key_store.write().await.revoke_user_keys(email).await?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use secrecy::Secret;
+    use crate::{
+        domain::{
+            data_stores::{LoginAttemptId, Session, TwoFACode},
+            ApiKey, ApiKeySecret, Password, TwoFAMethod, User,
+        },
+        services::{data_stores::*, mock_email_client::MockEmailClient},
+    };
+
+    fn create_app_state() -> AppState {
+        AppState::new(
+            Arc::new(RwLock::new(Box::new(HashmapUserStore::default()))),
+            Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::default()))),
+            Arc::new(RwLock::new(Box::new(HashmapTwoFACodeStore::default()))),
+            Arc::new(RwLock::new(Box::new(HashmapTwoFACodeStore::default()))),
+            Arc::new(RwLock::new(Box::new(HashmapPasswordResetTokenStore::default()))),
+            Arc::new(RwLock::new(Box::new(HashmapRefreshTokenStore::default()))),
+            Arc::new(RwLock::new(Box::new(HashmapRateLimitStore::default()))),
+            Arc::new(RwLock::new(Box::new(HashmapOAuthClientStore::default()))),
+            Arc::new(RwLock::new(Box::new(HashmapAuthorizationCodeStore::default()))),
+            Arc::new(RwLock::new(Box::new(HashmapSessionStore::default()))),
+            Arc::new(RwLock::new(Box::new(HashmapApiKeyStore::default()))),
+            Arc::new(HashmapAuditLogStore::new(Secret::new("secret".to_owned()))),
+            Arc::new(HashmapAuditHeadStore::default()),
+            Arc::new(MockEmailClient),
+        )
+    }
+
+    async fn add_user(state: &AppState, email: &str) -> Email {
+        let email = Email::parse(Secret::new(email.to_owned())).unwrap();
+        let user = User::new(
+            email.clone(),
+            Password::parse(Secret::new("password123".to_owned())).unwrap(),
+            TwoFAMethod::None,
+        );
+        state.user_store.write().await.add_user(user).await.unwrap();
+        email
+    }
+
+    #[tokio::test]
+    async fn test_purge_removes_accounts_past_grace_period() {
+        let mut state = create_app_state();
+        state.account_deletion_grace_period_seconds = 60;
+        let email = add_user(&state, "deleted@example.com").await;
+        let email_str = email.as_ref().expose_secret();
+
+        state
+            .user_store
+            .write()
+            .await
+            .mark_user_deleted(email_str, Utc::now().timestamp() - 61)
+            .await
+            .unwrap();
+        state
+            .two_fa_code_store
+            .write()
+            .await
+            .add_code(email.clone(), LoginAttemptId::default(), TwoFACode::default())
+            .await
+            .unwrap();
+        state
+            .session_store
+            .write()
+            .await
+            .add_session(&email, Session { id: "session".to_owned(), created_at: 0, ip: None, user_agent: None })
+            .await
+            .unwrap();
+        let key = ApiKey::new(email.clone(), "CI".to_owned(), vec![], 0, i64::MAX);
+        state
+            .api_key_store
+            .write()
+            .await
+            .add_key(key.clone(), &ApiKeySecret::generate(&key.id))
+            .await
+            .unwrap();
+
+        assert_eq!(purge_deleted_accounts(&state).await.unwrap(), 1);
+
+        assert_eq!(
+            state.user_store.read().await.get_user(email_str).await.unwrap_err(),
+            UserStoreError::UserNotFound
+        );
+        assert!(state.two_fa_code_store.read().await.get_code(&email).await.is_err());
+        assert!(state.session_store.read().await.get_sessions(&email).await.unwrap().is_empty());
+        assert!(state.api_key_store.read().await.get_keys(&email).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_purge_keeps_accounts_within_grace_period() {
+        let mut state = create_app_state();
+        state.account_deletion_grace_period_seconds = 60;
+        let deleted = add_user(&state, "deleted@example.com").await;
+        let active = add_user(&state, "active@example.com").await;
+
+        state
+            .user_store
+            .write()
+            .await
+            .mark_user_deleted(deleted.as_ref().expose_secret(), Utc::now().timestamp())
+            .await
+            .unwrap();
+
+        assert_eq!(purge_deleted_accounts(&state).await.unwrap(), 0);
+        let user_store = state.user_store.read().await;
+        assert!(user_store.get_user(deleted.as_ref().expose_secret()).await.is_ok());
+        assert!(user_store.get_user(active.as_ref().expose_secret()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_purge_accounts_without_pending_2fa_code() {
+        let mut state = create_app_state();
+        state.account_deletion_grace_period_seconds = 60;
+        let first = add_user(&state, "first@example.com").await;
+        let second = add_user(&state, "second@example.com").await;
+
+        for email in [&first, &second] {
+            state
+                .user_store
+                .write()
+                .await
+                .mark_user_deleted(email.as_ref().expose_secret(), Utc::now().timestamp() - 61)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(purge_deleted_accounts(&state).await.unwrap(), 2);
+        let user_store = state.user_store.read().await;
+        assert!(user_store.get_user(first.as_ref().expose_secret()).await.is_err());
+        assert!(user_store.get_user(second.as_ref().expose_secret()).await.is_err());
+    }
+}
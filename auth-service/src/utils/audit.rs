@@ -0,0 +1,120 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use axum::{
+    extract::{ConnectInfo, MatchedPath, RawPathParams, Request, State},
+    http::header::USER_AGENT,
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::extract::CookieJar;
+use chrono::Utc;
+use crate::{
+    app_state::AppState,
+    domain::{AuditAction, AuditEvent, AuditHead, AuditOutcome},
+};
+use super::{
+    auth::token_subject,
+    constants::JWT_COOKIE_NAME,
+    oauth::parse_bearer_token,
+    tracing::request_id,
+};
+
+// Lets a handler name what a request does, and its actor when the request isn't made with a
+// token, such as the account a login is attempted for. Handlers behind `audit` take it as
+// `Extension<AuditContext>`.
+#[derive(Clone, Default)]
+pub struct AuditContext(Arc<Mutex<AuditDetails>>);
+
+#[derive(Default)]
+struct AuditDetails {
+    actor: Option<String>,
+    action: Option<AuditAction>,
+}
+
+impl AuditContext {
+    pub fn set_actor(&self, actor: &str) {
+        self.0.lock().expect("audit context lock poisoned").actor = Some(actor.to_owned());
+    }
+
+    // The last action set is the one recorded.
+    pub fn set_action(&self, action: AuditAction) {
+        self.0.lock().expect("audit context lock poisoned").action = Some(action);
+    }
+
+    fn actor(&self) -> Option<String> {
+        self.0.lock().expect("audit context lock poisoned").actor.clone()
+    }
+
+    fn action(&self) -> Option<AuditAction> {
+        self.0.lock().expect("audit context lock poisoned").action
+    }
+}
+
+// Middleware recording an audit event for every request it wraps, once the response is ready.
+// The actor is the one the handler set, or else the subject of the request's token. The action
+// is the one the handler set, or else the route. Any status of 400 or above is a failure. The
+// recorded event becomes the head of the log, which is kept outside the database. Failing to
+// record an event doesn't fail the request.
+pub async fn audit(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    matched_path: MatchedPath,
+    params: Option<RawPathParams>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let token_actor = jar
+        .get(JWT_COOKIE_NAME)
+        .map(|cookie| cookie.value())
+        .or_else(|| parse_bearer_token(request.headers()))
+        .and_then(token_subject);
+    let target = params.and_then(|params| {
+        params
+            .iter()
+            .find(|(name, _)| *name == "email")
+            .map(|(_, value)| value.to_owned())
+    });
+    let route = format!("{} {}", request.method(), matched_path.as_str());
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string());
+    let user_agent = request
+        .headers()
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+    let request_id = request_id(&request).map(str::to_owned);
+
+    let context = AuditContext::default();
+    request.extensions_mut().insert(context.clone());
+    let response = next.run(request).await;
+
+    let status = response.status();
+    let event = AuditEvent {
+        actor: context.actor().or(token_actor),
+        target,
+        action: context.action().map_or(route, |action| action.as_str().to_owned()),
+        outcome: if status.is_client_error() || status.is_server_error() {
+            AuditOutcome::Failure
+        } else {
+            AuditOutcome::Success
+        },
+        ip,
+        user_agent,
+        request_id,
+        created_at: Utc::now().timestamp(),
+    };
+    match state.audit_log_store.append_event(event).await {
+        Ok(record) => {
+            if let Err(e) = state.audit_head_store.advance_head(AuditHead::from(&record)).await {
+                tracing::error!("Failed to advance audit head: {:?}", e);
+            }
+        }
+        Err(e) => tracing::error!("Failed to record audit event: {:?}", e),
+    }
+
+    response
+}
@@ -1,24 +1,114 @@
 use axum_extra::extract::cookie::{Cookie, SameSite};
 use chrono::Utc;
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Validation};
+use jsonwebtoken::{decode, decode_header, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
-use color_eyre::eyre::{eyre, Context, ContextCompat, Result, WrapErr};
-use crate::domain::Email;
-use crate::app_state::BannedTokenStoreType;
-use super::constants::{JWT_COOKIE_NAME, JWT_SECRET};
+use color_eyre::eyre::{eyre, Context, ContextCompat, Result};
+use crate::domain::{Authentication, AuthenticationMethod, Email, RefreshToken};
+use crate::app_state::{BannedTokenStoreType, RefreshTokenStoreType, UserStoreType};
+use super::constants::{JWT_COOKIE_NAME, JWT_SECRET, REFRESH_TOKEN_COOKIE_NAME};
+use super::{keyring::current_keyring, oauth::SERVICE_TOKEN_TYPE, signing_key::SIGNING_ALGORITHM};
 use secrecy::{ExposeSecret, Secret};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub exp: usize,
+    pub iat: usize,
+    #[serde(default)]
+    pub auth_time: i64,
+    #[serde(default)]
+    pub amr: Vec<AuthenticationMethod>,
+    // The session the token was issued for; every token of a session shares it.
+    #[serde(default)]
+    pub jti: String,
+    #[serde(default)]
+    pub token_version: i64,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    // Never set on auth tokens. Only tokens meant for some other party, such as ID tokens, have
+    // an audience, and `validate_token` refuses them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+}
+
+impl Claims {
+    pub fn authentication(&self) -> Authentication {
+        Authentication {
+            auth_time: self.auth_time,
+            methods: self.amr.clone(),
+            session_id: self.jti.clone(),
+            token_version: self.token_version,
+            roles: self.roles.clone(),
+            permissions: self.permissions.clone(),
+        }
+    }
 }
 
 pub const TOKEN_TTL_SECONDS: i64 = 600;
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailVerificationClaims {
+    pub sub: String,
+    pub exp: usize,
+}
+
+pub const EMAIL_VERIFICATION_TOKEN_TTL_SECONDS: i64 = 86400;
+// Verification links are signed with a key derived from JWT_SECRET so they can
+// never be accepted as auth tokens, nor auth tokens as verification links.
+const EMAIL_VERIFICATION_KEY_SUFFIX: &str = ":email-verification";
+
+// A requested change of a user's email address, as carried by the links emailed about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailChange {
+    pub email: Email,
+    pub new_email: Email,
+    // The user's token version when the change was requested. Confirming or undoing the change
+    // increments it, which is what keeps each link from being used more than once.
+    pub token_version: i64,
+}
+
+// Confirmation links go to the new address, undo links to the old one. Each kind is signed with
+// its own key so one can't be used as the other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmailChangeLink {
+    Confirm,
+    Undo,
+}
+
+impl EmailChangeLink {
+    pub fn ttl_seconds(&self) -> i64 {
+        match self {
+            EmailChangeLink::Confirm => EMAIL_CHANGE_TOKEN_TTL_SECONDS,
+            EmailChangeLink::Undo => EMAIL_CHANGE_UNDO_TOKEN_TTL_SECONDS,
+        }
+    }
+
+    fn key(&self) -> String {
+        let suffix = match self {
+            EmailChangeLink::Confirm => ":email-change",
+            EmailChangeLink::Undo => ":email-change-undo",
+        };
+        format!("{}{}", JWT_SECRET.expose_secret(), suffix)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailChangeClaims {
+    pub sub: String,
+    pub new_email: String,
+    pub token_version: i64,
+    pub exp: usize,
+}
+
+pub const EMAIL_CHANGE_TOKEN_TTL_SECONDS: i64 = 86400;
+// The old address can undo the change for longer, in case its owner only notices later.
+pub const EMAIL_CHANGE_UNDO_TOKEN_TTL_SECONDS: i64 = 7 * 86400;
+
 #[tracing::instrument(name = "Generating auth cookie", skip_all)]
-pub fn generate_auth_cookie(email: &Email) -> Result<Cookie<'static>> {
-    let token = generate_auth_token(email)?;
+pub fn generate_auth_cookie(email: &Email, authentication: &Authentication) -> Result<Cookie<'static>> {
+    let token = generate_auth_token(email, authentication)?;
     Ok(create_auth_cookie(token))
 }
 
@@ -30,12 +120,36 @@ fn create_auth_cookie(token: String) -> Cookie<'static> {
         .build()
 }
 
+#[tracing::instrument(name = "Generating refresh cookie", skip_all)]
+pub async fn generate_refresh_cookie(
+    email: &Email,
+    authentication: &Authentication,
+    refresh_token_store: RefreshTokenStoreType,
+) -> Result<Cookie<'static>> {
+    let token = RefreshToken::default();
+    refresh_token_store
+        .write()
+        .await
+        .add_token(token.clone(), email.clone(), authentication.clone())
+        .await?;
+    Ok(create_refresh_cookie(&token))
+}
+
+pub fn create_refresh_cookie(token: &RefreshToken) -> Cookie<'static> {
+    Cookie::build((REFRESH_TOKEN_COOKIE_NAME, token.as_ref().expose_secret().clone()))
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .build()
+}
+
 #[tracing::instrument(name = "Generating auth token", skip_all)]
-pub fn generate_auth_token(email: &Email) -> Result<String> {
+pub fn generate_auth_token(email: &Email, authentication: &Authentication) -> Result<String> {
     let delta = chrono::Duration::try_seconds(TOKEN_TTL_SECONDS)
         .wrap_err("failed to create 10 minute time delta")?;
 
-    let exp = Utc::now()
+    let now = Utc::now();
+    let exp = now
         .checked_add_signed(delta)
         .ok_or(eyre!("failed to add 10 minutes to current time"))?
         .timestamp();
@@ -45,8 +159,24 @@ pub fn generate_auth_token(email: &Email) -> Result<String> {
         exp
     ))?;
 
+    let iat: usize = now.timestamp().try_into().wrap_err(format!(
+        "failed to cast iat time to usize. iat time: {}",
+        now.timestamp()
+    ))?;
+
     let sub = email.as_ref().expose_secret().to_owned();
-    let claims = Claims { sub, exp };
+    let claims = Claims {
+        sub,
+        exp,
+        iat,
+        auth_time: authentication.auth_time,
+        amr: authentication.methods.clone(),
+        jti: authentication.session_id.clone(),
+        token_version: authentication.token_version,
+        roles: authentication.roles.clone(),
+        permissions: authentication.permissions.clone(),
+        aud: None,
+    };
 
     create_token(&claims)
 }
@@ -54,6 +184,7 @@ pub fn generate_auth_token(email: &Email) -> Result<String> {
 pub async fn validate_token(
     token: &str,
     banned_token_store: BannedTokenStoreType,
+    user_store: UserStoreType,
 ) -> Result<Claims> {
     match banned_token_store.read().await.contains_token(&Secret::new(token.to_string())).await {
         Ok(value) => {
@@ -64,38 +195,228 @@ pub async fn validate_token(
         Err(e) => return Err(e.into()),
     }
 
-    decode::<Claims>(
+    let header = decode_header(token).wrap_err("failed to decode token header")?;
+    if header.typ.as_deref() == Some(SERVICE_TOKEN_TYPE) {
+        return Err(eyre!("token is an OAuth access token"));
+    }
+    let kid = header.kid.ok_or(eyre!("token has no kid"))?;
+    let keyring = current_keyring();
+    let decoding_key = keyring
+        .decoding_key(&kid)
+        .ok_or(eyre!("token was not signed with a known key"))?;
+
+    let claims = decode::<Claims>(
         token,
-        &DecodingKey::from_secret(JWT_SECRET.expose_secret().as_bytes()),
+        decoding_key,
+        &Validation::new(SIGNING_ALGORITHM),
+    )
+    .map(|data| data.claims)
+    .wrap_err("failed to decode token")?;
+    if claims.aud.is_some() {
+        return Err(eyre!("token is meant for another party"));
+    }
+
+    check_token_not_revoked(&claims.sub, claims.iat, &claims.jti, claims.token_version, &banned_token_store, &user_store).await?;
+
+    Ok(claims)
+}
+
+// The checks every token issued to a user goes through, whatever its kind: that it wasn't
+// revoked, neither on its own nor with its session, and that its token version is current.
+pub(crate) async fn check_token_not_revoked(
+    sub: &str,
+    iat: usize,
+    session_id: &str,
+    token_version: i64,
+    banned_token_store: &BannedTokenStoreType,
+    user_store: &UserStoreType,
+) -> Result<()> {
+    let email = Email::parse(Secret::new(sub.to_owned()))?;
+    let revoked_before = banned_token_store
+        .read()
+        .await
+        .get_tokens_revoked_before(&email)
+        .await?;
+
+    if let Some(revoked_before) = revoked_before {
+        if (iat as i64) < revoked_before {
+            return Err(eyre!("token has been revoked"));
+        }
+    }
+
+    if !session_id.is_empty()
+        && banned_token_store
+            .read()
+            .await
+            .is_session_revoked(session_id)
+            .await?
+    {
+        return Err(eyre!("session has been revoked"));
+    }
+
+    // Also rejects tokens of users that no longer exist.
+    let current_token_version = user_store.read().await.get_token_version(sub).await?;
+    if token_version != current_token_version {
+        return Err(eyre!("token version is outdated"));
+    }
+
+    Ok(())
+}
+// The subject of a JWT this service signed, auth and service tokens alike, without any of the
+// other checks of `validate_token`. Only good for telling who sent a request, never for
+// deciding what they may do.
+pub fn token_subject(token: &str) -> Option<String> {
+    #[derive(Deserialize)]
+    struct Subject {
+        sub: String,
+    }
+
+    let kid = decode_header(token).ok()?.kid?;
+    let keyring = current_keyring();
+    let decoding_key = keyring.decoding_key(&kid)?;
+    decode::<Subject>(token, decoding_key, &Validation::new(SIGNING_ALGORITHM))
+        .ok()
+        .map(|data| data.claims.sub)
+}
+
+#[tracing::instrument(name = "Generating email verification token", skip_all)]
+pub fn generate_email_verification_token(email: &Email) -> Result<String> {
+    let delta = chrono::Duration::try_seconds(EMAIL_VERIFICATION_TOKEN_TTL_SECONDS)
+        .wrap_err("failed to create 24 hour time delta")?;
+
+    let exp = Utc::now()
+        .checked_add_signed(delta)
+        .ok_or(eyre!("failed to add 24 hours to current time"))?
+        .timestamp();
+
+    let exp: usize = exp.try_into().wrap_err(format!(
+        "failed to cast exp time to usize. exp time: {}",
+        exp
+    ))?;
+
+    let sub = email.as_ref().expose_secret().to_owned();
+    let claims = EmailVerificationClaims { sub, exp };
+
+    encode(
+        &jsonwebtoken::Header::default(),
+        &claims,
+        &EncodingKey::from_secret(email_verification_key().as_bytes()),
+    )
+    .wrap_err("failed to create email verification token")
+}
+
+#[tracing::instrument(name = "Validating email verification token", skip_all)]
+pub fn validate_email_verification_token(token: &str) -> Result<Email> {
+    let claims = decode::<EmailVerificationClaims>(
+        token,
+        &DecodingKey::from_secret(email_verification_key().as_bytes()),
         &Validation::default(),
     )
     .map(|data| data.claims)
-    .wrap_err("failed to decode token")
+    .wrap_err("failed to decode email verification token")?;
+
+    Email::parse(Secret::new(claims.sub))
 }
-#[tracing::instrument(name = "Creating token", skip_all)]
-fn create_token(claims: &Claims) -> Result<String> {
+
+fn email_verification_key() -> String {
+    format!("{}{}", JWT_SECRET.expose_secret(), EMAIL_VERIFICATION_KEY_SUFFIX)
+}
+
+#[tracing::instrument(name = "Generating email change token", skip_all)]
+pub fn generate_email_change_token(change: &EmailChange, link: EmailChangeLink) -> Result<String> {
+    let delta = chrono::Duration::try_seconds(link.ttl_seconds())
+        .wrap_err("failed to create email change time delta")?;
+
+    let exp = Utc::now()
+        .checked_add_signed(delta)
+        .ok_or(eyre!("failed to add email change time delta to current time"))?
+        .timestamp();
+
+    let exp: usize = exp.try_into().wrap_err(format!(
+        "failed to cast exp time to usize. exp time: {}",
+        exp
+    ))?;
+
+    let claims = EmailChangeClaims {
+        sub: change.email.as_ref().expose_secret().to_owned(),
+        new_email: change.new_email.as_ref().expose_secret().to_owned(),
+        token_version: change.token_version,
+        exp,
+    };
+
     encode(
         &jsonwebtoken::Header::default(),
         &claims,
-        &EncodingKey::from_secret(JWT_SECRET.expose_secret().as_bytes()),
+        &EncodingKey::from_secret(link.key().as_bytes()),
     )
+    .wrap_err("failed to create email change token")
+}
+
+#[tracing::instrument(name = "Validating email change token", skip_all)]
+pub fn validate_email_change_token(token: &str, link: EmailChangeLink) -> Result<EmailChange> {
+    let claims = decode::<EmailChangeClaims>(
+        token,
+        &DecodingKey::from_secret(link.key().as_bytes()),
+        &Validation::default(),
+    )
+    .map(|data| data.claims)
+    .wrap_err("failed to decode email change token")?;
+
+    Ok(EmailChange {
+        email: Email::parse(Secret::new(claims.sub))?,
+        new_email: Email::parse(Secret::new(claims.new_email))?,
+        token_version: claims.token_version,
+    })
+}
+
+// Signs `claims` with the current signing key, naming it in the `kid` header.
+pub(crate) fn create_token<T: Serialize>(claims: &T) -> Result<String> {
+    create_token_of_type(claims, "JWT")
+}
+
+// Like `create_token`, with `typ` as the `typ` header, which tells the kinds of token apart.
+#[tracing::instrument(name = "Creating token", skip_all)]
+pub(crate) fn create_token_of_type<T: Serialize>(claims: &T, typ: &str) -> Result<String> {
+    let keyring = current_keyring();
+    let signing_key = keyring.signing_key();
+    let header = Header {
+        kid: Some(signing_key.kid().to_owned()),
+        typ: Some(typ.to_owned()),
+        ..Header::new(SIGNING_ALGORITHM)
+    };
+    encode(&header, &claims, signing_key.encoding_key())
     .wrap_err("failed to create token")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::services::hashset_banned_token_store::HashsetBannedTokenStore;
-    use crate::domain::{Email, data_stores::BannedTokenStore};
+    use crate::services::{hashmap_user_store::HashmapUserStore, hashset_banned_token_store::HashsetBannedTokenStore};
+    use crate::utils::oidc::generate_id_token;
+    use crate::domain::{Email, Password, TwoFAMethod, User, data_stores::{BannedTokenStore, UserStore}};
     use std::sync::Arc;
     use tokio::sync::RwLock;
     use secrecy::Secret;
 
+    // A user store holding the test user, whose tokens are checked against it.
+    async fn create_user_store() -> UserStoreType {
+        let mut user_store = HashmapUserStore::default();
+        user_store
+            .add_user(User::new(
+                Email::parse(Secret::new("test@example.com".to_string())).unwrap(),
+                Password::parse(Secret::new("password123".to_string())).unwrap(),
+                TwoFAMethod::None,
+            ))
+            .await
+            .unwrap();
+        Arc::new(RwLock::new(Box::new(user_store) as Box<dyn UserStore + Send + Sync>))
+    }
+
     #[tokio::test]
     async fn test_generate_auth_cookie() {
         let email_str = "test@example.com";
         let email = Email::parse(Secret::new(email_str.to_string())).unwrap();
-        let cookie = generate_auth_cookie(&email).unwrap();
+        let cookie = generate_auth_cookie(&email, &Authentication::default()).unwrap();
         assert_eq!(cookie.name(), JWT_COOKIE_NAME);
         assert_eq!(cookie.value().split('.').count(), 3);
         assert_eq!(cookie.path(), Some("/"));
@@ -107,17 +428,147 @@ mod tests {
     async fn test_validate_token_with_valid_token() {
         let email_str = "test@example.com";
         let email = Email::parse(Secret::new(email_str.to_string())).unwrap();
-        let token = generate_auth_token(&email).unwrap();
+        let token = generate_auth_token(&email, &Authentication::default()).unwrap();
         let banned_token_store = Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::default()) as Box<dyn BannedTokenStore + Send + Sync>));
-        let result = validate_token(&token, banned_token_store).await;
+        let result = validate_token(&token, banned_token_store, create_user_store().await).await;
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_validate_token_with_revoked_token() {
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let token = generate_auth_token(&email, &Authentication::default()).unwrap();
+        let banned_token_store = Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::default()) as Box<dyn BannedTokenStore + Send + Sync>));
+        banned_token_store
+            .write()
+            .await
+            .revoke_tokens_issued_before(&email, Utc::now().timestamp() + 1)
+            .await
+            .unwrap();
+        let result = validate_token(&token, banned_token_store, create_user_store().await).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_issued_after_revocation() {
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let banned_token_store = Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::default()) as Box<dyn BannedTokenStore + Send + Sync>));
+        banned_token_store
+            .write()
+            .await
+            .revoke_tokens_issued_before(&email, Utc::now().timestamp())
+            .await
+            .unwrap();
+        let token = generate_auth_token(&email, &Authentication::default()).unwrap();
+        let result = validate_token(&token, banned_token_store, create_user_store().await).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_of_revoked_session() {
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let authentication = Authentication::now(vec![AuthenticationMethod::Pwd]);
+        let token = generate_auth_token(&email, &authentication).unwrap();
+        let banned_token_store = Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::default()) as Box<dyn BannedTokenStore + Send + Sync>));
+
+        let claims = validate_token(&token, banned_token_store.clone(), create_user_store().await).await.unwrap();
+        assert_eq!(claims.jti, authentication.session_id);
+
+        banned_token_store
+            .write()
+            .await
+            .revoke_session(&authentication.session_id)
+            .await
+            .unwrap();
+        assert!(validate_token(&token, banned_token_store, create_user_store().await).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_with_outdated_token_version() {
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let token = generate_auth_token(&email, &Authentication::default()).unwrap();
+        let banned_token_store = Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::default()) as Box<dyn BannedTokenStore + Send + Sync>));
+        let user_store = create_user_store().await;
+
+        assert!(validate_token(&token, banned_token_store.clone(), user_store.clone()).await.is_ok());
+
+        user_store
+            .write()
+            .await
+            .increment_token_version("test@example.com")
+            .await
+            .unwrap();
+        assert!(validate_token(&token, banned_token_store, user_store).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_of_unknown_user() {
+        let email = Email::parse(Secret::new("unknown@example.com".to_string())).unwrap();
+        let token = generate_auth_token(&email, &Authentication::default()).unwrap();
+        let banned_token_store = Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::default()) as Box<dyn BannedTokenStore + Send + Sync>));
+        assert!(validate_token(&token, banned_token_store, create_user_store().await).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_of_id_token() {
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let user = User::new(email, Password::parse(Secret::new("password123".to_string())).unwrap(), TwoFAMethod::None);
+        let id_token = generate_id_token("client", "user-id", &user, &Authentication::default(), None).unwrap();
+        let banned_token_store = Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::default()) as Box<dyn BannedTokenStore + Send + Sync>));
+        assert!(validate_token(&id_token, banned_token_store, create_user_store().await).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_email_verification_token_round_trip() {
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let token = generate_email_verification_token(&email).unwrap();
+        let result = validate_email_verification_token(&token).unwrap();
+        assert_eq!(result, email);
+    }
+
+    #[tokio::test]
+    async fn test_auth_token_is_not_an_email_verification_token() {
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let auth_token = generate_auth_token(&email, &Authentication::default()).unwrap();
+        assert!(validate_email_verification_token(&auth_token).is_err());
+
+        let verification_token = generate_email_verification_token(&email).unwrap();
+        let banned_token_store = Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::default()) as Box<dyn BannedTokenStore + Send + Sync>));
+        assert!(validate_token(&verification_token, banned_token_store, create_user_store().await).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_email_change_token_round_trip() {
+        let change = EmailChange {
+            email: Email::parse(Secret::new("old@example.com".to_string())).unwrap(),
+            new_email: Email::parse(Secret::new("new@example.com".to_string())).unwrap(),
+            token_version: 3,
+        };
+        for link in [EmailChangeLink::Confirm, EmailChangeLink::Undo] {
+            let token = generate_email_change_token(&change, link).unwrap();
+            assert_eq!(validate_email_change_token(&token, link).unwrap(), change);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_email_change_confirm_token_is_not_an_undo_token() {
+        let change = EmailChange {
+            email: Email::parse(Secret::new("old@example.com".to_string())).unwrap(),
+            new_email: Email::parse(Secret::new("new@example.com".to_string())).unwrap(),
+            token_version: 0,
+        };
+        let token = generate_email_change_token(&change, EmailChangeLink::Confirm).unwrap();
+        assert!(validate_email_change_token(&token, EmailChangeLink::Undo).is_err());
+
+        let verification_token = generate_email_verification_token(&change.email).unwrap();
+        assert!(validate_email_change_token(&verification_token, EmailChangeLink::Confirm).is_err());
+    }
+
     #[tokio::test]
     async fn test_validate_token_with_invalid_token() {
         let token = "invalid_token".to_owned();
         let banned_token_store = Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::default()) as Box<dyn BannedTokenStore + Send + Sync>));
-        let result = validate_token(&token, banned_token_store).await;
+        let result = validate_token(&token, banned_token_store, create_user_store().await).await;
         assert!(result.is_err());
     }
 }
\ No newline at end of file
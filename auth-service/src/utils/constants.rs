@@ -2,13 +2,41 @@ use dotenvy::dotenv;
 use lazy_static::lazy_static;
 use secrecy::Secret;
 use std::env as std_env;
+use crate::domain::data_stores::RateLimit;
 
 lazy_static! {
     pub static ref JWT_SECRET: Secret<String> = Secret::new(set_token());
+    pub static ref AUDIT_LOG_SECRET: Secret<String> = Secret::new(set_audit_log_secret());
+    pub static ref JWT_SIGNING_KEY_PEM: Option<Secret<String>> = set_signing_key_pem();
+    pub static ref JWT_KEYRING_PATH: Option<String> = set_keyring_path();
+    // Opt-in for development and tests: sign tokens with a key generated at startup when none is
+    // configured.
+    pub static ref JWT_ALLOW_EPHEMERAL_KEY: bool = set_optional(env::JWT_ALLOW_EPHEMERAL_KEY_ENV_VAR)
+        .map(|value| value.parse().expect("JWT_ALLOW_EPHEMERAL_KEY must be true or false."))
+        .unwrap_or(false);
     pub static ref DATABASE_URL: Secret<String> = Secret::new(set_db_url());
     pub static ref REDIS_HOST_NAME: String = set_redis_host();
 
     pub static ref POSTMARK_AUTH_TOKEN: Secret<String> = set_postmark_auth_token();
+    pub static ref AUTH_SERVICE_URL: String = set_auth_service_url();
+
+    pub static ref FEDERATED_LOGIN_ISSUER: Option<String> = set_optional(env::FEDERATED_LOGIN_ISSUER_ENV_VAR);
+    pub static ref FEDERATED_LOGIN_CLIENT_ID: Option<String> = set_optional(env::FEDERATED_LOGIN_CLIENT_ID_ENV_VAR);
+    pub static ref FEDERATED_LOGIN_CLIENT_SECRET: Option<Secret<String>> =
+        set_optional(env::FEDERATED_LOGIN_CLIENT_SECRET_ENV_VAR).map(Secret::new);
+    pub static ref MAX_SESSIONS_PER_USER: Option<usize> = set_optional(env::MAX_SESSIONS_PER_USER_ENV_VAR)
+        .map(|value| value.parse().expect("MAX_SESSIONS_PER_USER must be a number."));
+    pub static ref ACCOUNT_DELETION_GRACE_PERIOD_SECONDS: i64 = set_optional(env::ACCOUNT_DELETION_GRACE_PERIOD_SECONDS_ENV_VAR)
+        .map(|value| value.parse().expect("ACCOUNT_DELETION_GRACE_PERIOD_SECONDS must be a number."))
+        .unwrap_or(DEFAULT_ACCOUNT_DELETION_GRACE_PERIOD_SECONDS);
+    pub static ref LOGIN_RATE_LIMIT_PER_IP: RateLimit = set_rate_limit(env::LOGIN_RATE_LIMIT_PER_IP_ENV_VAR)
+        .unwrap_or(rate_limits::LOGIN_PER_IP);
+    pub static ref LOGIN_RATE_LIMIT_PER_EMAIL: RateLimit = set_rate_limit(env::LOGIN_RATE_LIMIT_PER_EMAIL_ENV_VAR)
+        .unwrap_or(rate_limits::LOGIN_PER_EMAIL);
+    pub static ref FORGOT_PASSWORD_RATE_LIMIT_PER_IP: RateLimit = set_rate_limit(env::FORGOT_PASSWORD_RATE_LIMIT_PER_IP_ENV_VAR)
+        .unwrap_or(rate_limits::FORGOT_PASSWORD_PER_IP);
+    pub static ref FORGOT_PASSWORD_RATE_LIMIT_PER_EMAIL: RateLimit = set_rate_limit(env::FORGOT_PASSWORD_RATE_LIMIT_PER_EMAIL_ENV_VAR)
+        .unwrap_or(rate_limits::FORGOT_PASSWORD_PER_EMAIL);
 
 }
 
@@ -21,6 +49,24 @@ fn set_token() -> String {
     secret
 }
 
+fn set_audit_log_secret() -> String {
+    dotenv().ok();
+    let secret = std_env::var(env::AUDIT_LOG_SECRET_ENV_VAR).expect("AUDIT_LOG_SECRET must be set.");
+    if secret.is_empty() {
+        panic!("AUDIT_LOG_SECRET must not be empty.");
+    }
+    secret
+}
+
+fn set_signing_key_pem() -> Option<Secret<String>> {
+    dotenv().ok();
+    std_env::var(env::JWT_SIGNING_KEY_ENV_VAR).ok().map(Secret::new)
+}
+
+fn set_keyring_path() -> Option<String> {
+    set_optional(env::JWT_KEYRING_PATH_ENV_VAR)
+}
+
 fn set_db_url() -> String {
     dotenv().ok();
     std_env::var(env::DATABASE_URL_ENV_VAR).expect("DATABASE_URL must be set.")
@@ -38,15 +84,79 @@ fn set_postmark_auth_token() -> Secret<String> {
     )
 }
 
+fn set_auth_service_url() -> String {
+    dotenv().ok();
+    std_env::var(env::AUTH_SERVICE_URL_ENV_VAR).unwrap_or(DEFAULT_AUTH_SERVICE_URL.to_owned())
+}
+
+fn set_optional(name: &str) -> Option<String> {
+    dotenv().ok();
+    std_env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+fn set_rate_limit(name: &str) -> Option<RateLimit> {
+    set_optional(name).map(|value| {
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("{} must be <max requests>/<window seconds>.", name))
+    })
+}
+
 pub mod env {
     pub const DATABASE_URL_ENV_VAR: &str = "DATABASE_URL";
     pub const JWT_SECRET_ENV_VAR: &str = "JWT_SECRET";
+    pub const AUDIT_LOG_SECRET_ENV_VAR: &str = "AUDIT_LOG_SECRET";
+    pub const JWT_SIGNING_KEY_ENV_VAR: &str = "JWT_SIGNING_KEY";
+    pub const JWT_KEYRING_PATH_ENV_VAR: &str = "JWT_KEYRING_PATH";
+    pub const JWT_ALLOW_EPHEMERAL_KEY_ENV_VAR: &str = "JWT_ALLOW_EPHEMERAL_KEY";
     pub const REDIS_HOST_NAME_ENV_VAR: &str = "redis";
     pub const POSTMARK_AUTH_TOKEN_ENV_VAR: &str = "POSTMARK_AUTH_TOKEN";
+    pub const AUTH_SERVICE_URL_ENV_VAR: &str = "AUTH_SERVICE_URL";
+    pub const FEDERATED_LOGIN_ISSUER_ENV_VAR: &str = "FEDERATED_LOGIN_ISSUER";
+    pub const FEDERATED_LOGIN_CLIENT_ID_ENV_VAR: &str = "FEDERATED_LOGIN_CLIENT_ID";
+    pub const FEDERATED_LOGIN_CLIENT_SECRET_ENV_VAR: &str = "FEDERATED_LOGIN_CLIENT_SECRET";
+    pub const MAX_SESSIONS_PER_USER_ENV_VAR: &str = "MAX_SESSIONS_PER_USER";
+    pub const ACCOUNT_DELETION_GRACE_PERIOD_SECONDS_ENV_VAR: &str = "ACCOUNT_DELETION_GRACE_PERIOD_SECONDS";
+    pub const LOGIN_RATE_LIMIT_PER_IP_ENV_VAR: &str = "LOGIN_RATE_LIMIT_PER_IP";
+    pub const LOGIN_RATE_LIMIT_PER_EMAIL_ENV_VAR: &str = "LOGIN_RATE_LIMIT_PER_EMAIL";
+    pub const FORGOT_PASSWORD_RATE_LIMIT_PER_IP_ENV_VAR: &str = "FORGOT_PASSWORD_RATE_LIMIT_PER_IP";
+    pub const FORGOT_PASSWORD_RATE_LIMIT_PER_EMAIL_ENV_VAR: &str = "FORGOT_PASSWORD_RATE_LIMIT_PER_EMAIL";
 }
 
 pub const JWT_COOKIE_NAME: &str = "jwt";
+pub const REFRESH_TOKEN_COOKIE_NAME: &str = "refresh_token";
+pub const FEDERATED_LOGIN_COOKIE_NAME: &str = "federated_login";
 pub const DEFAULT_REDIS_HOSTNAME: &str = "127.0.0.1";
+pub const DEFAULT_AUTH_SERVICE_URL: &str = "http://localhost:3000";
+pub const PASSWORD_RESET_TOKEN_TTL_SECONDS: i64 = 900;
+pub const REFRESH_TOKEN_TTL_SECONDS: i64 = 60 * 60 * 24 * 30;
+pub const TOTP_ISSUER: &str = "AuthService";
+pub const RECOVERY_CODE_COUNT: usize = 10;
+pub const AUTHORIZATION_CODE_TTL_SECONDS: i64 = 60;
+pub const JWT_KEYRING_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+pub const MAX_2FA_ATTEMPTS: u32 = 5;
+pub const TWO_FA_RESEND_COOLDOWN_SECONDS: i64 = 60;
+pub const MAX_2FA_RESENDS: u32 = 3;
+pub const DEFAULT_ACCOUNT_DELETION_GRACE_PERIOD_SECONDS: i64 = 60 * 60 * 24 * 30;
+pub const ADMIN_ROLE: &str = "admin";
+pub const DEFAULT_ADMIN_PAGE_SIZE: i64 = 20;
+pub const MAX_ADMIN_PAGE_SIZE: i64 = 100;
+// Events read at a time when verifying the audit log.
+pub const AUDIT_VERIFY_PAGE_SIZE: i64 = 1000;
+pub const DEFAULT_API_KEY_TTL_DAYS: i64 = 90;
+pub const MAX_API_KEY_TTL_DAYS: i64 = 365;
+pub const MAX_API_KEY_NAME_LENGTH: usize = 100;
+pub const ACCOUNT_PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+// Defaults for the rate limits, each of which can be overridden from the environment.
+pub mod rate_limits {
+    use crate::domain::data_stores::RateLimit;
+
+    pub const LOGIN_PER_IP: RateLimit = RateLimit { max_requests: 30, window_seconds: 60 };
+    pub const LOGIN_PER_EMAIL: RateLimit = RateLimit { max_requests: 10, window_seconds: 300 };
+    pub const FORGOT_PASSWORD_PER_IP: RateLimit = RateLimit { max_requests: 10, window_seconds: 3600 };
+    pub const FORGOT_PASSWORD_PER_EMAIL: RateLimit = RateLimit { max_requests: 3, window_seconds: 900 };
+}
 
 // pub mod prod {
 //     pub const APP_ADDRESS: &str = "0.0.0.0:3300";
@@ -61,6 +171,11 @@ pub const DEFAULT_REDIS_HOSTNAME: &str = "127.0.0.1";
 pub mod prod {
     pub const APP_ADDRESS: &str = "0.0.0.0:3001";
     pub const REDIS_HOST_NAME: &str = "127.0.0.1";
+    pub mod identity_provider {
+        use std::time::Duration;
+
+        pub const TIMEOUT: Duration = std::time::Duration::from_secs(10);
+    }
     pub mod email_client {
         use std::time::Duration;
 
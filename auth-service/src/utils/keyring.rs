@@ -0,0 +1,293 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{eyre, Context, Result};
+use jsonwebtoken::{
+    jwk::{AlgorithmParameters, Jwk, JwkSet},
+    DecodingKey,
+};
+use lazy_static::lazy_static;
+use secrecy::Secret;
+use serde::Deserialize;
+use super::{
+    constants::{JWT_ALLOW_EPHEMERAL_KEY, JWT_KEYRING_PATH, JWT_SIGNING_KEY_PEM},
+    signing_key::{jwk_thumbprint, SigningKey},
+};
+
+lazy_static! {
+    static ref JWT_KEYRING: RwLock<Arc<Keyring>> = RwLock::new(Arc::new(load_keyring()));
+}
+
+// The keys auth tokens are signed and verified with. There is always exactly one signing key;
+// verification keys are the public halves of previous signing keys, kept so that tokens they
+// signed stay valid until they expire. Rotating is a matter of moving the old signing key's JWK
+// (as published at `/.well-known/jwks.json`) into `verification_keys` with a `retire_at` past the
+// longest token lifetime, and putting the new key in `signing_key`.
+#[derive(Clone)]
+pub struct Keyring {
+    signing_key: SigningKey,
+    verification_keys: Vec<VerificationKey>,
+}
+
+#[derive(Clone)]
+pub struct VerificationKey {
+    kid: String,
+    decoding_key: DecodingKey,
+    jwk: Jwk,
+    retire_at: Option<DateTime<Utc>>,
+}
+
+// Shape of the file pointed to by `JWT_KEYRING_PATH`.
+#[derive(Deserialize)]
+pub struct KeyringConfig {
+    pub signing_key: Secret<String>,
+    #[serde(default)]
+    pub verification_keys: Vec<VerificationKeyConfig>,
+}
+
+#[derive(Deserialize)]
+pub struct VerificationKeyConfig {
+    pub jwk: Jwk,
+    // RFC 3339 timestamp after which the key is no longer accepted.
+    pub retire_at: Option<String>,
+}
+
+impl Keyring {
+    pub fn new(signing_key: SigningKey) -> Self {
+        Self {
+            signing_key,
+            verification_keys: Vec::new(),
+        }
+    }
+
+    pub fn from_config(config: &KeyringConfig) -> Result<Self> {
+        let signing_key = SigningKey::from_pkcs8_pem(&config.signing_key)?;
+        let verification_keys = config
+            .verification_keys
+            .iter()
+            .map(VerificationKey::from_config)
+            .collect::<Result<Vec<_>>>()?;
+
+        if verification_keys.iter().any(|key| key.kid == signing_key.kid()) {
+            return Err(eyre!("the signing key is also listed as a verification key"));
+        }
+
+        Ok(Self {
+            signing_key,
+            verification_keys,
+        })
+    }
+
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err(format!("failed to read keyring file {}", path))?;
+        let config: KeyringConfig =
+            serde_json::from_str(&contents).wrap_err("failed to parse keyring file")?;
+        Self::from_config(&config)
+    }
+
+    pub fn signing_key(&self) -> &SigningKey {
+        &self.signing_key
+    }
+
+    // Finds the key a token claims to be signed with, ignoring keys that have been retired.
+    pub fn decoding_key(&self, kid: &str) -> Option<&DecodingKey> {
+        if kid == self.signing_key.kid() {
+            return Some(self.signing_key.decoding_key());
+        }
+
+        let now = Utc::now();
+        self.verification_keys
+            .iter()
+            .find(|key| key.kid == kid && !key.is_retired(now))
+            .map(|key| &key.decoding_key)
+    }
+
+    pub fn jwks(&self) -> JwkSet {
+        let now = Utc::now();
+        let verification_jwks = self
+            .verification_keys
+            .iter()
+            .filter(|key| !key.is_retired(now))
+            .map(|key| key.jwk.clone());
+
+        JwkSet {
+            keys: std::iter::once(self.signing_key.jwk().clone())
+                .chain(verification_jwks)
+                .collect(),
+        }
+    }
+
+    pub fn remove_retired_keys(&mut self, now: DateTime<Utc>) {
+        self.verification_keys.retain(|key| !key.is_retired(now));
+    }
+}
+
+impl VerificationKey {
+    fn from_config(config: &VerificationKeyConfig) -> Result<Self> {
+        let AlgorithmParameters::OctetKeyPair(ref params) = config.jwk.algorithm else {
+            return Err(eyre!("verification keys must be Ed25519 JWKs"));
+        };
+
+        let kid = config
+            .jwk
+            .common
+            .key_id
+            .clone()
+            .unwrap_or_else(|| jwk_thumbprint(&params.x));
+        let mut jwk = config.jwk.clone();
+        jwk.common.key_id = Some(kid.clone());
+
+        let retire_at = config
+            .retire_at
+            .as_deref()
+            .map(|retire_at| {
+                DateTime::parse_from_rfc3339(retire_at)
+                    .map(|retire_at| retire_at.with_timezone(&Utc))
+                    .wrap_err(format!("invalid retire_at for key {}", kid))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            decoding_key: DecodingKey::from_jwk(&jwk)
+                .wrap_err(format!("invalid verification key {}", kid))?,
+            kid,
+            jwk,
+            retire_at,
+        })
+    }
+
+    fn is_retired(&self, now: DateTime<Utc>) -> bool {
+        self.retire_at.is_some_and(|retire_at| retire_at <= now)
+    }
+}
+
+pub fn current_keyring() -> Arc<Keyring> {
+    JWT_KEYRING.read().expect("keyring lock poisoned").clone()
+}
+
+fn replace_keyring(keyring: Keyring) {
+    *JWT_KEYRING.write().expect("keyring lock poisoned") = Arc::new(keyring);
+}
+
+// Reloads the keyring file, if one is configured, and drops retired keys every `interval`, so
+// keys can be rotated without restarting the service. A file that fails to load is logged and
+// the keys already in use are kept.
+pub fn spawn_keyring_refresh(interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            refresh_keyring();
+        }
+    })
+}
+
+#[tracing::instrument(name = "Refreshing JWT keyring", skip_all)]
+fn refresh_keyring() {
+    let mut keyring = match JWT_KEYRING_PATH.as_deref() {
+        Some(path) => match Keyring::from_file(path) {
+            Ok(keyring) => keyring,
+            Err(e) => {
+                tracing::error!("Failed to reload JWT keyring: {:?}", e);
+                (*current_keyring()).clone()
+            }
+        },
+        None => (*current_keyring()).clone(),
+    };
+    keyring.remove_retired_keys(Utc::now());
+    replace_keyring(keyring);
+}
+
+fn load_keyring() -> Keyring {
+    if let Some(path) = JWT_KEYRING_PATH.as_deref() {
+        return Keyring::from_file(path).expect("JWT_KEYRING_PATH must point to a valid keyring file.");
+    }
+
+    match JWT_SIGNING_KEY_PEM.as_ref() {
+        Some(pem) => Keyring::new(
+            SigningKey::from_pkcs8_pem(pem)
+                .expect("JWT_SIGNING_KEY must be a PKCS#8 Ed25519 private key."),
+        ),
+        // Tokens signed with a throwaway key stop validating on restart, aren't accepted by other
+        // instances and can't be rotated out, which is fine for development and tests only.
+        None if *JWT_ALLOW_EPHEMERAL_KEY => {
+            tracing::warn!("No JWT signing key configured, generating a temporary one");
+            Keyring::new(SigningKey::generate().expect("Failed to generate a JWT signing key."))
+        }
+        None => panic!("Either JWT_KEYRING_PATH or JWT_SIGNING_KEY must be set."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+
+    fn verification_key_config(key: &SigningKey, retire_at: Option<DateTime<Utc>>) -> VerificationKeyConfig {
+        VerificationKeyConfig {
+            jwk: key.jwk().clone(),
+            retire_at: retire_at.map(|retire_at| retire_at.to_rfc3339()),
+        }
+    }
+
+    fn keyring_with(verification_keys: Vec<VerificationKeyConfig>) -> (Keyring, SigningKey) {
+        let signing_key = SigningKey::generate().unwrap();
+        let mut keyring = Keyring::new(signing_key.clone());
+        keyring.verification_keys = verification_keys
+            .iter()
+            .map(|config| VerificationKey::from_config(config).unwrap())
+            .collect();
+        (keyring, signing_key)
+    }
+
+    #[test]
+    fn test_decoding_key_by_kid() {
+        let old_key = SigningKey::generate().unwrap();
+        let (keyring, signing_key) = keyring_with(vec![verification_key_config(&old_key, None)]);
+
+        assert!(keyring.decoding_key(signing_key.kid()).is_some());
+        assert!(keyring.decoding_key(old_key.kid()).is_some());
+        assert!(keyring.decoding_key("unknown").is_none());
+    }
+
+    #[test]
+    fn test_retired_keys_are_not_used() {
+        let retired_key = SigningKey::generate().unwrap();
+        let retiring_key = SigningKey::generate().unwrap();
+        let (mut keyring, _) = keyring_with(vec![
+            verification_key_config(&retired_key, Some(Utc::now() - ChronoDuration::seconds(1))),
+            verification_key_config(&retiring_key, Some(Utc::now() + ChronoDuration::hours(1))),
+        ]);
+
+        assert!(keyring.decoding_key(retired_key.kid()).is_none());
+        assert!(keyring.decoding_key(retiring_key.kid()).is_some());
+        assert_eq!(keyring.jwks().keys.len(), 2);
+
+        keyring.remove_retired_keys(Utc::now() + ChronoDuration::hours(2));
+        assert!(keyring.decoding_key(retiring_key.kid()).is_none());
+        assert_eq!(keyring.jwks().keys.len(), 1);
+    }
+
+    #[test]
+    fn test_verification_key_without_kid_uses_thumbprint() {
+        let old_key = SigningKey::generate().unwrap();
+        let mut config = verification_key_config(&old_key, None);
+        config.jwk.common.key_id = None;
+
+        let key = VerificationKey::from_config(&config).unwrap();
+        assert_eq!(key.kid, old_key.kid());
+    }
+
+    #[test]
+    fn test_from_config_rejects_invalid_retire_at() {
+        let old_key = SigningKey::generate().unwrap();
+        let mut config = verification_key_config(&old_key, None);
+        config.retire_at = Some("tomorrow".to_owned());
+
+        assert!(VerificationKey::from_config(&config).is_err());
+    }
+}
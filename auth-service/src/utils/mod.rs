@@ -1,7 +1,27 @@
 pub mod constants;
 pub mod auth;
+pub mod signing_key;
+pub mod keyring;
+pub mod totp;
+pub mod rate_limit;
+pub mod oauth;
+pub mod oidc;
+pub mod session;
+pub mod account_purge;
+pub mod rbac;
+pub mod audit;
 pub mod tracing; // Nouveau module
 
 pub use constants::*;
 pub use auth::*;
+pub use signing_key::*;
+pub use keyring::*;
+pub use totp::*;
+pub use rate_limit::*;
+pub use oauth::*;
+pub use oidc::*;
+pub use session::*;
+pub use account_purge::*;
+pub use rbac::*;
+pub use audit::*;
 pub use tracing::*; // Export des fonctions tracing
\ No newline at end of file
@@ -0,0 +1,364 @@
+use axum::http::{header, HeaderMap};
+use chrono::Utc;
+use color_eyre::eyre::{eyre, Context, Result};
+use data_encoding::BASE64;
+use jsonwebtoken::{decode, decode_header, Validation};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use crate::{
+    app_state::{BannedTokenStoreType, OAuthClientStoreType, UserStoreType},
+    domain::{data_stores::OAuthClientStoreError, error::OAuthAPIError, oauth::OAuthClient, Authentication, Email},
+};
+use super::{
+    auth::{check_token_not_revoked, create_token_of_type, TOKEN_TTL_SECONDS},
+    keyring::current_keyring,
+    signing_key::SIGNING_ALGORITHM,
+};
+
+// Authenticates the client calling a back-channel OAuth endpoint. Confidential clients may send
+// their credentials with HTTP Basic authentication or in the request body (RFC 6749 section
+// 2.3.1); public clients only identify themselves with `client_id`.
+#[tracing::instrument(name = "Authenticating OAuth client", skip_all)]
+pub async fn authenticate_client(
+    oauth_client_store: &OAuthClientStoreType,
+    headers: &HeaderMap,
+    client_id: Option<String>,
+    client_secret: Option<Secret<String>>,
+) -> Result<OAuthClient, OAuthAPIError> {
+    let basic_credentials = parse_basic_credentials(headers)?;
+    if basic_credentials.is_some() && client_secret.is_some() {
+        return Err(OAuthAPIError::InvalidRequest("only one client authentication method may be used"));
+    }
+
+    let (client_id, client_secret) = match basic_credentials {
+        Some((basic_client_id, basic_client_secret)) => {
+            if client_id.is_some_and(|client_id| client_id != basic_client_id) {
+                return Err(OAuthAPIError::InvalidClient);
+            }
+            (basic_client_id, Some(basic_client_secret))
+        }
+        None => (client_id.ok_or(OAuthAPIError::InvalidClient)?, client_secret),
+    };
+
+    let oauth_client_store = oauth_client_store.read().await;
+    let client = match oauth_client_store.get_client(&client_id).await {
+        Ok(client) => client,
+        Err(OAuthClientStoreError::ClientNotFound) => return Err(OAuthAPIError::InvalidClient),
+        Err(e) => return Err(OAuthAPIError::UnexpectedError(e.into())),
+    };
+
+    if client.is_confidential() {
+        let client_secret = client_secret.ok_or(OAuthAPIError::InvalidClient)?;
+        match oauth_client_store.validate_client_secret(&client_id, &client_secret).await {
+            Ok(()) => {}
+            Err(OAuthClientStoreError::InvalidCredentials) => return Err(OAuthAPIError::InvalidClient),
+            Err(e) => return Err(OAuthAPIError::UnexpectedError(e.into())),
+        }
+    }
+
+    Ok(client)
+}
+
+// The `typ` header of the access tokens issued to OAuth clients, service tokens and user access
+// tokens alike (RFC 9068 section 2.1). It keeps them from being accepted where an auth token is
+// expected, and auth tokens from being accepted in their place.
+pub const SERVICE_TOKEN_TYPE: &str = "at+jwt";
+// Service tokens can't be revoked, so they are kept short-lived instead.
+pub const SERVICE_TOKEN_TTL_SECONDS: i64 = 300;
+
+// Claims of the access token a client gets for itself with the client-credentials grant. The
+// client is the subject, and the scopes are space-separated as in the `scope` parameter.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServiceClaims {
+    pub sub: String,
+    pub client_id: String,
+    pub scope: String,
+    pub exp: usize,
+    pub iat: usize,
+    pub jti: String,
+}
+
+impl ServiceClaims {
+    pub fn scopes(&self) -> Vec<String> {
+        split_scope(&self.scope)
+    }
+}
+
+// Claims of the access token a client gets on a user's behalf with the authorization-code grant
+// (RFC 9068 section 2.2). The user is the subject and the client the audience; a resource
+// server should only accept it if it is the audience or trusts it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UserAccessClaims {
+    pub sub: String,
+    pub aud: String,
+    pub client_id: String,
+    #[serde(default)]
+    pub scope: String,
+    pub exp: usize,
+    pub iat: usize,
+    pub auth_time: i64,
+    pub jti: String,
+    // The session the user authorized the client in; ending it revokes the token as well.
+    pub sid: String,
+    pub token_version: i64,
+}
+
+impl UserAccessClaims {
+    pub fn scopes(&self) -> Vec<String> {
+        split_scope(&self.scope)
+    }
+}
+
+fn split_scope(scope: &str) -> Vec<String> {
+    scope
+        .split(' ')
+        .filter(|scope| !scope.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+#[tracing::instrument(name = "Generating service token", skip_all)]
+pub fn generate_service_token(client: &OAuthClient, scopes: &[String]) -> Result<String> {
+    let now = Utc::now().timestamp();
+    let iat: usize = now
+        .try_into()
+        .wrap_err(format!("failed to cast iat time to usize. iat time: {}", now))?;
+    let exp: usize = (now + SERVICE_TOKEN_TTL_SECONDS)
+        .try_into()
+        .wrap_err(format!("failed to cast exp time to usize. iat time: {}", now))?;
+
+    let claims = ServiceClaims {
+        sub: client.client_id.clone(),
+        client_id: client.client_id.clone(),
+        scope: scopes.join(" "),
+        exp,
+        iat,
+        jti: uuid::Uuid::new_v4().to_string(),
+    };
+
+    create_token_of_type(&claims, SERVICE_TOKEN_TYPE)
+}
+
+#[tracing::instrument(name = "Generating user access token", skip_all)]
+pub fn generate_user_access_token(
+    client_id: &str,
+    email: &Email,
+    authentication: &Authentication,
+    scope: Option<&str>,
+) -> Result<String> {
+    let now = Utc::now().timestamp();
+    let iat: usize = now
+        .try_into()
+        .wrap_err(format!("failed to cast iat time to usize. iat time: {}", now))?;
+    let exp: usize = (now + TOKEN_TTL_SECONDS)
+        .try_into()
+        .wrap_err(format!("failed to cast exp time to usize. iat time: {}", now))?;
+
+    let claims = UserAccessClaims {
+        sub: email.as_ref().expose_secret().clone(),
+        aud: client_id.to_owned(),
+        client_id: client_id.to_owned(),
+        scope: scope.unwrap_or_default().to_owned(),
+        exp,
+        iat,
+        auth_time: authentication.auth_time,
+        jti: uuid::Uuid::new_v4().to_string(),
+        sid: authentication.session_id.clone(),
+        token_version: authentication.token_version,
+    };
+
+    create_token_of_type(&claims, SERVICE_TOKEN_TYPE)
+}
+
+// True for both kinds of OAuth access token, which `validate_service_token` and
+// `validate_user_access_token` then tell apart.
+pub fn is_service_token(token: &str) -> bool {
+    decode_header(token).is_ok_and(|header| header.typ.as_deref() == Some(SERVICE_TOKEN_TYPE))
+}
+
+// Also rejects tokens of clients that no longer exist.
+#[tracing::instrument(name = "Validating service token", skip_all)]
+pub async fn validate_service_token(
+    token: &str,
+    oauth_client_store: &OAuthClientStoreType,
+) -> Result<ServiceClaims> {
+    let header = decode_header(token).wrap_err("failed to decode token header")?;
+    if header.typ.as_deref() != Some(SERVICE_TOKEN_TYPE) {
+        return Err(eyre!("token is not a service token"));
+    }
+    let kid = header.kid.ok_or(eyre!("token has no kid"))?;
+    let keyring = current_keyring();
+    let decoding_key = keyring
+        .decoding_key(&kid)
+        .ok_or(eyre!("token was not signed with a known key"))?;
+
+    let claims = decode::<ServiceClaims>(token, decoding_key, &Validation::new(SIGNING_ALGORITHM))
+        .map(|data| data.claims)
+        .wrap_err("failed to decode service token")?;
+    if claims.sub != claims.client_id {
+        return Err(eyre!("token was issued on behalf of a user"));
+    }
+
+    oauth_client_store.read().await.get_client(&claims.client_id).await?;
+
+    Ok(claims)
+}
+
+// Goes through the same revocation checks as the user's auth tokens, so logging out or being
+// locked out ends the client's access too. Any audience is accepted; checking it is up to the
+// caller.
+#[tracing::instrument(name = "Validating user access token", skip_all)]
+pub async fn validate_user_access_token(
+    token: &str,
+    banned_token_store: &BannedTokenStoreType,
+    user_store: &UserStoreType,
+) -> Result<UserAccessClaims> {
+    let header = decode_header(token).wrap_err("failed to decode token header")?;
+    if header.typ.as_deref() != Some(SERVICE_TOKEN_TYPE) {
+        return Err(eyre!("token is not an OAuth access token"));
+    }
+    let kid = header.kid.ok_or(eyre!("token has no kid"))?;
+    let keyring = current_keyring();
+    let decoding_key = keyring
+        .decoding_key(&kid)
+        .ok_or(eyre!("token was not signed with a known key"))?;
+
+    let claims = decode::<UserAccessClaims>(token, decoding_key, &Validation::new(SIGNING_ALGORITHM))
+        .map(|data| data.claims)
+        .wrap_err("failed to decode user access token")?;
+
+    check_token_not_revoked(&claims.sub, claims.iat, &claims.sid, claims.token_version, banned_token_store, user_store).await?;
+
+    Ok(claims)
+}
+
+pub fn parse_bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+fn parse_basic_credentials(headers: &HeaderMap) -> Result<Option<(String, Secret<String>)>, OAuthAPIError> {
+    let Some(authorization) = headers.get(header::AUTHORIZATION) else {
+        return Ok(None);
+    };
+
+    let credentials = authorization
+        .to_str()
+        .ok()
+        .and_then(|value| value.strip_prefix("Basic "))
+        .and_then(|encoded| BASE64.decode(encoded.trim().as_bytes()).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .ok_or(OAuthAPIError::InvalidClient)?;
+
+    let (client_id, client_secret) = credentials
+        .split_once(':')
+        .ok_or(OAuthAPIError::InvalidClient)?;
+
+    Ok(Some((client_id.to_owned(), Secret::new(client_secret.to_owned()))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use crate::domain::{
+        data_stores::{BannedTokenStore, OAuthClientStore, UserStore},
+        Password, TwoFAMethod, User,
+    };
+    use crate::services::{
+        hashmap_oauth_client_store::HashmapOAuthClientStore, hashmap_user_store::HashmapUserStore,
+        hashset_banned_token_store::HashsetBannedTokenStore,
+    };
+    use crate::utils::auth::{generate_auth_token, validate_token};
+
+    fn service_client() -> OAuthClient {
+        OAuthClient::new(
+            "service".to_owned(),
+            "Service".to_owned(),
+            Vec::new(),
+            Some(Secret::new("secret".to_owned())),
+            vec!["users:read".to_owned()],
+        )
+    }
+
+    async fn create_oauth_client_store(clients: Vec<OAuthClient>) -> OAuthClientStoreType {
+        let mut store = HashmapOAuthClientStore::new();
+        for client in clients {
+            store.add_client(client).await.unwrap();
+        }
+        Arc::new(RwLock::new(Box::new(store) as Box<dyn OAuthClientStore + Send + Sync>))
+    }
+
+    #[tokio::test]
+    async fn test_service_token_round_trip() {
+        let client = service_client();
+        let token = generate_service_token(&client, &client.scopes).unwrap();
+        assert!(is_service_token(&token));
+
+        let claims = validate_service_token(&token, &create_oauth_client_store(vec![client]).await).await.unwrap();
+        assert_eq!(claims.sub, "service");
+        assert_eq!(claims.client_id, "service");
+        assert_eq!(claims.scopes(), vec!["users:read".to_owned()]);
+    }
+
+    #[tokio::test]
+    async fn test_validate_service_token_of_unknown_client() {
+        let token = generate_service_token(&service_client(), &[]).unwrap();
+        let result = validate_service_token(&token, &create_oauth_client_store(Vec::new()).await).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_user_access_token_round_trip() {
+        let email = Email::parse(Secret::new("test@example.com".to_owned())).unwrap();
+        let mut user_store = HashmapUserStore::default();
+        user_store
+            .add_user(User::new(
+                email.clone(),
+                Password::parse(Secret::new("password123".to_owned())).unwrap(),
+                TwoFAMethod::None,
+            ))
+            .await
+            .unwrap();
+        let user_store: UserStoreType = Arc::new(RwLock::new(Box::new(user_store) as Box<dyn UserStore + Send + Sync>));
+        let banned_token_store: BannedTokenStoreType =
+            Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::default()) as Box<dyn BannedTokenStore + Send + Sync>));
+
+        let authentication = Authentication::now(Vec::new());
+        let token = generate_user_access_token("client", &email, &authentication, Some("openid email")).unwrap();
+        let claims = validate_user_access_token(&token, &banned_token_store, &user_store).await.unwrap();
+        assert_eq!(claims.sub, "test@example.com");
+        assert_eq!(claims.aud, "client");
+        assert_eq!(claims.scopes(), vec!["openid".to_owned(), "email".to_owned()]);
+
+        // Neither a service token nor an auth token.
+        let oauth_client_store = create_oauth_client_store(vec![OAuthClient::new(
+            "client".to_owned(),
+            "Client".to_owned(),
+            Vec::new(),
+            None,
+            Vec::new(),
+        )])
+        .await;
+        assert!(validate_service_token(&token, &oauth_client_store).await.is_err());
+        assert!(validate_token(&token, banned_token_store.clone(), user_store.clone()).await.is_err());
+
+        banned_token_store.write().await.revoke_session(&authentication.session_id).await.unwrap();
+        assert!(validate_user_access_token(&token, &banned_token_store, &user_store).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_auth_token_is_not_a_service_token() {
+        let email = Email::parse(Secret::new("service@example.com".to_owned())).unwrap();
+        let token = generate_auth_token(&email, &Authentication::default()).unwrap();
+        assert!(!is_service_token(&token));
+
+        let result = validate_service_token(&token, &create_oauth_client_store(vec![service_client()]).await).await;
+        assert!(result.is_err());
+    }
+}
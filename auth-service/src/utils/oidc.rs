@@ -0,0 +1,66 @@
+use chrono::Utc;
+use color_eyre::eyre::{Context, Result};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use crate::domain::{Authentication, AuthenticationMethod, User};
+use super::{
+    auth::{create_token, TOKEN_TTL_SECONDS},
+    constants::AUTH_SERVICE_URL,
+};
+
+// Claims of an OpenID Connect ID token (OpenID Connect Core 1.0 section 2). Like auth tokens,
+// ID tokens are signed with the keyring's signing key and verifiable with the published JWKS;
+// their `aud` is what keeps them from being accepted as auth tokens. The subject is the user's
+// ID, which stays the same when their email address changes.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub aud: String,
+    pub exp: usize,
+    pub iat: usize,
+    pub auth_time: i64,
+    pub amr: Vec<AuthenticationMethod>,
+    pub email: String,
+    pub email_verified: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+
+// The issuer identifier is the service's public URL, which the discovery document is served under.
+pub fn issuer() -> String {
+    AUTH_SERVICE_URL.trim_end_matches('/').to_owned()
+}
+
+#[tracing::instrument(name = "Generating ID token", skip_all)]
+pub fn generate_id_token(
+    client_id: &str,
+    user_id: &str,
+    user: &User,
+    authentication: &Authentication,
+    nonce: Option<String>,
+) -> Result<String> {
+    let now = Utc::now().timestamp();
+    let iat: usize = now
+        .try_into()
+        .wrap_err(format!("failed to cast iat time to usize. iat time: {}", now))?;
+    let exp: usize = (now + TOKEN_TTL_SECONDS)
+        .try_into()
+        .wrap_err(format!("failed to cast exp time to usize. iat time: {}", now))?;
+
+    let email = user.email.as_ref().expose_secret().to_owned();
+    let claims = IdTokenClaims {
+        iss: issuer(),
+        sub: user_id.to_owned(),
+        aud: client_id.to_owned(),
+        exp,
+        iat,
+        auth_time: authentication.auth_time,
+        amr: authentication.methods.clone(),
+        email,
+        email_verified: user.email_verified,
+        nonce,
+    };
+
+    create_token(&claims)
+}
@@ -0,0 +1,67 @@
+use std::net::IpAddr;
+use crate::{
+    app_state::RateLimitStoreType,
+    domain::{
+        data_stores::{RateLimit, RateLimitDecision},
+        AuthAPIError,
+    },
+};
+use super::constants::rate_limits;
+
+// Limits for a single route. Requests are counted both per client IP and per target account,
+// so neither spreading attempts over many accounts nor over many IPs gets around them.
+#[derive(Clone, Copy, Debug)]
+pub struct RouteRateLimits {
+    pub per_ip: RateLimit,
+    pub per_email: RateLimit,
+}
+
+#[derive(Clone, Debug)]
+pub struct RateLimitConfig {
+    pub login: RouteRateLimits,
+    pub forgot_password: RouteRateLimits,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            login: RouteRateLimits {
+                per_ip: rate_limits::LOGIN_PER_IP,
+                per_email: rate_limits::LOGIN_PER_EMAIL,
+            },
+            forgot_password: RouteRateLimits {
+                per_ip: rate_limits::FORGOT_PASSWORD_PER_IP,
+                per_email: rate_limits::FORGOT_PASSWORD_PER_EMAIL,
+            },
+        }
+    }
+}
+
+#[tracing::instrument(name = "Enforcing rate limits", skip_all, fields(route = route))]
+pub async fn enforce_rate_limits(
+    rate_limit_store: &RateLimitStoreType,
+    route: &str,
+    limits: &RouteRateLimits,
+    ip: IpAddr,
+    email: &str,
+) -> Result<(), AuthAPIError> {
+    let checks = [
+        (format!("{}:ip:{}", route, ip), &limits.per_ip),
+        (format!("{}:email:{}", route, email.trim().to_lowercase()), &limits.per_email),
+    ];
+
+    let mut store = rate_limit_store.write().await;
+    for (key, limit) in checks {
+        let decision = store
+            .check_and_record(&key, limit)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+        if let RateLimitDecision::Limited { retry_after_seconds } = decision {
+            tracing::warn!("Rate limit exceeded for {}", route);
+            return Err(AuthAPIError::TooManyRequests(retry_after_seconds));
+        }
+    }
+
+    Ok(())
+}
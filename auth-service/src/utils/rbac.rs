@@ -0,0 +1,42 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::extract::CookieJar;
+use crate::{app_state::AppState, domain::AuthAPIError};
+use super::{
+    auth::validate_token,
+    constants::{ADMIN_ROLE, JWT_COOKIE_NAME},
+};
+
+// Lets the request through only if its JWT is valid and carries `role`. The token's claims are
+// added to the request's extensions, so handlers behind it can take them as `Extension<Claims>`.
+pub async fn require_role(
+    role: &str,
+    state: &AppState,
+    jar: &CookieJar,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AuthAPIError> {
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let claims = validate_token(cookie.value(), state.banned_token_store.clone(), state.user_store.clone()).await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+
+    if !claims.roles.iter().any(|claimed| claimed == role) {
+        return Err(AuthAPIError::Forbidden);
+    }
+
+    request.extensions_mut().insert(claims);
+    Ok(next.run(request).await)
+}
+
+// Middleware for the admin routes, see `require_role`.
+pub async fn require_admin(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    request: Request,
+    next: Next,
+) -> Result<Response, AuthAPIError> {
+    require_role(ADMIN_ROLE, &state, &jar, request, next).await
+}
@@ -0,0 +1,165 @@
+use std::net::IpAddr;
+use axum::http::{header::USER_AGENT, HeaderMap};
+use chrono::Utc;
+use secrecy::ExposeSecret;
+use crate::{
+    app_state::AppState,
+    domain::{
+        data_stores::{Session, SessionStoreError},
+        AuthAPIError, Authentication, AuthenticationMethod, Email,
+    },
+};
+
+// Records the session a login starts and returns the authentication its tokens are issued for.
+// Locked users are turned away here, whichever way they logged in. Logging in restores an
+// account that is pending deletion. If the user is now over the configured limit, their oldest
+// sessions are ended to make room.
+#[tracing::instrument(name = "Starting session", skip_all)]
+pub async fn start_session(
+    state: &AppState,
+    email: &Email,
+    methods: Vec<AuthenticationMethod>,
+    ip: IpAddr,
+    headers: &HeaderMap,
+) -> Result<Authentication, AuthAPIError> {
+    let mut user_store = state.user_store.write().await;
+    if user_store
+        .is_user_locked(email.as_ref().expose_secret())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?
+    {
+        return Err(AuthAPIError::AccountLocked);
+    }
+
+    if user_store
+        .restore_user(email.as_ref().expose_secret())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?
+    {
+        tracing::info!("Account deletion cancelled by login");
+    }
+
+    let mut authentication = Authentication::now(methods);
+    authentication.token_version = user_store
+        .get_token_version(email.as_ref().expose_secret())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    drop(user_store);
+    load_access(state, email, &mut authentication).await?;
+
+    let session = Session {
+        id: authentication.session_id.clone(),
+        created_at: Utc::now().timestamp_millis(),
+        ip: Some(ip.to_string()),
+        user_agent: headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned),
+    };
+
+    let mut session_store = state.session_store.write().await;
+    session_store
+        .add_session(email, session)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let Some(max_sessions) = state.max_sessions_per_user else {
+        return Ok(authentication);
+    };
+    let sessions = session_store
+        .get_sessions(email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    drop(session_store);
+
+    let excess = sessions.len().saturating_sub(max_sessions);
+    for session in sessions
+        .iter()
+        .filter(|session| session.id != authentication.session_id)
+        .take(excess)
+    {
+        end_session(state, email, &session.id).await?;
+    }
+
+    Ok(authentication)
+}
+
+// Ends a session: its refresh tokens stop working and its access tokens are banned.
+#[tracing::instrument(name = "Ending session", skip_all)]
+pub async fn end_session(state: &AppState, email: &Email, session_id: &str) -> Result<(), AuthAPIError> {
+    state
+        .session_store
+        .write()
+        .await
+        .remove_session(email, session_id)
+        .await
+        .map_err(|e| match e {
+            SessionStoreError::SessionNotFound => AuthAPIError::SessionNotFound,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    state
+        .banned_token_store
+        .write()
+        .await
+        .revoke_session(session_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
+}
+
+// Sets the roles and permissions tokens issued for `authentication` carry to the user's current
+// ones. Done at login and again at every refresh, so changes reach the user's sessions within an
+// access token's lifetime.
+pub async fn load_access(
+    state: &AppState,
+    email: &Email,
+    authentication: &mut Authentication,
+) -> Result<(), AuthAPIError> {
+    let user_store = state.user_store.read().await;
+    authentication.roles = user_store
+        .get_roles(email.as_ref().expose_secret())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    authentication.permissions = user_store
+        .get_permissions(email.as_ref().expose_secret())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    Ok(())
+}
+
+// Logs the user out everywhere: outstanding access tokens carry the old token version and are
+// rejected from now on, and their refresh tokens, sessions and API keys are dropped.
+#[tracing::instrument(name = "Revoking all tokens", skip_all)]
+pub async fn revoke_all_tokens(state: &AppState, email: &Email) -> Result<(), AuthAPIError> {
+    state
+        .user_store
+        .write()
+        .await
+        .increment_token_version(email.as_ref().expose_secret())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_user_tokens(email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .session_store
+        .write()
+        .await
+        .remove_all_sessions(email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .api_key_store
+        .write()
+        .await
+        .revoke_user_keys(email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
+}
@@ -0,0 +1,158 @@
+use color_eyre::eyre::{eyre, Context, Result};
+use data_encoding::BASE64URL_NOPAD;
+use jsonwebtoken::{
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, OctetKeyPairParameters,
+        OctetKeyPairType, PublicKeyUse,
+    },
+    Algorithm, DecodingKey, EncodingKey,
+};
+use ring::{
+    digest::{digest, SHA256},
+    rand::SystemRandom,
+    signature::{Ed25519KeyPair, KeyPair},
+};
+use secrecy::{ExposeSecret, Secret};
+
+pub const SIGNING_ALGORITHM: Algorithm = Algorithm::EdDSA;
+
+// An Ed25519 key used to sign auth tokens. Only the public half is ever published, through the
+// JWKS endpoint, so other services can verify tokens without being able to mint them.
+#[derive(Clone)]
+pub struct SigningKey {
+    kid: String,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    jwk: Jwk,
+}
+
+impl SigningKey {
+    // Expects a PKCS#8 Ed25519 private key, as produced by `openssl genpkey -algorithm ed25519`.
+    pub fn from_pkcs8_pem(pem: &Secret<String>) -> Result<Self> {
+        let pem = pem::parse(pem.expose_secret()).wrap_err("failed to parse signing key PEM")?;
+        if pem.tag != "PRIVATE KEY" {
+            return Err(eyre!("expected a PKCS#8 private key, found {}", pem.tag));
+        }
+        Self::from_pkcs8_der(&pem.contents)
+    }
+
+    pub fn from_pkcs8_der(der: &[u8]) -> Result<Self> {
+        let key_pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(der)
+            .map_err(|e| eyre!("invalid Ed25519 signing key: {}", e))?;
+        let x = BASE64URL_NOPAD.encode(key_pair.public_key().as_ref());
+        let kid = jwk_thumbprint(&x);
+
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                algorithm: Some(SIGNING_ALGORITHM),
+                key_id: Some(kid.clone()),
+                ..Default::default()
+            },
+            algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: x.clone(),
+            }),
+        };
+
+        Ok(Self {
+            kid,
+            encoding_key: EncodingKey::from_ed_der(der),
+            decoding_key: DecodingKey::from_ed_components(&x)
+                .wrap_err("failed to create decoding key")?,
+            jwk,
+        })
+    }
+
+    pub fn generate() -> Result<Self> {
+        let document = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|_| eyre!("failed to generate Ed25519 signing key"))?;
+        Self::from_pkcs8_der(document.as_ref())
+    }
+
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    pub fn encoding_key(&self) -> &EncodingKey {
+        &self.encoding_key
+    }
+
+    pub fn decoding_key(&self) -> &DecodingKey {
+        &self.decoding_key
+    }
+
+    pub fn jwk(&self) -> &Jwk {
+        &self.jwk
+    }
+}
+
+// The key id is the RFC 7638 thumbprint of the public key, so it is stable across restarts
+// and the same on every instance sharing the key.
+pub(crate) fn jwk_thumbprint(x: &str) -> String {
+    let canonical = format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, x);
+    BASE64URL_NOPAD.encode(digest(&SHA256, canonical.as_bytes()).as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Key pair from RFC 8037, appendix A.
+    const RFC_8037_PRIVATE_KEY: &str = "nWGxne_9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A";
+    const RFC_8037_PUBLIC_KEY: &str = "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo";
+    const RFC_8037_THUMBPRINT: &str = "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k";
+
+    // PKCS#8 wrapping of a raw Ed25519 private key.
+    fn pkcs8_der(private_key: &[u8]) -> Vec<u8> {
+        let mut der = vec![
+            0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22,
+            0x04, 0x20,
+        ];
+        der.extend_from_slice(private_key);
+        der
+    }
+
+    #[test]
+    fn test_from_pkcs8_der_matches_rfc_8037() {
+        let private_key = BASE64URL_NOPAD.decode(RFC_8037_PRIVATE_KEY.as_bytes()).unwrap();
+        let key = SigningKey::from_pkcs8_der(&pkcs8_der(&private_key)).unwrap();
+
+        assert_eq!(key.kid(), RFC_8037_THUMBPRINT);
+        match &key.jwk().algorithm {
+            AlgorithmParameters::OctetKeyPair(params) => assert_eq!(params.x, RFC_8037_PUBLIC_KEY),
+            _ => panic!("expected an octet key pair"),
+        }
+        assert_eq!(key.jwk().common.key_id.as_deref(), Some(RFC_8037_THUMBPRINT));
+    }
+
+    #[test]
+    fn test_from_pkcs8_pem() {
+        let private_key = BASE64URL_NOPAD.decode(RFC_8037_PRIVATE_KEY.as_bytes()).unwrap();
+        let pem = pem::encode(&pem::Pem {
+            tag: "PRIVATE KEY".to_owned(),
+            contents: pkcs8_der(&private_key),
+        });
+
+        let key = SigningKey::from_pkcs8_pem(&Secret::new(pem)).unwrap();
+        assert_eq!(key.kid(), RFC_8037_THUMBPRINT);
+    }
+
+    #[test]
+    fn test_from_pkcs8_pem_rejects_other_pem_types() {
+        let pem = pem::encode(&pem::Pem {
+            tag: "PUBLIC KEY".to_owned(),
+            contents: vec![0; 44],
+        });
+
+        assert!(SigningKey::from_pkcs8_pem(&Secret::new(pem)).is_err());
+    }
+
+    #[test]
+    fn test_generated_keys_are_distinct() {
+        let first = SigningKey::generate().unwrap();
+        let second = SigningKey::generate().unwrap();
+        assert_ne!(first.kid(), second.kid());
+    }
+}
@@ -0,0 +1,128 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use secrecy::ExposeSecret;
+use sha1::Sha1;
+use crate::domain::{Email, TotpSecret};
+use super::constants::TOTP_ISSUER;
+
+// RFC 6238 defaults, which is what authenticator apps assume when the URI doesn't say otherwise.
+const TOTP_DIGITS: u32 = 6;
+const TOTP_PERIOD_SECONDS: i64 = 30;
+// Number of periods either side of the current one that are still accepted, to allow for clock drift.
+const TOTP_ALLOWED_DRIFT: i64 = 1;
+
+// The label and issuer are percent-encoded, as the Key URI format requires.
+pub fn get_totp_provisioning_uri(secret: &TotpSecret, email: &Email) -> String {
+    let issuer: String = url::form_urlencoded::byte_serialize(TOTP_ISSUER.as_bytes()).collect();
+    let account: String = url::form_urlencoded::byte_serialize(email.as_ref().expose_secret().as_bytes()).collect();
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={digits}&period={period}",
+        issuer = issuer.replace('+', "%20"),
+        account = account.replace('+', "%20"),
+        secret = secret.as_ref().expose_secret(),
+        digits = TOTP_DIGITS,
+        period = TOTP_PERIOD_SECONDS,
+    )
+}
+
+pub fn generate_totp_code(secret: &TotpSecret) -> String {
+    generate_totp_code_at(secret, Utc::now().timestamp())
+}
+
+pub fn generate_totp_code_at(secret: &TotpSecret, timestamp: i64) -> String {
+    let counter = timestamp / TOTP_PERIOD_SECONDS;
+    generate_hotp_code(&secret.to_bytes(), counter as u64, TOTP_DIGITS)
+}
+
+// Returns the time step the code is for, if it is valid. Callers record it with
+// `UserStore::use_totp_counter` so the code can't be used again.
+#[tracing::instrument(name = "Verifying TOTP code", skip_all)]
+pub fn verify_totp_code(secret: &TotpSecret, code: &str) -> Option<i64> {
+    verify_totp_code_at(secret, code, Utc::now().timestamp())
+}
+
+fn verify_totp_code_at(secret: &TotpSecret, code: &str, timestamp: i64) -> Option<i64> {
+    let key = secret.to_bytes();
+    let counter = timestamp / TOTP_PERIOD_SECONDS;
+
+    (counter - TOTP_ALLOWED_DRIFT..=counter + TOTP_ALLOWED_DRIFT)
+        .filter(|counter| *counter >= 0)
+        .find(|counter| generate_hotp_code(&key, *counter as u64, TOTP_DIGITS) == code)
+}
+
+// HOTP as defined in RFC 4226, section 5.3.
+fn generate_hotp_code(key: &[u8], counter: u64, digits: u32) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+
+    format!("{:0width$}", binary % 10u32.pow(digits), width = digits as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::Secret;
+
+    // The SHA1 seed from RFC 6238, appendix B.
+    const RFC_6238_SEED: &[u8] = b"12345678901234567890";
+
+    fn rfc_secret() -> TotpSecret {
+        let encoded = data_encoding::BASE32_NOPAD.encode(RFC_6238_SEED);
+        TotpSecret::parse(Secret::new(encoded)).unwrap()
+    }
+
+    #[test]
+    fn test_generate_hotp_code_matches_rfc_6238_vectors() {
+        let vectors = [
+            (59, "94287082"),
+            (1111111109, "07081804"),
+            (1111111111, "14050471"),
+            (1234567890, "89005924"),
+            (2000000000, "69279037"),
+        ];
+
+        for (timestamp, expected) in vectors {
+            let counter = (timestamp / TOTP_PERIOD_SECONDS) as u64;
+            assert_eq!(generate_hotp_code(RFC_6238_SEED, counter, 8), expected);
+        }
+    }
+
+    #[test]
+    fn test_verify_totp_code() {
+        let secret = rfc_secret();
+        assert_eq!(verify_totp_code_at(&secret, "287082", 59), Some(1));
+    }
+
+    #[test]
+    fn test_verify_totp_code_allows_one_period_of_drift() {
+        let secret = rfc_secret();
+        assert_eq!(verify_totp_code_at(&secret, "287082", 59 + TOTP_PERIOD_SECONDS), Some(1));
+        assert_eq!(verify_totp_code_at(&secret, "287082", 59 + 2 * TOTP_PERIOD_SECONDS), None);
+    }
+
+    #[test]
+    fn test_verify_totp_code_rejects_wrong_code() {
+        let secret = rfc_secret();
+        assert_eq!(verify_totp_code_at(&secret, "123456", 59), None);
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let secret = rfc_secret();
+        let email = Email::parse(Secret::new("test@example.com".to_owned())).unwrap();
+
+        let uri = get_totp_provisioning_uri(&secret, &email);
+        assert!(uri.starts_with("otpauth://totp/"));
+        assert!(uri.contains(":test%40example.com?"));
+        assert!(uri.contains(&format!("secret={}", secret.as_ref().expose_secret())));
+    }
+}
@@ -1,8 +1,19 @@
 use std::time::Duration;
-use axum::{body::Body, extract::Request, response::Response};
+use axum::{
+    body::Body,
+    extract::Request,
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
 use tracing::{Level, Span};
 use uuid; // Import uuid pour générer des IDs de requête uniques
 
+// Identifies a request in logs and audit events, and is echoed back in the response. Taken from
+// the request when a proxy in front of the service already set one.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
 pub fn init_tracing() {
     tracing_subscriber::fmt()
         .compact()
@@ -10,10 +21,35 @@ pub fn init_tracing() {
         .init();
 }
 
-// Creates a new tracing span with a unique request ID for each incoming request.
+// Makes sure every request has a request ID, see `REQUEST_ID_HEADER`. Runs outside the trace
+// layer so its span picks the ID up.
+pub async fn set_request_id(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .filter(|value| value.to_str().is_ok_and(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LENGTH))
+        .cloned()
+        .unwrap_or_else(|| {
+            HeaderValue::from_str(&uuid::Uuid::new_v4().to_string()).expect("UUIDs are valid header values")
+        });
+    request.headers_mut().insert(REQUEST_ID_HEADER, request_id.clone());
+
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+    response
+}
+
+pub fn request_id(request: &Request<Body>) -> Option<&str> {
+    request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+}
+
+// Creates a new tracing span with the request's ID.
 // This helps in tracking and correlating logs for individual requests.
 pub fn make_span_with_request_id(request: &Request<Body>) -> Span {
-    let request_id = uuid::Uuid::new_v4();
+    let request_id = request_id(request).unwrap_or_default();
     tracing::span!(
         Level::INFO,
         "[REQUEST]",
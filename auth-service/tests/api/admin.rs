@@ -0,0 +1,396 @@
+use crate::helper::{get_random_email, TestApp};
+use auth_service::routes::{
+    AdminUserResponse, AdminUsersResponse, ApiKeySecretResponse, ApiKeysResponse, OAuthClientResponse,
+    RolePermissionsResponse, TokenResponse,
+};
+
+// Signs up a verified user without logging them in, so the admin's cookies are left alone.
+async fn sign_up_user(app: &TestApp) -> String {
+    let email = get_random_email();
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+    email
+}
+
+async fn log_in(app: &TestApp, email: &str, password: &str) -> u16 {
+    let client = reqwest::Client::new();
+    client
+        .post(format!("{}/login", &app.address))
+        .json(&serde_json::json!({
+            "email": email,
+            "password": password
+        }))
+        .send()
+        .await
+        .expect("Failed to send request")
+        .status()
+        .as_u16()
+}
+
+async fn get_sessions_status(app: &TestApp, client: &reqwest::Client) -> u16 {
+    client
+        .get(format!("{}/sessions", &app.address))
+        .send()
+        .await
+        .expect("Failed to send request")
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn should_list_and_search_users_a_page_at_a_time() {
+    let app = TestApp::new().await;
+    let admin = app.log_in_new_admin().await;
+    let user = sign_up_user(&app).await;
+    sign_up_user(&app).await;
+
+    let response = app.get_admin("/users?perPage=2&page=2").await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.json::<AdminUsersResponse>().await.unwrap();
+    assert_eq!(body.total, 3);
+    assert_eq!(body.page, 2);
+    assert_eq!(body.per_page, 2);
+    assert_eq!(body.users.len(), 1);
+
+    let search = &user[..8];
+    let response = app.get_admin(&format!("/users?search={}", search)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.json::<AdminUsersResponse>().await.unwrap();
+    assert_eq!(body.total, 1);
+    assert_eq!(body.users[0].email, user);
+    assert!(body.users.iter().all(|account| account.email != admin));
+}
+
+#[tokio::test]
+async fn should_return_400_if_page_is_invalid() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+
+    for query in ["page=0", "perPage=0", "perPage=101"] {
+        let response = app.get_admin(&format!("/users?{}", query)).await;
+        assert_eq!(response.status().as_u16(), 400, "Failed for query: {}", query);
+    }
+}
+
+#[tokio::test]
+async fn should_return_400_if_page_is_too_large() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+
+    let response = app.get_admin("/users?page=9223372036854775807").await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_user() {
+    let app = TestApp::new().await;
+    let admin = app.log_in_new_admin().await;
+
+    let response = app.get_admin(&format!("/users/{}", admin)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.json::<AdminUserResponse>().await.unwrap();
+    assert_eq!(body.email, admin);
+    assert!(body.email_verified);
+    assert!(!body.locked);
+    assert_eq!(body.deleted_at, None);
+    assert_eq!(body.roles, vec!["admin".to_owned()]);
+}
+
+#[tokio::test]
+async fn should_return_404_if_user_does_not_exist() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+
+    let response = app.get_admin(&format!("/users/{}", get_random_email())).await;
+    assert_eq!(response.status().as_u16(), 404);
+
+    let response = app.post_admin(&format!("/users/{}/revoke-tokens", get_random_email())).await;
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn should_return_400_if_email_is_invalid() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+
+    let response = app.get_admin("/users/not-an-email").await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_403_if_user_is_not_admin() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+
+    assert_eq!(app.get_admin("/users").await.status().as_u16(), 403);
+    let response = app.post_admin(&format!("/users/{}/lock", email)).await;
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    assert_eq!(app.get_admin("/users").await.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_lock_user_out_until_unlocked() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+    let user = sign_up_user(&app).await;
+    let other_device = app.log_in_on_other_device(&user, "Other browser").await;
+
+    let response = app.post_admin(&format!("/users/{}/lock", user)).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(get_sessions_status(&app, &other_device).await, 401);
+    assert_eq!(log_in(&app, &user, "password123").await, 403);
+    let body = app.get_admin(&format!("/users/{}", user)).await.json::<AdminUserResponse>().await.unwrap();
+    assert!(body.locked);
+
+    let response = app.post_admin(&format!("/users/{}/unlock", user)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(log_in(&app, &user, "password123").await, 200);
+}
+
+#[tokio::test]
+async fn should_force_password_reset() {
+    let app = TestApp::new().await;
+    app.mount_email_server().await;
+    app.log_in_new_admin().await;
+    let user = sign_up_user(&app).await;
+    let other_device = app.log_in_on_other_device(&user, "Other browser").await;
+
+    let response = app.post_admin(&format!("/users/{}/force-password-reset", user)).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(get_sessions_status(&app, &other_device).await, 401);
+    assert_eq!(log_in(&app, &user, "password123").await, 401);
+
+    let token = app.get_token_emailed_to(&user).await;
+    let response = app.post_reset_password(&serde_json::json!({
+        "token": token,
+        "newPassword": "new-password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(log_in(&app, &user, "new-password123").await, 200);
+}
+
+#[tokio::test]
+async fn should_force_2fa() {
+    let app = TestApp::new().await;
+    app.mount_email_server().await;
+    app.log_in_new_admin().await;
+    let user = sign_up_user(&app).await;
+
+    let response = app.post_admin(&format!("/users/{}/force-2fa", user)).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let requests = app.email_server.received_requests().await.unwrap();
+    let notification: serde_json::Value = serde_json::from_slice(&requests.last().unwrap().body).unwrap();
+    assert_eq!(notification["To"], user);
+    assert_eq!(notification["Subject"], "Two-factor authentication was turned on");
+
+    assert_eq!(log_in(&app, &user, "password123").await, 206);
+}
+
+#[tokio::test]
+async fn should_revoke_all_tokens_of_user() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+    let user = sign_up_user(&app).await;
+    let other_device = app.log_in_on_other_device(&user, "Other browser").await;
+    assert_eq!(get_sessions_status(&app, &other_device).await, 200);
+
+    let response = app.post_admin(&format!("/users/{}/revoke-tokens", user)).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(get_sessions_status(&app, &other_device).await, 401);
+    assert_eq!(log_in(&app, &user, "password123").await, 200);
+}
+
+#[tokio::test]
+async fn should_grant_role_from_next_refresh_and_revoke_it_at_once() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+    let user = sign_up_user(&app).await;
+    let other_device = app.log_in_on_other_device(&user, "Other browser").await;
+    let get_users_status = || async {
+        other_device
+            .get(format!("{}/admin/users", &app.address))
+            .send()
+            .await
+            .expect("Failed to send request")
+            .status()
+            .as_u16()
+    };
+
+    let response = app.post_admin_with_body(&format!("/users/{}/roles", user), &serde_json::json!({
+        "role": "admin"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(get_users_status().await, 403);
+
+    let response = other_device
+        .post(format!("{}/refresh", &app.address))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(get_users_status().await, 200);
+
+    let response = app.delete_admin(&format!("/users/{}/roles/admin", user)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(get_users_status().await, 401);
+}
+
+#[tokio::test]
+async fn should_manage_role_permissions() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+
+    for permission in ["users:write", "users:read"] {
+        let response = app.post_admin_with_body("/roles/support/permissions", &serde_json::json!({
+            "permission": permission
+        })).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    let response = app.get_admin("/roles/support/permissions").await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.json::<RolePermissionsResponse>().await.unwrap();
+    assert_eq!(body.permissions, vec!["users:read", "users:write"]);
+
+    let response = app.delete_admin("/roles/support/permissions/users:write").await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = app.get_admin("/roles/support/permissions").await.json::<RolePermissionsResponse>().await.unwrap();
+    assert_eq!(body.permissions, vec!["users:read"]);
+}
+
+#[tokio::test]
+async fn should_revoke_tokens_of_role_users_when_permission_is_revoked() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+    let user = sign_up_user(&app).await;
+
+    let response = app.post_admin_with_body("/roles/support/permissions", &serde_json::json!({
+        "permission": "users:read"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app.post_admin_with_body(&format!("/users/{}/roles", user), &serde_json::json!({
+        "role": "support"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let other_device = app.log_in_on_other_device(&user, "Other browser").await;
+    assert_eq!(get_sessions_status(&app, &other_device).await, 200);
+
+    let response = app.delete_admin("/roles/support/permissions/users:read").await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(get_sessions_status(&app, &other_device).await, 401);
+    // Users without the role stay logged in.
+    assert_eq!(app.get_admin("/roles/support/permissions").await.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_400_if_role_or_permission_name_is_invalid() {
+    let app = TestApp::new().await;
+    let admin = app.log_in_new_admin().await;
+
+    let response = app.post_admin_with_body(&format!("/users/{}/roles", admin), &serde_json::json!({
+        "role": ""
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app.post_admin_with_body("/roles/support/permissions", &serde_json::json!({
+        "permission": "read users"
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_manage_api_keys_of_user() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+    let user = sign_up_user(&app).await;
+    let other_device = app.log_in_on_other_device(&user, "Other browser").await;
+    let response = other_device
+        .post(format!("{}/api-keys", &app.address))
+        .json(&serde_json::json!({ "name": "CI" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(response.status().as_u16(), 201);
+    let created = response.json::<ApiKeySecretResponse>().await.unwrap();
+
+    let response = app.get_admin(&format!("/users/{}/api-keys", user)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.json::<ApiKeysResponse>().await.unwrap();
+    assert_eq!(body.api_keys.len(), 1);
+    assert_eq!(body.api_keys[0].id, created.api_key.id);
+
+    let response = app.post_admin(&format!("/users/{}/api-keys/{}/rotate", user, created.api_key.id)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let rotated = response.json::<ApiKeySecretResponse>().await.unwrap();
+    assert_eq!(app.post_verify_token_with_bearer(&created.key).await.status().as_u16(), 401);
+    assert_eq!(app.post_verify_token_with_bearer(&rotated.key).await.status().as_u16(), 200);
+
+    let response = app.delete_admin(&format!("/users/{}/api-keys/{}", user, created.api_key.id)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(app.post_verify_token_with_bearer(&rotated.key).await.status().as_u16(), 401);
+
+    let response = app.get_admin(&format!("/users/{}/api-keys", get_random_email())).await;
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn should_create_oauth_client_for_service() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+
+    let response = app.post_admin_with_body("/oauth-clients", &serde_json::json!({
+        "clientId": "billing-job",
+        "name": "Billing job",
+        "scopes": ["users:read"],
+        "confidential": true
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+    let client = response.json::<OAuthClientResponse>().await.unwrap();
+    assert_eq!(client.client_id, "billing-job");
+    assert_eq!(client.scopes, vec!["users:read"]);
+    let client_secret = client.client_secret.expect("Confidential clients should get a secret");
+
+    let response = app.post_token_with_basic_auth("billing-job", &client_secret, &[("grant_type", "client_credentials")]).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.json::<TokenResponse>().await.unwrap();
+    assert_eq!(body.scope.as_deref(), Some("users:read"));
+
+    let response = app.post_admin_with_body("/oauth-clients", &serde_json::json!({
+        "clientId": "billing-job",
+        "name": "Billing job",
+        "confidential": true
+    })).await;
+    assert_eq!(response.status().as_u16(), 409);
+}
+
+#[tokio::test]
+async fn should_return_400_if_oauth_client_is_invalid() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+
+    let requests = [
+        serde_json::json!({ "clientId": "", "name": "Job", "confidential": true }),
+        serde_json::json!({ "clientId": "job", "name": " ", "confidential": true }),
+        serde_json::json!({ "clientId": "job", "name": "Job", "scopes": ["read users"], "confidential": true }),
+        serde_json::json!({ "clientId": "app", "name": "App", "redirectUris": ["not a uri"], "confidential": false }),
+    ];
+    for request in requests {
+        let response = app.post_admin_with_body("/oauth-clients", &request).await;
+        assert_eq!(response.status().as_u16(), 400, "Failed for input: {:?}", request);
+    }
+}
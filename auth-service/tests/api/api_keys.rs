@@ -0,0 +1,188 @@
+use crate::helper::TestApp;
+use auth_service::routes::{ApiKeySecretResponse, ApiKeysResponse, VerifyTokenResponse};
+use auth_service::utils::constants::JWT_COOKIE_NAME;
+
+async fn create_api_key(app: &TestApp) -> ApiKeySecretResponse {
+    let response = app.post_api_key(&serde_json::json!({
+        "name": "CI",
+        "scopes": ["deploy", "metrics:read"],
+        "expiresInDays": 30
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+    response.json::<ApiKeySecretResponse>().await.unwrap()
+}
+
+#[tokio::test]
+async fn should_create_api_key_accepted_by_verify_token() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+
+    let created = create_api_key(&app).await;
+    assert!(created.key.starts_with("ak_"));
+    assert_eq!(created.api_key.name, "CI");
+    assert_eq!(created.api_key.expires_at - created.api_key.created_at, 30 * 24 * 60 * 60);
+    assert_eq!(created.api_key.last_used_at, None);
+
+    let response = app.post_verify_token_with_bearer(&created.key).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.json::<VerifyTokenResponse>().await.unwrap();
+    assert_eq!(body.sub, email);
+    assert_eq!(body.scopes, vec!["deploy", "metrics:read"]);
+    assert!(body.roles.is_empty());
+    assert!(body.permissions.is_empty());
+}
+
+#[tokio::test]
+async fn should_list_api_keys_without_secrets() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+    let created = create_api_key(&app).await;
+    app.post_verify_token_with_bearer(&created.key).await;
+
+    let response = app.get_api_keys().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(!body.to_string().contains(&created.key));
+
+    let body: ApiKeysResponse = serde_json::from_value(body).unwrap();
+    assert_eq!(body.api_keys.len(), 1);
+    assert_eq!(body.api_keys[0].id, created.api_key.id);
+    assert!(body.api_keys[0].last_used_at.is_some());
+}
+
+#[tokio::test]
+async fn should_revoke_api_keys_when_all_tokens_are_revoked() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+    let created = create_api_key(&app).await;
+
+    assert_eq!(app.post_logout_all().await.status().as_u16(), 200);
+
+    let response = app.post_verify_token_with_bearer(&created.key).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_rotate_api_key() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+    let created = create_api_key(&app).await;
+
+    let response = app.post_rotate_api_key(&created.api_key.id).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let rotated = response.json::<ApiKeySecretResponse>().await.unwrap();
+    assert_eq!(rotated.api_key.id, created.api_key.id);
+    assert_ne!(rotated.key, created.key);
+
+    assert_eq!(app.post_verify_token_with_bearer(&created.key).await.status().as_u16(), 401);
+    assert_eq!(app.post_verify_token_with_bearer(&rotated.key).await.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_revoke_api_key() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+    let created = create_api_key(&app).await;
+
+    let response = app.delete_api_key(&created.api_key.id).await;
+    assert_eq!(response.status().as_u16(), 204);
+
+    assert_eq!(app.post_verify_token_with_bearer(&created.key).await.status().as_u16(), 401);
+    assert_eq!(app.delete_api_key(&created.api_key.id).await.status().as_u16(), 404);
+    assert_eq!(app.post_rotate_api_key(&created.api_key.id).await.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn should_not_manage_keys_of_other_users() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+    let created = create_api_key(&app).await;
+
+    app.log_in_new_user().await;
+    assert_eq!(app.post_rotate_api_key(&created.api_key.id).await.status().as_u16(), 404);
+    assert_eq!(app.delete_api_key(&created.api_key.id).await.status().as_u16(), 404);
+    let body = app.get_api_keys().await.json::<ApiKeysResponse>().await.unwrap();
+    assert!(body.api_keys.is_empty());
+
+    assert_eq!(app.post_verify_token_with_bearer(&created.key).await.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_refuse_api_key_of_locked_user() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+    let created = create_api_key(&app).await;
+
+    app.user_store.write().await.set_user_locked(&email, true).await.unwrap();
+
+    assert_eq!(app.post_verify_token_with_bearer(&created.key).await.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_if_api_key_is_invalid() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+    let created = create_api_key(&app).await;
+
+    let forged = format!("ak_{}.c2VjcmV0", created.api_key.id);
+    assert_eq!(app.post_verify_token_with_bearer(&forged).await.status().as_u16(), 401);
+    assert_eq!(app.post_verify_token_with_bearer("not-a-key").await.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_accept_jwt_in_authorization_header() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    let token = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+
+    let response = app.post_verify_token_with_bearer(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.json::<VerifyTokenResponse>().await.unwrap();
+    assert_eq!(body.sub, email);
+    assert!(body.scopes.is_empty());
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_input() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+
+    let test_cases = [
+        serde_json::json!({ "name": "" }),
+        serde_json::json!({ "name": "a".repeat(101) }),
+        serde_json::json!({ "name": "CI", "scopes": ["read users"] }),
+        serde_json::json!({ "name": "CI", "scopes": [""] }),
+        serde_json::json!({ "name": "CI", "expiresInDays": 0 }),
+        serde_json::json!({ "name": "CI", "expiresInDays": 366 }),
+    ];
+    for test_case in test_cases.iter() {
+        let response = app.post_api_key(test_case).await;
+        assert_eq!(response.status().as_u16(), 400, "Failed for input: {:?}", test_case);
+    }
+}
+
+#[tokio::test]
+async fn should_return_422_if_malformed_input() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+
+    let response = app.post_api_key(&serde_json::json!({ "scopes": ["deploy"] })).await;
+    assert_eq!(response.status().as_u16(), 422);
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    assert_eq!(app.post_api_key(&serde_json::json!({ "name": "CI" })).await.status().as_u16(), 400);
+    assert_eq!(app.get_api_keys().await.status().as_u16(), 400);
+}
@@ -0,0 +1,223 @@
+use crate::helper::{get_random_email, TestApp};
+use auth_service::{
+    domain::Email,
+    routes::{login::TwoFactorAuthResponse, AuditEventsResponse, AuditLogVerificationResponse},
+};
+use secrecy::{ExposeSecret, Secret};
+use wiremock::{matchers::{method, path}, Mock, ResponseTemplate};
+
+#[tokio::test]
+async fn should_record_failed_login_with_request_details() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+
+    let response = app
+        .http_client
+        .post(format!("{}/login", &app.address))
+        .header("user-agent", "audit-test")
+        .header("x-request-id", "login-request")
+        .json(&serde_json::json!({
+            "email": email,
+            "password": "wrong-password"
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(response.headers().get("x-request-id").unwrap(), "login-request");
+
+    app.log_in_new_admin().await;
+    let response = app.get_audit_events(&[("user", &email), ("action", "login")]).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: AuditEventsResponse = response.json().await.unwrap();
+    assert_eq!(body.total, 2);
+
+    // Newest first
+    let event = &body.events[0];
+    assert_eq!(event.actor.as_deref(), Some(email.as_str()));
+    assert_eq!(event.outcome, "failure");
+    assert_eq!(event.ip.as_deref(), Some("127.0.0.1"));
+    assert_eq!(event.user_agent.as_deref(), Some("audit-test"));
+    assert_eq!(event.request_id.as_deref(), Some("login-request"));
+    assert_eq!(body.events[1].outcome, "success");
+}
+
+#[tokio::test]
+async fn should_record_signup_and_logout_of_user() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+    // Every response gets a request ID.
+    assert!(response.headers().get("x-request-id").is_some());
+
+    app.log_in_new_admin().await;
+    let response = app.get_audit_events(&[("user", &email)]).await;
+    let body: AuditEventsResponse = response.json().await.unwrap();
+    let actions: Vec<&str> = body.events.iter().rev().map(|event| event.action.as_str()).collect();
+    assert_eq!(actions, vec!["signup", "email.verify", "login", "logout"]);
+    assert!(body.events.iter().all(|event| event.outcome == "success"));
+}
+
+#[tokio::test]
+async fn should_record_each_step_of_two_factor_login() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": true
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 206);
+    let body: TwoFactorAuthResponse = response.json().await.unwrap();
+
+    let (_, code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&Email::parse(Secret::new(email.clone())).unwrap())
+        .await
+        .unwrap();
+    let code = code.as_ref().expose_secret().to_owned();
+    let wrong_code = if code == "000000" { "111111" } else { "000000" };
+
+    for (two_fa_code, status) in [(wrong_code, 401), (code.as_str(), 200)] {
+        let response = app.post_verify_2fa(&serde_json::json!({
+            "email": email,
+            "loginAttemptId": body.login_attempt_id,
+            "2FACode": two_fa_code
+        })).await;
+        assert_eq!(response.status().as_u16(), status);
+    }
+
+    app.log_in_new_admin().await;
+    let response = app.get_audit_events(&[("user", &email)]).await;
+    let body: AuditEventsResponse = response.json().await.unwrap();
+    let events: Vec<(&str, &str)> = body
+        .events
+        .iter()
+        .rev()
+        .map(|event| (event.action.as_str(), event.outcome.as_str()))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            ("signup", "success"),
+            ("email.verify", "success"),
+            ("login.2fa_required", "success"),
+            ("login.2fa", "failure"),
+            ("login.2fa", "success"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn should_record_admin_actions_against_target_user() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let admin = app.log_in_new_admin().await;
+    let response = app.post_admin(&format!("/users/{}/lock", email)).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.get_audit_events(&[("user", &email), ("action", "admin.user.lock")]).await;
+    let body: AuditEventsResponse = response.json().await.unwrap();
+    assert_eq!(body.total, 1);
+    assert_eq!(body.events[0].actor.as_deref(), Some(admin.as_str()));
+    assert_eq!(body.events[0].target.as_deref(), Some(email.as_str()));
+}
+
+#[tokio::test]
+async fn should_filter_events_by_time_range() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_admin().await;
+
+    let response = app.get_audit_events(&[("user", &email), ("from", "0")]).await;
+    let body: AuditEventsResponse = response.json().await.unwrap();
+    assert!(body.total > 0);
+    let created_at = body.events[0].created_at;
+
+    let to = (created_at - 3600).to_string();
+    let response = app.get_audit_events(&[("user", &email), ("to", &to)]).await;
+    let body: AuditEventsResponse = response.json().await.unwrap();
+    assert_eq!(body.total, 0);
+    assert!(body.events.is_empty());
+}
+
+#[tokio::test]
+async fn should_detect_tampered_audit_event() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+
+    let response = app.get_admin("/audit-events/verify").await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: AuditLogVerificationResponse = response.json().await.unwrap();
+    assert!(body.valid);
+    assert_eq!(body.first_invalid_id, None);
+
+    app.tamper_with_audit_event(2, "someone-else@example.com").await;
+
+    let response = app.get_admin("/audit-events/verify").await;
+    let body: AuditLogVerificationResponse = response.json().await.unwrap();
+    assert!(!body.valid);
+    assert_eq!(body.first_invalid_id, Some(2));
+}
+
+#[tokio::test]
+async fn should_detect_audit_events_removed_from_end() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+
+    let id = app.delete_last_audit_event().await;
+
+    let response = app.get_admin("/audit-events/verify").await;
+    let body: AuditLogVerificationResponse = response.json().await.unwrap();
+    assert!(!body.valid);
+    assert_eq!(body.first_invalid_id, Some(id));
+}
+
+#[tokio::test]
+async fn should_return_403_if_not_admin() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+
+    let response = app.get_audit_events(&[]).await;
+    assert_eq!(response.status().as_u16(), 403);
+
+    // The refused attempt is itself recorded.
+    app.log_in_new_admin().await;
+    let response = app.get_audit_events(&[("user", &email), ("action", "GET /admin/audit-events")]).await;
+    let body: AuditEventsResponse = response.json().await.unwrap();
+    assert_eq!(body.total, 1);
+    assert_eq!(body.events[0].outcome, "failure");
+}
+
+#[tokio::test]
+async fn should_return_400_for_invalid_page() {
+    let app = TestApp::new().await;
+    app.log_in_new_admin().await;
+
+    for query in [[("page", "0")], [("perPage", "1000")], [("page", "9223372036854775807")]] {
+        let response = app.get_audit_events(&query).await;
+        assert_eq!(response.status().as_u16(), 400, "Failed for query: {:?}", query);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::helper::TestApp;
+use auth_service::domain::error::OAuthErrorResponse;
+use url::Url;
+
+const CLIENT_ID: &str = "test-client";
+const REDIRECT_URI: &str = "https://app.example.com/callback";
+// From RFC 7636, appendix B.
+const CODE_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+fn authorize_query<'a>() -> Vec<(&'a str, &'a str)> {
+    vec![
+        ("response_type", "code"),
+        ("client_id", CLIENT_ID),
+        ("redirect_uri", REDIRECT_URI),
+        ("code_challenge", CODE_CHALLENGE),
+        ("code_challenge_method", "S256"),
+        ("state", "xyz"),
+    ]
+}
+
+fn with_param<'a>(query: Vec<(&'a str, &'a str)>, name: &'a str, value: &'a str) -> Vec<(&'a str, &'a str)> {
+    let mut query: Vec<_> = query.into_iter().filter(|(key, _)| *key != name).collect();
+    query.push((name, value));
+    query
+}
+
+fn redirect_location(response: &reqwest::Response) -> Url {
+    assert!(response.status().is_redirection(), "expected a redirect, got {}", response.status());
+    let location = response.headers().get("location").unwrap().to_str().unwrap();
+    Url::parse(location).unwrap_or_else(|_| Url::parse("http://auth-service").unwrap().join(location).unwrap())
+}
+
+fn query_param(url: &Url, name: &str) -> Option<String> {
+    url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned())
+}
+
+#[tokio::test]
+async fn should_return_400_if_client_is_unknown() {
+    let app = TestApp::new().await;
+
+    let response = app.get_authorize(&authorize_query()).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let body: OAuthErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.error, "invalid_request");
+}
+
+#[tokio::test]
+async fn should_return_400_if_redirect_uri_is_not_registered() {
+    let app = TestApp::new().await;
+    app.add_oauth_client(CLIENT_ID, REDIRECT_URI, None).await;
+
+    let query = with_param(authorize_query(), "redirect_uri", "https://evil.example.com/callback");
+    let response = app.get_authorize(&query).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_redirect_with_error_if_response_type_is_unsupported() {
+    let app = TestApp::new().await;
+    app.add_oauth_client(CLIENT_ID, REDIRECT_URI, None).await;
+
+    let query = with_param(authorize_query(), "response_type", "token");
+    let response = app.get_authorize(&query).await;
+
+    let location = redirect_location(&response);
+    assert!(location.as_str().starts_with(REDIRECT_URI));
+    assert_eq!(query_param(&location, "error").as_deref(), Some("unsupported_response_type"));
+    assert_eq!(query_param(&location, "state").as_deref(), Some("xyz"));
+}
+
+#[tokio::test]
+async fn should_redirect_with_error_if_pkce_is_missing() {
+    let app = TestApp::new().await;
+    app.add_oauth_client(CLIENT_ID, REDIRECT_URI, None).await;
+
+    let query: Vec<_> = authorize_query()
+        .into_iter()
+        .filter(|(key, _)| !key.starts_with("code_challenge"))
+        .collect();
+    let response = app.get_authorize(&query).await;
+
+    let location = redirect_location(&response);
+    assert!(location.as_str().starts_with(REDIRECT_URI));
+    assert_eq!(query_param(&location, "error").as_deref(), Some("invalid_request"));
+    assert!(query_param(&location, "code").is_none());
+}
+
+#[tokio::test]
+async fn should_send_user_to_login_page_if_not_logged_in() {
+    let app = TestApp::new().await;
+    app.add_oauth_client(CLIENT_ID, REDIRECT_URI, None).await;
+
+    let response = app.get_authorize(&authorize_query()).await;
+
+    let location = redirect_location(&response);
+    assert_eq!(location.path(), "/");
+    let return_to = query_param(&location, "return_to").unwrap();
+    assert!(return_to.starts_with("/authorize?"));
+    assert!(return_to.contains(CODE_CHALLENGE));
+}
+
+#[tokio::test]
+async fn should_redirect_with_code_if_logged_in() {
+    let app = TestApp::new().await;
+    app.add_oauth_client(CLIENT_ID, REDIRECT_URI, None).await;
+    app.log_in_new_user().await;
+
+    let response = app.get_authorize(&authorize_query()).await;
+
+    let location = redirect_location(&response);
+    assert!(location.as_str().starts_with(REDIRECT_URI));
+    assert!(query_param(&location, "code").is_some());
+    assert_eq!(query_param(&location, "state").as_deref(), Some("xyz"));
+}
@@ -0,0 +1,226 @@
+use crate::helper::{get_random_email, TestApp};
+
+// Logs in a new user and requests a change of their address, returning the old and new ones.
+async fn request_email_change(app: &TestApp) -> (String, String) {
+    app.mount_email_server().await;
+    let email = app.log_in_new_user().await;
+    let new_email = get_random_email();
+
+    let response = app.post_change_email(&serde_json::json!({
+        "newEmail": new_email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+    (email, new_email)
+}
+
+async fn login_status(app: &TestApp, email: &str) -> u16 {
+    app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    }))
+    .await
+    .status()
+    .as_u16()
+}
+
+#[tokio::test]
+async fn should_change_email_only_once_confirmed() {
+    let app = TestApp::new().await;
+    let (email, new_email) = request_email_change(&app).await;
+
+    // Nothing changes until the new address is confirmed.
+    assert_eq!(app.get_sessions().await.status().as_u16(), 200);
+    assert_eq!(login_status(&app, &new_email).await, 401);
+
+    let token = app.get_token_emailed_to(&new_email).await;
+    let response = app.post_confirm_email_change(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // The old address no longer logs in, and its sessions are gone.
+    assert_eq!(app.get_sessions().await.status().as_u16(), 401);
+    assert_eq!(app.post_refresh().await.status().as_u16(), 401);
+    assert_eq!(login_status(&app, &email).await, 401);
+    assert_eq!(login_status(&app, &new_email).await, 200);
+
+    // The link only works once.
+    let response = app.post_confirm_email_change(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_undo_confirmed_change() {
+    let app = TestApp::new().await;
+    let (email, new_email) = request_email_change(&app).await;
+
+    let token = app.get_token_emailed_to(&new_email).await;
+    assert_eq!(app.post_confirm_email_change(&token).await.status().as_u16(), 200);
+    assert_eq!(login_status(&app, &new_email).await, 200);
+
+    let undo_token = app.get_token_emailed_to(&email).await;
+    let response = app.post_undo_email_change(&undo_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(app.get_sessions().await.status().as_u16(), 401);
+    assert_eq!(login_status(&app, &new_email).await, 401);
+    assert_eq!(login_status(&app, &email).await, 200);
+
+    let response = app.post_undo_email_change(&undo_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_not_change_email_again_while_undo_is_possible() {
+    let app = TestApp::new().await;
+    let (email, new_email) = request_email_change(&app).await;
+
+    let token = app.get_token_emailed_to(&new_email).await;
+    assert_eq!(app.post_confirm_email_change(&token).await.status().as_u16(), 200);
+    assert_eq!(login_status(&app, &new_email).await, 200);
+
+    let response = app.post_change_email(&serde_json::json!({
+        "newEmail": get_random_email(),
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 409);
+
+    let undo_token = app.get_token_emailed_to(&email).await;
+    assert_eq!(app.post_undo_email_change(&undo_token).await.status().as_u16(), 200);
+    assert_eq!(login_status(&app, &email).await, 200);
+}
+
+#[tokio::test]
+async fn should_reserve_old_email_while_undo_is_possible() {
+    let app = TestApp::new().await;
+    let (email, new_email) = request_email_change(&app).await;
+
+    let token = app.get_token_emailed_to(&new_email).await;
+    assert_eq!(app.post_confirm_email_change(&token).await.status().as_u16(), 200);
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    })).await;
+    assert_eq!(response.status().as_u16(), 409);
+
+    let other_email = app.log_in_new_user().await;
+    let response = app.post_change_email(&serde_json::json!({
+        "newEmail": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 409);
+    assert_eq!(login_status(&app, &other_email).await, 200);
+
+    let undo_token = app.get_token_emailed_to(&email).await;
+    assert_eq!(app.post_undo_email_change(&undo_token).await.status().as_u16(), 200);
+    assert_eq!(login_status(&app, &email).await, 200);
+}
+
+#[tokio::test]
+async fn should_cancel_pending_change() {
+    let app = TestApp::new().await;
+    let (email, new_email) = request_email_change(&app).await;
+
+    let undo_token = app.get_token_emailed_to(&email).await;
+    let response = app.post_undo_email_change(&undo_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // The confirmation link no longer works, and the account is logged out everywhere.
+    let token = app.get_token_emailed_to(&new_email).await;
+    assert_eq!(app.post_confirm_email_change(&token).await.status().as_u16(), 401);
+    assert_eq!(app.get_sessions().await.status().as_u16(), 401);
+    assert_eq!(login_status(&app, &email).await, 200);
+}
+
+#[tokio::test]
+async fn should_not_undo_into_an_account_signed_up_later() {
+    let app = TestApp::new().await;
+    let (email, new_email) = request_email_change(&app).await;
+
+    let undo_token = app.get_token_emailed_to(&email).await;
+    assert_eq!(app.post_undo_email_change(&undo_token).await.status().as_u16(), 200);
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": new_email,
+        "password": "password123",
+        "requires2FA": false
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    assert_eq!(app.post_undo_email_change(&undo_token).await.status().as_u16(), 401);
+    assert_eq!(login_status(&app, &email).await, 200);
+}
+
+#[tokio::test]
+async fn should_return_409_if_new_email_is_taken() {
+    let app = TestApp::new().await;
+    app.mount_email_server().await;
+    let other_email = app.log_in_new_user().await;
+    app.log_in_new_user().await;
+
+    let response = app.post_change_email(&serde_json::json!({
+        "newEmail": other_email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 409);
+}
+
+#[tokio::test]
+async fn should_return_409_if_new_email_was_taken_before_confirmation() {
+    let app = TestApp::new().await;
+    let (_, new_email) = request_email_change(&app).await;
+    let token = app.get_token_emailed_to(&new_email).await;
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": new_email,
+        "password": "password123",
+        "requires2FA": false
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    assert_eq!(app.post_confirm_email_change(&token).await.status().as_u16(), 409);
+}
+
+#[tokio::test]
+async fn should_return_401_if_password_is_incorrect() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+
+    let response = app.post_change_email(&serde_json::json!({
+        "newEmail": get_random_email(),
+        "password": "wrong-password"
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_400_if_new_email_is_invalid() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+
+    let response = app.post_change_email(&serde_json::json!({
+        "newEmail": "not-an-email",
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_401_if_token_is_invalid() {
+    let app = TestApp::new().await;
+
+    assert_eq!(app.post_confirm_email_change("invalid").await.status().as_u16(), 401);
+    assert_eq!(app.post_undo_email_change("invalid").await.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app.post_change_email(&serde_json::json!({
+        "newEmail": get_random_email(),
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
@@ -0,0 +1,115 @@
+use crate::helper::TestApp;
+use wiremock::{matchers::{method, path}, Mock, ResponseTemplate};
+
+#[tokio::test]
+async fn should_change_password_and_send_notification() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app.post_change_password(&serde_json::json!({
+        "currentPassword": "password123",
+        "newPassword": "new-password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "new-password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_end_other_sessions_only() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+    let other_device = app.log_in_on_other_device(&email, "Other browser").await;
+
+    let response = app.post_change_password(&serde_json::json!({
+        "currentPassword": "password123",
+        "newPassword": "new-password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(app.get_sessions().await.status().as_u16(), 200);
+    assert_eq!(app.post_refresh().await.status().as_u16(), 200);
+
+    let response = other_device
+        .get(format!("{}/sessions", &app.address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = other_device
+        .post(format!("{}/refresh", &app.address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_if_current_password_is_incorrect() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+
+    let response = app.post_change_password(&serde_json::json!({
+        "currentPassword": "wrong-password",
+        "newPassword": "new-password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_400_if_new_password_is_invalid() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+
+    let response = app.post_change_password(&serde_json::json!({
+        "currentPassword": "password123",
+        "newPassword": "short"
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app.post_change_password(&serde_json::json!({
+        "currentPassword": "password123",
+        "newPassword": "new-password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_422_if_malformed_input() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+
+    let response = app.post_change_password(&serde_json::json!({
+        "newPassword": "new-password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 422);
+}
@@ -0,0 +1,109 @@
+use crate::helper::TestApp;
+use auth_service::{
+    domain::TotpSecret,
+    routes::{login::TwoFactorAuthResponse, RecoveryCodesResponse},
+    utils::{constants::RECOVERY_CODE_COUNT, totp::{generate_totp_code, generate_totp_code_at}},
+};
+use secrecy::Secret;
+use wiremock::{matchers::{method, path}, Mock, ResponseTemplate};
+
+async fn enroll(app: &TestApp) -> TotpSecret {
+    let response = app.post_enroll_totp().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    TotpSecret::parse(Secret::new(body["secret"].as_str().unwrap().to_owned())).unwrap()
+}
+
+#[tokio::test]
+async fn should_return_422_if_malformed_input() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+
+    let response = app.post_confirm_totp(&serde_json::json!({})).await;
+    assert_eq!(response.status().as_u16(), 422);
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app.post_confirm_totp(&serde_json::json!({
+        "code": "123456"
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_400_if_no_enrollment_pending() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+
+    let response = app.post_confirm_totp(&serde_json::json!({
+        "code": "123456"
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_401_if_incorrect_code() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+    let secret = enroll(&app).await;
+
+    let code = generate_totp_code(&secret);
+    let wrong_code = format!("{:06}", (code.parse::<u32>().unwrap() + 1) % 1_000_000);
+
+    let response = app.post_confirm_totp(&serde_json::json!({
+        "code": wrong_code
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_require_totp_code_on_login_once_confirmed() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+    let secret = enroll(&app).await;
+    let confirmation_code = generate_totp_code(&secret);
+
+    let response = app.post_confirm_totp(&serde_json::json!({
+        "code": confirmation_code
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.json::<RecoveryCodesResponse>().await.unwrap();
+    assert_eq!(body.recovery_codes.len(), RECOVERY_CODE_COUNT);
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let body = response.json::<TwoFactorAuthResponse>().await.unwrap();
+    assert_eq!(body.two_fa_method, "totp");
+
+    // The code used to confirm enrollment can't be replayed.
+    let response = app.post_verify_2fa(&serde_json::json!({
+        "email": email,
+        "loginAttemptId": body.login_attempt_id,
+        "2FACode": confirmation_code
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // The next period's code is accepted, within the allowed clock drift, but only once.
+    let next_code = generate_totp_code_at(&secret, chrono::Utc::now().timestamp() + 30);
+    let response = app.post_verify_2fa(&serde_json::json!({
+        "email": email,
+        "loginAttemptId": body.login_attempt_id,
+        "2FACode": next_code
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
@@ -0,0 +1,87 @@
+use crate::helper::TestApp;
+use auth_service::routes::DeleteAccountResponse;
+use chrono::Utc;
+
+async fn users_pending_deletion(app: &TestApp) -> usize {
+    app.user_store
+        .read()
+        .await
+        .get_users_deleted_before(Utc::now().timestamp() + 1)
+        .await
+        .unwrap()
+        .len()
+}
+
+#[tokio::test]
+async fn should_schedule_deletion_and_log_out() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+    let other_device = app.log_in_on_other_device(&email, "Other browser").await;
+
+    let response = app.delete_account(&serde_json::json!({
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: DeleteAccountResponse = response.json().await.unwrap();
+    assert!(body.purge_after > Utc::now().timestamp());
+    assert_eq!(users_pending_deletion(&app).await, 1);
+
+    // The account is logged out everywhere.
+    assert_eq!(app.get_sessions().await.status().as_u16(), 400);
+    let response = other_device
+        .get(format!("{}/sessions", &app.address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_restore_account_on_login() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+
+    let response = app.delete_account(&serde_json::json!({
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(users_pending_deletion(&app).await, 0);
+}
+
+#[tokio::test]
+async fn should_return_401_if_password_is_incorrect() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+
+    let response = app.delete_account(&serde_json::json!({
+        "password": "wrong-password"
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(users_pending_deletion(&app).await, 0);
+    assert_eq!(app.get_sessions().await.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app.delete_account(&serde_json::json!({
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_422_if_malformed_input() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+
+    let response = app.delete_account(&serde_json::json!({})).await;
+    assert_eq!(response.status().as_u16(), 422);
+}
@@ -0,0 +1,107 @@
+use crate::helper::{get_random_email, TestApp};
+use auth_service::routes::login::TwoFactorAuthResponse;
+use wiremock::{matchers::{method, path}, Mock, ResponseTemplate};
+
+// Signs up a user with email 2FA and logs them in, returning their email.
+async fn log_in_user_with_2fa(app: &TestApp) -> String {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    let email = get_random_email();
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": true
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 206);
+    let body = response.json::<TwoFactorAuthResponse>().await.unwrap();
+
+    let requests = app.email_server.received_requests().await.unwrap();
+    let message: serde_json::Value = serde_json::from_slice(&requests.last().unwrap().body).unwrap();
+    let response = app.post_verify_2fa(&serde_json::json!({
+        "email": email,
+        "loginAttemptId": body.login_attempt_id,
+        "2FACode": message["TextBody"]
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    email
+}
+
+#[tokio::test]
+async fn should_disable_2fa_and_send_notification() {
+    let app = TestApp::new().await;
+    let email = log_in_user_with_2fa(&app).await;
+
+    let response = app.post_disable_2fa(&serde_json::json!({
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let requests = app.email_server.received_requests().await.unwrap();
+    let notification: serde_json::Value = serde_json::from_slice(&requests.last().unwrap().body).unwrap();
+    assert_eq!(notification["Subject"], "Two-factor authentication was turned off");
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_401_if_password_is_incorrect() {
+    let app = TestApp::new().await;
+    let email = log_in_user_with_2fa(&app).await;
+
+    let response = app.post_disable_2fa(&serde_json::json!({
+        "password": "wrong-password"
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 206);
+}
+
+#[tokio::test]
+async fn should_return_400_if_2fa_is_not_enabled() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+
+    let response = app.post_disable_2fa(&serde_json::json!({
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app.post_disable_2fa(&serde_json::json!({
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_422_if_malformed_input() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+
+    let response = app.post_disable_2fa(&serde_json::json!({})).await;
+    assert_eq!(response.status().as_u16(), 422);
+}
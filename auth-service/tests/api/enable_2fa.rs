@@ -0,0 +1,164 @@
+use crate::helper::TestApp;
+use auth_service::{
+    domain::{data_stores::{LoginAttemptId, TwoFACode}, Email},
+    routes::RecoveryCodesResponse,
+    utils::constants::MAX_2FA_ATTEMPTS,
+};
+use secrecy::{ExposeSecret, Secret};
+
+// Returns the content of the last email sent, which for 2FA emails is the code itself.
+async fn get_emailed_code(app: &TestApp) -> String {
+    let requests = app.email_server.received_requests().await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&requests.last().unwrap().body).unwrap();
+    body["TextBody"].as_str().unwrap().to_owned()
+}
+
+#[tokio::test]
+async fn should_enable_2fa_once_code_is_confirmed() {
+    let app = TestApp::new().await;
+    app.mount_email_server().await;
+    let email = app.log_in_new_user().await;
+
+    assert_eq!(app.post_enable_2fa().await.status().as_u16(), 200);
+    let code = get_emailed_code(&app).await;
+
+    let response = app.post_confirm_enable_2fa(&serde_json::json!({
+        "2FACode": code
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.json::<RecoveryCodesResponse>().await.unwrap();
+    assert!(!body.recovery_codes.is_empty());
+
+    let requests = app.email_server.received_requests().await.unwrap();
+    let notification: serde_json::Value = serde_json::from_slice(&requests.last().unwrap().body).unwrap();
+    assert_eq!(notification["Subject"], "Two-factor authentication was turned on");
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 206);
+}
+
+#[tokio::test]
+async fn should_keep_enrollment_and_login_codes_apart() {
+    let app = TestApp::new().await;
+    app.mount_email_server().await;
+    let email = app.log_in_new_user().await;
+    let parsed_email = Email::parse(Secret::new(email.clone())).unwrap();
+
+    let login_attempt_id = LoginAttemptId::default();
+    let login_code = TwoFACode::default();
+    app.two_fa_code_store
+        .write()
+        .await
+        .add_code(parsed_email.clone(), login_attempt_id.clone(), login_code.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(app.post_enable_2fa().await.status().as_u16(), 200);
+    let code = get_emailed_code(&app).await;
+
+    // Asking for an enrollment code leaves the pending login code in place.
+    let (stored_login_attempt_id, stored_code) = app.two_fa_code_store.read().await.get_code(&parsed_email).await.unwrap();
+    assert_eq!(stored_login_attempt_id, login_attempt_id);
+    assert_eq!(stored_code, login_code);
+
+    // A login code doesn't turn 2FA on.
+    if code != *login_code.as_ref().expose_secret() {
+        let response = app.post_confirm_enable_2fa(&serde_json::json!({
+            "2FACode": login_code.as_ref().expose_secret()
+        })).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_401_if_code_is_incorrect() {
+    let app = TestApp::new().await;
+    app.mount_email_server().await;
+    let email = app.log_in_new_user().await;
+
+    assert_eq!(app.post_enable_2fa().await.status().as_u16(), 200);
+    let code = get_emailed_code(&app).await;
+    let wrong_code = if code == "000000" { "111111" } else { "000000" };
+
+    let response = app.post_confirm_enable_2fa(&serde_json::json!({
+        "2FACode": wrong_code
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_discard_code_after_too_many_incorrect_attempts() {
+    let app = TestApp::new().await;
+    app.mount_email_server().await;
+    app.log_in_new_user().await;
+
+    assert_eq!(app.post_enable_2fa().await.status().as_u16(), 200);
+    let code = get_emailed_code(&app).await;
+    let wrong_code = if code == "000000" { "111111" } else { "000000" };
+
+    for _ in 0..MAX_2FA_ATTEMPTS {
+        let response = app.post_confirm_enable_2fa(&serde_json::json!({
+            "2FACode": wrong_code
+        })).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    // The code is gone, so even the right one no longer works.
+    let response = app.post_confirm_enable_2fa(&serde_json::json!({
+        "2FACode": code
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_if_no_code_was_requested() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+
+    let response = app.post_confirm_enable_2fa(&serde_json::json!({
+        "2FACode": "123456"
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_400_if_2fa_is_already_enabled() {
+    let app = TestApp::new().await;
+    app.mount_email_server().await;
+    app.log_in_new_user().await;
+
+    assert_eq!(app.post_enable_2fa().await.status().as_u16(), 200);
+    let code = get_emailed_code(&app).await;
+    let response = app.post_confirm_enable_2fa(&serde_json::json!({
+        "2FACode": code
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(app.post_enable_2fa().await.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    assert_eq!(app.post_enable_2fa().await.status().as_u16(), 400);
+    let response = app.post_confirm_enable_2fa(&serde_json::json!({
+        "2FACode": "123456"
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
@@ -0,0 +1,46 @@
+use crate::helper::{get_random_email, TestApp};
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app.post_enroll_totp().await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_200_with_secret_and_provisioning_uri() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_enroll_totp().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    let secret = body["secret"].as_str().unwrap();
+    let otpauth_uri = body["otpauthUri"].as_str().unwrap();
+    assert!(!secret.is_empty());
+    assert!(otpauth_uri.starts_with("otpauth://totp/"));
+    assert!(otpauth_uri.contains(&format!("secret={}", secret)));
+
+    // Enrolling alone doesn't change how the user logs in.
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
@@ -0,0 +1,329 @@
+use crate::helper::{get_random_email, TestApp, FEDERATED_LOGIN_CLIENT_ID};
+use auth_service::{
+    domain::{Email, TwoFAMethod},
+    routes::UserInfoResponse,
+    utils::{constants::JWT_COOKIE_NAME, signing_key::{SigningKey, SIGNING_ALGORITHM}},
+};
+use jsonwebtoken::{encode, Header};
+use reqwest::Response;
+use secrecy::{ExposeSecret, Secret};
+use url::Url;
+use wiremock::{matchers::{method, path}, Mock, ResponseTemplate};
+
+// Serves discovery and JWKS for the mock identity provider and signs the ID tokens it issues.
+struct IdentityProvider {
+    key: SigningKey,
+    issuer: String,
+}
+
+impl IdentityProvider {
+    async fn mount(app: &TestApp) -> Self {
+        let issuer = app.identity_provider_server.uri();
+        let key = SigningKey::generate().unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/.well-known/openid-configuration"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "issuer": issuer,
+                "authorization_endpoint": format!("{}/authorize", issuer),
+                "token_endpoint": format!("{}/token", issuer),
+                "jwks_uri": format!("{}/jwks", issuer),
+            })))
+            .mount(&app.identity_provider_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/jwks"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "keys": [key.jwk()]
+            })))
+            .mount(&app.identity_provider_server)
+            .await;
+
+        Self { key, issuer }
+    }
+
+    fn id_token(&self, claims: serde_json::Value) -> String {
+        let header = Header {
+            kid: Some(self.key.kid().to_owned()),
+            ..Header::new(SIGNING_ALGORITHM)
+        };
+        self.id_token_with_header(&header, claims)
+    }
+
+    fn id_token_with_header(&self, header: &Header, claims: serde_json::Value) -> String {
+        let now = chrono::Utc::now().timestamp();
+        let mut all_claims = serde_json::json!({
+            "iss": self.issuer,
+            "aud": FEDERATED_LOGIN_CLIENT_ID,
+            "iat": now,
+            "exp": now + 300,
+        });
+        all_claims.as_object_mut().unwrap().extend(claims.as_object().unwrap().clone());
+
+        encode(header, &all_claims, self.key.encoding_key()).unwrap()
+    }
+
+    // Responds to the next code exchange with an ID token carrying `claims`.
+    async fn issue_id_token(&self, app: &TestApp, claims: serde_json::Value) {
+        self.issue_raw_id_token(app, self.id_token(claims)).await;
+    }
+
+    async fn issue_raw_id_token(&self, app: &TestApp, id_token: String) {
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "upstream-access-token",
+                "token_type": "Bearer",
+                "id_token": id_token,
+            })))
+            .up_to_n_times(1)
+            .mount(&app.identity_provider_server)
+            .await;
+    }
+}
+
+fn location(response: &Response) -> Url {
+    assert!(response.status().is_redirection(), "expected a redirect, got {}", response.status());
+    let location = response.headers().get("location").unwrap().to_str().unwrap();
+    Url::parse("http://auth-service").unwrap().join(location).unwrap()
+}
+
+fn query_param(url: &Url, name: &str) -> Option<String> {
+    url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned())
+}
+
+// Starts a federated login and returns the state and nonce sent to the identity provider.
+async fn start_login(app: &TestApp) -> (String, String) {
+    let response = app.get_federated_login().await;
+    let url = location(&response);
+    (query_param(&url, "state").unwrap(), query_param(&url, "nonce").unwrap())
+}
+
+async fn log_in(app: &TestApp, provider: &IdentityProvider, sub: &str, email: &str) -> Response {
+    let (state, nonce) = start_login(app).await;
+    provider.issue_id_token(app, serde_json::json!({
+        "sub": sub,
+        "email": email,
+        "email_verified": true,
+        "nonce": nonce,
+    })).await;
+    app.get_federated_login_callback(&[("code", "upstream-code"), ("state", &state)]).await
+}
+
+fn auth_token(response: &Response) -> String {
+    response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("Auth cookie should be set")
+        .value()
+        .to_owned()
+}
+
+#[tokio::test]
+async fn should_redirect_to_identity_provider() {
+    let app = TestApp::new().await;
+    let provider = IdentityProvider::mount(&app).await;
+
+    let response = app.get_federated_login().await;
+
+    let url = location(&response);
+    assert_eq!(url.as_str().split('?').next().unwrap(), format!("{}/authorize", provider.issuer));
+    assert_eq!(query_param(&url, "client_id").as_deref(), Some(FEDERATED_LOGIN_CLIENT_ID));
+    assert_eq!(query_param(&url, "response_type").as_deref(), Some("code"));
+    assert_eq!(query_param(&url, "code_challenge_method").as_deref(), Some("S256"));
+    assert!(query_param(&url, "scope").unwrap().contains("openid"));
+    assert!(query_param(&url, "state").is_some());
+    assert!(query_param(&url, "nonce").is_some());
+}
+
+#[tokio::test]
+async fn should_create_user_on_first_login() {
+    let app = TestApp::new().await;
+    let provider = IdentityProvider::mount(&app).await;
+    let email = get_random_email();
+
+    let response = log_in(&app, &provider, "upstream-user", &email).await;
+    assert_eq!(location(&response).path(), "/");
+
+    let response = app.get_userinfo(&auth_token(&response)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: UserInfoResponse = response.json().await.unwrap();
+    assert_eq!(body.email, email);
+    assert!(body.email_verified);
+}
+
+#[tokio::test]
+async fn should_link_identity_to_logged_in_account() {
+    let app = TestApp::new().await;
+    let provider = IdentityProvider::mount(&app).await;
+    let email = app.log_in_new_user().await;
+
+    let response = log_in(&app, &provider, "upstream-user", &email).await;
+    assert_eq!(location(&response).path(), "/");
+    assert_eq!(app.post_logout().await.status().as_u16(), 200);
+
+    // Once linked, the provider's subject identifies the account even if its email changes there.
+    let response = log_in(&app, &provider, "upstream-user", &get_random_email()).await;
+    let body: UserInfoResponse = app.get_userinfo(&auth_token(&response)).await.json().await.unwrap();
+    assert_eq!(body.email, email);
+}
+
+#[tokio::test]
+async fn should_not_link_identity_to_account_with_same_email() {
+    let app = TestApp::new().await;
+    let provider = IdentityProvider::mount(&app).await;
+
+    for verified in [true, false] {
+        let email = get_random_email();
+        let response = app.post_signup(&serde_json::json!({
+            "email": email,
+            "password": "password123",
+            "requires2FA": false
+        })).await;
+        assert_eq!(response.status().as_u16(), 201);
+        if verified {
+            app.verify_email(&email).await;
+        }
+
+        let response = log_in(&app, &provider, "upstream-user", &email).await;
+        assert_eq!(response.status().as_u16(), 409);
+        assert!(response.cookies().all(|cookie| cookie.name() != JWT_COOKIE_NAME));
+
+        let user = app.user_store.read().await.get_user(&email).await.unwrap();
+        assert_eq!(user.email_verified, verified);
+        assert!(app.user_store.read().await.validate_user(&email, "password123").await.is_ok());
+    }
+}
+
+#[tokio::test]
+async fn should_require_2fa_of_linked_account() {
+    let app = TestApp::new().await;
+    let provider = IdentityProvider::mount(&app).await;
+    let email = app.log_in_new_user().await;
+
+    let response = log_in(&app, &provider, "upstream-user", &email).await;
+    assert_eq!(location(&response).path(), "/");
+    assert_eq!(app.post_logout().await.status().as_u16(), 200);
+    app.user_store.write().await.set_two_fa_method(&email, TwoFAMethod::Email).await.unwrap();
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let response = log_in(&app, &provider, "upstream-user", &email).await;
+    assert!(response.cookies().all(|cookie| cookie.name() != JWT_COOKIE_NAME));
+    let url = location(&response);
+    assert_eq!(url.path(), "/");
+    assert_eq!(query_param(&url, "email").as_deref(), Some(email.as_str()));
+    assert_eq!(query_param(&url, "two_fa_method").as_deref(), Some("email"));
+
+    let (login_attempt_id, code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&Email::parse(Secret::new(email.clone())).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(query_param(&url, "login_attempt_id").as_deref(), Some(login_attempt_id.as_ref().expose_secret().as_str()));
+
+    let response = app.post_verify_2fa(&serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id.as_ref().expose_secret(),
+        "2FACode": code.as_ref().expose_secret(),
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_401_if_state_does_not_match() {
+    let app = TestApp::new().await;
+    let provider = IdentityProvider::mount(&app).await;
+
+    let (_, nonce) = start_login(&app).await;
+    provider.issue_id_token(&app, serde_json::json!({
+        "sub": "upstream-user",
+        "email": get_random_email(),
+        "email_verified": true,
+        "nonce": nonce,
+    })).await;
+
+    let response = app.get_federated_login_callback(&[("code", "upstream-code"), ("state", "forged")]).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_if_nonce_does_not_match() {
+    let app = TestApp::new().await;
+    let provider = IdentityProvider::mount(&app).await;
+
+    let (state, _) = start_login(&app).await;
+    provider.issue_id_token(&app, serde_json::json!({
+        "sub": "upstream-user",
+        "email": get_random_email(),
+        "email_verified": true,
+        "nonce": "replayed",
+    })).await;
+
+    let response = app.get_federated_login_callback(&[("code", "upstream-code"), ("state", &state)]).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_if_id_token_is_not_signed_by_provider() {
+    let app = TestApp::new().await;
+    let provider = IdentityProvider::mount(&app).await;
+    let impostor = IdentityProvider {
+        key: SigningKey::generate().unwrap(),
+        issuer: provider.issuer.clone(),
+    };
+
+    let (state, nonce) = start_login(&app).await;
+    impostor.issue_id_token(&app, serde_json::json!({
+        "sub": "upstream-user",
+        "email": get_random_email(),
+        "email_verified": true,
+        "nonce": nonce,
+    })).await;
+
+    let response = app.get_federated_login_callback(&[("code", "upstream-code"), ("state", &state)]).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_403_if_email_is_not_verified_by_provider() {
+    let app = TestApp::new().await;
+    let provider = IdentityProvider::mount(&app).await;
+
+    let (state, nonce) = start_login(&app).await;
+    provider.issue_id_token(&app, serde_json::json!({
+        "sub": "upstream-user",
+        "email": get_random_email(),
+        "email_verified": false,
+        "nonce": nonce,
+    })).await;
+
+    let response = app.get_federated_login_callback(&[("code", "upstream-code"), ("state", &state)]).await;
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn should_return_401_if_id_token_has_no_key_id() {
+    let app = TestApp::new().await;
+    let provider = IdentityProvider::mount(&app).await;
+
+    let (state, nonce) = start_login(&app).await;
+    let id_token = provider.id_token_with_header(&Header::new(SIGNING_ALGORITHM), serde_json::json!({
+        "sub": "upstream-user",
+        "email": get_random_email(),
+        "email_verified": true,
+        "nonce": nonce,
+    }));
+    provider.issue_raw_id_token(&app, id_token).await;
+
+    let response = app.get_federated_login_callback(&[("code", "upstream-code"), ("state", &state)]).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
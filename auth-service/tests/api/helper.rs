@@ -1,16 +1,25 @@
 use auth_service::{
     Application,
-    app_state::{AppState, BannedTokenStoreType, TwoFACodeStoreType},
+    app_state::{AppState, BannedTokenStoreType, OAuthClientStoreType, TwoFACodeStoreType, UserStoreType},
     get_postgres_pool,
     get_redis_client,
     services::data_stores::{
         PostgresUserStore,
         RedisBannedTokenStore,
         RedisTwoFACodeStore,
+        RedisPasswordResetTokenStore,
+        RedisRefreshTokenStore,
+        HashmapRateLimitStore,
+        PostgresOAuthClientStore,
+        RedisAuthorizationCodeStore,
+        RedisSessionStore,
+        RedisAuditHeadStore,
+        PostgresApiKeyStore,
+        PostgresAuditLogStore,
     },
-    services::PostmarkEmailClient,
-    domain::{data_stores::{UserStore, BannedTokenStore, TwoFACodeStore}, Email},
-    utils::constants::{test, DATABASE_URL},
+    services::{OidcIdentityProvider, PostmarkEmailClient},
+    domain::{data_stores::{UserStore, BannedTokenStore, TwoFACodeStore, PasswordResetTokenStore, RefreshTokenStore, RateLimitStore, OAuthClientStore, AuthorizationCodeStore, SessionStore, ApiKeyStore, AuditLogStore, AuditHeadStore}, Email, OAuthClient},
+    utils::{auth::generate_email_verification_token, constants::{test, AUDIT_LOG_SECRET, DATABASE_URL, REFRESH_TOKEN_COOKIE_NAME}},
 };
 use reqwest::{Response, Client};
 use uuid::Uuid;
@@ -19,22 +28,32 @@ use std::sync::Arc;
 use reqwest::cookie::Jar;
 use sqlx::{PgPool, postgres::PgPoolOptions, Executor};
 use secrecy::{ExposeSecret, Secret};
-use redis;
-use wiremock::MockServer;
+use wiremock::{matchers::{method, path}, Mock, MockServer, ResponseTemplate};
+
+pub const FEDERATED_LOGIN_CLIENT_ID: &str = "auth-service";
+pub const FEDERATED_LOGIN_CLIENT_SECRET: &str = "identity-provider-secret";
 
 pub struct TestApp {
     pub address: String,
     pub cookie_jar: Arc<Jar>,
+    pub user_store: UserStoreType,
     pub banned_token_store: BannedTokenStoreType,
     pub two_fa_code_store: TwoFACodeStoreType,
+    pub oauth_client_store: OAuthClientStoreType,
     pub http_client: reqwest::Client,
     pub email_server: MockServer, // New!
+    pub identity_provider_server: MockServer,
     pub db_name: String,
     pub clean_up_called: bool,
 }
 
 impl TestApp {
     pub async fn new() -> Self {
+        Self::with_config(|_| {}).await
+    }
+
+    // Starts the app after `configure` has adjusted its settings.
+    pub async fn with_config(configure: impl FnOnce(&mut AppState)) -> Self {
         // Generate unique database name for this test
         let db_name = Uuid::new_v4().to_string();
         
@@ -44,7 +63,20 @@ impl TestApp {
         // Configure Redis connections for tests
         let redis_conn_banned = configure_redis();
         let redis_conn_2fa = configure_redis();
+        let redis_conn_2fa_enrollment = configure_redis();
+        let redis_conn_password_reset = configure_redis();
+        let redis_conn_refresh_token = configure_redis();
+        let redis_conn_authorization_code = configure_redis();
+        let redis_conn_session = configure_redis();
         
+        let oauth_client_store = Arc::new(RwLock::new(Box::new(PostgresOAuthClientStore::new(pg_pool.clone())) as Box<dyn OAuthClientStore + Send + Sync>));
+
+        let api_key_store = Arc::new(RwLock::new(Box::new(PostgresApiKeyStore::new(pg_pool.clone())) as Box<dyn ApiKeyStore + Send + Sync>));
+
+        let audit_log_store = Arc::new(PostgresAuditLogStore::new(pg_pool.clone(), AUDIT_LOG_SECRET.clone())) as Arc<dyn AuditLogStore + Send + Sync>;
+        // Namespaced by database, as the tests share Redis but each has its own log.
+        let audit_head_store = Arc::new(RedisAuditHeadStore::with_namespace(Arc::new(RwLock::new(configure_redis())), &db_name)) as Arc<dyn AuditHeadStore + Send + Sync>;
+
         // Use PostgresUserStore
         let user_store = Arc::new(RwLock::new(Box::new(PostgresUserStore::new(pg_pool)) as Box<dyn UserStore + Send + Sync>));
         
@@ -53,18 +85,51 @@ impl TestApp {
         
         // Use RedisTwoFACodeStore instead of HashmapTwoFACodeStore
         let two_fa_code_store = Arc::new(RwLock::new(Box::new(RedisTwoFACodeStore::new(Arc::new(RwLock::new(redis_conn_2fa)))) as Box<dyn TwoFACodeStore + Send + Sync>));
+        let two_fa_enrollment_code_store = Arc::new(RwLock::new(Box::new(RedisTwoFACodeStore::with_namespace(Arc::new(RwLock::new(redis_conn_2fa_enrollment)), "two_fa_enrollment")) as Box<dyn TwoFACodeStore + Send + Sync>));
         
+        let password_reset_token_store = Arc::new(RwLock::new(Box::new(RedisPasswordResetTokenStore::new(Arc::new(RwLock::new(redis_conn_password_reset)))) as Box<dyn PasswordResetTokenStore + Send + Sync>));
+
+        let refresh_token_store = Arc::new(RwLock::new(Box::new(RedisRefreshTokenStore::new(Arc::new(RwLock::new(redis_conn_refresh_token)))) as Box<dyn RefreshTokenStore + Send + Sync>));
+
+        let authorization_code_store = Arc::new(RwLock::new(Box::new(RedisAuthorizationCodeStore::new(Arc::new(RwLock::new(redis_conn_authorization_code)))) as Box<dyn AuthorizationCodeStore + Send + Sync>));
+
+        let session_store = Arc::new(RwLock::new(Box::new(RedisSessionStore::new(Arc::new(RwLock::new(redis_conn_session)))) as Box<dyn SessionStore + Send + Sync>));
+
+        // Rate limit counters are kept in memory so parallel tests, which all connect from 127.0.0.1, don't throttle each other.
+        let rate_limit_store = Arc::new(RwLock::new(Box::new(HashmapRateLimitStore::new()) as Box<dyn RateLimitStore + Send + Sync>));
+
         // Set up a mock email server
         let email_server = MockServer::start().await; // New!
         let base_url = email_server.uri(); // New!
         let email_client = Arc::new(configure_postmark_email_client(base_url)); // Updated!
 
-        let app_state = AppState::new(
-            user_store,
+        // Set up a mock upstream identity provider for federated login
+        let identity_provider_server = MockServer::start().await;
+        let identity_provider = OidcIdentityProvider::new(
+            identity_provider_server.uri(),
+            FEDERATED_LOGIN_CLIENT_ID.to_owned(),
+            Some(Secret::new(FEDERATED_LOGIN_CLIENT_SECRET.to_owned())),
+            Client::new(),
+        );
+
+        let mut app_state = AppState::new(
+            user_store.clone(),
             banned_token_store.clone(),
             two_fa_code_store.clone(),
+            two_fa_enrollment_code_store,
+            password_reset_token_store,
+            refresh_token_store,
+            rate_limit_store,
+            oauth_client_store.clone(),
+            authorization_code_store,
+            session_store,
+            api_key_store,
+            audit_log_store,
+            audit_head_store,
             email_client,
         );
+        app_state.identity_provider = Some(Arc::new(identity_provider));
+        configure(&mut app_state);
 
         // Build the application using the test address
         let app = Application::build(app_state, test::APP_ADDRESS)
@@ -83,7 +148,7 @@ impl TestApp {
 
         // Create an HTTP client
         let http_client = reqwest::Client::builder()
-            .cookie_store(true)
+            .cookie_provider(cookie_jar.clone())
             .build()
             .unwrap();
 
@@ -91,18 +156,55 @@ impl TestApp {
         Self {
             address,
             cookie_jar,
+            user_store,
             banned_token_store,
             two_fa_code_store,
+            oauth_client_store,
             http_client,
             email_server, // New!
+            identity_provider_server,
             db_name,
             clean_up_called: false,
         }
     }
 
+    // Redirects aren't followed so tests can inspect where the user would be sent.
+    fn no_redirect_client(&self) -> Client {
+        reqwest::Client::builder()
+            .cookie_provider(self.cookie_jar.clone())
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap()
+    }
+
     pub async fn get_root(&self) -> Response {
         self.http_client
-            .get(&format!("{}/", &self.address))
+            .get(format!("{}/", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_jwks(&self) -> Response {
+        self.http_client
+            .get(format!("{}/.well-known/jwks.json", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_openid_configuration(&self) -> Response {
+        self.http_client
+            .get(format!("{}/.well-known/openid-configuration", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_userinfo(&self, access_token: &str) -> Response {
+        self.http_client
+            .get(format!("{}/userinfo", &self.address))
+            .bearer_auth(access_token)
             .send()
             .await
             .expect("Failed to execute request.")
@@ -113,7 +215,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/signup", &self.address))
+            .post(format!("{}/signup", &self.address))
             .json(body)
             .send()
             .await
@@ -129,7 +231,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/login", &self.address))
+            .post(format!("{}/login", &self.address))
             .json(body)
             .send()
             .await
@@ -138,7 +240,7 @@ impl TestApp {
 
     pub async fn post_login_without_body(&self) -> Response {
         self.http_client
-            .post(&format!("{}/login", &self.address))
+            .post(format!("{}/login", &self.address))
             .send()
             .await
             .expect("Failed to send request")
@@ -146,7 +248,7 @@ impl TestApp {
 
     pub async fn post_login_malformed(&self) -> Response {
         self.http_client
-            .post(&format!("{}/login", &self.address))
+            .post(format!("{}/login", &self.address))
             .json(&serde_json::json!({}))
             .send()
             .await
@@ -162,7 +264,229 @@ impl TestApp {
 
     pub async fn post_logout(&self) -> Response {
         self.http_client
-            .post(&format!("{}/logout", &self.address))
+            .post(format!("{}/logout", &self.address))
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn get_sessions(&self) -> Response {
+        self.http_client
+            .get(format!("{}/sessions", &self.address))
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn delete_session(&self, session_id: &str) -> Response {
+        self.http_client
+            .delete(format!("{}/sessions/{}", &self.address, session_id))
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn post_logout_all(&self) -> Response {
+        self.http_client
+            .post(format!("{}/logout-all", &self.address))
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn delete_account<Body>(&self, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .delete(format!("{}/account", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn post_change_password<Body>(&self, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/change-password", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn post_change_email<Body>(&self, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/change-email", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn post_confirm_email_change(&self, token: &str) -> Response {
+        self.http_client
+            .post(format!("{}/confirm-email-change", &self.address))
+            .json(&serde_json::json!({ "token": token }))
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn post_undo_email_change(&self, token: &str) -> Response {
+        self.http_client
+            .post(format!("{}/undo-email-change", &self.address))
+            .json(&serde_json::json!({ "token": token }))
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn post_enable_2fa(&self) -> Response {
+        self.http_client
+            .post(format!("{}/enable-2fa", &self.address))
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn post_confirm_enable_2fa<Body>(&self, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/confirm-enable-2fa", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn post_disable_2fa<Body>(&self, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/disable-2fa", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn get_admin(&self, path: &str) -> Response {
+        self.http_client
+            .get(format!("{}/admin{}", &self.address, path))
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn post_admin(&self, path: &str) -> Response {
+        self.http_client
+            .post(format!("{}/admin{}", &self.address, path))
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn post_admin_with_body<Body>(&self, path: &str, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/admin{}", &self.address, path))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn delete_admin(&self, path: &str) -> Response {
+        self.http_client
+            .delete(format!("{}/admin{}", &self.address, path))
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn get_audit_events(&self, query: &[(&str, &str)]) -> Response {
+        self.http_client
+            .get(format!("{}/admin/audit-events", &self.address))
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    // Rewrites an audit event behind the service's back, as an attacker with database access could.
+    pub async fn tamper_with_audit_event(&self, id: i64, actor: &str) {
+        let url = format!("{}/{}", DATABASE_URL.expose_secret(), self.db_name);
+        let pool = get_postgres_pool(&Secret::new(url)).await.expect("Failed to connect to database");
+        sqlx::query("UPDATE audit_events SET actor = $2 WHERE id = $1")
+            .bind(id)
+            .bind(actor)
+            .execute(&pool)
+            .await
+            .expect("Failed to update audit event");
+    }
+
+    // Removes the newest audit event and returns its id.
+    pub async fn delete_last_audit_event(&self) -> i64 {
+        let url = format!("{}/{}", DATABASE_URL.expose_secret(), self.db_name);
+        let pool = get_postgres_pool(&Secret::new(url)).await.expect("Failed to connect to database");
+        sqlx::query_scalar("DELETE FROM audit_events WHERE id = (SELECT MAX(id) FROM audit_events) RETURNING id")
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to delete audit event")
+    }
+
+    pub async fn post_api_key<Body>(&self, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/api-keys", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn get_api_keys(&self) -> Response {
+        self.http_client
+            .get(format!("{}/api-keys", &self.address))
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn post_rotate_api_key(&self, id: &str) -> Response {
+        self.http_client
+            .post(format!("{}/api-keys/{}/rotate", &self.address, id))
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn delete_api_key(&self, id: &str) -> Response {
+        self.http_client
+            .delete(format!("{}/api-keys/{}", &self.address, id))
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn post_verify_token_with_bearer(&self, token: &str) -> Response {
+        self.http_client
+            .post(format!("{}/verify-token", &self.address))
+            .bearer_auth(token)
             .send()
             .await
             .expect("Failed to send request")
@@ -173,7 +497,19 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/verify-2fa", &self.address))
+            .post(format!("{}/verify-2fa", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn post_resend_2fa<Body>(&self, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/resend-2fa", &self.address))
             .json(body)
             .send()
             .await
@@ -185,12 +521,318 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/verify-token", &self.address))
+            .post(format!("{}/verify-token", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_forgot_password<Body>(&self, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/forgot-password", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_reset_password<Body>(&self, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/reset-password", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_verify_email<Body>(&self, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/verify-email", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_resend_verification_email<Body>(&self, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/resend-verification-email", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Marks the account as verified without going through the mock email server.
+    pub async fn verify_email(&self, email: &str) {
+        let email = Email::parse(Secret::new(email.to_owned())).unwrap();
+        let token = generate_email_verification_token(&email).unwrap();
+
+        let response = self.post_verify_email(&serde_json::json!({ "token": token })).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    // Signs up a verified user without 2FA and logs them in, returning their email.
+    pub async fn log_in_new_user(&self) -> String {
+        self.log_in_new_user_with_response().await.0
+    }
+
+    // Same as `log_in_new_user`, also returning the login response to read its cookies from.
+    pub async fn log_in_new_user_with_response(&self) -> (String, Response) {
+        let email = get_random_email();
+        let response = self.post_signup(&serde_json::json!({
+            "email": email,
+            "password": "password123",
+            "requires2FA": false
+        })).await;
+        assert_eq!(response.status().as_u16(), 201);
+        self.verify_email(&email).await;
+
+        let response = self.post_login(&serde_json::json!({
+            "email": email,
+            "password": "password123"
+        })).await;
+        assert_eq!(response.status().as_u16(), 200);
+        (email, response)
+    }
+
+    // Signs up a user with the admin role and logs them in, returning their email.
+    pub async fn log_in_new_admin(&self) -> String {
+        let email = self.log_in_new_user().await;
+        self.user_store.write().await.add_role(&email, "admin").await.unwrap();
+
+        // The role is only picked up by tokens issued after it was granted.
+        let response = self.post_login(&serde_json::json!({
+            "email": email,
+            "password": "password123"
+        })).await;
+        assert_eq!(response.status().as_u16(), 200);
+        email
+    }
+
+    // Logs `email` in from a separate client, as if from another browser.
+    pub async fn log_in_on_other_device(&self, email: &str, user_agent: &str) -> Client {
+        let client = Client::builder()
+            .cookie_store(true)
+            .user_agent(user_agent)
+            .build()
+            .unwrap();
+        let response = client
+            .post(format!("{}/login", &self.address))
+            .json(&serde_json::json!({
+                "email": email,
+                "password": "password123"
+            }))
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert_eq!(response.status().as_u16(), 200);
+        client
+    }
+
+    pub async fn add_oauth_client(&self, client_id: &str, redirect_uri: &str, client_secret: Option<&str>) {
+        let client = OAuthClient::new(
+            client_id.to_owned(),
+            client_id.to_owned(),
+            vec![redirect_uri.to_owned()],
+            client_secret.map(|secret| Secret::new(secret.to_owned())),
+            Vec::new(),
+        );
+        self.oauth_client_store.write().await.add_client(client).await.unwrap();
+    }
+
+    // Adds a confidential client with no redirect URIs, for the client-credentials grant.
+    pub async fn add_service_client(&self, client_id: &str, client_secret: &str, scopes: &[&str]) {
+        let client = OAuthClient::new(
+            client_id.to_owned(),
+            client_id.to_owned(),
+            Vec::new(),
+            Some(Secret::new(client_secret.to_owned())),
+            scopes.iter().map(|scope| scope.to_string()).collect(),
+        );
+        self.oauth_client_store.write().await.add_client(client).await.unwrap();
+    }
+
+    pub async fn get_authorize(&self, query: &[(&str, &str)]) -> Response {
+        self.no_redirect_client()
+            .get(format!("{}/authorize", &self.address))
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_federated_login(&self) -> Response {
+        self.no_redirect_client()
+            .get(format!("{}/federated-login", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_federated_login_callback(&self, query: &[(&str, &str)]) -> Response {
+        self.no_redirect_client()
+            .get(format!("{}/federated-login/callback", &self.address))
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_token(&self, form: &[(&str, &str)]) -> Response {
+        self.http_client
+            .post(format!("{}/token", &self.address))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_token_with_basic_auth(&self, client_id: &str, client_secret: &str, form: &[(&str, &str)]) -> Response {
+        self.http_client
+            .post(format!("{}/token", &self.address))
+            .basic_auth(client_id, Some(client_secret))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_introspect(&self, form: &[(&str, &str)]) -> Response {
+        self.http_client
+            .post(format!("{}/introspect", &self.address))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_introspect_with_basic_auth(&self, client_id: &str, client_secret: &str, form: &[(&str, &str)]) -> Response {
+        self.http_client
+            .post(format!("{}/introspect", &self.address))
+            .basic_auth(client_id, Some(client_secret))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_introspect_with_bearer(&self, token: &str, form: &[(&str, &str)]) -> Response {
+        self.http_client
+            .post(format!("{}/introspect", &self.address))
+            .bearer_auth(token)
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_refresh(&self) -> Response {
+        self.http_client
+            .post(format!("{}/refresh", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Sends `token` as the refresh cookie instead of whatever the cookie store holds.
+    pub async fn post_refresh_with_token(&self, token: &str) -> Response {
+        Client::new()
+            .post(format!("{}/refresh", &self.address))
+            .header("Cookie", format!("{}={}", REFRESH_TOKEN_COOKIE_NAME, token))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_enroll_totp(&self) -> Response {
+        self.http_client
+            .post(format!("{}/enroll-totp", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_confirm_totp<Body>(&self, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/confirm-totp", &self.address))
             .json(body)
             .send()
             .await
             .expect("Failed to execute request.")
     }
+
+    pub async fn post_regenerate_recovery_codes(&self) -> Response {
+        self.http_client
+            .post(format!("{}/regenerate-recovery-codes", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Has the mock email server accept every email sent.
+    pub async fn mount_email_server(&self) {
+        Mock::given(path("/email"))
+            .and(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&self.email_server)
+            .await;
+    }
+
+    // Returns the value of the `token` query parameter from the link in the last email sent.
+    pub async fn get_emailed_token(&self) -> String {
+        let requests = self
+            .email_server
+            .received_requests()
+            .await
+            .expect("Request recording should be enabled");
+        let request = requests.last().expect("No email was sent");
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        extract_token(body["TextBody"].as_str().unwrap())
+    }
+
+    // Like `get_emailed_token`, but for the last email sent to `recipient`.
+    pub async fn get_token_emailed_to(&self, recipient: &str) -> String {
+        let requests = self
+            .email_server
+            .received_requests()
+            .await
+            .expect("Request recording should be enabled");
+        let body = requests
+            .iter()
+            .rev()
+            .map(|request| serde_json::from_slice::<serde_json::Value>(&request.body).unwrap())
+            .find(|body| body["To"] == recipient)
+            .unwrap_or_else(|| panic!("No email was sent to {}", recipient));
+        extract_token(body["TextBody"].as_str().unwrap())
+    }
+}
+
+fn extract_token(content: &str) -> String {
+    content
+        .split("token=")
+        .nth(1)
+        .expect("Email does not contain a token")
+        .split_whitespace()
+        .next()
+        .unwrap()
+        .to_owned()
 }
 
 // New!
@@ -256,6 +898,15 @@ pub fn get_random_email() -> String {
     format!("{}@example.com", Uuid::new_v4())
 }
 
+pub fn get_cookie(response: &Response, name: &str) -> String {
+    response
+        .cookies()
+        .find(|cookie| cookie.name() == name)
+        .unwrap_or_else(|| panic!("No {} cookie found", name))
+        .value()
+        .to_owned()
+}
+
 pub fn assert_status_eq(actual: reqwest::StatusCode, expected: u16) {
     assert_eq!(actual.as_u16(), expected);
 }
\ No newline at end of file
@@ -0,0 +1,127 @@
+use crate::helper::TestApp;
+use auth_service::{
+    domain::error::OAuthErrorResponse,
+    routes::{ApiKeySecretResponse, IntrospectionResponse, TokenResponse},
+    utils::constants::JWT_COOKIE_NAME,
+};
+
+const CLIENT_ID: &str = "app-service";
+const CLIENT_SECRET: &str = "app-service-secret";
+
+// Logs the admin in again to get hold of their auth token, which the cookie jar keeps to itself.
+async fn log_in_admin(app: &TestApp) -> (String, String) {
+    let email = app.log_in_new_admin().await;
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let token = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+    (email, token)
+}
+
+#[tokio::test]
+async fn should_return_claims_of_active_auth_token() {
+    let app = TestApp::new().await;
+    app.add_service_client(CLIENT_ID, CLIENT_SECRET, &[]).await;
+    let (email, token) = log_in_admin(&app).await;
+
+    let response = app.post_introspect_with_basic_auth(CLIENT_ID, CLIENT_SECRET, &[("token", &token)]).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers().get("cache-control").unwrap(), "no-store");
+    let body: IntrospectionResponse = response.json().await.unwrap();
+    assert!(body.active);
+    assert_eq!(body.sub, Some(email));
+    assert_eq!(body.exp.unwrap() - body.iat.unwrap(), 600);
+    assert_eq!(body.roles, Some(vec!["admin".to_owned()]));
+    assert_eq!(body.permissions, Some(Vec::new()));
+    assert_eq!(body.client_id, None);
+}
+
+#[tokio::test]
+async fn should_return_only_inactive_for_banned_or_invalid_token() {
+    let app = TestApp::new().await;
+    app.add_service_client(CLIENT_ID, CLIENT_SECRET, &[]).await;
+    let (_, token) = log_in_admin(&app).await;
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    for token in [token.as_str(), "invalid-token", "ak_unknown.c2VjcmV0"] {
+        let response = app.post_introspect(&[
+            ("token", token),
+            ("client_id", CLIENT_ID),
+            ("client_secret", CLIENT_SECRET),
+        ]).await;
+        assert_eq!(response.status().as_u16(), 200);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body, serde_json::json!({ "active": false }), "Failed for token: {}", token);
+    }
+}
+
+#[tokio::test]
+async fn should_introspect_api_key_for_caller_with_service_token() {
+    let app = TestApp::new().await;
+    app.add_service_client(CLIENT_ID, CLIENT_SECRET, &["introspect"]).await;
+    let email = app.log_in_new_user().await;
+    let response = app.post_api_key(&serde_json::json!({
+        "name": "CI",
+        "scopes": ["deploy", "read"]
+    })).await;
+    let api_key = response.json::<ApiKeySecretResponse>().await.unwrap();
+
+    let response = app.post_token_with_basic_auth(CLIENT_ID, CLIENT_SECRET, &[("grant_type", "client_credentials")]).await;
+    let service_token = response.json::<TokenResponse>().await.unwrap().access_token;
+
+    let response = app.post_introspect_with_bearer(&service_token, &[("token", &api_key.key)]).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: IntrospectionResponse = response.json().await.unwrap();
+    assert!(body.active);
+    assert_eq!(body.sub, Some(email));
+    assert_eq!(body.scope.as_deref(), Some("deploy read"));
+    assert_eq!(body.exp, Some(api_key.api_key.expires_at));
+    assert_eq!(body.roles, None);
+
+    let response = app.post_introspect_with_bearer(&service_token, &[("token", &service_token)]).await;
+    let body: IntrospectionResponse = response.json().await.unwrap();
+    assert!(body.active);
+    assert_eq!(body.sub.as_deref(), Some(CLIENT_ID));
+    assert_eq!(body.client_id.as_deref(), Some(CLIENT_ID));
+    assert_eq!(body.scope.as_deref(), Some("introspect"));
+}
+
+#[tokio::test]
+async fn should_return_401_if_caller_is_not_an_authenticated_service() {
+    let app = TestApp::new().await;
+    app.add_service_client(CLIENT_ID, CLIENT_SECRET, &[]).await;
+    app.add_oauth_client("public-client", "https://app.example.com/callback", None).await;
+    let (_, token) = log_in_admin(&app).await;
+
+    let responses = [
+        app.post_introspect(&[("token", &token)]).await,
+        app.post_introspect(&[("token", &token), ("client_id", "public-client")]).await,
+        app.post_introspect_with_basic_auth(CLIENT_ID, "wrong-secret", &[("token", &token)]).await,
+        // A user's token doesn't identify a service.
+        app.post_introspect_with_bearer(&token, &[("token", &token)]).await,
+    ];
+    for response in responses {
+        assert_eq!(response.status().as_u16(), 401);
+        let body: OAuthErrorResponse = response.json().await.unwrap();
+        assert_eq!(body.error, "invalid_client");
+    }
+}
+
+#[tokio::test]
+async fn should_return_400_if_token_is_missing() {
+    let app = TestApp::new().await;
+    app.add_service_client(CLIENT_ID, CLIENT_SECRET, &[]).await;
+
+    let response = app.post_introspect_with_basic_auth(CLIENT_ID, CLIENT_SECRET, &[]).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let body: OAuthErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.error, "invalid_request");
+}
@@ -0,0 +1,58 @@
+use crate::helper::{get_random_email, TestApp};
+use auth_service::utils::{auth::Claims, constants::JWT_COOKIE_NAME};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+
+#[tokio::test]
+async fn should_publish_signing_key_with_kid() {
+    let app = TestApp::new().await;
+
+    let response = app.get_jwks().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let jwks: JwkSet = response.json().await.unwrap();
+    assert!(!jwks.keys.is_empty());
+    assert!(jwks.keys.iter().all(|jwk| jwk.common.key_id.is_some()));
+}
+
+#[tokio::test]
+async fn should_allow_verifying_auth_tokens_with_published_keys() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let token = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("Auth cookie should be set")
+        .value()
+        .to_owned();
+
+    let jwks: JwkSet = app.get_jwks().await.json().await.unwrap();
+    let header = decode_header(&token).unwrap();
+    assert_eq!(header.alg, Algorithm::EdDSA);
+
+    let jwk = jwks
+        .find(&header.kid.expect("Auth token should have a kid"))
+        .expect("Auth token should be signed with a published key");
+    let claims = decode::<Claims>(
+        &token,
+        &DecodingKey::from_jwk(jwk).unwrap(),
+        &Validation::new(Algorithm::EdDSA),
+    )
+    .unwrap()
+    .claims;
+    assert_eq!(claims.sub, email);
+}
@@ -1,7 +1,7 @@
 use crate::helper::{get_random_email, TestApp};
 use auth_service::{
-    utils::constants::JWT_COOKIE_NAME, 
-    domain::{error::ErrorResponse, Email},
+    utils::constants::{rate_limits, JWT_COOKIE_NAME},
+    domain::Email,
     routes::login::TwoFactorAuthResponse
 };
 use secrecy::ExposeSecret;
@@ -49,6 +49,7 @@ async fn should_return_200_if_valid_credentials_and_2fa_disabled() {
 
     let response = app.post_signup(&signup_body).await;
     assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
 
     let login_body = serde_json::json!({
         "email": random_email,
@@ -85,8 +86,8 @@ async fn should_return_401_if_invalid_token() {
         .unwrap();
 
     let response = invalid_cookie_client
-        .post(&format!("{}/logout", &app.address))
-        .header("Cookie", &format!("{}=invalid", JWT_COOKIE_NAME))
+        .post(format!("{}/logout", &app.address))
+        .header("Cookie", format!("{}=invalid", JWT_COOKIE_NAME))
         .send()
         .await
         .unwrap();
@@ -108,6 +109,7 @@ async fn should_return_206_if_valid_credentials_and_2fa_enabled() {
 
     let response = app.post_signup(&signup_body).await;
     assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
 
     // Define an expectation for the mock server
     Mock::given(path("/email")) // Expect an HTTP request to the "/email" path
@@ -135,6 +137,7 @@ async fn should_return_206_if_valid_credentials_and_2fa_enabled() {
         .expect("Could not deserialize response body to TwoFactorAuthResponse");
         
     assert_eq!(json_body.message, "2FA required".to_owned());
+    assert_eq!(json_body.two_fa_method, "email");
 
     // NOUVEAU: Vérifier que le login_attempt_id est stocké dans le 2FA code store
     let email = Email::parse(random_email.into()).unwrap();
@@ -152,4 +155,39 @@ async fn should_return_206_if_valid_credentials_and_2fa_enabled() {
         &json_body.login_attempt_id,
         "Login attempt ID in response should match the one stored in 2FA code store"
     );
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn should_return_429_if_too_many_attempts_for_account() {
+    let app = TestApp::new().await;
+    let random_email = get_random_email();
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "wrongpassword"
+    });
+
+    for _ in 0..rate_limits::LOGIN_PER_EMAIL.max_requests {
+        let response = app.post_login(&login_body).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 429);
+
+    let retry_after: i64 = response
+        .headers()
+        .get("retry-after")
+        .expect("Retry-After header should be set")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after > 0 && retry_after <= rate_limits::LOGIN_PER_EMAIL.window_seconds);
+
+    // Other accounts aren't affected.
+    let response = app.post_login(&serde_json::json!({
+        "email": get_random_email(),
+        "password": "wrongpassword"
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
@@ -16,6 +16,7 @@ async fn should_return_200_if_valid_jwt_cookie() {
         "password": password,
         "requires2FA": false // AJOUT: champ manquant
     })).await;
+    app.verify_email(&email).await;
 
     let response = app.post_login(&serde_json::json!({
         "email": email,
@@ -50,8 +51,8 @@ async fn should_return_200_if_valid_jwt_cookie() {
         .unwrap();
 
     let logout_response = logout_client
-        .post(&format!("{}/logout", &app.address))
-        .header("Cookie", &format!("{}={}", JWT_COOKIE_NAME, token))
+        .post(format!("{}/logout", &app.address))
+        .header("Cookie", format!("{}={}", JWT_COOKIE_NAME, token))
         .send()
         .await
         .unwrap();
@@ -78,6 +79,7 @@ async fn should_return_400_if_logout_called_twice_in_a_row() {
         "password": password,
         "requires2FA": false // AJOUT: champ manquant
     })).await;
+    app.verify_email(&email).await;
 
     let response = app.post_login(&serde_json::json!({
         "email": email,
@@ -112,8 +114,8 @@ async fn should_return_400_if_logout_called_twice_in_a_row() {
 
     // Attempt to logout
     let first_logout_response = logout_client
-        .post(&format!("{}/logout", &app.address))
-        .header("Cookie", &format!("{}={}", JWT_COOKIE_NAME, token.clone()))
+        .post(format!("{}/logout", &app.address))
+        .header("Cookie", format!("{}={}", JWT_COOKIE_NAME, token.clone()))
         .send()
         .await
         .unwrap();
@@ -121,8 +123,8 @@ async fn should_return_400_if_logout_called_twice_in_a_row() {
 
     // Attempt to logout again with the same token
     let second_logout_response = logout_client
-        .post(&format!("{}/logout", &app.address))
-        .header("Cookie", &format!("{}={}", JWT_COOKIE_NAME, token))
+        .post(format!("{}/logout", &app.address))
+        .header("Cookie", format!("{}={}", JWT_COOKIE_NAME, token))
         .send()
         .await
         .unwrap();
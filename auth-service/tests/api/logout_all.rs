@@ -0,0 +1,85 @@
+use crate::helper::{get_cookie, TestApp};
+use auth_service::utils::constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME};
+
+// Signs up and logs in a new user, returning their email and the auth and refresh tokens issued.
+async fn login(app: &TestApp) -> (String, String, String) {
+    let (email, response) = app.log_in_new_user_with_response().await;
+    let auth_token = get_cookie(&response, JWT_COOKIE_NAME);
+    let refresh_token = get_cookie(&response, REFRESH_TOKEN_COOKIE_NAME);
+    (email, auth_token, refresh_token)
+}
+
+#[tokio::test]
+async fn should_invalidate_tokens_of_every_session() {
+    let app = TestApp::new().await;
+    let (email, auth_token, refresh_token) = login(&app).await;
+    let other_device = app.log_in_on_other_device(&email, "Other browser").await;
+
+    let response = app.post_logout_all().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_verify_token(&serde_json::json!({
+        "token": auth_token
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app.post_refresh_with_token(&refresh_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = other_device
+        .get(format!("{}/sessions", &app.address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = other_device
+        .post(format!("{}/refresh", &app.address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_allow_logging_in_again() {
+    let app = TestApp::new().await;
+    let (email, _, _) = login(&app).await;
+
+    assert_eq!(app.post_logout_all().await.status().as_u16(), 200);
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_verify_token(&serde_json::json!({
+        "token": get_cookie(&response, JWT_COOKIE_NAME)
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app.post_logout_all().await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_401_if_already_logged_out_everywhere() {
+    let app = TestApp::new().await;
+    let (_, auth_token, _) = login(&app).await;
+
+    assert_eq!(app.post_logout_all().await.status().as_u16(), 200);
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/logout-all", &app.address))
+        .header("Cookie", format!("{}={}", JWT_COOKIE_NAME, auth_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+}
@@ -1,9 +1,35 @@
 mod helper;
+mod admin;
+mod api_keys;
+mod audit_events;
+mod authorize;
+mod change_email;
+mod change_password;
+mod confirm_totp;
+mod delete_account;
+mod disable_2fa;
+mod enable_2fa;
+mod enroll_totp;
+mod federated_login;
+mod forgot_password;
+mod introspect;
+mod jwks;
 mod login;
 mod logout;
+mod logout_all;
+mod openid_configuration;
+mod refresh;
+mod regenerate_recovery_codes;
+mod resend_2fa;
+mod resend_verification_email;
+mod reset_password;
 mod root;
+mod sessions;
 mod signup;
+mod token;
+mod userinfo;
 mod verify_2fa;
+mod verify_email;
 mod verify_token;
 
 pub use helper::*;
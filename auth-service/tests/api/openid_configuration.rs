@@ -0,0 +1,19 @@
+use crate::helper::TestApp;
+use auth_service::routes::OpenIdConfiguration;
+
+#[tokio::test]
+async fn should_publish_provider_metadata() {
+    let app = TestApp::new().await;
+
+    let response = app.get_openid_configuration().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let configuration: OpenIdConfiguration = response.json().await.unwrap();
+    assert_eq!(configuration.authorization_endpoint, format!("{}/authorize", configuration.issuer));
+    assert_eq!(configuration.token_endpoint, format!("{}/token", configuration.issuer));
+    assert_eq!(configuration.introspection_endpoint, format!("{}/introspect", configuration.issuer));
+    assert_eq!(configuration.userinfo_endpoint, format!("{}/userinfo", configuration.issuer));
+    assert_eq!(configuration.jwks_uri, format!("{}/.well-known/jwks.json", configuration.issuer));
+    assert_eq!(configuration.id_token_signing_alg_values_supported, vec!["EdDSA"]);
+    assert_eq!(configuration.code_challenge_methods_supported, vec!["S256"]);
+}
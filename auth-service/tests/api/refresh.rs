@@ -0,0 +1,77 @@
+use crate::helper::{get_cookie, TestApp};
+use auth_service::utils::constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME};
+
+// Signs up and logs in a new user, returning the refresh token that was issued.
+async fn login(app: &TestApp) -> String {
+    let (_, response) = app.log_in_new_user_with_response().await;
+    get_cookie(&response, REFRESH_TOKEN_COOKIE_NAME)
+}
+
+#[tokio::test]
+async fn should_return_400_if_refresh_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+    let app = TestApp::new().await;
+
+    let response = app.post_refresh_with_token("invalid").await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app.post_refresh_with_token(&uuid::Uuid::new_v4().to_string()).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_200_and_rotate_tokens() {
+    let app = TestApp::new().await;
+    let refresh_token = login(&app).await;
+
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let auth_token = get_cookie(&response, JWT_COOKIE_NAME);
+    let new_refresh_token = get_cookie(&response, REFRESH_TOKEN_COOKIE_NAME);
+    assert_ne!(new_refresh_token, refresh_token);
+
+    let response = app.post_verify_token(&serde_json::json!({
+        "token": auth_token
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_refresh_with_token(&new_refresh_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_revoke_token_family_if_rotated_token_is_reused() {
+    let app = TestApp::new().await;
+    let refresh_token = login(&app).await;
+
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let new_refresh_token = get_cookie(&response, REFRESH_TOKEN_COOKIE_NAME);
+
+    let response = app.post_refresh_with_token(&refresh_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // The legitimate holder of the latest token is logged out as well.
+    let response = app.post_refresh_with_token(&new_refresh_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_revoke_refresh_token_on_logout() {
+    let app = TestApp::new().await;
+    let refresh_token = login(&app).await;
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_refresh_with_token(&refresh_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
@@ -0,0 +1,42 @@
+use crate::helper::{get_random_email, TestApp};
+use auth_service::{routes::RecoveryCodesResponse, utils::constants::RECOVERY_CODE_COUNT};
+use std::collections::HashSet;
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app.post_regenerate_recovery_codes().await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_200_with_new_codes() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_regenerate_recovery_codes().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let first_set = response.json::<RecoveryCodesResponse>().await.unwrap().recovery_codes;
+    assert_eq!(first_set.len(), RECOVERY_CODE_COUNT);
+    assert_eq!(first_set.iter().collect::<HashSet<_>>().len(), RECOVERY_CODE_COUNT);
+
+    let response = app.post_regenerate_recovery_codes().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let second_set = response.json::<RecoveryCodesResponse>().await.unwrap().recovery_codes;
+    assert!(first_set.iter().all(|code| !second_set.contains(code)));
+}
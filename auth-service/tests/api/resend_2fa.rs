@@ -0,0 +1,97 @@
+use crate::helper::{get_random_email, TestApp};
+use auth_service::{
+    domain::{data_stores::LoginAttemptId, error::ErrorResponse},
+    routes::login::TwoFactorAuthResponse,
+};
+use secrecy::ExposeSecret;
+use wiremock::{matchers::{method, path}, Mock, ResponseTemplate};
+
+// Signs up a verified user with email 2FA and logs in, returning the login attempt id.
+// `expected_emails` counts the 2FA emails sent from the login onwards.
+async fn start_2fa_login(app: &TestApp, email: &str, expected_emails: u64) -> String {
+    let password = "password123";
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": password,
+        "requires2FA": true
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(email).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(expected_emails)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": password
+    })).await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let body: TwoFactorAuthResponse = response.json().await.unwrap();
+    body.login_attempt_id
+}
+
+#[tokio::test]
+async fn should_return_422_if_malformed_input() {
+    let app = TestApp::new().await;
+
+    let response = app.post_resend_2fa(&serde_json::json!({
+        "email": get_random_email()
+    })).await;
+    assert_eq!(response.status().as_u16(), 422);
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_input() {
+    let app = TestApp::new().await;
+
+    let response = app.post_resend_2fa(&serde_json::json!({
+        "email": "not-an-email",
+        "loginAttemptId": LoginAttemptId::default().as_ref().expose_secret()
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app.post_resend_2fa(&serde_json::json!({
+        "email": get_random_email(),
+        "loginAttemptId": "invalid"
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_401_if_login_attempt_id_does_not_match() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+
+    start_2fa_login(&app, &email, 1).await;
+
+    let response = app.post_resend_2fa(&serde_json::json!({
+        "email": email,
+        "loginAttemptId": LoginAttemptId::default().as_ref().expose_secret()
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_enforce_cooldown_from_when_code_was_sent() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+
+    // Only the login's email; resending right away isn't allowed.
+    let login_attempt_id = start_2fa_login(&app, &email, 1).await;
+
+    let response = app.post_resend_2fa(&serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id
+    })).await;
+    assert_eq!(response.status().as_u16(), 429);
+    assert!(response.headers().contains_key("retry-after"));
+
+    let body: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.error, "Too many requests");
+}
@@ -0,0 +1,94 @@
+use crate::helper::{get_random_email, TestApp};
+use wiremock::{matchers::{method, path}, Mock, ResponseTemplate};
+
+#[tokio::test]
+async fn should_return_422_if_malformed_input() {
+    let app = TestApp::new().await;
+
+    let response = app.post_resend_verification_email(&serde_json::json!({})).await;
+    assert_eq!(response.status().as_u16(), 422);
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_email() {
+    let app = TestApp::new().await;
+
+    let response = app.post_resend_verification_email(&serde_json::json!({
+        "email": "not-an-email"
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_200_and_send_email_if_user_is_unverified() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app.post_resend_verification_email(&serde_json::json!({
+        "email": email
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let token = app.get_emailed_token().await;
+    let response = app.post_verify_email(&serde_json::json!({
+        "token": token
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_200_without_sending_email_if_already_verified() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app.post_resend_verification_email(&serde_json::json!({
+        "email": email
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_200_without_sending_email_if_user_does_not_exist() {
+    let app = TestApp::new().await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app.post_resend_verification_email(&serde_json::json!({
+        "email": get_random_email()
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
@@ -0,0 +1,159 @@
+use crate::helper::{get_random_email, TestApp};
+use auth_service::{domain::data_stores::PasswordResetToken, utils::constants::JWT_COOKIE_NAME};
+use secrecy::ExposeSecret;
+use wiremock::{matchers::{method, path}, Mock, ResponseTemplate};
+
+async fn request_reset_token(app: &TestApp, email: &str) -> String {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app.post_forgot_password(&serde_json::json!({
+        "email": email
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.get_emailed_token().await
+}
+
+async fn signup(app: &TestApp, email: &str, password: &str) {
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": password,
+        "requires2FA": false
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(email).await;
+}
+
+#[tokio::test]
+async fn should_return_422_if_malformed_input() {
+    let app = TestApp::new().await;
+
+    let response = app.post_reset_password(&serde_json::json!({})).await;
+    assert_eq!(response.status().as_u16(), 422);
+}
+
+#[tokio::test]
+async fn should_return_400_if_new_password_is_invalid() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email, "password123").await;
+    let token = request_reset_token(&app, &email).await;
+
+    let response = app.post_reset_password(&serde_json::json!({
+        "token": token,
+        "newPassword": "short"
+    })).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    // The token must still be usable after a rejected password.
+    let response = app.post_reset_password(&serde_json::json!({
+        "token": token,
+        "newPassword": "newpassword123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_401_if_unknown_token() {
+    let app = TestApp::new().await;
+
+    let response = app.post_reset_password(&serde_json::json!({
+        "token": PasswordResetToken::default().as_ref().expose_secret(),
+        "newPassword": "newpassword123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app.post_reset_password(&serde_json::json!({
+        "token": "not-a-token",
+        "newPassword": "newpassword123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_200_and_update_password_if_valid_token() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email, "password123").await;
+    let token = request_reset_token(&app, &email).await;
+
+    let response = app.post_reset_password(&serde_json::json!({
+        "token": token,
+        "newPassword": "newpassword123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "newpassword123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_401_if_token_used_twice() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email, "password123").await;
+    let token = request_reset_token(&app, &email).await;
+
+    let response = app.post_reset_password(&serde_json::json!({
+        "token": token,
+        "newPassword": "newpassword123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_reset_password(&serde_json::json!({
+        "token": token,
+        "newPassword": "anotherpassword123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_revoke_existing_tokens() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email, "password123").await;
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+    let jwt = auth_cookie.value().to_owned();
+
+    // Token timestamps have one second resolution.
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+
+    let token = request_reset_token(&app, &email).await;
+    let response = app.post_reset_password(&serde_json::json!({
+        "token": token,
+        "newPassword": "newpassword123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_verify_token(&serde_json::json!({
+        "token": jwt
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 401);
+}
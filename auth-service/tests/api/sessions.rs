@@ -0,0 +1,114 @@
+use crate::helper::TestApp;
+use auth_service::routes::SessionsResponse;
+use reqwest::Client;
+
+async fn get_sessions(app: &TestApp, client: &Client) -> reqwest::Response {
+    client
+        .get(format!("{}/sessions", &app.address))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn should_list_sessions() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+    app.log_in_on_other_device(&email, "Other browser").await;
+
+    let response = app.get_sessions().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: SessionsResponse = response.json().await.unwrap();
+
+    assert_eq!(body.sessions.len(), 2);
+    assert!(body.sessions[0].current);
+    assert!(body.sessions[0].created_at <= body.sessions[1].created_at);
+    assert!(!body.sessions[1].current);
+    assert_eq!(body.sessions[1].user_agent.as_deref(), Some("Other browser"));
+    assert_eq!(body.sessions[1].ip.as_deref(), Some("127.0.0.1"));
+}
+
+#[tokio::test]
+async fn should_revoke_other_session() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+    let other_device = app.log_in_on_other_device(&email, "Other browser").await;
+
+    let body: SessionsResponse = app.get_sessions().await.json().await.unwrap();
+    let other_session = body.sessions.iter().find(|session| !session.current).unwrap();
+
+    let response = app.delete_session(&other_session.id).await;
+    assert_eq!(response.status().as_u16(), 204);
+
+    // Both the access token and the refresh token of the other device stop working.
+    assert_eq!(get_sessions(&app, &other_device).await.status().as_u16(), 401);
+    let response = other_device
+        .post(format!("{}/refresh", &app.address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+
+    let body: SessionsResponse = app.get_sessions().await.json().await.unwrap();
+    assert_eq!(body.sessions.len(), 1);
+    assert!(body.sessions[0].current);
+}
+
+#[tokio::test]
+async fn should_keep_session_across_refresh() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+    let body: SessionsResponse = app.get_sessions().await.json().await.unwrap();
+
+    assert_eq!(app.post_refresh().await.status().as_u16(), 200);
+
+    let refreshed: SessionsResponse = app.get_sessions().await.json().await.unwrap();
+    assert_eq!(refreshed.sessions.len(), 1);
+    assert_eq!(refreshed.sessions[0].id, body.sessions[0].id);
+    assert!(refreshed.sessions[0].current);
+}
+
+#[tokio::test]
+async fn should_remove_session_on_logout() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+    let other_device = app.log_in_on_other_device(&email, "Other browser").await;
+
+    assert_eq!(app.post_logout().await.status().as_u16(), 200);
+
+    let body: SessionsResponse = get_sessions(&app, &other_device).await.json().await.unwrap();
+    assert_eq!(body.sessions.len(), 1);
+    assert!(body.sessions[0].current);
+}
+
+#[tokio::test]
+async fn should_end_oldest_session_beyond_limit() {
+    let app = TestApp::with_config(|state| state.max_sessions_per_user = Some(2)).await;
+    let email = app.log_in_new_user().await;
+    let second_device = app.log_in_on_other_device(&email, "Second browser").await;
+    let third_device = app.log_in_on_other_device(&email, "Third browser").await;
+
+    assert_eq!(app.get_sessions().await.status().as_u16(), 401);
+    assert_eq!(get_sessions(&app, &second_device).await.status().as_u16(), 200);
+
+    let body: SessionsResponse = get_sessions(&app, &third_device).await.json().await.unwrap();
+    let user_agents: Vec<_> = body.sessions.iter().map(|session| session.user_agent.as_deref()).collect();
+    assert_eq!(user_agents, vec![Some("Second browser"), Some("Third browser")]);
+}
+
+#[tokio::test]
+async fn should_return_404_if_session_does_not_exist() {
+    let app = TestApp::new().await;
+    app.log_in_new_user().await;
+
+    let response = app.delete_session("unknown").await;
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    assert_eq!(app.get_sessions().await.status().as_u16(), 400);
+    assert_eq!(app.delete_session("unknown").await.status().as_u16(), 400);
+}
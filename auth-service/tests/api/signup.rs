@@ -1,19 +1,6 @@
 use crate::helper::TestApp;
-use axum::{http::StatusCode, response::IntoResponse, Json};
-use serde::Deserialize;
 use auth_service::domain::error::ErrorResponse; // CORRECTION: import correct
-
-pub async fn signup(Json(_request): Json<SignupRequest>) -> impl IntoResponse { // CORRECTION: préfixe avec _
-    StatusCode::OK.into_response()
-}
-
-#[derive(Deserialize)]
-pub struct SignupRequest {
-    pub email: String,
-    pub password: String,
-    #[serde(rename = "requires2FA")]
-    pub requires_2fa: bool,
-}
+use auth_service::{routes::RecoveryCodesResponse, utils::constants::RECOVERY_CODE_COUNT};
 
 #[tokio::test]
 async fn should_return_400_if_invalid_input() {
@@ -80,4 +67,22 @@ async fn should_return_409_if_email_already_exists() {
             .error,
         "User already exists".to_owned()
     );
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn should_return_recovery_codes_if_2fa_enabled() {
+    let app = TestApp::new().await;
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": TestApp::get_random_email(),
+        "password": "password123",
+        "requires2FA": true
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let body = response
+        .json::<RecoveryCodesResponse>()
+        .await
+        .expect("Could not deserialize response body to RecoveryCodesResponse");
+    assert_eq!(body.recovery_codes.len(), RECOVERY_CODE_COUNT);
+}
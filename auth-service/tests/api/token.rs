@@ -0,0 +1,303 @@
+use crate::helper::TestApp;
+use auth_service::{
+    domain::{error::OAuthErrorResponse, AuthenticationMethod},
+    routes::{OpenIdConfiguration, TokenResponse, UserInfoResponse, VerifyTokenResponse},
+    utils::{constants::JWT_COOKIE_NAME, oidc::IdTokenClaims},
+};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use url::Url;
+
+const CLIENT_ID: &str = "test-client";
+const CLIENT_SECRET: &str = "test-client-secret";
+const REDIRECT_URI: &str = "https://app.example.com/callback";
+// From RFC 7636, appendix B.
+const CODE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+const CODE_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+// Logs a new user in and runs the authorization step, returning the authorization code.
+async fn get_authorization_code(app: &TestApp) -> String {
+    get_authorization_code_with(app, &[]).await
+}
+
+async fn get_authorization_code_with(app: &TestApp, extra_params: &[(&str, &str)]) -> String {
+    app.log_in_new_user().await;
+
+    let mut query = vec![
+        ("response_type", "code"),
+        ("client_id", CLIENT_ID),
+        ("redirect_uri", REDIRECT_URI),
+        ("code_challenge", CODE_CHALLENGE),
+        ("code_challenge_method", "S256"),
+    ];
+    query.extend_from_slice(extra_params);
+    let response = app.get_authorize(&query).await;
+    assert!(response.status().is_redirection());
+
+    let location = Url::parse(response.headers().get("location").unwrap().to_str().unwrap()).unwrap();
+    location
+        .query_pairs()
+        .find(|(key, _)| key == "code")
+        .map(|(_, value)| value.into_owned())
+        .expect("Redirect should contain a code")
+}
+
+fn token_form<'a>(code: &'a str, code_verifier: &'a str) -> Vec<(&'a str, &'a str)> {
+    vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", REDIRECT_URI),
+        ("client_id", CLIENT_ID),
+        ("code_verifier", code_verifier),
+    ]
+}
+
+#[tokio::test]
+async fn should_exchange_code_for_access_token() {
+    let app = TestApp::new().await;
+    app.add_oauth_client(CLIENT_ID, REDIRECT_URI, None).await;
+    let code = get_authorization_code(&app).await;
+
+    let response = app.post_token(&token_form(&code, CODE_VERIFIER)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers().get("cache-control").unwrap(), "no-store");
+
+    let body: TokenResponse = response.json().await.unwrap();
+    assert_eq!(body.token_type, "Bearer");
+    assert_eq!(decode_header(&body.access_token).unwrap().typ.as_deref(), Some("at+jwt"));
+
+    let response = app.post_verify_token(&serde_json::json!({ "token": body.access_token })).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let verified: VerifyTokenResponse = response.json().await.unwrap();
+    assert_eq!(verified.aud.as_deref(), Some(CLIENT_ID));
+    assert!(verified.roles.is_empty());
+
+    let response = app.get_userinfo(&body.access_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_not_accept_access_token_as_auth_token() {
+    let app = TestApp::new().await;
+    app.add_oauth_client(CLIENT_ID, REDIRECT_URI, None).await;
+    let code = get_authorization_code(&app).await;
+    let response = app.post_token(&token_form(&code, CODE_VERIFIER)).await;
+    let body: TokenResponse = response.json().await.unwrap();
+
+    // Without the cookies of the user's own login.
+    let response = reqwest::Client::new()
+        .get(format!("{}/sessions", &app.address))
+        .header("Cookie", format!("{}={}", JWT_COOKIE_NAME, body.access_token))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_issue_id_token_for_openid_scope() {
+    let app = TestApp::new().await;
+    app.add_oauth_client(CLIENT_ID, REDIRECT_URI, None).await;
+    let code = get_authorization_code_with(&app, &[("scope", "openid email"), ("nonce", "n-0S6_WzA2Mj")]).await;
+
+    let response = app.post_token(&token_form(&code, CODE_VERIFIER)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: TokenResponse = response.json().await.unwrap();
+    let id_token = body.id_token.expect("Response should contain an ID token");
+
+    let configuration: OpenIdConfiguration = app.get_openid_configuration().await.json().await.unwrap();
+    let jwks: JwkSet = app.get_jwks().await.json().await.unwrap();
+    let jwk = jwks
+        .find(&decode_header(&id_token).unwrap().kid.unwrap())
+        .expect("ID token should be signed with a published key");
+    let mut validation = Validation::new(Algorithm::EdDSA);
+    validation.set_audience(&[CLIENT_ID]);
+    validation.set_issuer(&[&configuration.issuer]);
+    let claims = decode::<IdTokenClaims>(&id_token, &DecodingKey::from_jwk(jwk).unwrap(), &validation)
+        .unwrap()
+        .claims;
+
+    assert_ne!(claims.sub, claims.email);
+    assert!(claims.email_verified);
+    assert_eq!(claims.nonce.as_deref(), Some("n-0S6_WzA2Mj"));
+    assert_eq!(claims.amr, vec![AuthenticationMethod::Pwd]);
+    assert!(claims.auth_time > 0 && claims.auth_time as usize <= claims.iat);
+
+    // UserInfo knows the user by the same subject.
+    let response = app.get_userinfo(&body.access_token).await;
+    let userinfo: UserInfoResponse = response.json().await.unwrap();
+    assert_eq!(userinfo.sub, claims.sub);
+    assert_eq!(userinfo.email, claims.email);
+
+    // The ID token is meant for the client, not to be used as the user's own auth token.
+    let response = app.get_userinfo(&id_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+    let response = reqwest::Client::new()
+        .get(format!("{}/sessions", &app.address))
+        .header("Cookie", format!("{}={}", JWT_COOKIE_NAME, id_token))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(response.status().as_u16(), 401);
+    let response = app.post_verify_token(&serde_json::json!({ "token": id_token })).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_not_issue_id_token_without_openid_scope() {
+    let app = TestApp::new().await;
+    app.add_oauth_client(CLIENT_ID, REDIRECT_URI, None).await;
+    let code = get_authorization_code(&app).await;
+
+    let response = app.post_token(&token_form(&code, CODE_VERIFIER)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: TokenResponse = response.json().await.unwrap();
+    assert!(body.id_token.is_none());
+}
+
+#[tokio::test]
+async fn should_reject_code_used_twice() {
+    let app = TestApp::new().await;
+    app.add_oauth_client(CLIENT_ID, REDIRECT_URI, None).await;
+    let code = get_authorization_code(&app).await;
+
+    let response = app.post_token(&token_form(&code, CODE_VERIFIER)).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_token(&token_form(&code, CODE_VERIFIER)).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let body: OAuthErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.error, "invalid_grant");
+}
+
+#[tokio::test]
+async fn should_reject_wrong_code_verifier() {
+    let app = TestApp::new().await;
+    app.add_oauth_client(CLIENT_ID, REDIRECT_URI, None).await;
+    let code = get_authorization_code(&app).await;
+
+    let wrong_verifier = "a".repeat(43);
+    let response = app.post_token(&token_form(&code, &wrong_verifier)).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let body: OAuthErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.error, "invalid_grant");
+}
+
+#[tokio::test]
+async fn should_reject_mismatched_redirect_uri() {
+    let app = TestApp::new().await;
+    app.add_oauth_client(CLIENT_ID, REDIRECT_URI, None).await;
+    let code = get_authorization_code(&app).await;
+
+    let mut form = token_form(&code, CODE_VERIFIER);
+    form.retain(|(key, _)| *key != "redirect_uri");
+    form.push(("redirect_uri", "https://app.example.com/other"));
+
+    let response = app.post_token(&form).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_require_secret_for_confidential_clients() {
+    let app = TestApp::new().await;
+    app.add_oauth_client(CLIENT_ID, REDIRECT_URI, Some(CLIENT_SECRET)).await;
+    let code = get_authorization_code(&app).await;
+
+    let response = app.post_token(&token_form(&code, CODE_VERIFIER)).await;
+    assert_eq!(response.status().as_u16(), 401);
+    let body: OAuthErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.error, "invalid_client");
+
+    // A failed client authentication doesn't use up the code.
+    let response = app.post_token_with_basic_auth(CLIENT_ID, CLIENT_SECRET, &token_form(&code, CODE_VERIFIER)).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_400_if_grant_type_is_unsupported() {
+    let app = TestApp::new().await;
+
+    let response = app.post_token(&[("grant_type", "password")]).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let body: OAuthErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.error, "unsupported_grant_type");
+}
+
+#[tokio::test]
+async fn should_grant_client_credentials_with_client_scopes() {
+    let app = TestApp::new().await;
+    app.add_service_client(CLIENT_ID, CLIENT_SECRET, &["users:read", "users:write"]).await;
+
+    let response = app.post_token_with_basic_auth(CLIENT_ID, CLIENT_SECRET, &[("grant_type", "client_credentials")]).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers().get("cache-control").unwrap(), "no-store");
+    let body: TokenResponse = response.json().await.unwrap();
+    assert_eq!(body.token_type, "Bearer");
+    assert_eq!(body.expires_in, 300);
+    assert_eq!(body.scope.as_deref(), Some("users:read users:write"));
+    assert_eq!(body.id_token, None);
+
+    let response = app.post_verify_token_with_bearer(&body.access_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: VerifyTokenResponse = response.json().await.unwrap();
+    assert_eq!(body.sub, CLIENT_ID);
+    assert_eq!(body.scopes, vec!["users:read", "users:write"]);
+    assert!(body.roles.is_empty());
+}
+
+#[tokio::test]
+async fn should_grant_requested_client_scopes_only() {
+    let app = TestApp::new().await;
+    app.add_service_client(CLIENT_ID, CLIENT_SECRET, &["users:read", "users:write"]).await;
+
+    let response = app.post_token(&[
+        ("grant_type", "client_credentials"),
+        ("client_id", CLIENT_ID),
+        ("client_secret", CLIENT_SECRET),
+        ("scope", "users:read"),
+    ]).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: TokenResponse = response.json().await.unwrap();
+    assert_eq!(body.scope.as_deref(), Some("users:read"));
+
+    let response = app.post_token_with_basic_auth(CLIENT_ID, CLIENT_SECRET, &[
+        ("grant_type", "client_credentials"),
+        ("scope", "users:read admin"),
+    ]).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let body: OAuthErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.error, "invalid_scope");
+}
+
+#[tokio::test]
+async fn should_reject_client_credentials_of_unauthenticated_or_public_clients() {
+    let app = TestApp::new().await;
+    app.add_service_client(CLIENT_ID, CLIENT_SECRET, &["users:read"]).await;
+    app.add_oauth_client("public-client", REDIRECT_URI, None).await;
+
+    let response = app.post_token_with_basic_auth(CLIENT_ID, "wrong-secret", &[("grant_type", "client_credentials")]).await;
+    assert_eq!(response.status().as_u16(), 401);
+    let body: OAuthErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.error, "invalid_client");
+
+    let response = app.post_token(&[("grant_type", "client_credentials"), ("client_id", "public-client")]).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let body: OAuthErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.error, "unauthorized_client");
+}
+
+#[tokio::test]
+async fn should_not_accept_service_token_as_auth_token() {
+    let app = TestApp::new().await;
+    app.add_service_client(CLIENT_ID, CLIENT_SECRET, &[]).await;
+    let response = app.post_token_with_basic_auth(CLIENT_ID, CLIENT_SECRET, &[("grant_type", "client_credentials")]).await;
+    let body: TokenResponse = response.json().await.unwrap();
+
+    let response = app
+        .http_client
+        .get(format!("{}/sessions", &app.address))
+        .header("Cookie", format!("{}={}", JWT_COOKIE_NAME, body.access_token))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(response.status().as_u16(), 401);
+}
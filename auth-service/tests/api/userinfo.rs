@@ -0,0 +1,43 @@
+use crate::helper::{get_random_email, TestApp};
+use auth_service::{routes::UserInfoResponse, utils::constants::JWT_COOKIE_NAME};
+
+#[tokio::test]
+async fn should_return_claims_of_token_owner() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    let token = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("Auth cookie should be set")
+        .value()
+        .to_owned();
+
+    let response = app.get_userinfo(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body: UserInfoResponse = response.json().await.unwrap();
+    assert!(uuid::Uuid::parse_str(&body.sub).is_ok());
+    assert_eq!(body.email, email);
+    assert!(body.email_verified);
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+    let app = TestApp::new().await;
+
+    let response = app.get_userinfo("invalid").await;
+    assert_eq!(response.status().as_u16(), 401);
+}
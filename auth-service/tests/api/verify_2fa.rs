@@ -5,8 +5,8 @@ use auth_service::{
         data_stores::{LoginAttemptId, TwoFACode},
         error::ErrorResponse,
     },
-    routes::login::TwoFactorAuthResponse,
-    utils::constants::JWT_COOKIE_NAME,
+    routes::{login::TwoFactorAuthResponse, RecoveryCodesResponse},
+    utils::constants::{JWT_COOKIE_NAME, MAX_2FA_ATTEMPTS},
 };
 use secrecy::{ExposeSecret, Secret};
 use wiremock::{matchers::{method, path}, Mock, ResponseTemplate};
@@ -82,6 +82,7 @@ async fn should_return_200_if_correct_code() {
     })).await;
 
     assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
 
     Mock::given(path("/email"))
         .and(method("POST"))
@@ -100,7 +101,7 @@ async fn should_return_200_if_correct_code() {
     
     let two_fa_store = app.two_fa_code_store.read().await;
     let email_obj = Email::parse(Secret::new(email.clone())).unwrap();
-    let (stored_login_attempt_id, stored_code) = two_fa_store.get_code(&email_obj).await.unwrap();
+    let (_, stored_code) = two_fa_store.get_code(&email_obj).await.unwrap();
     drop(two_fa_store);
 
     let response = app.post_verify_2fa(&serde_json::json!({
@@ -137,6 +138,7 @@ async fn should_return_401_if_same_code_twice() {
     })).await;
 
     assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
 
     Mock::given(path("/email"))
         .and(method("POST"))
@@ -189,6 +191,7 @@ async fn should_return_401_if_incorrect_credentials() {
     })).await;
 
     assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
 
     Mock::given(path("/email"))
         .and(method("POST"))
@@ -229,6 +232,7 @@ async fn should_return_401_if_old_code() {
     })).await;
 
     assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
 
     Mock::given(path("/email"))
         .and(method("POST"))
@@ -267,4 +271,111 @@ async fn should_return_401_if_old_code() {
     })).await;
     
     assert_eq!(response.status().as_u16(), 401);
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn should_accept_each_recovery_code_once() {
+    let app = TestApp::new().await;
+
+    let email = get_random_email();
+    let password = "password123";
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": password,
+        "requires2FA": true
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+    let recovery_codes = response.json::<RecoveryCodesResponse>().await.unwrap().recovery_codes;
+    app.verify_email(&email).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+
+    for expected_status in [200, 401] {
+        let login_response = app.post_login(&serde_json::json!({
+            "email": email,
+            "password": password
+        })).await;
+        assert_eq!(login_response.status().as_u16(), 206);
+        let login_response_body: TwoFactorAuthResponse = login_response.json().await.unwrap();
+
+        let response = app.post_verify_2fa(&serde_json::json!({
+            "email": email,
+            "loginAttemptId": login_response_body.login_attempt_id,
+            "2FACode": recovery_codes[0]
+        })).await;
+        assert_eq!(response.status().as_u16(), expected_status);
+    }
+}
+
+#[tokio::test]
+async fn should_invalidate_login_attempt_after_too_many_incorrect_codes() {
+    let app = TestApp::new().await;
+
+    let email = get_random_email();
+    let password = "password123";
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": password,
+        "requires2FA": true
+    })).await;
+
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let login_response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": password
+    })).await;
+
+    assert_eq!(login_response.status().as_u16(), 206);
+    let login_response_body: TwoFactorAuthResponse = login_response.json().await.unwrap();
+
+    let two_fa_store = app.two_fa_code_store.read().await;
+    let email_obj = Email::parse(Secret::new(email.clone())).unwrap();
+    let (_, stored_code) = two_fa_store.get_code(&email_obj).await.unwrap();
+    drop(two_fa_store);
+
+    let wrong_code = if stored_code.as_ref().expose_secret() == "999999" { "000000" } else { "999999" };
+
+    for _ in 1..MAX_2FA_ATTEMPTS {
+        let response = app.post_verify_2fa(&serde_json::json!({
+            "email": email,
+            "loginAttemptId": login_response_body.login_attempt_id,
+            "2FACode": wrong_code
+        })).await;
+        assert_eq!(response.status().as_u16(), 401);
+        let body: ErrorResponse = response.json().await.unwrap();
+        assert_eq!(body.error, "Incorrect credentials");
+    }
+
+    let response = app.post_verify_2fa(&serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_response_body.login_attempt_id,
+        "2FACode": wrong_code
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+    let body: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.error, "Too many incorrect 2FA codes, please log in again");
+
+    // The right code no longer works for this login attempt.
+    let response = app.post_verify_2fa(&serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_response_body.login_attempt_id,
+        "2FACode": stored_code.as_ref().expose_secret()
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
@@ -0,0 +1,86 @@
+use crate::helper::{get_random_email, TestApp};
+use auth_service::{domain::Email, utils::auth::generate_email_verification_token};
+use secrecy::Secret;
+use wiremock::{matchers::{method, path}, Mock, ResponseTemplate};
+
+#[tokio::test]
+async fn should_return_422_if_malformed_input() {
+    let app = TestApp::new().await;
+
+    let response = app.post_verify_email(&serde_json::json!({})).await;
+    assert_eq!(response.status().as_u16(), 422);
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+    let app = TestApp::new().await;
+
+    let response = app.post_verify_email(&serde_json::json!({
+        "token": "invalid"
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_if_user_does_not_exist() {
+    let app = TestApp::new().await;
+    let email = Email::parse(Secret::new(get_random_email())).unwrap();
+    let token = generate_email_verification_token(&email).unwrap();
+
+    let response = app.post_verify_email(&serde_json::json!({
+        "token": token
+    })).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_send_verification_email_on_signup() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let token = app.get_emailed_token().await;
+    let response = app.post_verify_email(&serde_json::json!({
+        "token": token
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_reject_login_until_email_is_verified() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+
+    let response = app.post_signup(&serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    })).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "password123"
+    });
+
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 403);
+
+    app.verify_email(&email).await;
+
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
@@ -1,5 +1,6 @@
 use crate::helper::{get_random_email, TestApp}; // CORRECTION: helper au lieu de helpers
 use auth_service::utils::constants::JWT_COOKIE_NAME; // AJOUT: import du trait BannedTokenStore
+use auth_service::routes::VerifyTokenResponse;
 use secrecy::Secret;
 
 #[tokio::test]
@@ -24,6 +25,7 @@ async fn should_return_200_valid_token() {
         "password": password,
         "requires2FA": false // AJOUT: champ manquant
     })).await;
+    app.verify_email(&email).await;
     let response = app.post_login(&serde_json::json!({
         "email": email,
         "password": password
@@ -82,6 +84,7 @@ async fn should_return_401_if_banned_token() {
         "password": password,
         "requires2FA": false // AJOUT: champ manquant
     })).await;
+    app.verify_email(&email).await;
 
     let response = app.post_login(&serde_json::json!({
         "email": email,
@@ -121,4 +124,35 @@ async fn should_return_401_if_banned_token() {
 
     // Assert that the response status code is 401 Unauthorized
     assert_eq!(verify_response.status().as_u16(), 401);
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn should_return_roles_and_permissions_of_token() {
+    let app = TestApp::new().await;
+    let email = app.log_in_new_user().await;
+    {
+        let mut user_store = app.user_store.write().await;
+        user_store.add_role(&email, "support").await.unwrap();
+        user_store.grant_permission("support", "users:read").await.unwrap();
+    }
+
+    let response = app.post_login(&serde_json::json!({
+        "email": email,
+        "password": "password123"
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let token = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+
+    let response = app.post_verify_token(&serde_json::json!({
+        "token": token
+    })).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.json::<VerifyTokenResponse>().await.unwrap();
+    assert_eq!(body.sub, email);
+    assert_eq!(body.roles, vec!["support"]);
+    assert_eq!(body.permissions, vec!["users:read"]);
+}